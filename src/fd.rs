@@ -0,0 +1,308 @@
+//! Registration of file descriptor based I/O objects with the event loop.
+//!
+//! The `mio` crate only provides `Evented` implementations for the TCP and UDP
+//! types it defines itself. This module contains a small adapter which allows
+//! any owned I/O object backed by a file descriptor, such as the standard
+//! library's Unix socket types, to be used with a `PollEvented`. It's also
+//! exported as `reactor::EventedFd` for registering other kinds of descriptors.
+
+use std::cmp;
+use std::fmt;
+use std::io::{self, IoSlice, Read, Write};
+use std::mem;
+use std::os::unix::prelude::*;
+
+use libc;
+use mio::{Evented, Poll, PollOpt, Ready, Token};
+use mio::unix::EventedFd as MioEventedFd;
+
+/// An adapter registering any object backed by a file descriptor with an event
+/// loop.
+///
+/// Wrapping an object in an `EventedFd` provides an implementation of
+/// `mio::Evented` for it, allowing it to be used with a `PollEvented` to learn
+/// about its readiness. This works with anything the platform's selector
+/// supports, such as character devices, FUSE handles or sockets of families
+/// this crate has no dedicated type for. The wrapped object is expected to be
+/// in nonblocking mode already. `PollEvented::from_fd` wraps an object and
+/// registers it in one go.
+///
+/// Since `RawFd` itself implements `AsRawFd`, a bare descriptor owned by other
+/// code can be wrapped as well. In that case the descriptor must stay open
+/// for as long as it's registered with an event loop.
+pub struct EventedFd<T> {
+    io: T,
+}
+
+impl<T: AsRawFd> EventedFd<T> {
+    /// Creates a new adapter for the file descriptor of `io`.
+    pub fn new(io: T) -> EventedFd<T> {
+        EventedFd { io: io }
+    }
+}
+
+impl<T> EventedFd<T> {
+    /// Returns a reference to the wrapped object.
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    /// Returns a mutable reference to the wrapped object.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.io
+    }
+
+    /// Consumes this adapter, returning the wrapped object.
+    pub fn into_inner(self) -> T {
+        self.io
+    }
+}
+
+impl<T: AsRawFd> Evented for EventedFd<T> {
+    fn register(&self,
+                poll: &Poll,
+                token: Token,
+                interest: Ready,
+                opts: PollOpt) -> io::Result<()> {
+        MioEventedFd(&self.io.as_raw_fd()).register(poll, token, interest, opts)
+    }
+
+    fn reregister(&self,
+                  poll: &Poll,
+                  token: Token,
+                  interest: Ready,
+                  opts: PollOpt) -> io::Result<()> {
+        MioEventedFd(&self.io.as_raw_fd()).reregister(poll, token, interest, opts)
+    }
+
+    fn deregister(&self, poll: &Poll) -> io::Result<()> {
+        MioEventedFd(&self.io.as_raw_fd()).deregister(poll)
+    }
+}
+
+impl<T: AsRawFd> AsRawFd for EventedFd<T> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.as_raw_fd()
+    }
+}
+
+impl<T: Read> Read for EventedFd<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.io.read(buf)
+    }
+}
+
+impl<T: Write> Write for EventedFd<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.io.write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        self.io.write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.io.flush()
+    }
+}
+
+impl<'a, T> Read for &'a EventedFd<T>
+    where &'a T: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.io).read(buf)
+    }
+}
+
+impl<'a, T> Write for &'a EventedFd<T>
+    where &'a T: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.io).write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        (&self.io).write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.io).flush()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for EventedFd<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("EventedFd").field(&self.io).finish()
+    }
+}
+
+/// An owned raw file descriptor, closed when dropped.
+///
+/// This is used for descriptors, such as sockets of families other than the
+/// internet and Unix ones, which have no corresponding type in the standard
+/// library.
+pub struct OwnedFd {
+    fd: RawFd,
+}
+
+impl OwnedFd {
+    /// Takes ownership of `fd`.
+    ///
+    /// This function is unsafe as nothing else may close `fd` afterwards.
+    pub unsafe fn new(fd: RawFd) -> OwnedFd {
+        OwnedFd { fd: fd }
+    }
+}
+
+impl AsRawFd for OwnedFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
+    }
+}
+
+impl IntoRawFd for OwnedFd {
+    fn into_raw_fd(self) -> RawFd {
+        let fd = self.fd;
+        mem::forget(self);
+        fd
+    }
+}
+
+impl Read for OwnedFd {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self).read(buf)
+    }
+}
+
+impl Write for OwnedFd {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self).write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        (&*self).write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> Read for &'a OwnedFd {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = unsafe {
+            libc::read(self.fd, buf.as_mut_ptr() as *mut _, buf.len())
+        };
+        if n == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(n as usize)
+        }
+    }
+}
+
+impl<'a> Write for &'a OwnedFd {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = unsafe {
+            libc::write(self.fd, buf.as_ptr() as *const _, buf.len())
+        };
+        if n == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        writev(self.fd, bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Debug for OwnedFd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OwnedFd").field("fd", &self.fd).finish()
+    }
+}
+
+impl Drop for OwnedFd {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+/// Places `fd` into nonblocking mode and marks it close-on-exec.
+pub fn set_nonblocking_cloexec(fd: RawFd) -> io::Result<()> {
+    unsafe {
+        let flags = try!(cvt(libc::fcntl(fd, libc::F_GETFL)));
+        try!(cvt(libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK)));
+    }
+    set_cloexec(fd)
+}
+
+/// Marks `fd` close-on-exec.
+pub fn set_cloexec(fd: RawFd) -> io::Result<()> {
+    unsafe {
+        let flags = try!(cvt(libc::fcntl(fd, libc::F_GETFD)));
+        try!(cvt(libc::fcntl(fd, libc::F_SETFD, flags | libc::FD_CLOEXEC)));
+    }
+    Ok(())
+}
+
+/// Writes the data of `bufs` to `fd` with a single `writev` call.
+///
+/// Only as many buffers as a call accepts are written, as the number of bytes
+/// written is returned anyway.
+pub fn writev(fd: RawFd, bufs: &[IoSlice]) -> io::Result<usize> {
+    // The `IOV_MAX` of Linux and the BSDs, which libc doesn't expose.
+    const IOV_MAX: usize = 1024;
+    let iovcnt = cmp::min(bufs.len(), IOV_MAX);
+    // `IoSlice` is guaranteed to be ABI compatible with `iovec` on Unix.
+    let n = unsafe {
+        libc::writev(fd, bufs.as_ptr() as *const libc::iovec, iovcnt as libc::c_int)
+    };
+    if n == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+/// Moves up to `len` bytes from `fd_in` to `fd_out` with a single `splice`
+/// call, one of them being a pipe, without blocking on the pipe.
+#[cfg(target_os = "linux")]
+pub fn splice(fd_in: RawFd, fd_out: RawFd, len: usize) -> io::Result<usize> {
+    let flags = libc::SPLICE_F_MOVE | libc::SPLICE_F_NONBLOCK;
+    let n = unsafe {
+        let null = ::std::ptr::null_mut();
+        libc::splice(fd_in, null, fd_out, null, len, flags)
+    };
+    if n == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+/// Creates a nonblocking pipe, returning its reading and writing ends.
+#[cfg(target_os = "linux")]
+pub fn pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    unsafe {
+        try!(cvt(libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC)));
+        Ok((OwnedFd::new(fds[0]), OwnedFd::new(fds[1])))
+    }
+}
+
+pub fn cvt(t: libc::c_int) -> io::Result<libc::c_int> {
+    if t == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(t)
+    }
+}
@@ -0,0 +1,273 @@
+//! Read-only memory maps of files.
+
+use std::cmp;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::ops::Deref;
+use std::os::unix::prelude::*;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+use std::sync::Arc;
+
+use futures::Future;
+use libc;
+
+use io::{AsyncRead, AsyncSeek, IoFuture};
+use pool;
+
+/// A read-only memory map of a file.
+///
+/// Mapping a file lets its contents be copied straight from the page cache,
+/// without a `read` system call for every buffer, which suits large static
+/// assets served to many clients. The map dereferences to the mapped bytes,
+/// and `reader` creates independent cursors over it implementing `AsyncRead`
+/// and `AsyncSeek`.
+///
+/// Cloning a `Mmap` is cheap, as the clones share the same mapping, which is
+/// removed once the last of them is dropped. Note that the file must not be
+/// truncated while it's mapped, as accessing pages past the end of the file
+/// raises `SIGBUS`.
+///
+/// Touching a page which isn't in memory yet still blocks while it's read
+/// from disk, so `advise` should be used to tell the kernel how the map is
+/// going to be accessed, and in particular `Advice::WillNeed` to start reading
+/// it ahead of time.
+#[derive(Clone)]
+pub struct Mmap {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// The mapping is read-only and never changes while it exists, so it can be
+// shared between threads like a `&[u8]`.
+unsafe impl Send for Inner {}
+unsafe impl Sync for Inner {}
+
+/// Hints about how a `Mmap` is going to be accessed, passed to `madvise`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Advice {
+    /// No particular access pattern, the default.
+    Normal,
+    /// The map is going to be read from start to end, so the kernel can read
+    /// ahead aggressively and drop pages soon after they've been read.
+    Sequential,
+    /// The map is going to be accessed in random order, so reading ahead is
+    /// of little use.
+    Random,
+    /// The map is going to be accessed soon, so the kernel can start reading
+    /// it in now.
+    WillNeed,
+    /// The map isn't going to be accessed for a while, so the kernel can free
+    /// the memory backing it.
+    DontNeed,
+}
+
+impl Mmap {
+    /// Opens the file at `path` and maps it, returning a future which
+    /// resolves to the map.
+    ///
+    /// The file is opened and mapped on the blocking pool.
+    pub fn open<P: AsRef<Path>>(path: P) -> IoFuture<Mmap> {
+        let path = path.as_ref().to_path_buf();
+        pool::run(move || {
+            let file = try!(fs::File::open(path));
+            Mmap::map(&file)
+        }).boxed()
+    }
+
+    /// Maps the whole of `file`, which must have been opened for reading.
+    ///
+    /// The map stays valid after `file` has been closed.
+    pub fn map(file: &fs::File) -> io::Result<Mmap> {
+        let size = try!(file.metadata()).len();
+        let len = size as usize;
+        if len as u64 != size {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "file is too large to be mapped"))
+        }
+        // Empty mappings aren't allowed, so an empty file is represented by
+        // an empty map without any mapping behind it.
+        let ptr = if len == 0 {
+            ptr::null_mut()
+        } else {
+            let ptr = unsafe {
+                libc::mmap(ptr::null_mut(),
+                           len,
+                           libc::PROT_READ,
+                           libc::MAP_SHARED,
+                           file.as_raw_fd(),
+                           0)
+            };
+            if ptr == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error())
+            }
+            ptr
+        };
+        Ok(Mmap { inner: Arc::new(Inner { ptr: ptr, len: len }) })
+    }
+
+    /// Tells the kernel how the whole map is going to be accessed.
+    pub fn advise(&self, advice: Advice) -> io::Result<()> {
+        let len = self.inner.len;
+        self.advise_range(advice, 0, len)
+    }
+
+    /// Tells the kernel how the `len` bytes of the map starting at `offset`
+    /// are going to be accessed.
+    ///
+    /// The range is widened to cover whole pages.
+    pub fn advise_range(&self, advice: Advice, offset: usize, len: usize)
+                        -> io::Result<()> {
+        let in_range = match offset.checked_add(len) {
+            Some(end) => end <= self.inner.len,
+            None => false,
+        };
+        if !in_range {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "range is outside of the map"))
+        }
+        if len == 0 {
+            return Ok(())
+        }
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let start = offset - offset % page;
+        let advice = match advice {
+            Advice::Normal => libc::MADV_NORMAL,
+            Advice::Sequential => libc::MADV_SEQUENTIAL,
+            Advice::Random => libc::MADV_RANDOM,
+            Advice::WillNeed => libc::MADV_WILLNEED,
+            Advice::DontNeed => libc::MADV_DONTNEED,
+        };
+        let rc = unsafe {
+            libc::madvise((self.inner.ptr as *mut u8).add(start) as *mut _,
+                          offset + len - start,
+                          advice)
+        };
+        if rc == -1 {
+            return Err(io::Error::last_os_error())
+        }
+        Ok(())
+    }
+
+    /// Creates a new cursor reading the map from the start.
+    pub fn reader(&self) -> MmapReader {
+        MmapReader { mmap: self.clone(), pos: 0 }
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.inner.len == 0 {
+            return &[]
+        }
+        unsafe { slice::from_raw_parts(self.inner.ptr as *const u8, self.inner.len) }
+    }
+}
+
+impl fmt::Debug for Mmap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Mmap")
+         .field("ptr", &self.inner.ptr)
+         .field("len", &self.inner.len)
+         .finish()
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if self.len != 0 {
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
+/// A cursor over a `Mmap`, created by `Mmap::reader`.
+///
+/// Reading copies bytes straight out of the map and never blocks on anything
+/// but page faults, so the reader is always ready. It can be used wherever an
+/// `AsyncRead` object is expected, for example as the source of `io::copy`
+/// to a `TcpStream`.
+#[derive(Clone, Debug)]
+pub struct MmapReader {
+    mmap: Mmap,
+    pos: usize,
+}
+
+impl MmapReader {
+    /// Returns the map this reader reads from.
+    pub fn get_ref(&self) -> &Mmap {
+        &self.mmap
+    }
+
+    /// Returns the current position of this reader within the map.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+}
+
+impl Read for MmapReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let available = try!(self.fill_buf());
+            let n = cmp::min(available.len(), buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for MmapReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        let pos = cmp::min(self.pos, self.mmap.len());
+        Ok(&self.mmap[pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt;
+    }
+}
+
+impl Seek for MmapReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(n) => offset(self.mmap.len() as u64, n),
+            SeekFrom::Current(n) => offset(self.pos as u64, n),
+        };
+        let pos = match pos {
+            Some(pos) if pos as usize as u64 == pos => pos,
+            _ => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          "invalid seek to a negative or \
+                                           overflowing position"))
+            }
+        };
+        self.pos = pos as usize;
+        Ok(pos)
+    }
+}
+
+/// Returns `base` moved by `offset`, unless that's negative or overflows.
+fn offset(base: u64, offset: i64) -> Option<u64> {
+    if offset >= 0 {
+        base.checked_add(offset as u64)
+    } else {
+        base.checked_sub(offset.unsigned_abs())
+    }
+}
+
+impl AsyncRead for MmapReader {}
+
+impl AsyncSeek for MmapReader {}
@@ -0,0 +1,320 @@
+//! Asynchronous filesystem operations.
+//!
+//! Disk I/O can't generally be performed in a nonblocking fashion, so the
+//! operations in this module are executed on a pool of helper threads managed
+//! by this crate, which keeps them from stalling the event loop. Each
+//! operation returns a future which resolves once it has completed.
+//!
+//! Like the `send_dgram` and `recv_dgram` methods on `UdpSocket`, the methods
+//! of `File` take ownership of the file (and of any buffer involved) for the
+//! duration of the operation, handing them back once it has finished. The
+//! free functions, such as `metadata`, `rename` and `read_dir`, are
+//! asynchronous counterparts of those in `std::fs`.
+//!
+//! On Unix this module also contains `Mmap`, a read-only memory map of a file
+//! for serving its contents without `read` system calls, and on Linux
+//! `Watcher`, a stream of changes made to watched files and directories.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, DirEntry, Metadata, OpenOptions};
+use std::io::{self, Read, Write, Seek, SeekFrom};
+use std::path::Path;
+
+use futures::{Async, Future, Poll};
+use futures::stream::Stream;
+
+use io::{AsyncSeek, IoFuture};
+use pool::{self, Blocking};
+
+#[cfg(unix)]
+mod mmap;
+#[cfg(target_os = "linux")]
+mod watch;
+#[cfg(unix)]
+pub use self::mmap::{Mmap, MmapReader, Advice};
+#[cfg(target_os = "linux")]
+pub use self::watch::{Watcher, Watch, Event, EventKind};
+
+/// A reference to an open file on the filesystem, whose operations are run on
+/// the blocking pool.
+///
+/// Besides the methods below, `File` implements `AsyncSeek`, so it can be
+/// used with the `seek` function of the `io` module. Each such seek is run on
+/// the blocking pool as well, and has to complete before any other operation
+/// is started on the file.
+pub struct File {
+    std: fs::File,
+    seeking: Option<Blocking<u64>>,
+}
+
+impl File {
+    /// Opens a file in read-only mode, returning a future for the opened
+    /// file.
+    pub fn open<P: AsRef<Path>>(path: P) -> IoFuture<File> {
+        let path = path.as_ref().to_path_buf();
+        pool::run(move || fs::File::open(path)).map(File::from_std).boxed()
+    }
+
+    /// Opens a file in write-only mode, creating it if it doesn't exist and
+    /// truncating it if it does, returning a future for the opened file.
+    pub fn create<P: AsRef<Path>>(path: P) -> IoFuture<File> {
+        let path = path.as_ref().to_path_buf();
+        pool::run(move || fs::File::create(path)).map(File::from_std).boxed()
+    }
+
+    /// Opens a file with the options specified by `options`, returning a
+    /// future for the opened file.
+    pub fn open_with<P: AsRef<Path>>(path: P, options: &OpenOptions)
+                                     -> IoFuture<File> {
+        let path = path.as_ref().to_path_buf();
+        let options = options.clone();
+        pool::run(move || options.open(path)).map(File::from_std).boxed()
+    }
+
+    /// Creates a new `File` from an already opened standard library file.
+    pub fn from_std(std: fs::File) -> File {
+        File { std: std, seeking: None }
+    }
+
+    /// Consumes this `File`, returning the underlying standard library file.
+    pub fn into_std(self) -> fs::File {
+        self.std
+    }
+
+    /// Reads some bytes from the file into `buf`, returning a future which
+    /// resolves to the file, the buffer and the number of bytes read.
+    ///
+    /// As with `Read::read`, a return value of 0 bytes read indicates the end
+    /// of the file has been reached.
+    pub fn read<T>(self, mut buf: T) -> IoFuture<(File, T, usize)>
+        where T: AsMut<[u8]> + Send + 'static,
+    {
+        let mut std = self.std;
+        pool::run(move || {
+            let n = try!(std.read(buf.as_mut()));
+            Ok((File::from_std(std), buf, n))
+        }).boxed()
+    }
+
+    /// Reads the remainder of the file, appending it to `buf` and returning a
+    /// future which resolves to the file and the buffer.
+    pub fn read_to_end(self, mut buf: Vec<u8>) -> IoFuture<(File, Vec<u8>)> {
+        let mut std = self.std;
+        pool::run(move || {
+            try!(std.read_to_end(&mut buf));
+            Ok((File::from_std(std), buf))
+        }).boxed()
+    }
+
+    /// Writes some bytes from `buf` to the file, returning a future which
+    /// resolves to the file, the buffer and the number of bytes written.
+    pub fn write<T>(self, buf: T) -> IoFuture<(File, T, usize)>
+        where T: AsRef<[u8]> + Send + 'static,
+    {
+        let mut std = self.std;
+        pool::run(move || {
+            let n = try!(std.write(buf.as_ref()));
+            Ok((File::from_std(std), buf, n))
+        }).boxed()
+    }
+
+    /// Writes the entire contents of `buf` to the file, returning a future
+    /// which resolves to the file and the buffer.
+    pub fn write_all<T>(self, buf: T) -> IoFuture<(File, T)>
+        where T: AsRef<[u8]> + Send + 'static,
+    {
+        let mut std = self.std;
+        pool::run(move || {
+            try!(std.write_all(buf.as_ref()));
+            Ok((File::from_std(std), buf))
+        }).boxed()
+    }
+
+    /// Seeks to the offset `pos` in the file, returning a future which
+    /// resolves to the file and the new position from the start of the file.
+    pub fn seek(self, pos: SeekFrom) -> IoFuture<(File, u64)> {
+        let mut std = self.std;
+        pool::run(move || {
+            let n = try!(std.seek(pos));
+            Ok((File::from_std(std), n))
+        }).boxed()
+    }
+
+    /// Attempts to sync all data and metadata of the file to disk, returning
+    /// a future which resolves to the file once that's done.
+    pub fn sync_all(self) -> IoFuture<File> {
+        let std = self.std;
+        pool::run(move || {
+            try!(std.sync_all());
+            Ok(File::from_std(std))
+        }).boxed()
+    }
+
+    /// Truncates or extends the file to `size` bytes, returning a future
+    /// which resolves to the file once that's done.
+    pub fn set_len(self, size: u64) -> IoFuture<File> {
+        let std = self.std;
+        pool::run(move || {
+            try!(std.set_len(size));
+            Ok(File::from_std(std))
+        }).boxed()
+    }
+
+    /// Queries metadata about the file, returning a future which resolves to
+    /// the file and its metadata.
+    pub fn metadata(self) -> IoFuture<(File, Metadata)> {
+        let std = self.std;
+        pool::run(move || {
+            let metadata = try!(std.metadata());
+            Ok((File::from_std(std), metadata))
+        }).boxed()
+    }
+}
+
+/// Seeks the file right away on the current thread, rather than on the
+/// blocking pool, and so shouldn't be mixed with a seek started by
+/// `AsyncSeek::poll_seek` which hasn't completed yet.
+impl Seek for File {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.std.seek(pos)
+    }
+}
+
+impl AsyncSeek for File {
+    /// Seeks the file on the blocking pool.
+    ///
+    /// Until the seek has completed this returns `Async::NotReady`, and the
+    /// `pos` given to later calls is ignored in favor of the one the seek was
+    /// started with.
+    fn poll_seek(&mut self, pos: SeekFrom) -> Poll<u64, io::Error> {
+        if self.seeking.is_none() {
+            // The clone shares the file offset with `self.std`.
+            let mut std = try!(self.std.try_clone());
+            self.seeking = Some(pool::run(move || std.seek(pos)));
+        }
+        let res = self.seeking.as_mut().unwrap().poll();
+        if let Ok(Async::NotReady) = res {
+            return res
+        }
+        self.seeking = None;
+        res
+    }
+}
+
+impl fmt::Debug for File {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.std.fmt(f)
+    }
+}
+
+/// Queries the metadata of the file or directory at `path`, following
+/// symbolic links, returning a future which resolves to it.
+pub fn metadata<P: AsRef<Path>>(path: P) -> IoFuture<Metadata> {
+    let path = path.as_ref().to_path_buf();
+    pool::run(move || fs::metadata(path)).boxed()
+}
+
+/// Renames the file or directory at `from` to `to`, replacing `to` if it
+/// already exists, returning a future which resolves once that's done.
+pub fn rename<P, Q>(from: P, to: Q) -> IoFuture<()>
+    where P: AsRef<Path>,
+          Q: AsRef<Path>,
+{
+    let from = from.as_ref().to_path_buf();
+    let to = to.as_ref().to_path_buf();
+    pool::run(move || fs::rename(from, to)).boxed()
+}
+
+/// Removes the file at `path`, returning a future which resolves once that's
+/// done.
+pub fn remove_file<P: AsRef<Path>>(path: P) -> IoFuture<()> {
+    let path = path.as_ref().to_path_buf();
+    pool::run(move || fs::remove_file(path)).boxed()
+}
+
+/// Creates the directory at `path` along with any missing parents, returning
+/// a future which resolves once that's done.
+pub fn create_dir_all<P: AsRef<Path>>(path: P) -> IoFuture<()> {
+    let path = path.as_ref().to_path_buf();
+    pool::run(move || fs::create_dir_all(path)).boxed()
+}
+
+/// The number of entries `ReadDir` reads per trip to the blocking pool.
+const READ_DIR_BATCH: usize = 32;
+
+/// Returns a stream of the entries of the directory at `path`.
+///
+/// The entries are read on the blocking pool in batches. Note that querying
+/// an entry's `metadata` or `file_type` may block on some platforms, so the
+/// `metadata` function should be used with the entry's `path` instead where
+/// that matters.
+pub fn read_dir<P: AsRef<Path>>(path: P) -> ReadDir {
+    let path = path.as_ref().to_path_buf();
+    ReadDir {
+        entries: VecDeque::new(),
+        state: ReadDirState::Pending(pool::run(move || {
+            next_batch(try!(fs::read_dir(path)))
+        })),
+    }
+}
+
+/// A stream of the entries of a directory, created by the `read_dir`
+/// function.
+pub struct ReadDir {
+    entries: VecDeque<io::Result<DirEntry>>,
+    state: ReadDirState,
+}
+
+enum ReadDirState {
+    Idle(Option<fs::ReadDir>),
+    Pending(Blocking<(Option<fs::ReadDir>, Vec<io::Result<DirEntry>>)>),
+}
+
+fn next_batch(mut dir: fs::ReadDir)
+              -> io::Result<(Option<fs::ReadDir>, Vec<io::Result<DirEntry>>)> {
+    let mut entries = Vec::with_capacity(READ_DIR_BATCH);
+    while entries.len() < READ_DIR_BATCH {
+        match dir.next() {
+            Some(entry) => entries.push(entry),
+            None => return Ok((None, entries)),
+        }
+    }
+    Ok((Some(dir), entries))
+}
+
+impl Stream for ReadDir {
+    type Item = DirEntry;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<DirEntry>, io::Error> {
+        loop {
+            if let Some(entry) = self.entries.pop_front() {
+                return entry.map(|e| Async::Ready(Some(e)))
+            }
+            let next = match self.state {
+                ReadDirState::Idle(ref mut dir) => {
+                    match dir.take() {
+                        Some(dir) => pool::run(move || next_batch(dir)),
+                        None => return Ok(Async::Ready(None)),
+                    }
+                }
+                ReadDirState::Pending(ref mut batch) => {
+                    let (dir, entries) = try_ready!(batch.poll());
+                    self.entries.extend(entries);
+                    self.state = ReadDirState::Idle(dir);
+                    continue
+                }
+            };
+            self.state = ReadDirState::Pending(next);
+        }
+    }
+}
+
+impl fmt::Debug for ReadDir {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReadDir")
+         .field("buffered", &self.entries.len())
+         .finish()
+    }
+}
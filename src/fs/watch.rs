@@ -0,0 +1,255 @@
+//! Filesystem change notifications, backed by inotify.
+
+use std::collections::HashMap;
+use std::ffi::{CString, OsStr};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::mem;
+use std::os::unix::prelude::*;
+use std::path::{Path, PathBuf};
+use std::ptr;
+
+use futures::{Async, Poll};
+use futures::stream::Stream;
+use libc::{self, c_int};
+
+use fd::EventedFd;
+use reactor::{Handle, PollEvented};
+
+/// The events watched for on every path added to a `Watcher`.
+const MASK: u32 = libc::IN_CREATE | libc::IN_DELETE | libc::IN_DELETE_SELF |
+                  libc::IN_MODIFY | libc::IN_ATTRIB | libc::IN_MOVED_FROM |
+                  libc::IN_MOVED_TO | libc::IN_MOVE_SELF;
+
+/// Large enough for at least one event with a maximum length name.
+const BUF_SIZE: usize = 4096;
+
+/// A stream of changes made to a set of watched files and directories.
+///
+/// Paths are added with `add`. Watching a directory reports changes to the
+/// entries within it (but not recursively), while watching a file reports
+/// changes to that file.
+pub struct Watcher {
+    io: PollEvented<EventedFd<File>>,
+    paths: HashMap<c_int, PathBuf>,
+    buf: Vec<u8>,
+    pos: usize,
+    len: usize,
+}
+
+/// An identifier for a path added to a `Watcher`, used to remove it again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Watch(c_int);
+
+/// A change reported by a `Watcher`.
+#[derive(Clone, Debug)]
+pub struct Event {
+    watch: Option<Watch>,
+    path: PathBuf,
+    kind: EventKind,
+    cookie: u32,
+}
+
+/// The kind of change an `Event` describes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    /// A file or directory was created in a watched directory.
+    Create,
+    /// The contents of a file were modified.
+    Modify,
+    /// The metadata of a file, such as its permissions or timestamps, was
+    /// changed.
+    Metadata,
+    /// A file or directory was removed, or a watched path was itself removed.
+    Remove,
+    /// A file or directory was renamed away from the path of the event, or a
+    /// watched path was itself renamed.
+    RenameFrom,
+    /// A file or directory was renamed to the path of the event.
+    RenameTo,
+    /// The kernel's queue of events overflowed and some were lost, so any
+    /// state derived from the watched paths should be rebuilt.
+    Overflow,
+}
+
+impl Watcher {
+    /// Creates a new watcher, with no paths being watched, associated with
+    /// the event loop `handle` points to.
+    pub fn new(handle: &Handle) -> io::Result<Watcher> {
+        let flags = libc::IN_NONBLOCK | libc::IN_CLOEXEC;
+        let raw = unsafe { libc::inotify_init1(flags) };
+        if raw == -1 {
+            return Err(io::Error::last_os_error())
+        }
+        let file = unsafe { File::from_raw_fd(raw) };
+        let io = try!(PollEvented::new(EventedFd::new(file), handle));
+        Ok(Watcher {
+            io: io,
+            paths: HashMap::new(),
+            buf: vec![0; BUF_SIZE],
+            pos: 0,
+            len: 0,
+        })
+    }
+
+    /// Starts watching `path` for changes.
+    ///
+    /// Adding a path which is already being watched returns the same `Watch`
+    /// as before.
+    pub fn add<P: AsRef<Path>>(&mut self, path: P) -> io::Result<Watch> {
+        let path = path.as_ref();
+        let cstr = match CString::new(path.as_os_str().as_bytes()) {
+            Ok(s) => s,
+            Err(_) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          "path contains a nul byte"))
+            }
+        };
+        let wd = unsafe {
+            libc::inotify_add_watch(self.as_raw_fd(), cstr.as_ptr(), MASK)
+        };
+        if wd == -1 {
+            return Err(io::Error::last_os_error())
+        }
+        self.paths.insert(wd, path.to_path_buf());
+        Ok(Watch(wd))
+    }
+
+    /// Stops watching the path identified by `watch`.
+    pub fn remove(&mut self, watch: Watch) -> io::Result<()> {
+        if unsafe { libc::inotify_rm_watch(self.as_raw_fd(), watch.0) } == -1 {
+            return Err(io::Error::last_os_error())
+        }
+        self.paths.remove(&watch.0);
+        Ok(())
+    }
+
+    /// Parses the next event out of the buffer of data read from inotify,
+    /// skipping any which aren't of interest.
+    fn next_event(&mut self) -> Option<Event> {
+        let header = mem::size_of::<libc::inotify_event>();
+        while self.pos + header <= self.len {
+            let raw = unsafe {
+                let ptr = self.buf[self.pos..].as_ptr();
+                ptr::read_unaligned(ptr as *const libc::inotify_event)
+            };
+            let start = self.pos + header;
+            self.pos = start + raw.len as usize;
+            let name = &self.buf[start..self.pos];
+            let name = match name.iter().position(|b| *b == 0) {
+                Some(i) => &name[..i],
+                None => name,
+            };
+
+            if raw.mask & libc::IN_Q_OVERFLOW != 0 {
+                return Some(Event {
+                    watch: None,
+                    path: PathBuf::new(),
+                    kind: EventKind::Overflow,
+                    cookie: 0,
+                })
+            }
+            if raw.mask & libc::IN_IGNORED != 0 {
+                self.paths.remove(&raw.wd);
+                continue
+            }
+            let kind = if raw.mask & libc::IN_CREATE != 0 {
+                EventKind::Create
+            } else if raw.mask & libc::IN_MODIFY != 0 {
+                EventKind::Modify
+            } else if raw.mask & libc::IN_ATTRIB != 0 {
+                EventKind::Metadata
+            } else if raw.mask & (libc::IN_DELETE | libc::IN_DELETE_SELF) != 0 {
+                EventKind::Remove
+            } else if raw.mask & (libc::IN_MOVED_FROM | libc::IN_MOVE_SELF) != 0 {
+                EventKind::RenameFrom
+            } else if raw.mask & libc::IN_MOVED_TO != 0 {
+                EventKind::RenameTo
+            } else {
+                continue
+            };
+            let path = match self.paths.get(&raw.wd) {
+                Some(path) if name.is_empty() => path.clone(),
+                Some(path) => path.join(OsStr::from_bytes(name)),
+                None => continue,
+            };
+            return Some(Event {
+                watch: Some(Watch(raw.wd)),
+                path: path,
+                kind: kind,
+                cookie: raw.cookie,
+            })
+        }
+        None
+    }
+}
+
+impl Stream for Watcher {
+    type Item = Event;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<Event>, io::Error> {
+        loop {
+            if let Some(event) = self.next_event() {
+                return Ok(Async::Ready(Some(event)))
+            }
+            self.pos = 0;
+            self.len = 0;
+            match self.io.read(&mut self.buf) {
+                Ok(0) => return Ok(Async::Ready(None)),
+                Ok(n) => self.len = n,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    return Ok(Async::NotReady)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl AsRawFd for Watcher {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl fmt::Debug for Watcher {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Watcher")
+         .field("fd", &self.as_raw_fd())
+         .field("paths", &self.paths)
+         .finish()
+    }
+}
+
+impl Event {
+    /// Returns the watch this event was reported for, or `None` for an
+    /// `Overflow` event.
+    pub fn watch(&self) -> Option<Watch> {
+        self.watch
+    }
+
+    /// Returns the path this event applies to.
+    ///
+    /// For events about an entry of a watched directory this is the path of
+    /// the entry, otherwise it's the watched path itself. `Overflow` events
+    /// have an empty path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the kind of change this event describes.
+    pub fn kind(&self) -> EventKind {
+        self.kind
+    }
+
+    /// Returns the cookie associated with this event.
+    ///
+    /// The `RenameFrom` and `RenameTo` events for a rename within the watched
+    /// paths share the same nonzero cookie, allowing them to be paired up.
+    /// For all other events this is 0.
+    pub fn cookie(&self) -> u32 {
+        self.cookie
+    }
+}
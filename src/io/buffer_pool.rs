@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+/// The default sizes of the buffers of a pool.
+const DEFAULT_CLASSES: &'static [usize] = &[2 * 1024, 8 * 1024, 64 * 1024];
+
+/// The default number of idle buffers kept for each size.
+const DEFAULT_MAX_IDLE: usize = 64;
+
+/// A pool of byte buffers to reuse rather than allocate.
+///
+/// A pool keeps idle buffers of a few fixed sizes, the size classes, for
+/// buffers such as the ones of `copy_with_buffer` and the framed adapters
+/// which are allocated for each connection and dropped along with it. A
+/// buffer is taken from the pool with `get` and handed back with `put`, so a
+/// server handling many short connections allocates about as many buffers as
+/// it has connections open at once.
+///
+/// A pool can't be shared between threads, so it's not locked: each event
+/// loop has one, which is returned by `Handle::buffer_pool`, and clones of a
+/// `BufferPool` refer to the same idle buffers.
+#[derive(Clone)]
+pub struct BufferPool {
+    inner: Rc<RefCell<Inner>>,
+}
+
+struct Inner {
+    classes: Vec<Class>,
+    max_idle: usize,
+}
+
+struct Class {
+    size: usize,
+    idle: Vec<Vec<u8>>,
+}
+
+impl BufferPool {
+    /// Creates a pool with size classes of 2KB, 8KB and 64KB, keeping up to
+    /// 64 idle buffers of each.
+    pub fn new() -> BufferPool {
+        BufferPool::with_classes(DEFAULT_CLASSES, DEFAULT_MAX_IDLE)
+    }
+
+    /// Creates a pool with the given size classes, keeping up to `max_idle`
+    /// idle buffers of each.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sizes` is empty or contains 0.
+    pub fn with_classes(sizes: &[usize], max_idle: usize) -> BufferPool {
+        assert!(!sizes.is_empty(), "buffer pool needs a size class");
+        assert!(sizes.iter().all(|s| *s > 0), "buffer pool size classes must not be 0");
+        let mut sizes = sizes.to_vec();
+        sizes.sort();
+        sizes.dedup();
+        let classes = sizes.into_iter().map(|size| {
+            Class { size: size, idle: Vec::new() }
+        }).collect();
+        BufferPool {
+            inner: Rc::new(RefCell::new(Inner {
+                classes: classes,
+                max_idle: max_idle,
+            })),
+        }
+    }
+
+    /// Returns a buffer of at least `size` bytes.
+    ///
+    /// The buffer is as long as the smallest size class of at least `size`
+    /// bytes, with a capacity of the same length, and is taken from the idle
+    /// buffers of the pool if there are any. Its contents are unspecified, as
+    /// they may have been left over by a previous user. A buffer larger than
+    /// any class is allocated exactly, and isn't kept when handed back.
+    pub fn get(&self, size: usize) -> Vec<u8> {
+        let mut inner = self.inner.borrow_mut();
+        match inner.classes.iter_mut().find(|c| c.size >= size) {
+            Some(class) => {
+                class.idle.pop().unwrap_or_else(|| vec![0; class.size])
+            }
+            None => vec![0; size],
+        }
+    }
+
+    /// Hands a buffer back to the pool.
+    ///
+    /// The buffer is kept for the largest size class it has the capacity for,
+    /// if the pool doesn't have enough idle buffers of that size already, and
+    /// dropped otherwise. Buffers which weren't returned by `get` can be handed
+    /// back as well.
+    pub fn put(&self, mut buf: Vec<u8>) {
+        let mut inner = self.inner.borrow_mut();
+        let max_idle = inner.max_idle;
+        let cap = buf.capacity();
+        if let Some(class) = inner.classes.iter_mut().rev().find(|c| c.size <= cap) {
+            if class.idle.len() < max_idle {
+                buf.resize(class.size, 0);
+                class.idle.push(buf);
+            }
+        }
+    }
+
+    /// Returns the number of idle buffers kept by the pool.
+    pub fn idle(&self) -> usize {
+        self.inner.borrow().classes.iter().map(|c| c.idle.len()).sum()
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> BufferPool {
+        BufferPool::new()
+    }
+}
+
+impl fmt::Debug for BufferPool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let inner = self.inner.borrow();
+        let classes = inner.classes.iter().map(|c| (c.size, c.idle.len()));
+        f.debug_map().entries(classes).finish()
+    }
+}
@@ -0,0 +1,344 @@
+use std::cmp;
+use std::fmt;
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+
+use futures::{Async, Poll};
+
+use io::{AsyncRead, AsyncSeek, AsyncWrite};
+
+const DEFAULT_BUF_SIZE: usize = 8 * 1024;
+
+/// Adds buffering to any reader, for I/O objects of this crate.
+///
+/// This is the counterpart of `std::io::BufReader` for non-blocking readers:
+/// data is read from the underlying reader in large chunks, so parsers which
+/// read a byte or a line at a time, such as the `read_until` future, don't
+/// issue a system call per read. When the buffer is empty and the reader has
+/// no data available a "would block" error is returned as usual, and the
+/// buffer is only refilled once it has been consumed.
+///
+/// The buffered data is accessed without copying through `poll_fill_buf` and
+/// `consume`, or through the `BufRead` trait. Writes pass through to the
+/// underlying object, so a `BufReader` of an `Io` object is an `Io` object as
+/// well. Seeking discards the buffered data, `SeekFrom::Current` offsets
+/// being relative to the data consumed.
+pub struct BufReader<R> {
+    inner: R,
+    buf: Box<[u8]>,
+    pos: usize,
+    cap: usize,
+}
+
+/// Adds buffering to any writer, for I/O objects of this crate.
+///
+/// This is the counterpart of `std::io::BufWriter` for non-blocking writers:
+/// small writes are gathered in a buffer which is written out to the
+/// underlying writer when it's full or when the `BufWriter` is flushed, for
+/// example with the `flush` future of this module. A "would block" error from
+/// the underlying writer is passed on, with the data which couldn't be
+/// written kept in the buffer until the next write or flush.
+///
+/// Dropping a `BufWriter` only makes a single attempt at writing its buffer,
+/// so it should be flushed before being dropped. Reads pass through to the
+/// underlying object, so a `BufWriter` of an `Io` object is an `Io` object as
+/// well.
+pub struct BufWriter<W: Write> {
+    inner: Option<W>,
+    buf: Vec<u8>,
+}
+
+impl<R: Read> BufReader<R> {
+    /// Creates a new `BufReader` with a default buffer capacity of 8KB.
+    pub fn new(inner: R) -> BufReader<R> {
+        BufReader::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Creates a new `BufReader` with the specified buffer capacity.
+    pub fn with_capacity(cap: usize, inner: R) -> BufReader<R> {
+        BufReader {
+            inner: inner,
+            buf: vec![0; cap].into_boxed_slice(),
+            pos: 0,
+            cap: 0,
+        }
+    }
+
+    /// Returns the contents of the internal buffer, filling it with more data
+    /// from the underlying reader if it's empty.
+    ///
+    /// The data returned is left in the buffer until `consume` is called. An
+    /// empty slice is returned at EOF.
+    ///
+    /// If the buffer is empty and no data is available from the underlying
+    /// reader then `Async::NotReady` is returned, and the current task is
+    /// scheduled to receive a notification when it becomes readable.
+    pub fn poll_fill_buf(&mut self) -> Poll<&[u8], io::Error> {
+        if self.pos >= self.cap {
+            self.cap = try_nb!(self.inner.read(&mut self.buf));
+            self.pos = 0;
+        }
+        Ok(Async::Ready(&self.buf[self.pos..self.cap]))
+    }
+}
+
+impl<R> BufReader<R> {
+    /// Marks `amt` bytes of the internal buffer as consumed, so they're no
+    /// longer returned by `poll_fill_buf` or reads.
+    pub fn consume(&mut self, amt: usize) {
+        self.pos = cmp::min(self.pos + amt, self.cap);
+    }
+
+    /// Returns the data buffered but not consumed yet, without reading more.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..self.cap]
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// It is inadvisable to directly read from the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Unwraps this `BufReader`, returning the underlying reader.
+    ///
+    /// Note that any leftover data in the internal buffer is lost.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns the offset to seek the underlying reader to for `pos`, which
+    /// is relative to the data consumed for `SeekFrom::Current`.
+    fn seek_pos(&self, pos: SeekFrom) -> SeekFrom {
+        match pos {
+            SeekFrom::Current(n) => SeekFrom::Current(n - (self.cap - self.pos) as i64),
+            pos => pos,
+        }
+    }
+}
+
+impl<R: Read> Read for BufReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // If we don't have any buffered data and we're doing a massive read
+        // (larger than our internal buffer), bypass our internal buffer
+        // entirely.
+        if self.pos == self.cap && buf.len() >= self.buf.len() {
+            return self.inner.read(buf)
+        }
+        let n = {
+            let mut rem = try!(self.fill_buf());
+            try!(rem.read(buf))
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read> BufRead for BufReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos >= self.cap {
+            self.cap = try!(self.inner.read(&mut self.buf));
+            self.pos = 0;
+        }
+        Ok(&self.buf[self.pos..self.cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        BufReader::consume(self, amt)
+    }
+}
+
+impl<R: Seek> Seek for BufReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = self.seek_pos(pos);
+        let n = try!(self.inner.seek(pos));
+        self.pos = 0;
+        self.cap = 0;
+        Ok(n)
+    }
+}
+
+impl<R: AsyncSeek> AsyncSeek for BufReader<R> {
+    fn poll_seek(&mut self, pos: SeekFrom) -> Poll<u64, io::Error> {
+        let pos = self.seek_pos(pos);
+        let n = try_ready!(self.inner.poll_seek(pos));
+        self.pos = 0;
+        self.cap = 0;
+        Ok(Async::Ready(n))
+    }
+}
+
+impl<R: Write> Write for BufReader<R> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for BufReader<R> {
+    fn poll_read(&mut self) -> Async<()> {
+        if self.pos < self.cap {
+            Async::Ready(())
+        } else {
+            self.inner.poll_read()
+        }
+    }
+}
+
+impl<R: AsyncWrite> AsyncWrite for BufReader<R> {
+    fn poll_write(&mut self) -> Async<()> {
+        self.inner.poll_write()
+    }
+
+    fn poll_flush(&mut self) -> Poll<(), io::Error> {
+        self.inner.poll_flush()
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.inner.shutdown()
+    }
+}
+
+impl<R: fmt::Debug> fmt::Debug for BufReader<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BufReader")
+         .field("reader", &self.inner)
+         .field("buffer", &format_args!("{}/{}", self.cap - self.pos, self.buf.len()))
+         .finish()
+    }
+}
+
+impl<W: Write> BufWriter<W> {
+    /// Creates a new `BufWriter` with a default buffer capacity of 8KB.
+    pub fn new(inner: W) -> BufWriter<W> {
+        BufWriter::with_capacity(DEFAULT_BUF_SIZE, inner)
+    }
+
+    /// Creates a new `BufWriter` with the specified buffer capacity.
+    pub fn with_capacity(cap: usize, inner: W) -> BufWriter<W> {
+        BufWriter {
+            inner: Some(inner),
+            buf: Vec::with_capacity(cap),
+        }
+    }
+
+    /// Writes as much of the buffer as possible to the underlying writer,
+    /// without flushing it.
+    fn flush_buf(&mut self) -> io::Result<()> {
+        let mut written = 0;
+        let mut ret = Ok(());
+        while written < self.buf.len() {
+            match self.inner.as_mut().unwrap().write(&self.buf[written..]) {
+                Ok(0) => {
+                    ret = Err(io::Error::new(io::ErrorKind::WriteZero,
+                                             "failed to write the buffered data"));
+                    break
+                }
+                Ok(n) => written += n,
+                Err(e) => {
+                    ret = Err(e);
+                    break
+                }
+            }
+        }
+        self.buf.drain(..written);
+        ret
+    }
+
+    /// Returns the data buffered but not written yet.
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        self.inner.as_ref().unwrap()
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    ///
+    /// It is inadvisable to directly write to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        self.inner.as_mut().unwrap()
+    }
+
+    /// Unwraps this `BufWriter`, returning the underlying writer.
+    ///
+    /// Note that any data in the internal buffer is lost, so the `BufWriter`
+    /// should be flushed first.
+    pub fn into_inner(mut self) -> W {
+        self.inner.take().unwrap()
+    }
+}
+
+impl<W: Write> Write for BufWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + buf.len() > self.buf.capacity() {
+            try!(self.flush_buf());
+        }
+        if buf.len() >= self.buf.capacity() {
+            self.get_mut().write(buf)
+        } else {
+            self.buf.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        try!(self.flush_buf());
+        self.get_mut().flush()
+    }
+}
+
+impl<W: Write + Read> Read for BufWriter<W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.get_mut().read(buf)
+    }
+}
+
+impl<W: AsyncRead + Write> AsyncRead for BufWriter<W> {
+    fn poll_read(&mut self) -> Async<()> {
+        self.get_mut().poll_read()
+    }
+}
+
+impl<W: AsyncWrite> AsyncWrite for BufWriter<W> {
+    fn poll_write(&mut self) -> Async<()> {
+        if self.buf.len() < self.buf.capacity() {
+            Async::Ready(())
+        } else {
+            self.get_mut().poll_write()
+        }
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        try_nb!(self.flush());
+        self.get_mut().shutdown()
+    }
+}
+
+impl<W: Write + fmt::Debug> fmt::Debug for BufWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BufWriter")
+         .field("writer", self.get_ref())
+         .field("buffer", &format_args!("{}/{}", self.buf.len(), self.buf.capacity()))
+         .finish()
+    }
+}
+
+impl<W: Write> Drop for BufWriter<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            // Dtors should not panic, so we ignore a failed flush.
+            let _r = self.flush_buf();
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+
+use futures::{Async, AsyncSink, Poll, Sink, StartSend, Stream};
+
+/// A sink adapter queueing up to a fixed number of items ahead of a slower
+/// sink.
+///
+/// Items sent to a `BufferedSink` are accepted right away as long as fewer
+/// than its capacity are queued, and are handed to the inner sink in order
+/// as it accepts them, whenever the `BufferedSink` is polled with
+/// `poll_complete` or `close`. Sending only hands items on once the queue is
+/// full: `start_send` then first tries to hand some of the queued items on,
+/// and gives the item back as `AsyncSink::NotReady` if the inner sink is
+/// still not accepting them, the current task being notified when it is. Producers then run ahead of the inner sink by at most the capacity
+/// while being held back smoothly past it, without keeping track of a
+/// pending item themselves.
+///
+/// Like any sink the `BufferedSink` needs to be polled with `poll_complete`
+/// for the queued items to all be sent. If the inner sink is also a stream
+/// the `BufferedSink` passes its items through.
+#[derive(Debug)]
+pub struct BufferedSink<S: Sink> {
+    inner: S,
+    queue: VecDeque<S::SinkItem>,
+    cap: usize,
+}
+
+impl<S: Sink> BufferedSink<S> {
+    /// Creates an adapter queueing up to `cap` items ahead of `inner`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is zero.
+    pub fn new(inner: S, cap: usize) -> BufferedSink<S> {
+        assert!(cap > 0, "BufferedSink capacity must be at least one");
+        BufferedSink {
+            inner: inner,
+            queue: VecDeque::with_capacity(cap),
+            cap: cap,
+        }
+    }
+
+    /// Returns the number of items queued which haven't been handed to the
+    /// inner sink yet.
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns the most items which are queued ahead of the inner sink.
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Gets a reference to the inner sink.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the inner sink.
+    ///
+    /// Items sent directly to the inner sink go ahead of the queued ones.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Consumes the `BufferedSink`, returning the inner sink and the items
+    /// which haven't been handed to it yet, in order.
+    pub fn into_inner(self) -> (S, VecDeque<S::SinkItem>) {
+        (self.inner, self.queue)
+    }
+
+    /// Hands the queued items to the inner sink until they're all sent or it
+    /// stops accepting them.
+    fn send_queued(&mut self) -> Poll<(), S::SinkError> {
+        while let Some(item) = self.queue.pop_front() {
+            if let AsyncSink::NotReady(item) = try!(self.inner.start_send(item)) {
+                self.queue.push_front(item);
+                return Ok(Async::NotReady)
+            }
+        }
+        Ok(Async::Ready(()))
+    }
+}
+
+impl<S: Sink> Sink for BufferedSink<S> {
+    type SinkItem = S::SinkItem;
+    type SinkError = S::SinkError;
+
+    fn start_send(&mut self, item: S::SinkItem) -> StartSend<S::SinkItem, S::SinkError> {
+        if self.queue.len() >= self.cap {
+            try!(self.send_queued());
+            if self.queue.len() >= self.cap {
+                return Ok(AsyncSink::NotReady(item))
+            }
+        }
+        self.queue.push_back(item);
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), S::SinkError> {
+        try_ready!(self.send_queued());
+        self.inner.poll_complete()
+    }
+
+    fn close(&mut self) -> Poll<(), S::SinkError> {
+        try_ready!(self.send_queued());
+        self.inner.close()
+    }
+}
+
+impl<S: Sink + Stream> Stream for BufferedSink<S> {
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<S::Item>, S::Error> {
+        self.inner.poll()
+    }
+}
@@ -0,0 +1,105 @@
+use std::io::{self, Read, Write};
+
+use futures::{Async, Poll};
+
+use io::{AsyncRead, AsyncWrite};
+
+/// An adapter which reads all the data of one reader and then all the data of
+/// another one.
+///
+/// Created by the [`chain`] function.
+///
+/// [`chain`]: fn.chain.html
+#[derive(Debug)]
+pub struct Chain<A, B> {
+    first: A,
+    second: B,
+    done_first: bool,
+}
+
+/// Creates an adapter which reads all the data of `first` until EOF and then
+/// all the data of `second`.
+///
+/// This is like `Read::chain`, except that writes are passed through to
+/// `second`, so chaining a reader to an `Io` object gives an `Io` object. The
+/// main use is replaying data already read off of a connection, for example
+/// while sniffing the protocol it uses, in front of the rest of it: chaining
+/// a `Cursor` of that data to the connection gives an object which can be
+/// handed to `Io::framed` as if nothing had been read.
+pub fn chain<A, B>(first: A, second: B) -> Chain<A, B>
+    where A: Read,
+          B: Read,
+{
+    Chain {
+        first: first,
+        second: second,
+        done_first: false,
+    }
+}
+
+impl<A, B> Chain<A, B> {
+    /// Gets references to the underlying readers.
+    pub fn get_ref(&self) -> (&A, &B) {
+        (&self.first, &self.second)
+    }
+
+    /// Gets mutable references to the underlying readers.
+    ///
+    /// Care should be taken to avoid modifying the internal I/O state of the
+    /// underlying readers as doing so may corrupt the internal state of this
+    /// `Chain`.
+    pub fn get_mut(&mut self) -> (&mut A, &mut B) {
+        (&mut self.first, &mut self.second)
+    }
+
+    /// Consumes the `Chain`, returning the wrapped readers.
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: Read, B: Read> Read for Chain<A, B> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.done_first {
+            match try!(self.first.read(buf)) {
+                0 if !buf.is_empty() => self.done_first = true,
+                n => return Ok(n),
+            }
+        }
+        self.second.read(buf)
+    }
+}
+
+impl<A, B: Write> Write for Chain<A, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.second.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.second.flush()
+    }
+}
+
+impl<A: Read, B: AsyncRead> AsyncRead for Chain<A, B> {
+    fn poll_read(&mut self) -> Async<()> {
+        if self.done_first {
+            self.second.poll_read()
+        } else {
+            Async::Ready(())
+        }
+    }
+}
+
+impl<A, B: AsyncWrite> AsyncWrite for Chain<A, B> {
+    fn poll_write(&mut self) -> Async<()> {
+        self.second.poll_write()
+    }
+
+    fn poll_flush(&mut self) -> Poll<(), io::Error> {
+        self.second.poll_flush()
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.second.shutdown()
+    }
+}
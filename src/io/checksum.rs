@@ -0,0 +1,267 @@
+use std::cmp;
+use std::io::{self, Read, Write};
+
+use futures::{Async, Poll};
+
+use io::{AsyncRead, AsyncWrite};
+
+/// A checksum or digest computed over a stream of bytes by `ChecksumReader`
+/// and `ChecksumWriter`.
+///
+/// `Crc32` is provided by this crate. Other algorithms, such as the HMACs and
+/// hashes of cryptography libraries, can be used by implementing this trait
+/// for them, their digest being read from the implementation once the data
+/// has been transferred.
+pub trait Checksum {
+    /// Adds `data` to the data the checksum is computed over.
+    fn update(&mut self, data: &[u8]);
+}
+
+/// The CRC-32 checksum used by Ethernet, gzip and PNG among others.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Crc32 {
+    crc: u32,
+}
+
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 != 0 {
+                0xedb88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+impl Crc32 {
+    /// Creates a checksum of no data.
+    pub fn new() -> Crc32 {
+        Crc32::default()
+    }
+
+    /// Returns the checksum of the data added so far.
+    pub fn value(&self) -> u32 {
+        self.crc
+    }
+
+    /// Resets the checksum to that of no data.
+    pub fn reset(&mut self) {
+        self.crc = 0;
+    }
+}
+
+impl Checksum for Crc32 {
+    fn update(&mut self, data: &[u8]) {
+        let mut crc = !self.crc;
+        for b in data {
+            crc = CRC32_TABLE[((crc ^ *b as u32) & 0xff) as usize] ^ (crc >> 8);
+        }
+        self.crc = !crc;
+    }
+}
+
+/// An adapter computing a checksum over the bytes read from a reader.
+///
+/// Reads are passed through to the wrapped reader, and the bytes it returns
+/// are added to the checksum, which can be looked at with `checksum` at any
+/// time. Writes pass through to the reader unchanged, so a `ChecksumReader`
+/// of an `Io` object is an `Io` object as well.
+#[derive(Debug)]
+pub struct ChecksumReader<R, C> {
+    inner: R,
+    checksum: C,
+}
+
+/// An adapter computing a checksum over the bytes written to a writer.
+///
+/// Writes are passed through to the wrapped writer, and the bytes it accepts
+/// are added to the checksum, which can be looked at with `checksum` at any
+/// time. Reads pass through to the writer unchanged, so a `ChecksumWriter` of
+/// an `Io` object is an `Io` object as well.
+#[derive(Debug)]
+pub struct ChecksumWriter<W, C> {
+    inner: W,
+    checksum: C,
+}
+
+impl<R, C: Checksum> ChecksumReader<R, C> {
+    /// Wraps `inner`, adding the bytes read from it to `checksum`.
+    pub fn new(inner: R, checksum: C) -> ChecksumReader<R, C> {
+        ChecksumReader {
+            inner: inner,
+            checksum: checksum,
+        }
+    }
+
+    /// Returns the checksum of the bytes read so far.
+    pub fn checksum(&self) -> &C {
+        &self.checksum
+    }
+
+    /// Returns a mutable reference to the checksum, for example to reset it
+    /// between messages.
+    pub fn checksum_mut(&mut self) -> &mut C {
+        &mut self.checksum
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// The bytes read directly from the underlying reader aren't added to the
+    /// checksum.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Consumes the `ChecksumReader`, returning the underlying reader and the
+    /// checksum.
+    pub fn into_inner(self) -> (R, C) {
+        (self.inner, self.checksum)
+    }
+}
+
+impl<W, C: Checksum> ChecksumWriter<W, C> {
+    /// Wraps `inner`, adding the bytes written to it to `checksum`.
+    pub fn new(inner: W, checksum: C) -> ChecksumWriter<W, C> {
+        ChecksumWriter {
+            inner: inner,
+            checksum: checksum,
+        }
+    }
+
+    /// Returns the checksum of the bytes written so far.
+    pub fn checksum(&self) -> &C {
+        &self.checksum
+    }
+
+    /// Returns a mutable reference to the checksum, for example to reset it
+    /// between messages.
+    pub fn checksum_mut(&mut self) -> &mut C {
+        &mut self.checksum
+    }
+
+    /// Gets a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying writer.
+    ///
+    /// The bytes written directly to the underlying writer aren't added to
+    /// the checksum.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Consumes the `ChecksumWriter`, returning the underlying writer and the
+    /// checksum.
+    pub fn into_inner(self) -> (W, C) {
+        (self.inner, self.checksum)
+    }
+}
+
+impl<R: Read, C: Checksum> Read for ChecksumReader<R, C> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = try!(self.inner.read(buf));
+        self.checksum.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
+impl<R: Write, C> Write for ChecksumReader<R, C> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<R: AsyncRead, C: Checksum> AsyncRead for ChecksumReader<R, C> {
+    fn poll_read(&mut self) -> Async<()> {
+        self.inner.poll_read()
+    }
+}
+
+impl<R: AsyncWrite, C> AsyncWrite for ChecksumReader<R, C> {
+    fn poll_write(&mut self) -> Async<()> {
+        self.inner.poll_write()
+    }
+
+    fn poll_flush(&mut self) -> Poll<(), io::Error> {
+        self.inner.poll_flush()
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.inner.shutdown()
+    }
+}
+
+impl<W: Write, C: Checksum> Write for ChecksumWriter<W, C> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = try!(self.inner.write(buf));
+        self.checksum.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        let n = try!(self.inner.write_vectored(bufs));
+        let mut left = n;
+        for buf in bufs {
+            let len = cmp::min(buf.len(), left);
+            self.checksum.update(&buf[..len]);
+            left -= len;
+            if left == 0 {
+                break
+            }
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Read, C> Read for ChecksumWriter<W, C> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<W: AsyncRead, C> AsyncRead for ChecksumWriter<W, C> {
+    fn poll_read(&mut self) -> Async<()> {
+        self.inner.poll_read()
+    }
+}
+
+impl<W: AsyncWrite, C: Checksum> AsyncWrite for ChecksumWriter<W, C> {
+    fn poll_write(&mut self) -> Async<()> {
+        self.inner.poll_write()
+    }
+
+    fn poll_flush(&mut self) -> Poll<(), io::Error> {
+        self.inner.poll_flush()
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.inner.shutdown()
+    }
+}
@@ -0,0 +1,191 @@
+use std::cmp;
+use std::io;
+
+use io::{Codec, EasyBuf};
+use super::hex::is_whitespace;
+
+const STANDARD: &'static [u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE: &'static [u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A codec for a stream of bytes encoded as base64 text.
+///
+/// Like `Hex` this codec doesn't split the stream into frames: the data
+/// decoded is yielded as soon as it's read, in chunks of any length, and the
+/// frames sent are the next bytes of the stream to encode. Base64 encodes
+/// each group of three bytes as four characters, so the characters of an
+/// incomplete group are left in the read buffer until the rest have been
+/// read, and the last one or two bytes sent, which don't make a full group,
+/// are held by the codec until more bytes are sent. Sending an empty frame
+/// ends the encoded data, encoding the held bytes with `=` padding.
+///
+/// The standard alphabet is used unless `set_url_safe` selects the URL and
+/// filename safe one. Whitespace, including line breaks, is skipped when
+/// decoding, as is padding in the middle of the stream, so the output of
+/// several encoders one after another can be decoded. Characters outside of
+/// the alphabet, or a stream ending in the middle of a group, are an error.
+#[derive(Debug)]
+pub struct Base64 {
+    url_safe: bool,
+    held: Vec<u8>,
+}
+
+impl Base64 {
+    /// Creates a codec for base64 with the standard alphabet.
+    pub fn new() -> Base64 {
+        Base64 {
+            url_safe: false,
+            held: Vec::with_capacity(2),
+        }
+    }
+
+    /// Sets whether the URL and filename safe alphabet, with `-` and `_` in
+    /// place of `+` and `/`, is used rather than the standard one.
+    pub fn set_url_safe(&mut self, url_safe: bool) {
+        self.url_safe = url_safe;
+    }
+
+    fn alphabet(&self) -> &'static [u8; 64] {
+        if self.url_safe {
+            URL_SAFE
+        } else {
+            STANDARD
+        }
+    }
+
+    fn char_value(&self, c: u8) -> Option<u8> {
+        match c {
+            b'A'..=b'Z' => Some(c - b'A'),
+            b'a'..=b'z' => Some(c - b'a' + 26),
+            b'0'..=b'9' => Some(c - b'0' + 52),
+            b'+' if !self.url_safe => Some(62),
+            b'/' if !self.url_safe => Some(63),
+            b'-' if self.url_safe => Some(62),
+            b'_' if self.url_safe => Some(63),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Base64 {
+    fn default() -> Base64 {
+        Base64::new()
+    }
+}
+
+impl Codec for Base64 {
+    type In = EasyBuf;
+    type Out = Vec<u8>;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<EasyBuf>> {
+        let mut out = Vec::with_capacity(buf.len() / 4 * 3);
+        // The characters up to the end of the last group decoded, along with
+        // the whitespace after it, are consumed, while those of an incomplete
+        // group are left in the buffer for the next read to complete.
+        let mut consumed = 0;
+        let mut group = [0u8; 4];
+        let mut len = 0;
+        let mut padding = 0;
+        for (i, &c) in buf.as_slice().iter().enumerate() {
+            if is_whitespace(c) {
+                if len == 0 {
+                    consumed = i + 1;
+                }
+                continue
+            }
+            if c == b'=' {
+                // At least two characters are needed for a byte.
+                if len < 2 {
+                    return Err(invalid_data("invalid base64 padding"))
+                }
+                padding += 1;
+            } else {
+                if padding > 0 {
+                    return Err(invalid_data("invalid base64 padding"))
+                }
+                group[len] = match self.char_value(c) {
+                    Some(value) => value,
+                    None => return Err(invalid_data("invalid base64 character")),
+                };
+            }
+            len += 1;
+
+            if len == 4 {
+                let bytes = [group[0] << 2 | group[1] >> 4,
+                             group[1] << 4 | group[2] >> 2,
+                             group[2] << 6 | group[3]];
+                out.extend_from_slice(&bytes[..3 - padding]);
+                group = [0; 4];
+                len = 0;
+                padding = 0;
+                consumed = i + 1;
+            }
+        }
+        buf.drain_to(consumed);
+
+        if out.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(out.into()))
+        }
+    }
+
+    fn encode(&mut self, msg: Vec<u8>, buf: &mut Vec<u8>) -> io::Result<()> {
+        let alphabet = self.alphabet();
+        if msg.is_empty() {
+            if !self.held.is_empty() {
+                encode_group(alphabet, &self.held, buf);
+                self.held.clear();
+            }
+            return Ok(())
+        }
+
+        // Complete the group held from the previous frame first.
+        let mut data = &msg[..];
+        if !self.held.is_empty() {
+            let n = cmp::min(3 - self.held.len(), data.len());
+            self.held.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if self.held.len() < 3 {
+                return Ok(())
+            }
+            encode_group(alphabet, &self.held, buf);
+            self.held.clear();
+        }
+
+        buf.reserve(data.len() / 3 * 4);
+        let mut groups = data.chunks_exact(3);
+        for group in &mut groups {
+            encode_group(alphabet, group, buf);
+        }
+        self.held.extend_from_slice(groups.remainder());
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.held.clear();
+    }
+}
+
+/// Encodes a group of one to three bytes, padding it to four characters.
+fn encode_group(alphabet: &[u8; 64], group: &[u8], buf: &mut Vec<u8>) {
+    let b = [group[0],
+             group.get(1).cloned().unwrap_or(0),
+             group.get(2).cloned().unwrap_or(0)];
+    let chars = [b[0] >> 2,
+                 (b[0] & 0x3) << 4 | b[1] >> 4,
+                 (b[1] & 0xf) << 2 | b[2] >> 6,
+                 b[2] & 0x3f];
+    for (i, &c) in chars.iter().enumerate() {
+        if i <= group.len() {
+            buf.push(alphabet[c as usize]);
+        } else {
+            buf.push(b'=');
+        }
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
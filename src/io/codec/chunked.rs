@@ -0,0 +1,218 @@
+use std::cmp;
+use std::io;
+use std::str;
+
+use io::{Codec, EasyBuf};
+
+/// The longest size or trailer line accepted, not counting its terminator.
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+
+/// The most trailer fields accepted after a body.
+const MAX_TRAILERS: usize = 128;
+
+/// A codec for the chunked transfer coding of HTTP/1.1 bodies.
+///
+/// Each chunk of a body is preceded by its size as a hexadecimal line and
+/// followed by `\r\n`, and the body ends with a chunk of size zero followed
+/// by the trailer fields, if any, and an empty line. Chunk extensions after
+/// the size are ignored.
+///
+/// The data of the chunks is yielded as `Chunk::Data` frames as soon as it's
+/// read, so a large chunk doesn't have to be buffered entirely and its data
+/// may be split across several frames. The end of a body is yielded as
+/// `Chunk::End` with its trailers, after which another body may follow, as
+/// on a persistent connection.
+#[derive(Debug)]
+pub struct Chunked {
+    state: State,
+    trailers: Vec<(String, String)>,
+}
+
+/// A frame of a chunked body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Chunk {
+    /// Data of the body.
+    ///
+    /// Encoding empty data does nothing, as it would end the body.
+    Data(EasyBuf),
+
+    /// The end of the body, with the names and values of its trailer fields.
+    End(Vec<(String, String)>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Size,
+    Data(u64),
+    DataEnd,
+    Trailers,
+}
+
+impl Chunked {
+    /// Creates a codec for chunked bodies.
+    pub fn new() -> Chunked {
+        Chunked {
+            state: State::Size,
+            trailers: Vec::new(),
+        }
+    }
+}
+
+impl Default for Chunked {
+    fn default() -> Chunked {
+        Chunked::new()
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Removes the line at the start of `buf`, returning it without its `\r\n`
+/// once it's complete.
+fn take_line(buf: &mut EasyBuf) -> io::Result<Option<EasyBuf>> {
+    let end = match buf.as_slice().iter().position(|b| *b == b'\n') {
+        Some(i) => i,
+        None if buf.len() > MAX_LINE_LENGTH + 1 => {
+            return Err(invalid_data("chunked line exceeds the maximum length"))
+        }
+        None => return Ok(None),
+    };
+    if end == 0 || buf.as_slice()[end - 1] != b'\r' {
+        return Err(invalid_data("chunked line not terminated by CRLF"))
+    }
+    let mut line = buf.drain_to(end + 1);
+    line.split_off(end - 1);
+    Ok(Some(line))
+}
+
+fn parse_size(line: &[u8]) -> io::Result<u64> {
+    let digits = match line.iter().position(|b| *b == b';') {
+        Some(i) => &line[..i],
+        None => line,
+    };
+    let digits = match str::from_utf8(digits) {
+        Ok(digits) => digits.trim_end_matches(&[' ', '\t'][..]),
+        Err(_) => return Err(invalid_data("invalid chunk size")),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(invalid_data("invalid chunk size"))
+    }
+    u64::from_str_radix(digits, 16).map_err(|_| invalid_data("chunk size overflows"))
+}
+
+fn parse_trailer(line: &[u8]) -> io::Result<(String, String)> {
+    let line = match str::from_utf8(line) {
+        Ok(line) => line,
+        Err(_) => return Err(invalid_data("trailer field is not valid UTF-8")),
+    };
+    let colon = match line.find(':') {
+        Some(i) if i > 0 => i,
+        _ => return Err(invalid_data("invalid trailer field")),
+    };
+    let value = line[colon + 1..].trim_matches(&[' ', '\t'][..]);
+    Ok((line[..colon].to_string(), value.to_string()))
+}
+
+fn check_field(s: &str) -> io::Result<()> {
+    if s.contains(&['\r', '\n'][..]) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                  "trailer field contains a line break"))
+    }
+    Ok(())
+}
+
+impl Codec for Chunked {
+    type In = Chunk;
+    type Out = Chunk;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<Chunk>> {
+        loop {
+            match self.state {
+                State::Size => {
+                    let line = match try!(take_line(buf)) {
+                        Some(line) => line,
+                        None => return Ok(None),
+                    };
+                    self.state = match try!(parse_size(line.as_slice())) {
+                        0 => State::Trailers,
+                        n => State::Data(n),
+                    };
+                }
+                State::Data(remaining) => {
+                    if buf.is_empty() {
+                        return Ok(None)
+                    }
+                    let n = cmp::min(remaining, buf.len() as u64);
+                    self.state = if n == remaining {
+                        State::DataEnd
+                    } else {
+                        State::Data(remaining - n)
+                    };
+                    return Ok(Some(Chunk::Data(buf.drain_to(n as usize))))
+                }
+                State::DataEnd => {
+                    if buf.len() < 2 {
+                        return Ok(None)
+                    }
+                    if &buf.as_slice()[..2] != b"\r\n" {
+                        return Err(invalid_data("chunk data not terminated by CRLF"))
+                    }
+                    buf.drain_to(2);
+                    self.state = State::Size;
+                }
+                State::Trailers => {
+                    let line = match try!(take_line(buf)) {
+                        Some(line) => line,
+                        None => return Ok(None),
+                    };
+                    if line.is_empty() {
+                        self.state = State::Size;
+                        let trailers = self.trailers.drain(..).collect();
+                        return Ok(Some(Chunk::End(trailers)))
+                    }
+                    if self.trailers.len() == MAX_TRAILERS {
+                        return Err(invalid_data("too many trailer fields"))
+                    }
+                    let trailer = try!(parse_trailer(line.as_slice()));
+                    self.trailers.push(trailer);
+                }
+            }
+        }
+    }
+
+    fn encode(&mut self, msg: Chunk, buf: &mut Vec<u8>) -> io::Result<()> {
+        match msg {
+            Chunk::Data(ref data) if data.is_empty() => {}
+            Chunk::Data(data) => {
+                buf.extend_from_slice(format!("{:x}\r\n", data.len()).as_bytes());
+                buf.extend_from_slice(data.as_slice());
+                buf.extend_from_slice(b"\r\n");
+            }
+            Chunk::End(trailers) => {
+                for &(ref name, ref value) in trailers.iter() {
+                    try!(check_field(name));
+                    try!(check_field(value));
+                    if name.is_empty() || name.contains(':') {
+                        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                                  "invalid trailer field name"))
+                    }
+                }
+                buf.extend_from_slice(b"0\r\n");
+                for (name, value) in trailers {
+                    buf.extend_from_slice(name.as_bytes());
+                    buf.extend_from_slice(b": ");
+                    buf.extend_from_slice(value.as_bytes());
+                    buf.extend_from_slice(b"\r\n");
+                }
+                buf.extend_from_slice(b"\r\n");
+            }
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.state = State::Size;
+        self.trailers.clear();
+    }
+}
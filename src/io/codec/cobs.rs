@@ -0,0 +1,149 @@
+use std::io;
+
+use io::{Codec, EasyBuf};
+
+/// The default maximum length of the frames decoded, 8MB.
+const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// The most data bytes of a block of the encoding.
+const MAX_BLOCK_LEN: usize = 254;
+
+/// A codec for frames encoded with Consistent Overhead Byte Stuffing.
+///
+/// COBS removes the zero bytes from a frame at the cost of one byte of
+/// overhead per 254 bytes, so each encoded frame is terminated by a zero
+/// byte. The encoding is a sequence of blocks, each starting with a code byte
+/// one greater than the number of data bytes following it; a zero byte is
+/// implied after each block with less than 254 data bytes, except the last
+/// one.
+///
+/// Empty frames are skipped when decoding, so that a peer may send a zero
+/// byte before each frame to resynchronize, and a frame which isn't
+/// terminated by the end of the stream is an error. Frames longer than the
+/// maximum frame length, 8MB by default, are refused with an error in both
+/// directions.
+#[derive(Debug)]
+pub struct Cobs {
+    max_frame_len: usize,
+    next_index: usize,
+}
+
+impl Cobs {
+    /// Creates a codec for COBS frames.
+    pub fn new() -> Cobs {
+        Cobs {
+            max_frame_len: DEFAULT_MAX_FRAME_LENGTH,
+            next_index: 0,
+        }
+    }
+
+    /// Creates a codec for COBS frames, refusing the frames longer than `max`
+    /// bytes once decoded.
+    pub fn with_max_frame_length(max: usize) -> Cobs {
+        let mut codec = Cobs::new();
+        codec.set_max_frame_length(max);
+        codec
+    }
+
+    /// Sets the maximum length of the frames, once decoded, which are decoded
+    /// or encoded.
+    pub fn set_max_frame_length(&mut self, max: usize) {
+        self.max_frame_len = max;
+    }
+
+    /// The longest encoding of a frame of the maximum length, not counting
+    /// its terminator.
+    fn max_encoded_len(&self) -> usize {
+        self.max_frame_len.saturating_add(self.max_frame_len / MAX_BLOCK_LEN + 1)
+    }
+}
+
+impl Default for Cobs {
+    fn default() -> Cobs {
+        Cobs::new()
+    }
+}
+
+impl Codec for Cobs {
+    type In = EasyBuf;
+    type Out = Vec<u8>;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<EasyBuf>> {
+        loop {
+            // Only search the bytes which weren't searched by a previous call.
+            let rest = &buf.as_slice()[self.next_index..];
+            let end = match rest.iter().position(|b| *b == 0) {
+                Some(i) => self.next_index + i,
+                None => {
+                    if buf.len() > self.max_encoded_len() {
+                        return Err(invalid_data("frame exceeds the maximum frame length"))
+                    }
+                    self.next_index = buf.len();
+                    return Ok(None)
+                }
+            };
+            self.next_index = 0;
+
+            let frame = buf.drain_to(end + 1);
+            let frame = &frame.as_slice()[..end];
+            if frame.is_empty() {
+                continue
+            }
+            if frame.len() > self.max_encoded_len() {
+                return Err(invalid_data("frame exceeds the maximum frame length"))
+            }
+            return decode_blocks(frame).map(|frame| Some(frame.into()))
+        }
+    }
+
+    fn encode(&mut self, msg: Vec<u8>, buf: &mut Vec<u8>) -> io::Result<()> {
+        if msg.len() > self.max_frame_len {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "frame exceeds the maximum frame length"))
+        }
+        buf.reserve(msg.len() + msg.len() / MAX_BLOCK_LEN + 2);
+        let mut code_idx = buf.len();
+        buf.push(0);
+        for b in msg {
+            if b != 0 {
+                buf.push(b);
+            }
+            // Close the block at a zero byte or once it's full.
+            let block_len = buf.len() - code_idx - 1;
+            if b == 0 || block_len == MAX_BLOCK_LEN {
+                buf[code_idx] = block_len as u8 + 1;
+                code_idx = buf.len();
+                buf.push(0);
+            }
+        }
+        buf[code_idx] = (buf.len() - code_idx) as u8;
+        buf.push(0);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.next_index = 0;
+    }
+}
+
+fn decode_blocks(frame: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(frame.len());
+    let mut i = 0;
+    while i < frame.len() {
+        let block_len = frame[i] as usize - 1;
+        i += 1;
+        if frame.len() - i < block_len {
+            return Err(invalid_data("truncated COBS block"))
+        }
+        out.extend_from_slice(&frame[i..i + block_len]);
+        i += block_len;
+        if block_len < MAX_BLOCK_LEN && i < frame.len() {
+            out.push(0);
+        }
+    }
+    Ok(out)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
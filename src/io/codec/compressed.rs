@@ -0,0 +1,131 @@
+use std::io;
+
+use io::{Codec, EasyBuf};
+
+/// The default maximum length of the frames once decompressed, 8MB.
+const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// A compression algorithm applied to frames by `Compressed`.
+///
+/// The methods take `&mut self` so that an implementation may keep a
+/// compression context across frames, like a deflate stream which is flushed
+/// after each frame, as long as the peer decompresses the frames in the same
+/// order. `Deflate` and `Gzip` are provided with the `flate2` feature, and
+/// bindings to other compression libraries, such as `zstd`, can be used by
+/// implementing this trait for them.
+pub trait Compression {
+    /// Compresses a whole frame.
+    fn compress(&mut self, data: &[u8]) -> io::Result<Vec<u8>>;
+
+    /// Decompresses a whole frame compressed by the peer, which mustn't be
+    /// longer than `max` bytes once decompressed.
+    ///
+    /// Frames which can't be decompressed should be refused with an error of
+    /// the `InvalidData` kind, and so should the frames which decompress to
+    /// more than `max` bytes, as soon as that's known rather than once
+    /// they've been decompressed, so that a small frame expanding to a huge
+    /// one can't exhaust the memory.
+    fn decompress(&mut self, data: &[u8], max: usize) -> io::Result<Vec<u8>>;
+
+    /// Resets the compression context kept across frames, if any, when the
+    /// codec is reset.
+    ///
+    /// The default implementation does nothing.
+    fn reset(&mut self) {}
+}
+
+/// A codec compressing the frames of another codec.
+///
+/// The frames of the inner codec, such as `LengthDelimited`, are the
+/// compressed representation of the frames of this codec: frames sent are
+/// compressed and then encoded by the inner codec, and frames decoded by the
+/// inner codec are decompressed before being yielded. The inner codec thereby
+/// keeps handling partial frames, and the frames are compressed one at a
+/// time.
+///
+/// The maximum frame length of the inner codec limits the length of the
+/// compressed frames. Frames which decompress to more than the maximum frame
+/// length of the `Compressed` codec itself, 8MB by default, are refused with
+/// an error as well.
+#[derive(Debug)]
+pub struct Compressed<C, Z> {
+    inner: C,
+    compression: Z,
+    max_frame_len: usize,
+}
+
+impl<C, Z> Compressed<C, Z>
+    where C: Codec<In = EasyBuf, Out = Vec<u8>>,
+          Z: Compression,
+{
+    /// Creates a codec compressing the frames of `inner` with `compression`.
+    pub fn new(inner: C, compression: Z) -> Compressed<C, Z> {
+        Compressed {
+            inner: inner,
+            compression: compression,
+            max_frame_len: DEFAULT_MAX_FRAME_LENGTH,
+        }
+    }
+
+    /// Sets the maximum length of the frames once decompressed.
+    pub fn set_max_frame_length(&mut self, max: usize) {
+        self.max_frame_len = max;
+    }
+
+    fn decompress(&mut self, frame: EasyBuf) -> io::Result<EasyBuf> {
+        let frame = try!(self.compression.decompress(frame.as_slice(),
+                                                     self.max_frame_len));
+        if frame.len() > self.max_frame_len {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      "frame exceeds the maximum frame length"))
+        }
+        Ok(frame.into())
+    }
+}
+
+impl<C, Z> Compressed<C, Z> {
+    /// Returns a reference to the inner codec.
+    pub fn get_ref(&self) -> &C {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner codec.
+    pub fn get_mut(&mut self) -> &mut C {
+        &mut self.inner
+    }
+
+    /// Consumes this codec, returning the inner codec and the compression.
+    pub fn into_inner(self) -> (C, Z) {
+        (self.inner, self.compression)
+    }
+}
+
+impl<C, Z> Codec for Compressed<C, Z>
+    where C: Codec<In = EasyBuf, Out = Vec<u8>>,
+          Z: Compression,
+{
+    type In = EasyBuf;
+    type Out = Vec<u8>;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<EasyBuf>> {
+        match try!(self.inner.decode(buf)) {
+            Some(frame) => self.decompress(frame).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut EasyBuf) -> io::Result<EasyBuf> {
+        let frame = try!(self.inner.decode_eof(buf));
+        self.decompress(frame)
+    }
+
+    fn encode(&mut self, msg: Vec<u8>, buf: &mut Vec<u8>) -> io::Result<()> {
+        let msg = try!(self.compression.compress(&msg));
+        self.inner.encode(msg, buf)
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.compression.reset();
+    }
+}
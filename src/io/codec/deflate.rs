@@ -0,0 +1,97 @@
+use std::io::{self, Read, Write};
+
+use flate2;
+use flate2::bufread::{DeflateDecoder, GzDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder};
+
+use io::codec::Compression;
+
+/// The deflate compression of RFC 1951, for `Compressed`.
+///
+/// Each frame is compressed on its own into a complete deflate stream, with
+/// no header, so frames can be decompressed in any order. This is only
+/// available with the `flate2` feature, the compression being done by the
+/// `flate2` crate.
+#[derive(Debug, Clone, Default)]
+pub struct Deflate {
+    _priv: (),
+}
+
+/// The gzip format of RFC 1952, for `Compressed`.
+///
+/// This is the compression of `Deflate` with the gzip header and trailer
+/// around each frame, the trailer's CRC-32 of the frame being checked when
+/// decompressing. This is only available with the `flate2` feature.
+#[derive(Debug, Clone, Default)]
+pub struct Gzip {
+    _priv: (),
+}
+
+impl Deflate {
+    /// Creates a deflate compression.
+    pub fn new() -> Deflate {
+        Deflate { _priv: () }
+    }
+}
+
+impl Gzip {
+    /// Creates a gzip compression.
+    pub fn new() -> Gzip {
+        Gzip { _priv: () }
+    }
+}
+
+impl Compression for Deflate {
+    fn compress(&mut self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        try!(encoder.write_all(data));
+        encoder.finish()
+    }
+
+    fn decompress(&mut self, data: &[u8], max: usize) -> io::Result<Vec<u8>> {
+        let mut decoder = DeflateDecoder::new(data);
+        let out = try!(read_limited(&mut decoder, max));
+        if !decoder.into_inner().is_empty() {
+            return Err(invalid_data("trailing data after the deflate stream"))
+        }
+        Ok(out)
+    }
+}
+
+impl Compression for Gzip {
+    fn compress(&mut self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        try!(encoder.write_all(data));
+        encoder.finish()
+    }
+
+    fn decompress(&mut self, data: &[u8], max: usize) -> io::Result<Vec<u8>> {
+        let mut decoder = GzDecoder::new(data);
+        let out = try!(read_limited(&mut decoder, max));
+        if !decoder.into_inner().is_empty() {
+            return Err(invalid_data("trailing data after the gzip stream"))
+        }
+        Ok(out)
+    }
+}
+
+/// Reads all of `decoder`, failing as soon as more than `max` bytes come
+/// out of it.
+fn read_limited<R: Read>(decoder: &mut R, max: usize) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let limit = (max as u64).saturating_add(1);
+    if let Err(e) = decoder.take(limit).read_to_end(&mut out) {
+        if e.kind() == io::ErrorKind::InvalidData {
+            return Err(e)
+        }
+        return Err(io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+    if out.len() > max {
+        return Err(invalid_data("frame exceeds the maximum frame length"))
+    }
+    Ok(out)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
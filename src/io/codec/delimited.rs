@@ -0,0 +1,170 @@
+use std::io;
+
+use io::{Codec, EasyBuf};
+
+/// The default maximum length of the frames decoded, 8MB.
+const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// A codec for frames terminated by a delimiter of one or more bytes.
+///
+/// Each decoded frame is the data up to the next occurrence of the
+/// delimiter, which is stripped, such as a command of a text protocol
+/// terminated by `\r\n`. Unlike `Lines`, frames are bytes rather than
+/// strings and the delimiter is arbitrary.
+///
+/// Frames may contain the delimiter if an escape byte is set with
+/// `set_escape`: the byte following an escape byte is always part of the
+/// frame, and the escape bytes are removed from the decoded frames. Encoding
+/// then escapes every escape byte of a frame and every byte equal to the
+/// first byte of the delimiter.
+///
+/// Frames longer than the maximum frame length, 8MB by default, are refused
+/// with an error in both directions. The length is that of the frame on the
+/// wire, escape bytes included and the delimiter excluded.
+#[derive(Debug)]
+pub struct Delimited {
+    delim: Vec<u8>,
+    escape: Option<u8>,
+    max_frame_len: usize,
+    next_index: usize,
+}
+
+impl Delimited {
+    /// Creates a codec for frames terminated by `delim`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `delim` is empty.
+    pub fn new<D: Into<Vec<u8>>>(delim: D) -> Delimited {
+        let delim = delim.into();
+        assert!(!delim.is_empty(), "delimiter must not be empty");
+        Delimited {
+            delim: delim,
+            escape: None,
+            max_frame_len: DEFAULT_MAX_FRAME_LENGTH,
+            next_index: 0,
+        }
+    }
+
+    /// Sets the escape byte, or disables escaping with `None`, which is the
+    /// default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `escape` is the first byte of the delimiter.
+    pub fn set_escape(&mut self, escape: Option<u8>) {
+        assert!(escape != Some(self.delim[0]),
+                "escape byte must not start the delimiter");
+        self.escape = escape;
+    }
+
+    /// Sets the maximum length of the frames on the wire which are decoded or
+    /// encoded.
+    pub fn set_max_frame_length(&mut self, max: usize) {
+        self.max_frame_len = max;
+    }
+
+    /// Searches `buf` for the delimiter, from the first byte which wasn't
+    /// searched by a previous call, returning the length of the frame it
+    /// terminates.
+    fn find(&mut self, buf: &[u8]) -> Option<usize> {
+        let mut i = self.next_index;
+        while i < buf.len() {
+            if Some(buf[i]) == self.escape {
+                if i + 1 == buf.len() {
+                    break
+                }
+                i += 2;
+                continue
+            }
+            let rest = &buf[i..];
+            if rest.starts_with(&self.delim) {
+                self.next_index = 0;
+                return Some(i)
+            }
+            // The delimiter may be completed by the next read.
+            if rest.len() < self.delim.len() && self.delim.starts_with(rest) {
+                break
+            }
+            i += 1;
+        }
+        self.next_index = i;
+        None
+    }
+}
+
+impl Codec for Delimited {
+    type In = EasyBuf;
+    type Out = Vec<u8>;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<EasyBuf>> {
+        let end = match self.find(buf.as_slice()) {
+            Some(end) => end,
+            None => {
+                if self.next_index > self.max_frame_len {
+                    return Err(invalid_data("frame exceeds the maximum frame length"))
+                }
+                return Ok(None)
+            }
+        };
+        if end > self.max_frame_len {
+            return Err(invalid_data("frame exceeds the maximum frame length"))
+        }
+
+        let mut frame = buf.drain_to(end + self.delim.len());
+        frame.split_off(end);
+        let escape = match self.escape {
+            Some(escape) if frame.as_slice().contains(&escape) => escape,
+            _ => return Ok(Some(frame)),
+        };
+        let mut out = Vec::with_capacity(frame.len());
+        let mut bytes = frame.as_slice().iter();
+        while let Some(b) = bytes.next() {
+            if *b == escape {
+                // Frames only end after the byte following an escape byte.
+                out.push(*bytes.next().unwrap());
+            } else {
+                out.push(*b);
+            }
+        }
+        Ok(Some(out.into()))
+    }
+
+    fn encode(&mut self, msg: Vec<u8>, buf: &mut Vec<u8>) -> io::Result<()> {
+        let start = buf.len();
+        match self.escape {
+            Some(escape) => {
+                buf.reserve(msg.len() + self.delim.len());
+                for b in msg {
+                    if b == escape || b == self.delim[0] {
+                        buf.push(escape);
+                    }
+                    buf.push(b);
+                }
+            }
+            None => {
+                let found = msg.windows(self.delim.len()).any(|w| w == &self.delim[..]);
+                if found {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                              "frame contains the delimiter"))
+                }
+                buf.extend_from_slice(&msg);
+            }
+        }
+        if buf.len() - start > self.max_frame_len {
+            buf.truncate(start);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "frame exceeds the maximum frame length"))
+        }
+        buf.extend_from_slice(&self.delim);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.next_index = 0;
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -0,0 +1,112 @@
+use std::io;
+
+use io::{Codec, EasyBuf};
+
+/// A codec for a stream of bytes encoded as hexadecimal text.
+///
+/// Unlike most codecs this one doesn't split the stream into frames: each
+/// frame sent is encoded as two hex digits per byte and written out as is,
+/// and the data decoded is yielded as soon as it's read, in chunks of any
+/// length. A byte whose two digits are split across reads is decoded once
+/// its second digit arrives. This is handy for text-armored protocols and
+/// for the debugging proxies and consoles which show the bytes they relay.
+///
+/// Digits of either case are decoded, and whitespace, including line breaks,
+/// is skipped. Anything else, or a stream ending after an odd number of
+/// digits, is an error.
+#[derive(Debug)]
+pub struct Hex {
+    uppercase: bool,
+}
+
+impl Hex {
+    /// Creates a codec encoding bytes as lowercase hex digits.
+    pub fn new() -> Hex {
+        Hex {
+            uppercase: false,
+        }
+    }
+
+    /// Sets whether bytes are encoded as uppercase rather than lowercase hex
+    /// digits.
+    pub fn set_uppercase(&mut self, uppercase: bool) {
+        self.uppercase = uppercase;
+    }
+}
+
+impl Default for Hex {
+    fn default() -> Hex {
+        Hex::new()
+    }
+}
+
+impl Codec for Hex {
+    type In = EasyBuf;
+    type Out = Vec<u8>;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<EasyBuf>> {
+        let mut out = Vec::with_capacity(buf.len() / 2);
+        // The bytes up to the end of the last byte decoded, along with the
+        // whitespace after it, are consumed, while a lone digit is left in
+        // the buffer for the next read to complete.
+        let mut consumed = 0;
+        let mut high = None;
+        for (i, &c) in buf.as_slice().iter().enumerate() {
+            if is_whitespace(c) {
+                if high.is_none() {
+                    consumed = i + 1;
+                }
+                continue
+            }
+            let digit = match digit_value(c) {
+                Some(digit) => digit,
+                None => return Err(invalid_data("invalid hex digit")),
+            };
+            match high.take() {
+                Some(high) => {
+                    out.push(high << 4 | digit);
+                    consumed = i + 1;
+                }
+                None => high = Some(digit),
+            }
+        }
+        buf.drain_to(consumed);
+
+        if out.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(out.into()))
+        }
+    }
+
+    fn encode(&mut self, msg: Vec<u8>, buf: &mut Vec<u8>) -> io::Result<()> {
+        let digits = if self.uppercase {
+            b"0123456789ABCDEF"
+        } else {
+            b"0123456789abcdef"
+        };
+        buf.reserve(msg.len() * 2);
+        for b in msg {
+            buf.push(digits[(b >> 4) as usize]);
+            buf.push(digits[(b & 0xf) as usize]);
+        }
+        Ok(())
+    }
+}
+
+fn digit_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+pub(crate) fn is_whitespace(c: u8) -> bool {
+    c == b' ' || c == b'\t' || c == b'\r' || c == b'\n'
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+use std::io;
+
+use io::{Codec, EasyBuf};
+
+/// A codec calling hooks on the frames of another codec.
+///
+/// The decode hook is called with every frame decoded by the inner codec
+/// before it's yielded, and the encode hook with every frame sent before the
+/// inner codec encodes it. The hooks can look at the frames, for logging or
+/// metrics, and modify them, and an error returned by a hook is returned in
+/// place of the frame. This takes the place of a wrapper codec written for
+/// each such use.
+///
+/// The hooks can be set when creating the `Framed`, or later on through
+/// `Framed::codec_mut`.
+pub struct Hooked<C: Codec> {
+    inner: C,
+    decode_hook: Option<Hook<C::In>>,
+    encode_hook: Option<Hook<C::Out>>,
+}
+
+type Hook<T> = Box<FnMut(&mut T) -> io::Result<()>>;
+
+impl<C: Codec> Hooked<C> {
+    /// Creates a codec calling hooks on the frames of `inner`, with no hooks
+    /// set yet.
+    pub fn new(inner: C) -> Hooked<C> {
+        Hooked {
+            inner: inner,
+            decode_hook: None,
+            encode_hook: None,
+        }
+    }
+
+    /// Sets the hook called with every frame decoded, replacing any previous
+    /// one.
+    pub fn set_decode_hook<F>(&mut self, hook: F)
+        where F: FnMut(&mut C::In) -> io::Result<()> + 'static,
+    {
+        self.decode_hook = Some(Box::new(hook));
+    }
+
+    /// Sets the hook called with every frame about to be encoded, replacing
+    /// any previous one.
+    pub fn set_encode_hook<F>(&mut self, hook: F)
+        where F: FnMut(&mut C::Out) -> io::Result<()> + 'static,
+    {
+        self.encode_hook = Some(Box::new(hook));
+    }
+
+    /// Removes both hooks.
+    pub fn clear_hooks(&mut self) {
+        self.decode_hook = None;
+        self.encode_hook = None;
+    }
+
+    /// Returns a reference to the inner codec.
+    pub fn get_ref(&self) -> &C {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner codec.
+    pub fn get_mut(&mut self) -> &mut C {
+        &mut self.inner
+    }
+
+    /// Consumes this codec, returning the inner codec.
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+
+    fn decoded(&mut self, mut frame: C::In) -> io::Result<C::In> {
+        if let Some(ref mut hook) = self.decode_hook {
+            try!(hook(&mut frame));
+        }
+        Ok(frame)
+    }
+}
+
+impl<C: Codec> Codec for Hooked<C> {
+    type In = C::In;
+    type Out = C::Out;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<C::In>> {
+        match try!(self.inner.decode(buf)) {
+            Some(frame) => self.decoded(frame).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut EasyBuf) -> io::Result<C::In> {
+        let frame = try!(self.inner.decode_eof(buf));
+        self.decoded(frame)
+    }
+
+    fn encode(&mut self, mut msg: C::Out, buf: &mut Vec<u8>) -> io::Result<()> {
+        if let Some(ref mut hook) = self.encode_hook {
+            try!(hook(&mut msg));
+        }
+        self.inner.encode(msg, buf)
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+impl<C: Codec + fmt::Debug> fmt::Debug for Hooked<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Hooked")
+         .field("inner", &self.inner)
+         .field("decode_hook", &self.decode_hook.is_some())
+         .field("encode_hook", &self.encode_hook.is_some())
+         .finish()
+    }
+}
@@ -0,0 +1,242 @@
+use std::io;
+
+use io::{Codec, EasyBuf};
+
+/// The default maximum length of the frames decoded, 8MB.
+const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// A codec for frames preceded by a header containing their length.
+///
+/// By default the header is a 4 byte big-endian integer holding the length of
+/// the frame following it, which is stripped from the decoded frames. The
+/// header can be configured to match most binary protocols:
+///
+/// * `set_length_field_length` sets the width of the length field, 1, 2, 4 or
+///   8 bytes.
+/// * `set_little_endian` makes the length field little-endian.
+/// * `set_length_field_offset` sets the number of bytes in the header before
+///   the length field, for example holding a message type.
+/// * `set_length_adjustment` sets a value added to the length field to get
+///   the number of bytes following it, for protocols where the length
+///   includes the header or excludes a trailer.
+/// * `set_num_skip` sets the number of bytes stripped from the start of each
+///   decoded frame, by default the whole header.
+///
+/// Encoding writes the length field, adjusted by the length adjustment,
+/// followed by the frame. The length field offset and bytes skipped only
+/// apply to decoding, so frames with a header before the length field have
+/// to be encoded by hand.
+///
+/// Frames longer than the maximum frame length, 8MB by default, are refused
+/// with an error in both directions, so a peer can't make the read buffer
+/// grow without bound by announcing a huge frame.
+#[derive(Debug)]
+pub struct LengthDelimited {
+    field_len: usize,
+    field_offset: usize,
+    adjustment: isize,
+    num_skip: Option<usize>,
+    little_endian: bool,
+    max_frame_len: usize,
+    state: State,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Head,
+    Data(usize),
+}
+
+impl LengthDelimited {
+    /// Creates a codec for frames preceded by their length as a 4 byte
+    /// big-endian integer.
+    pub fn new() -> LengthDelimited {
+        LengthDelimited {
+            field_len: 4,
+            field_offset: 0,
+            adjustment: 0,
+            num_skip: None,
+            little_endian: false,
+            max_frame_len: DEFAULT_MAX_FRAME_LENGTH,
+            state: State::Head,
+        }
+    }
+
+    /// Creates a codec for frames preceded by their length as a 4 byte
+    /// big-endian integer, refusing the frames longer than `max` bytes not
+    /// counting the header.
+    pub fn with_max_frame_length(max: usize) -> LengthDelimited {
+        let mut codec = LengthDelimited::new();
+        codec.set_max_frame_length(max);
+        codec
+    }
+
+    /// Sets the width of the length field in bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` isn't 1, 2, 4 or 8.
+    pub fn set_length_field_length(&mut self, len: usize) {
+        assert!(len == 1 || len == 2 || len == 4 || len == 8,
+                "invalid length field length: {}", len);
+        self.field_len = len;
+    }
+
+    /// Sets the number of bytes in the header before the length field.
+    pub fn set_length_field_offset(&mut self, offset: usize) {
+        self.field_offset = offset;
+    }
+
+    /// Sets whether the length field is little-endian rather than big-endian.
+    pub fn set_little_endian(&mut self, little_endian: bool) {
+        self.little_endian = little_endian;
+    }
+
+    /// Sets the value added to the length field to get the number of bytes
+    /// following it in a frame.
+    ///
+    /// For example a protocol whose length field counts the 2 bytes of the
+    /// field itself needs an adjustment of -2.
+    pub fn set_length_adjustment(&mut self, adjustment: isize) {
+        self.adjustment = adjustment;
+    }
+
+    /// Sets the number of bytes stripped from the start of each decoded
+    /// frame.
+    ///
+    /// This defaults to the length of the header, the length field offset
+    /// plus the width of the length field, so only the data following the
+    /// header is yielded. Setting it to 0 yields whole frames, header
+    /// included.
+    pub fn set_num_skip(&mut self, num_skip: usize) {
+        self.num_skip = Some(num_skip);
+    }
+
+    /// Sets the maximum length of the frames, not counting the header, which
+    /// are decoded or encoded.
+    pub fn set_max_frame_length(&mut self, max: usize) {
+        self.max_frame_len = max;
+    }
+
+    fn head_len(&self) -> usize {
+        self.field_offset + self.field_len
+    }
+
+    /// Parses the header at the start of `buf`, returning the length of the
+    /// whole frame, header included.
+    fn decode_head(&self, buf: &EasyBuf) -> io::Result<Option<usize>> {
+        let head_len = self.head_len();
+        if buf.len() < head_len {
+            return Ok(None)
+        }
+
+        let field = &buf.as_slice()[self.field_offset..head_len];
+        let mut value = 0u64;
+        if self.little_endian {
+            for b in field.iter().rev() {
+                value = (value << 8) | *b as u64;
+            }
+        } else {
+            for b in field {
+                value = (value << 8) | *b as u64;
+            }
+        }
+
+        let len = if self.adjustment < 0 {
+            value.checked_sub((-self.adjustment) as u64)
+        } else {
+            value.checked_add(self.adjustment as u64)
+        };
+        let len = match len {
+            Some(len) => len,
+            None => return Err(invalid_data("invalid frame length")),
+        };
+        if len > self.max_frame_len as u64 {
+            return Err(invalid_data("frame exceeds the maximum frame length"))
+        }
+
+        let frame_len = head_len + len as usize;
+        if self.num_skip.unwrap_or(head_len) > frame_len {
+            return Err(invalid_data("frame shorter than the bytes skipped"))
+        }
+        Ok(Some(frame_len))
+    }
+}
+
+impl Default for LengthDelimited {
+    fn default() -> LengthDelimited {
+        LengthDelimited::new()
+    }
+}
+
+impl Codec for LengthDelimited {
+    type In = EasyBuf;
+    type Out = Vec<u8>;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<EasyBuf>> {
+        let frame_len = match self.state {
+            State::Head => {
+                match try!(self.decode_head(buf)) {
+                    Some(frame_len) => frame_len,
+                    None => return Ok(None),
+                }
+            }
+            State::Data(frame_len) => frame_len,
+        };
+
+        // Remember the header we just parsed until the rest of the frame has
+        // been read.
+        if buf.len() < frame_len {
+            self.state = State::Data(frame_len);
+            return Ok(None)
+        }
+        self.state = State::Head;
+
+        let mut frame = buf.drain_to(frame_len);
+        frame.drain_to(self.num_skip.unwrap_or(self.head_len()));
+        Ok(Some(frame))
+    }
+
+    fn encode(&mut self, msg: Vec<u8>, buf: &mut Vec<u8>) -> io::Result<()> {
+        if msg.len() > self.max_frame_len {
+            return Err(invalid_input("frame exceeds the maximum frame length"))
+        }
+
+        let value = if self.adjustment < 0 {
+            (msg.len() as u64).checked_add((-self.adjustment) as u64)
+        } else {
+            (msg.len() as u64).checked_sub(self.adjustment as u64)
+        };
+        let value = match value {
+            Some(value) if self.field_len == 8 || value >> (self.field_len * 8) == 0 => {
+                value
+            }
+            _ => return Err(invalid_input("frame length doesn't fit the length field")),
+        };
+
+        let mut field = [0; 8];
+        for (i, b) in field[..self.field_len].iter_mut().enumerate() {
+            let shift = if self.little_endian {
+                i
+            } else {
+                self.field_len - 1 - i
+            };
+            *b = (value >> (shift * 8)) as u8;
+        }
+        buf.extend_from_slice(&field[..self.field_len]);
+        buf.extend_from_slice(&msg);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.state = State::Head;
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn invalid_input(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
@@ -0,0 +1,137 @@
+use std::io;
+
+use io::{Codec, EasyBuf};
+
+/// A codec for newline-delimited lines of text.
+///
+/// Each decoded frame is a line without its terminating `\n`, or `\r\n` as the
+/// carriage return preceding a newline is also stripped. A last line which
+/// isn't terminated is yielded once the end of the stream is reached. Encoded
+/// lines are terminated with `\n`, or `\r\n` once enabled with `set_crlf`.
+///
+/// Lines which aren't valid UTF-8 are an error unless `set_lossy` is enabled,
+/// in which case the invalid sequences are replaced with `U+FFFD`.
+#[derive(Debug)]
+pub struct Lines {
+    max_length: usize,
+    crlf: bool,
+    lossy: bool,
+    next_index: usize,
+}
+
+impl Lines {
+    /// Creates a codec for lines of any length.
+    pub fn new() -> Lines {
+        Lines {
+            max_length: usize::MAX,
+            crlf: false,
+            lossy: false,
+            next_index: 0,
+        }
+    }
+
+    /// Creates a codec for lines of at most `max` bytes, not counting their
+    /// terminator.
+    ///
+    /// This should be preferred over `new` when reading from untrusted peers.
+    pub fn with_max_length(max: usize) -> Lines {
+        let mut codec = Lines::new();
+        codec.set_max_length(max);
+        codec
+    }
+
+    /// Sets the maximum length of the lines, not counting their terminator,
+    /// which are decoded.
+    ///
+    /// Decoding a longer line is an error, which bounds the memory used by the
+    /// read buffer of a `Framed` on a peer never sending a newline.
+    pub fn set_max_length(&mut self, max: usize) {
+        self.max_length = max;
+    }
+
+    /// Sets whether encoded lines are terminated with `\r\n` rather than `\n`.
+    pub fn set_crlf(&mut self, crlf: bool) {
+        self.crlf = crlf;
+    }
+
+    /// Sets whether invalid UTF-8 sequences in decoded lines are replaced with
+    /// `U+FFFD` rather than being an error.
+    pub fn set_lossy(&mut self, lossy: bool) {
+        self.lossy = lossy;
+    }
+
+    fn line(&self, line: &[u8]) -> io::Result<String> {
+        if line.len() > self.max_length {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      "line exceeds the maximum length"))
+        }
+        if self.lossy {
+            return Ok(String::from_utf8_lossy(line).into_owned())
+        }
+        match String::from_utf8(line.to_vec()) {
+            Ok(s) => Ok(s),
+            Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+impl Default for Lines {
+    fn default() -> Lines {
+        Lines::new()
+    }
+}
+
+impl Codec for Lines {
+    type In = String;
+    type Out = String;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<String>> {
+        // Only search the bytes which weren't searched by a previous call.
+        let rest = &buf.as_slice()[self.next_index..];
+        let end = match rest.iter().position(|b| *b == b'\n') {
+            Some(i) => self.next_index + i,
+            None => {
+                // The last byte may be the carriage return of a line of the
+                // maximum length.
+                if buf.len() > self.max_length.saturating_add(1) {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                              "line exceeds the maximum length"))
+                }
+                self.next_index = buf.len();
+                return Ok(None)
+            }
+        };
+        self.next_index = 0;
+
+        let line = buf.drain_to(end + 1);
+        let mut line = &line.as_slice()[..end];
+        if line.ends_with(b"\r") {
+            line = &line[..line.len() - 1];
+        }
+        self.line(line).map(Some)
+    }
+
+    fn decode_eof(&mut self, buf: &mut EasyBuf) -> io::Result<String> {
+        if let Some(line) = try!(self.decode(buf)) {
+            return Ok(line)
+        }
+        self.next_index = 0;
+        let len = buf.len();
+        let line = buf.drain_to(len);
+        self.line(line.as_slice())
+    }
+
+    fn encode(&mut self, msg: String, buf: &mut Vec<u8>) -> io::Result<()> {
+        buf.extend_from_slice(msg.as_bytes());
+        if self.crlf {
+            buf.extend_from_slice(b"\r\n");
+        } else {
+            buf.push(b'\n');
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.next_index = 0;
+    }
+}
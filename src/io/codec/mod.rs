@@ -0,0 +1,52 @@
+//! Implementations of `Codec` for common ways of framing a byte stream.
+//!
+//! Each codec here can be passed to `Io::framed` to turn an I/O object into a
+//! `Stream` and `Sink` of frames, and most are configurable to cover the
+//! variations of their format found in the wild.
+//!
+//! The codecs decoding length-prefixed frames refuse frames longer than a
+//! maximum length, which can be given when creating them, as soon as their
+//! header is read. This keeps a peer from making the read buffer grow without
+//! bound by announcing a huge frame. Custom codecs can follow the same
+//! pattern, and `Framed::set_max_read_buffer` provides a limit for any codec.
+//!
+//! Codecs can also be layered: `Compressed` compresses the frames of a codec
+//! yielding `EasyBuf` frames with any algorithm implementing `Compression`,
+//! such as the `Deflate` and `Gzip` compressions provided with the `flate2`
+//! feature, and `Hooked` calls closures on the frames of any codec to inspect
+//! or modify them.
+//!
+//! `Hex` and `Base64` don't frame the stream at all but decode and encode it
+//! as text, yielding the data as it's read, for text-armored protocols or to
+//! show the bytes relayed by a debugging proxy.
+
+mod base64;
+mod chunked;
+mod cobs;
+mod compressed;
+#[cfg(feature = "flate2")]
+mod deflate;
+mod delimited;
+mod hex;
+mod hooked;
+mod length_delimited;
+mod lines;
+mod multipart;
+mod slip;
+mod tagged;
+mod varint;
+pub use self::base64::Base64;
+pub use self::chunked::{Chunk, Chunked};
+pub use self::cobs::Cobs;
+pub use self::compressed::{Compressed, Compression};
+#[cfg(feature = "flate2")]
+pub use self::deflate::{Deflate, Gzip};
+pub use self::delimited::Delimited;
+pub use self::hex::Hex;
+pub use self::hooked::Hooked;
+pub use self::length_delimited::LengthDelimited;
+pub use self::lines::Lines;
+pub use self::multipart::{Multipart, Part};
+pub use self::slip::Slip;
+pub use self::tagged::Tagged;
+pub use self::varint::VarintDelimited;
@@ -0,0 +1,235 @@
+use std::io;
+
+use io::{Codec, EasyBuf};
+
+/// The default maximum length of the parts decoded, 8MB.
+const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// The most transport padding accepted after a boundary.
+const MAX_PADDING: usize = 1024;
+
+/// A codec for bodies made of parts separated by a boundary, as with MIME
+/// multipart bodies.
+///
+/// Each part is preceded by a line with `--` and the boundary, and the body
+/// ends with a line with `--`, the boundary and `--` again. The line break
+/// before a boundary belongs to the boundary rather than to the part before
+/// it, and the preamble before the first boundary and the epilogue after
+/// the last one are discarded. The parts are yielded whole, their headers
+/// included, as `Part::Body` frames, and the end of the body as `Part::End`.
+///
+/// The boundary can be split across reads anywhere, bytes which may be the
+/// start of a boundary being kept in the buffer until the next read tells,
+/// so protocols carrying multipart bodies don't need to handle that
+/// themselves. A stream ending before the end of the body isn't yielded a
+/// `Part::End`.
+///
+/// Parts longer than the maximum frame length, 8MB by default, are refused
+/// with an error in both directions, and parts sent which contain the
+/// boundary are refused as well.
+#[derive(Debug)]
+pub struct Multipart {
+    delim: Vec<u8>,
+    max_frame_len: usize,
+    state: State,
+    next_index: usize,
+    encoding: bool,
+}
+
+/// A frame of a multipart body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Part {
+    /// A part of the body, its headers and its data.
+    Body(EasyBuf),
+
+    /// The end of the body.
+    End,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Start,
+    Preamble,
+    Boundary,
+    Body,
+    Epilogue,
+}
+
+impl Multipart {
+    /// Creates a codec for bodies whose parts are separated by `boundary`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `boundary` is empty or longer than 70 bytes, the limit of
+    /// MIME.
+    pub fn new<B: Into<Vec<u8>>>(boundary: B) -> Multipart {
+        let boundary = boundary.into();
+        assert!(!boundary.is_empty() && boundary.len() <= 70,
+                "boundary must be 1 to 70 bytes long");
+        let mut delim = b"\r\n--".to_vec();
+        delim.extend_from_slice(&boundary);
+        Multipart {
+            delim: delim,
+            max_frame_len: DEFAULT_MAX_FRAME_LENGTH,
+            state: State::Start,
+            next_index: 0,
+            encoding: false,
+        }
+    }
+
+    /// Sets the maximum length of the parts which are decoded or encoded.
+    pub fn set_max_frame_length(&mut self, max: usize) {
+        self.max_frame_len = max;
+    }
+
+    /// Searches `buf` for the delimiter, a line break followed by the
+    /// boundary, from `from`, returning its index. Otherwise the index of the
+    /// first byte which may start a delimiter completed by the next read is
+    /// returned as an error.
+    fn find(&self, buf: &[u8], from: usize) -> Result<usize, usize> {
+        for i in from..buf.len() {
+            let rest = &buf[i..];
+            if rest.starts_with(&self.delim) {
+                return Ok(i)
+            }
+            if rest.len() < self.delim.len() && self.delim.starts_with(rest) {
+                return Err(i)
+            }
+        }
+        Err(buf.len())
+    }
+}
+
+impl Codec for Multipart {
+    type In = Part;
+    type Out = Part;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<Part>> {
+        loop {
+            match self.state {
+                State::Start => {
+                    // The first boundary may start the stream without a line
+                    // break before it.
+                    let dash_boundary = &self.delim[2..];
+                    if buf.len() < dash_boundary.len() &&
+                       dash_boundary.starts_with(buf.as_slice()) {
+                        return Ok(None)
+                    }
+                    if buf.as_slice().starts_with(dash_boundary) {
+                        buf.drain_to(dash_boundary.len());
+                        self.state = State::Boundary;
+                    } else {
+                        self.state = State::Preamble;
+                    }
+                }
+                State::Preamble => {
+                    match self.find(buf.as_slice(), 0) {
+                        Ok(i) => {
+                            buf.drain_to(i + self.delim.len());
+                            self.state = State::Boundary;
+                        }
+                        Err(i) => {
+                            buf.drain_to(i);
+                            return Ok(None)
+                        }
+                    }
+                }
+                State::Boundary => {
+                    if buf.len() < 2 {
+                        return Ok(None)
+                    }
+                    if buf.as_slice().starts_with(b"--") {
+                        buf.drain_to(2);
+                        self.state = State::Epilogue;
+                        return Ok(Some(Part::End))
+                    }
+                    let end = match buf.as_slice().iter().position(|b| *b == b'\n') {
+                        Some(end) => end,
+                        None if buf.len() > MAX_PADDING + 1 => {
+                            return Err(invalid_data("invalid multipart boundary"))
+                        }
+                        None => return Ok(None),
+                    };
+                    // Only transport padding may follow the boundary.
+                    let line = &buf.as_slice()[..end];
+                    if !line.ends_with(b"\r") ||
+                       !line[..end - 1].iter().all(|b| *b == b' ' || *b == b'\t') {
+                        return Err(invalid_data("invalid multipart boundary"))
+                    }
+                    buf.drain_to(end + 1);
+                    self.state = State::Body;
+                }
+                State::Body => {
+                    let end = match self.find(buf.as_slice(), self.next_index) {
+                        Ok(end) => end,
+                        Err(i) => {
+                            self.next_index = i;
+                            if i > self.max_frame_len {
+                                return Err(invalid_data("part exceeds the maximum frame length"))
+                            }
+                            return Ok(None)
+                        }
+                    };
+                    if end > self.max_frame_len {
+                        return Err(invalid_data("part exceeds the maximum frame length"))
+                    }
+                    self.next_index = 0;
+                    self.state = State::Boundary;
+                    let part = buf.drain_to(end);
+                    buf.drain_to(self.delim.len());
+                    return Ok(Some(Part::Body(part)))
+                }
+                State::Epilogue => {
+                    let len = buf.len();
+                    buf.drain_to(len);
+                    return Ok(None)
+                }
+            }
+        }
+    }
+
+    fn encode(&mut self, msg: Part, buf: &mut Vec<u8>) -> io::Result<()> {
+        // The first boundary of a body doesn't need a line break before it.
+        let delim = if self.encoding {
+            &self.delim[..]
+        } else {
+            &self.delim[2..]
+        };
+        match msg {
+            Part::Body(part) => {
+                let part = part.as_slice();
+                if part.len() > self.max_frame_len {
+                    return Err(invalid_input("part exceeds the maximum frame length"))
+                }
+                if part.windows(self.delim.len()).any(|w| w == &self.delim[..]) {
+                    return Err(invalid_input("part contains the boundary"))
+                }
+                buf.reserve(delim.len() + 2 + part.len());
+                buf.extend_from_slice(delim);
+                buf.extend_from_slice(b"\r\n");
+                buf.extend_from_slice(part);
+                self.encoding = true;
+            }
+            Part::End => {
+                buf.extend_from_slice(delim);
+                buf.extend_from_slice(b"--\r\n");
+                self.encoding = false;
+            }
+        }
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.state = State::Start;
+        self.next_index = 0;
+        self.encoding = false;
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn invalid_input(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
@@ -0,0 +1,138 @@
+use std::io;
+
+use io::{Codec, EasyBuf};
+
+/// The default maximum length of the frames decoded, 8MB.
+const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+const END: u8 = 0xc0;
+const ESC: u8 = 0xdb;
+const ESC_END: u8 = 0xdc;
+const ESC_ESC: u8 = 0xdd;
+
+/// A codec for frames delimited as in SLIP, the Serial Line Internet
+/// Protocol of RFC 1055.
+///
+/// Each frame is terminated by an `END` byte, `0xc0`, and the `END` and `ESC`
+/// bytes within it are escaped as `ESC ESC_END` and `ESC ESC_ESC`
+/// respectively. Empty frames are skipped when decoding, so that a peer may
+/// send an `END` byte before each frame to flush out line noise, and a frame
+/// which isn't terminated by the end of the stream is an error.
+///
+/// Frames longer than the maximum frame length, 8MB by default, are refused
+/// with an error in both directions.
+#[derive(Debug)]
+pub struct Slip {
+    max_frame_len: usize,
+    next_index: usize,
+}
+
+impl Slip {
+    /// Creates a codec for SLIP frames.
+    pub fn new() -> Slip {
+        Slip {
+            max_frame_len: DEFAULT_MAX_FRAME_LENGTH,
+            next_index: 0,
+        }
+    }
+
+    /// Creates a codec for SLIP frames, refusing the frames longer than `max`
+    /// bytes once unescaped.
+    pub fn with_max_frame_length(max: usize) -> Slip {
+        let mut codec = Slip::new();
+        codec.set_max_frame_length(max);
+        codec
+    }
+
+    /// Sets the maximum length of the frames, once unescaped, which are
+    /// decoded or encoded.
+    pub fn set_max_frame_length(&mut self, max: usize) {
+        self.max_frame_len = max;
+    }
+}
+
+impl Default for Slip {
+    fn default() -> Slip {
+        Slip::new()
+    }
+}
+
+impl Codec for Slip {
+    type In = EasyBuf;
+    type Out = Vec<u8>;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<EasyBuf>> {
+        loop {
+            // Only search the bytes which weren't searched by a previous call.
+            let rest = &buf.as_slice()[self.next_index..];
+            let end = match rest.iter().position(|b| *b == END) {
+                Some(i) => self.next_index + i,
+                None => {
+                    if buf.len() > self.max_frame_len.saturating_mul(2) {
+                        return Err(invalid_data("frame exceeds the maximum frame length"))
+                    }
+                    self.next_index = buf.len();
+                    return Ok(None)
+                }
+            };
+            self.next_index = 0;
+
+            let mut frame = buf.drain_to(end + 1);
+            frame.split_off(end);
+            if frame.is_empty() {
+                continue
+            }
+            let frame = try!(unescape(frame));
+            if frame.len() > self.max_frame_len {
+                return Err(invalid_data("frame exceeds the maximum frame length"))
+            }
+            return Ok(Some(frame))
+        }
+    }
+
+    fn encode(&mut self, msg: Vec<u8>, buf: &mut Vec<u8>) -> io::Result<()> {
+        if msg.len() > self.max_frame_len {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "frame exceeds the maximum frame length"))
+        }
+        buf.reserve(msg.len() + 1);
+        for b in msg {
+            match b {
+                END => buf.extend_from_slice(&[ESC, ESC_END]),
+                ESC => buf.extend_from_slice(&[ESC, ESC_ESC]),
+                b => buf.push(b),
+            }
+        }
+        buf.push(END);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.next_index = 0;
+    }
+}
+
+/// Removes the escapes of a frame, which is only copied if it has any.
+fn unescape(frame: EasyBuf) -> io::Result<EasyBuf> {
+    if !frame.as_slice().contains(&ESC) {
+        return Ok(frame)
+    }
+    let mut out = Vec::with_capacity(frame.len());
+    let mut bytes = frame.as_slice().iter();
+    while let Some(b) = bytes.next() {
+        if *b != ESC {
+            out.push(*b);
+            continue
+        }
+        match bytes.next() {
+            Some(&ESC_END) => out.push(END),
+            Some(&ESC_ESC) => out.push(ESC),
+            _ => return Err(invalid_data("invalid SLIP escape sequence")),
+        }
+    }
+    Ok(out.into())
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -0,0 +1,188 @@
+use std::io;
+
+use io::{Codec, EasyBuf};
+
+/// The default maximum length of the frames decoded, 8MB.
+const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// A codec for frames preceded by a type tag and their length.
+///
+/// Each frame starts with a header made of a one byte tag, telling what kind
+/// of message the frame holds, followed by the length of the data of the
+/// frame. Frames are decoded to and encoded from a tag along with their data,
+/// the header being stripped. The length field is a 4 byte big-endian
+/// integer by default, which `set_length_field_length` and
+/// `set_little_endian` can change to match the protocol.
+///
+/// Frames longer than the maximum frame length, 8MB by default, are refused
+/// with an error in both directions. `set_tag_max_frame_length` gives the
+/// frames of a tag their own maximum, so for example the short control
+/// messages of a protocol can be held to a few bytes while its data messages
+/// can be large.
+#[derive(Debug)]
+pub struct Tagged {
+    field_len: usize,
+    little_endian: bool,
+    max_frame_len: usize,
+    tag_max_frame_len: Vec<Option<usize>>,
+    state: State,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Head,
+    Data(u8, usize),
+}
+
+impl Tagged {
+    /// Creates a codec for frames preceded by a tag and their length as a 4
+    /// byte big-endian integer.
+    pub fn new() -> Tagged {
+        Tagged {
+            field_len: 4,
+            little_endian: false,
+            max_frame_len: DEFAULT_MAX_FRAME_LENGTH,
+            tag_max_frame_len: vec![None; 256],
+            state: State::Head,
+        }
+    }
+
+    /// Creates a codec for frames preceded by a tag and their length as a 4
+    /// byte big-endian integer, refusing the frames longer than `max` bytes
+    /// not counting the header.
+    pub fn with_max_frame_length(max: usize) -> Tagged {
+        let mut codec = Tagged::new();
+        codec.set_max_frame_length(max);
+        codec
+    }
+
+    /// Sets the width of the length field in bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` isn't 1, 2, 4 or 8.
+    pub fn set_length_field_length(&mut self, len: usize) {
+        assert!(len == 1 || len == 2 || len == 4 || len == 8,
+                "invalid length field length: {}", len);
+        self.field_len = len;
+    }
+
+    /// Sets whether the length field is little-endian rather than big-endian.
+    pub fn set_little_endian(&mut self, little_endian: bool) {
+        self.little_endian = little_endian;
+    }
+
+    /// Sets the maximum length of the frames, not counting the header, which
+    /// are decoded or encoded, for the tags without a maximum of their own.
+    pub fn set_max_frame_length(&mut self, max: usize) {
+        self.max_frame_len = max;
+    }
+
+    /// Sets the maximum length of the frames with the tag `tag`, not counting
+    /// the header, which are decoded or encoded.
+    ///
+    /// This takes precedence over the maximum set by `set_max_frame_length`,
+    /// whether it's smaller or larger.
+    pub fn set_tag_max_frame_length(&mut self, tag: u8, max: usize) {
+        self.tag_max_frame_len[tag as usize] = Some(max);
+    }
+
+    /// Returns the maximum length of the frames with the tag `tag`.
+    pub fn tag_max_frame_length(&self, tag: u8) -> usize {
+        self.tag_max_frame_len[tag as usize].unwrap_or(self.max_frame_len)
+    }
+
+    /// Parses the header at the start of `buf`, returning the tag and the
+    /// length of the data of the frame.
+    fn decode_head(&self, buf: &EasyBuf) -> io::Result<Option<(u8, usize)>> {
+        if buf.len() < 1 + self.field_len {
+            return Ok(None)
+        }
+
+        let head = buf.as_slice();
+        let tag = head[0];
+        let field = &head[1..1 + self.field_len];
+        let mut len = 0u64;
+        if self.little_endian {
+            for b in field.iter().rev() {
+                len = (len << 8) | *b as u64;
+            }
+        } else {
+            for b in field {
+                len = (len << 8) | *b as u64;
+            }
+        }
+
+        if len > self.tag_max_frame_length(tag) as u64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      "frame exceeds the maximum frame length"))
+        }
+        Ok(Some((tag, len as usize)))
+    }
+}
+
+impl Default for Tagged {
+    fn default() -> Tagged {
+        Tagged::new()
+    }
+}
+
+impl Codec for Tagged {
+    type In = (u8, EasyBuf);
+    type Out = (u8, Vec<u8>);
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<(u8, EasyBuf)>> {
+        let (tag, len) = match self.state {
+            State::Head => {
+                match try!(self.decode_head(buf)) {
+                    Some((tag, len)) => {
+                        buf.drain_to(1 + self.field_len);
+                        (tag, len)
+                    }
+                    None => return Ok(None),
+                }
+            }
+            State::Data(tag, len) => (tag, len),
+        };
+
+        // The header has been consumed, so remember it until the rest of the
+        // frame has been read.
+        if buf.len() < len {
+            self.state = State::Data(tag, len);
+            return Ok(None)
+        }
+        self.state = State::Head;
+        Ok(Some((tag, buf.drain_to(len))))
+    }
+
+    fn encode(&mut self, msg: (u8, Vec<u8>), buf: &mut Vec<u8>) -> io::Result<()> {
+        let (tag, data) = msg;
+        if data.len() > self.tag_max_frame_length(tag) {
+            return Err(invalid_input("frame exceeds the maximum frame length"))
+        }
+        let len = data.len() as u64;
+        if self.field_len < 8 && len >> (self.field_len * 8) != 0 {
+            return Err(invalid_input("frame length doesn't fit the length field"))
+        }
+
+        buf.push(tag);
+        for i in 0..self.field_len {
+            let shift = if self.little_endian {
+                i
+            } else {
+                self.field_len - 1 - i
+            };
+            buf.push((len >> (shift * 8)) as u8);
+        }
+        buf.extend_from_slice(&data);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.state = State::Head;
+    }
+}
+
+fn invalid_input(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
@@ -0,0 +1,141 @@
+use std::io;
+
+use io::{Codec, EasyBuf};
+
+/// The default maximum length of the frames decoded, 8MB.
+const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// The longest encoding of a 64 bit varint.
+const MAX_VARINT_LEN: usize = 10;
+
+/// A codec for frames preceded by their length as a varint.
+///
+/// This is the framing of streams of protocol buffers messages written with
+/// `writeDelimitedTo` and similar functions: the length is encoded in the base
+/// 128 varint format of protocol buffers, seven bits per byte from the least
+/// significant ones with the high bit set on all but the last byte. The
+/// length is stripped from the decoded frames.
+///
+/// Frames longer than the maximum frame length, 8MB by default, are refused
+/// with an error in both directions.
+#[derive(Debug)]
+pub struct VarintDelimited {
+    max_frame_len: usize,
+    state: State,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Head,
+    Data(usize),
+}
+
+impl VarintDelimited {
+    /// Creates a codec for frames preceded by their length as a varint.
+    pub fn new() -> VarintDelimited {
+        VarintDelimited {
+            max_frame_len: DEFAULT_MAX_FRAME_LENGTH,
+            state: State::Head,
+        }
+    }
+
+    /// Creates a codec for frames preceded by their length as a varint,
+    /// refusing the frames longer than `max` bytes not counting the prefix.
+    pub fn with_max_frame_length(max: usize) -> VarintDelimited {
+        let mut codec = VarintDelimited::new();
+        codec.set_max_frame_length(max);
+        codec
+    }
+
+    /// Sets the maximum length of the frames, not counting their length
+    /// prefix, which are decoded or encoded.
+    pub fn set_max_frame_length(&mut self, max: usize) {
+        self.max_frame_len = max;
+    }
+
+    /// Parses the varint at the start of `buf`, removing it and returning its
+    /// value once it's complete.
+    fn decode_head(&self, buf: &mut EasyBuf) -> io::Result<Option<usize>> {
+        let mut value = 0u64;
+        let mut len = None;
+        for (i, b) in buf.as_slice().iter().take(MAX_VARINT_LEN).enumerate() {
+            let bits = (*b & 0x7f) as u64;
+            // The tenth byte only holds the most significant bit.
+            if i == MAX_VARINT_LEN - 1 && bits > 1 {
+                return Err(invalid_data("varint length prefix overflows"))
+            }
+            value |= bits << (i * 7);
+            if *b & 0x80 == 0 {
+                len = Some(i + 1);
+                break
+            }
+        }
+        let len = match len {
+            Some(len) => len,
+            None if buf.len() >= MAX_VARINT_LEN => {
+                return Err(invalid_data("varint length prefix is too long"))
+            }
+            None => return Ok(None),
+        };
+        if value > self.max_frame_len as u64 {
+            return Err(invalid_data("frame exceeds the maximum frame length"))
+        }
+        buf.drain_to(len);
+        Ok(Some(value as usize))
+    }
+}
+
+impl Default for VarintDelimited {
+    fn default() -> VarintDelimited {
+        VarintDelimited::new()
+    }
+}
+
+impl Codec for VarintDelimited {
+    type In = EasyBuf;
+    type Out = Vec<u8>;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<EasyBuf>> {
+        let frame_len = match self.state {
+            State::Head => {
+                match try!(self.decode_head(buf)) {
+                    Some(frame_len) => frame_len,
+                    None => return Ok(None),
+                }
+            }
+            State::Data(frame_len) => frame_len,
+        };
+
+        // The prefix has been removed from the buffer, so remember the length
+        // it held until the rest of the frame has been read.
+        if buf.len() < frame_len {
+            self.state = State::Data(frame_len);
+            return Ok(None)
+        }
+        self.state = State::Head;
+        Ok(Some(buf.drain_to(frame_len)))
+    }
+
+    fn encode(&mut self, msg: Vec<u8>, buf: &mut Vec<u8>) -> io::Result<()> {
+        if msg.len() > self.max_frame_len {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "frame exceeds the maximum frame length"))
+        }
+        let mut value = msg.len() as u64;
+        while value >= 0x80 {
+            buf.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        buf.push(value as u8);
+        buf.extend_from_slice(&msg);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.state = State::Head;
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
@@ -1,6 +1,6 @@
 use std::io::{self, Read, Write};
 
-use futures::{Future, Poll};
+use futures::{Async, Future, Poll};
 
 /// A future which will copy all data from a reader into a writer.
 ///
@@ -29,6 +29,11 @@ pub struct Copy<R, W> {
 /// On success the number of bytes is returned and the `reader` and `writer` are
 /// consumed. On error the error is returned and the I/O objects are consumed as
 /// well.
+///
+/// Copying between two sockets, as a relay does, is cheaper with
+/// [`splice_copy`] on Unix, which has the kernel move the bytes on Linux.
+///
+/// [`splice_copy`]: fn.splice_copy.html
 pub fn copy<R, W>(reader: R, writer: W) -> Copy<R, W>
     where R: Read,
           W: Write,
@@ -44,6 +49,65 @@ pub fn copy<R, W>(reader: R, writer: W) -> Copy<R, W>
     }
 }
 
+/// A future which will copy all data from a reader into a writer through a
+/// caller-provided buffer.
+///
+/// Created by the [`copy_with_buffer`] function, this future will resolve to
+/// the number of bytes copied along with the buffer, or an error if one
+/// happens.
+///
+/// [`copy_with_buffer`]: fn.copy_with_buffer.html
+pub struct CopyWithBuffer<R, W> {
+    copy: Option<Copy<R, W>>,
+}
+
+/// Creates a future which represents copying all the bytes from one object to
+/// another through the buffer `buf`.
+///
+/// This behaves like [`copy`], except that the data is copied in chunks of up
+/// to the length of `buf` rather than of a fixed size, and `buf` is returned
+/// along with the number of bytes copied once the copy is complete so it can
+/// be reused. A large buffer suits bulk transfers, while a small one keeps the
+/// memory used by many mostly idle copies, such as the connections of a
+/// proxy, low. The buffer can be taken from a [`BufferPool`], such as the one
+/// of the event loop, and handed back to it once the copy is done.
+///
+/// [`copy`]: fn.copy.html
+/// [`BufferPool`]: struct.BufferPool.html
+///
+/// # Panics
+///
+/// Panics if `buf` is empty.
+pub fn copy_with_buffer<R, W>(reader: R, writer: W, buf: Vec<u8>)
+                              -> CopyWithBuffer<R, W>
+    where R: Read,
+          W: Write,
+{
+    assert!(!buf.is_empty(), "copy buffer must not be empty");
+    let mut copy = copy(reader, writer);
+    copy.buf = buf.into_boxed_slice();
+    CopyWithBuffer {
+        copy: Some(copy),
+    }
+}
+
+impl<R, W> Future for CopyWithBuffer<R, W>
+    where R: Read,
+          W: Write,
+{
+    type Item = (u64, Vec<u8>);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(u64, Vec<u8>), io::Error> {
+        let amt = match self.copy {
+            Some(ref mut copy) => try_ready!(copy.poll()),
+            None => panic!("poll a CopyWithBuffer after it's done"),
+        };
+        let copy = self.copy.take().unwrap();
+        Ok(Async::Ready((amt, copy.buf.into_vec())))
+    }
+}
+
 impl<R, W> Future for Copy<R, W>
     where R: Read,
           W: Write,
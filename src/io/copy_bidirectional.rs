@@ -0,0 +1,123 @@
+use std::io::{self, Read};
+
+use futures::{Async, Future, Poll};
+
+use io::{AsyncRead, AsyncWrite};
+
+/// A future which copies data in both directions between two I/O objects.
+///
+/// Created by the [`copy_bidirectional`] function, this future will resolve to
+/// the number of bytes copied in each direction or an error if one happens.
+///
+/// [`copy_bidirectional`]: fn.copy_bidirectional.html
+pub struct CopyBidirectional<A, B> {
+    a: A,
+    b: B,
+    a_to_b: Transfer,
+    b_to_a: Transfer,
+}
+
+/// The state of copying data in one direction.
+struct Transfer {
+    buf: Box<[u8]>,
+    pos: usize,
+    cap: usize,
+    amt: u64,
+    read_done: bool,
+    done: bool,
+}
+
+/// Creates a future which copies all the bytes read from `a` to `b`, and all
+/// the bytes read from `b` to `a`, at the same time.
+///
+/// When one of the objects hits EOF, the data read from it is flushed to the
+/// other one and the other one is shut down with `AsyncWrite::shutdown`,
+/// which for sockets shuts down their writing half only, so its peer
+/// sees EOF in turn while data may still flow in the opposite direction. The
+/// returned future completes once both directions are done, resolving to the
+/// number of bytes copied from `a` to `b` and from `b` to `a`.
+///
+/// This is what a proxy relaying a connection needs, as combining two uses of
+/// `copy` doesn't propagate half-closes. On error the error is returned and
+/// the I/O objects are consumed as well.
+pub fn copy_bidirectional<A, B>(a: A, b: B) -> CopyBidirectional<A, B>
+    where A: AsyncRead + AsyncWrite,
+          B: AsyncRead + AsyncWrite,
+{
+    CopyBidirectional {
+        a: a,
+        b: b,
+        a_to_b: Transfer::new(),
+        b_to_a: Transfer::new(),
+    }
+}
+
+impl<A, B> Future for CopyBidirectional<A, B>
+    where A: AsyncRead + AsyncWrite,
+          B: AsyncRead + AsyncWrite,
+{
+    type Item = (u64, u64);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(u64, u64), io::Error> {
+        let a_to_b = try!(self.a_to_b.poll(&mut self.a, &mut self.b));
+        let b_to_a = try!(self.b_to_a.poll(&mut self.b, &mut self.a));
+        match (a_to_b, b_to_a) {
+            (Async::Ready(a_to_b), Async::Ready(b_to_a)) => {
+                Ok(Async::Ready((a_to_b, b_to_a)))
+            }
+            _ => Ok(Async::NotReady),
+        }
+    }
+}
+
+impl Transfer {
+    fn new() -> Transfer {
+        Transfer {
+            buf: Box::new([0; 2048]),
+            pos: 0,
+            cap: 0,
+            amt: 0,
+            read_done: false,
+            done: false,
+        }
+    }
+
+    fn poll<R, W>(&mut self, reader: &mut R, writer: &mut W) -> Poll<u64, io::Error>
+        where R: Read,
+              W: AsyncWrite,
+    {
+        while !self.done {
+            // If our buffer is empty, then we need to read some data to
+            // continue.
+            if self.pos == self.cap && !self.read_done {
+                let n = try_nb!(reader.read(&mut self.buf));
+                if n == 0 {
+                    self.read_done = true;
+                } else {
+                    self.pos = 0;
+                    self.cap = n;
+                }
+            }
+
+            // If our buffer has some data, let's write it out!
+            while self.pos < self.cap {
+                let i = try_nb!(writer.write(&self.buf[self.pos..self.cap]));
+                if i == 0 {
+                    return Err(io::Error::new(io::ErrorKind::WriteZero,
+                                              "write zero byte into writer"))
+                }
+                self.pos += i;
+                self.amt += i as u64;
+            }
+
+            // Once all the data has been written and we've seen EOF, flush
+            // the data and pass the EOF on.
+            if self.pos == self.cap && self.read_done {
+                try_ready!(writer.shutdown());
+                self.done = true;
+            }
+        }
+        Ok(Async::Ready(self.amt))
+    }
+}
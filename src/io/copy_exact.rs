@@ -0,0 +1,104 @@
+use std::cmp;
+use std::io::{self, Read, Write};
+use std::mem;
+
+use futures::{Future, Poll};
+
+/// A future which will copy a fixed number of bytes from a reader into a
+/// writer.
+///
+/// Created by the [`copy_exact`] function.
+///
+/// [`copy_exact`]: fn.copy_exact.html
+pub struct CopyExact<R, W> {
+    state: State<R, W>,
+    remaining: u64,
+    pos: usize,
+    cap: usize,
+    buf: Box<[u8]>,
+}
+
+enum State<R, W> {
+    Copying {
+        reader: R,
+        writer: W,
+    },
+    Empty,
+}
+
+/// Creates a future which will copy exactly `n` bytes from `reader` into
+/// `writer`, returning an error if EOF is hit sooner.
+///
+/// No more than `n` bytes are read from `reader`, so it can be used again
+/// afterwards, which is what relaying a body with a known length, such as
+/// one given by a `Content-Length` header, needs. The future completes once
+/// all the bytes have been written to and flushed from `writer`, resolving
+/// to both I/O objects.
+///
+/// In the case of an error both objects are discarded, with the error
+/// yielded.
+pub fn copy_exact<R, W>(reader: R, writer: W, n: u64) -> CopyExact<R, W>
+    where R: Read,
+          W: Write,
+{
+    CopyExact {
+        state: State::Copying {
+            reader: reader,
+            writer: writer,
+        },
+        remaining: n,
+        pos: 0,
+        cap: 0,
+        buf: Box::new([0; 2048]),
+    }
+}
+
+impl<R, W> Future for CopyExact<R, W>
+    where R: Read,
+          W: Write,
+{
+    type Item = (R, W);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(R, W), io::Error> {
+        match self.state {
+            State::Copying { ref mut reader, ref mut writer } => {
+                loop {
+                    // Refill the buffer once it's been written out, without
+                    // reading past the bytes to copy.
+                    if self.pos == self.cap && self.remaining > 0 {
+                        let max = cmp::min(self.buf.len() as u64, self.remaining);
+                        let n = try_nb!(reader.read(&mut self.buf[..max as usize]));
+                        if n == 0 {
+                            return Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                                                      "early eof"))
+                        }
+                        self.remaining -= n as u64;
+                        self.pos = 0;
+                        self.cap = n;
+                    }
+
+                    while self.pos < self.cap {
+                        let i = try_nb!(writer.write(&self.buf[self.pos..self.cap]));
+                        if i == 0 {
+                            return Err(io::Error::new(io::ErrorKind::WriteZero,
+                                                      "zero-length write"))
+                        }
+                        self.pos += i;
+                    }
+
+                    if self.remaining == 0 {
+                        try_nb!(writer.flush());
+                        break
+                    }
+                }
+            }
+            State::Empty => panic!("poll a CopyExact after it's done"),
+        }
+
+        match mem::replace(&mut self.state, State::Empty) {
+            State::Copying { reader, writer } => Ok((reader, writer).into()),
+            State::Empty => panic!(),
+        }
+    }
+}
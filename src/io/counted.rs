@@ -0,0 +1,163 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use futures::{Async, Poll};
+
+use io::{AsyncRead, AsyncWrite};
+
+/// An adapter counting the bytes read from and written to an I/O object.
+///
+/// All reads and writes are passed through to the wrapped object unchanged,
+/// so a `Counted` of an `Io` object is an `Io` object as well, and the bytes
+/// they transfer successfully are added to its `Counters`. The counters can
+/// be cloned out of the `Counted` to be looked at from elsewhere, such as
+/// from a task reporting metrics, while the object is in use.
+pub struct Counted<T> {
+    inner: T,
+    counters: Counters,
+}
+
+/// Shared counts of the bytes read and written by `Counted` objects.
+///
+/// Clones of a `Counters` share the same counts, so giving the same counters
+/// to several `Counted` objects sums their transfers, for example those of
+/// all the connections of a server.
+#[derive(Clone, Default)]
+pub struct Counters {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    read: AtomicU64,
+    written: AtomicU64,
+}
+
+impl Counters {
+    /// Creates new counters starting from zero.
+    pub fn new() -> Counters {
+        Counters::default()
+    }
+
+    /// Returns the number of bytes read so far.
+    pub fn read(&self) -> u64 {
+        self.inner.read.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of bytes written so far.
+    pub fn written(&self) -> u64 {
+        self.inner.written.load(Ordering::Relaxed)
+    }
+
+    fn add_read(&self, n: usize) {
+        self.inner.read.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    fn add_written(&self, n: usize) {
+        self.inner.written.fetch_add(n as u64, Ordering::Relaxed);
+    }
+}
+
+impl fmt::Debug for Counters {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Counters")
+         .field("read", &self.read())
+         .field("written", &self.written())
+         .finish()
+    }
+}
+
+impl<T> Counted<T> {
+    /// Wraps `inner`, counting its transfers with new counters.
+    pub fn new(inner: T) -> Counted<T> {
+        Counted::with_counters(inner, &Counters::new())
+    }
+
+    /// Wraps `inner`, adding its transfers to `counters`.
+    pub fn with_counters(inner: T, counters: &Counters) -> Counted<T> {
+        Counted {
+            inner: inner,
+            counters: counters.clone(),
+        }
+    }
+
+    /// Returns the counters the transfers of this object are added to.
+    pub fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    /// Gets a reference to the underlying I/O object.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying I/O object.
+    ///
+    /// Reads and writes made directly on the underlying object aren't
+    /// counted.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes the `Counted`, returning the underlying I/O object.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Read> Read for Counted<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = try!(self.inner.read(buf));
+        self.counters.add_read(n);
+        Ok(n)
+    }
+}
+
+impl<T: Write> Write for Counted<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = try!(self.inner.write(buf));
+        self.counters.add_written(n);
+        Ok(n)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        let n = try!(self.inner.write_vectored(bufs));
+        self.counters.add_written(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for Counted<T> {
+    fn poll_read(&mut self) -> Async<()> {
+        self.inner.poll_read()
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for Counted<T> {
+    fn poll_write(&mut self) -> Async<()> {
+        self.inner.poll_write()
+    }
+
+    fn poll_flush(&mut self) -> Poll<(), io::Error> {
+        self.inner.poll_flush()
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.inner.shutdown()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Counted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Counted")
+         .field("inner", &self.inner)
+         .field("counters", &self.counters)
+         .finish()
+    }
+}
@@ -0,0 +1,1371 @@
+use std::cmp;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use futures::{Async, AsyncSink, Poll, Stream, Sink, StartSend};
+
+use io::{AsyncWrite, BufferPool, Io};
+
+const INITIAL_CAPACITY: usize = 8 * 1024;
+const BACKPRESSURE_BOUNDARY: usize = INITIAL_CAPACITY;
+/// The most buffers of encoded frames written by a single vectored write.
+const MAX_WRITE_BUFS: usize = 64;
+/// The most emptied buffers of encoded frames kept for the next frames.
+const MAX_SPARE_BUFS: usize = 4;
+
+/// A reference counted buffer of bytes.
+///
+/// An `EasyBuf` is a representation of a byte buffer where sub-slices of it
+/// can be handed out efficiently, each with a `'static` lifetime which keeps
+/// the data alive. The buffer also supports mutation but may require bytes to
+/// be copied to complete the operation.
+///
+/// This is the type of the read buffer of `Framed`, which hands it to
+/// `Codec::decode` so complete frames can be split off of it without copying.
+#[derive(Clone, Eq)]
+pub struct EasyBuf {
+    buf: Arc<Vec<u8>>,
+    start: usize,
+    end: usize,
+}
+
+/// An RAII object returned from `get_mut` which provides mutable access to the
+/// underlying `Vec<u8>`.
+pub struct EasyBufMut<'a> {
+    buf: &'a mut Vec<u8>,
+    end: &'a mut usize,
+}
+
+impl EasyBuf {
+    /// Creates a new empty buffer.
+    pub fn new() -> EasyBuf {
+        EasyBuf::with_capacity(INITIAL_CAPACITY)
+    }
+
+    /// Creates a new empty buffer whose underlying storage has been allocated
+    /// with the `cap` bytes of capacity.
+    pub fn with_capacity(cap: usize) -> EasyBuf {
+        EasyBuf {
+            buf: Arc::new(Vec::with_capacity(cap)),
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Returns the number of bytes contained in this `EasyBuf`.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns whether this `EasyBuf` contains no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the inner contents of this `EasyBuf` as a slice.
+    pub fn as_slice(&self) -> &[u8] {
+        self.as_ref()
+    }
+
+    /// Splits the buffer into two at the given index.
+    ///
+    /// Afterwards `self` contains elements `[0, at)`, and the returned
+    /// `EasyBuf` contains elements `[at, len)`.
+    ///
+    /// This is an O(1) operation that just increases the reference count and
+    /// sets a few indexes. Both halves keep the underlying buffer alive, so
+    /// either may be dropped or outlive the other.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`
+    pub fn split_off(&mut self, at: usize) -> EasyBuf {
+        assert!(at <= self.len(), "split_off past the end of the buffer");
+        let mut other = EasyBuf { buf: self.buf.clone(), ..*self };
+        let idx = self.start + at;
+        other.start = idx;
+        self.end = idx;
+        other
+    }
+
+    /// Splits the buffer into two at the given index.
+    ///
+    /// Afterwards `self` contains elements `[at, len)`, and the returned
+    /// `EasyBuf` contains elements `[0, at)`.
+    ///
+    /// This is an O(1) operation that just increases the reference count and
+    /// sets a few indexes. Both halves keep the underlying buffer alive, so
+    /// either may be dropped or outlive the other.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `at > len`
+    pub fn drain_to(&mut self, at: usize) -> EasyBuf {
+        assert!(at <= self.len(), "drain_to past the end of the buffer");
+        let mut other = EasyBuf { buf: self.buf.clone(), ..*self };
+        let idx = self.start + at;
+        other.end = idx;
+        self.start = idx;
+        other
+    }
+
+    /// Returns a new `EasyBuf` containing elements `[begin, end)` of this
+    /// one, which is left untouched.
+    ///
+    /// This is an O(1) operation that just increases the reference count and
+    /// sets a few indexes. The returned `EasyBuf` keeps the underlying buffer
+    /// alive on its own, so sub-slices of a frame can be handed to other tasks
+    /// without copying them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `begin > end` or `end > len`
+    pub fn slice(&self, begin: usize, end: usize) -> EasyBuf {
+        assert!(begin <= end, "slice begins after it ends");
+        assert!(end <= self.len(), "slice past the end of the buffer");
+        EasyBuf {
+            buf: self.buf.clone(),
+            start: self.start + begin,
+            end: self.start + end,
+        }
+    }
+
+    /// Converts this `EasyBuf` into a `Vec<u8>` of its contents.
+    ///
+    /// If this `EasyBuf` is the only instance pointing at the underlying
+    /// buffer of bytes then that allocation is returned, with the contents
+    /// moved to its front if bytes were drained from this `EasyBuf`.
+    /// Otherwise the contents are copied into a fresh `Vec<u8>`.
+    pub fn into_vec(self) -> Vec<u8> {
+        let EasyBuf { buf, start, end } = self;
+        match Arc::try_unwrap(buf) {
+            Ok(mut v) => {
+                v.truncate(end);
+                v.drain(..start);
+                v
+            }
+            Err(buf) => buf[start..end].to_vec(),
+        }
+    }
+
+    /// Returns the number of bytes this `EasyBuf` can hold without
+    /// reallocating.
+    ///
+    /// Note that if other handles point at the same underlying buffer then
+    /// any mutation will still copy the contents of this `EasyBuf` first.
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity() - self.start
+    }
+
+    /// Reserves capacity for at least `additional` more bytes to be appended
+    /// to this `EasyBuf`.
+    ///
+    /// If other handles point at the underlying buffer then the contents of
+    /// this `EasyBuf` are copied into a fresh allocation which is large
+    /// enough, so only a single copy is made.
+    pub fn reserve(&mut self, additional: usize) {
+        self.make_unique(additional);
+        Arc::get_mut(&mut self.buf).unwrap().reserve(additional);
+    }
+
+    /// Appends all the bytes of `data` to this `EasyBuf`.
+    ///
+    /// Unlike `get_mut`, this doesn't move the contents of the buffer to the
+    /// front of the underlying storage if it's the only handle to it, so
+    /// appending to a buffer which frames have been drained from is cheap.
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        self.make_unique(data.len());
+        let buf = Arc::get_mut(&mut self.buf).unwrap();
+        buf.extend_from_slice(data);
+        self.end = buf.len();
+    }
+
+    /// Returns the inner contents of this `EasyBuf` as a mutable slice.
+    ///
+    /// This copies the contents of this `EasyBuf` if other handles point at
+    /// the underlying buffer, but it never moves them otherwise.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        self.make_unique(0);
+        let (start, end) = (self.start, self.end);
+        &mut Arc::get_mut(&mut self.buf).unwrap()[start..end]
+    }
+
+    /// Returns a mutable reference to the underlying growable buffer of bytes.
+    ///
+    /// If this `EasyBuf` is the only instance pointing at the underlying
+    /// buffer of bytes, a direct mutable reference will be returned.
+    /// Otherwise the contents of this `EasyBuf` will be copied into a fresh
+    /// `Vec<u8>` allocation, and that allocation will be returned.
+    ///
+    /// This operation **is not O(1)** as it may clone the entire contents of
+    /// this buffer.
+    ///
+    /// The returned `EasyBufMut` implements `Deref` and `DerefMut` to
+    /// `Vec<u8>` so the byte buffer can be manipulated using the standard
+    /// `Vec<u8>` methods. To append bytes or to modify them in place,
+    /// `extend_from_slice` and `as_mut_slice` are cheaper.
+    pub fn get_mut<'a>(&'a mut self) -> EasyBufMut<'a> {
+        self.make_unique(0);
+
+        // With the buffer to ourselves the bytes before our window can be
+        // dropped.
+        let buf = Arc::get_mut(&mut self.buf).unwrap();
+        buf.drain(..self.start);
+        self.start = 0;
+        EasyBufMut { buf: buf, end: &mut self.end }
+    }
+
+    /// Returns an iterator draining successive pieces off the front of this
+    /// buffer, where `split` tells how long each piece is.
+    ///
+    /// `split` is called with the bytes remaining in the buffer and returns
+    /// the length of the next piece, which is then split off with `drain_to`
+    /// and yielded, or `None` if there isn't a complete piece yet. Iteration
+    /// stops at the first `None`, leaving the rest of the bytes in the
+    /// buffer, so simple tokenizers, for example splitting at each delimiter,
+    /// can be written without a full `Codec`:
+    ///
+    /// ```
+    /// use tokio_core::io::EasyBuf;
+    ///
+    /// let mut buf = EasyBuf::from(b"one two thr".to_vec());
+    /// let words = buf.drain_with(|b| b.iter().position(|&b| b == b' ').map(|i| i + 1))
+    ///                .collect::<Vec<_>>();
+    /// assert_eq!(words, [EasyBuf::from(b"one ".to_vec()),
+    ///                    EasyBuf::from(b"two ".to_vec())]);
+    /// assert_eq!(buf.as_slice(), b"thr");
+    /// ```
+    ///
+    /// Like `drain_to` this doesn't copy any bytes. Pieces which aren't
+    /// yielded because the iterator is dropped early stay in the buffer.
+    ///
+    /// # Panics
+    ///
+    /// The iterator panics if `split` returns zero, which would never consume
+    /// anything, or more than the number of bytes remaining.
+    pub fn drain_with<'a, F>(&'a mut self, split: F) -> EasyBufDrain<'a, F>
+        where F: FnMut(&[u8]) -> Option<usize>,
+    {
+        EasyBufDrain {
+            buf: self,
+            split: split,
+        }
+    }
+
+    /// Makes this `EasyBuf` the only instance pointing at its underlying
+    /// buffer, which then ends where this `EasyBuf` does.
+    ///
+    /// If other handles share the buffer then our window of it is copied into
+    /// a fresh allocation with room for `additional` more bytes.
+    fn make_unique(&mut self, additional: usize) {
+        if Arc::get_mut(&mut self.buf).is_none() {
+            let cap = cmp::max(INITIAL_CAPACITY, self.len() + additional);
+            let mut v = Vec::with_capacity(cap);
+            v.extend_from_slice(self.as_ref());
+            self.buf = Arc::new(v);
+            self.start = 0;
+            self.end = self.buf.len();
+        }
+        Arc::get_mut(&mut self.buf).unwrap().truncate(self.end);
+    }
+
+    /// Returns the underlying allocation if this `EasyBuf` is the only
+    /// instance pointing at it.
+    fn into_unique_vec(self) -> Option<Vec<u8>> {
+        Arc::try_unwrap(self.buf).ok()
+    }
+}
+
+/// An iterator draining pieces off the front of an `EasyBuf`.
+///
+/// Created by `EasyBuf::drain_with`.
+pub struct EasyBufDrain<'a, F> {
+    buf: &'a mut EasyBuf,
+    split: F,
+}
+
+impl<'a, F> Iterator for EasyBufDrain<'a, F>
+    where F: FnMut(&[u8]) -> Option<usize>,
+{
+    type Item = EasyBuf;
+
+    fn next(&mut self) -> Option<EasyBuf> {
+        let n = match (self.split)(self.buf.as_slice()) {
+            Some(n) => n,
+            None => return None,
+        };
+        assert!(n > 0, "drain_with split off an empty piece");
+        Some(self.buf.drain_to(n))
+    }
+}
+
+impl<'a, F> fmt::Debug for EasyBufDrain<'a, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EasyBufDrain")
+         .field("buf", &self.buf)
+         .finish()
+    }
+}
+
+impl AsRef<[u8]> for EasyBuf {
+    fn as_ref(&self) -> &[u8] {
+        &self.buf[self.start..self.end]
+    }
+}
+
+impl AsMut<[u8]> for EasyBuf {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+impl Default for EasyBuf {
+    fn default() -> EasyBuf {
+        EasyBuf::new()
+    }
+}
+
+impl PartialEq for EasyBuf {
+    fn eq(&self, other: &EasyBuf) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl From<Vec<u8>> for EasyBuf {
+    fn from(vec: Vec<u8>) -> EasyBuf {
+        let end = vec.len();
+        EasyBuf {
+            buf: Arc::new(vec),
+            start: 0,
+            end: end,
+        }
+    }
+}
+
+impl From<EasyBuf> for Vec<u8> {
+    fn from(buf: EasyBuf) -> Vec<u8> {
+        buf.into_vec()
+    }
+}
+
+impl fmt::Debug for EasyBuf {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl<'a> Deref for EasyBufMut<'a> {
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        self.buf
+    }
+}
+
+impl<'a> DerefMut for EasyBufMut<'a> {
+    fn deref_mut(&mut self) -> &mut Vec<u8> {
+        self.buf
+    }
+}
+
+impl<'a> Drop for EasyBufMut<'a> {
+    fn drop(&mut self) {
+        *self.end = self.buf.len();
+    }
+}
+
+/// Encoding and decoding of frames via buffers.
+///
+/// This trait is used when constructing an instance of `Framed` and provides
+/// the `In` and `Out` types which are decoded and encoded from the I/O object,
+/// respectively.
+///
+/// The trait itself is implemented on a type that can track state for decoding
+/// or encoding, which is particularly useful for streaming parsers. In many
+/// cases, though, this type will simply be a unit struct (e.g. `struct
+/// HttpCodec`).
+pub trait Codec {
+    /// The type of decoded frames.
+    type In;
+
+    /// The type of frames to be encoded.
+    type Out;
+
+    /// Attempts to decode a frame from the provided buffer of bytes.
+    ///
+    /// This method is called by `Framed` whenever bytes are ready to be parsed.
+    /// The provided buffer of bytes is what's been read so far, and this
+    /// instance of `Codec` can determine whether an entire frame is in the
+    /// buffer and is ready to be returned.
+    ///
+    /// If an entire frame is available, then this instance will remove those
+    /// bytes from the buffer provided, typically with `EasyBuf::drain_to`, and
+    /// return them as a decoded frame. Note that removing bytes from the
+    /// provided buffer doesn't always necessarily copy the bytes, so this
+    /// should be an efficient operation in most circumstances.
+    ///
+    /// If the bytes look valid, but a frame isn't fully available yet, then
+    /// `Ok(None)` is returned. This indicates to `Framed` that it needs to
+    /// read some more bytes before calling this method again.
+    ///
+    /// Finally, if the bytes in the buffer are malformed then an error is
+    /// returned indicating why. This informs `Framed` that the stream is now
+    /// corrupt and should be terminated, unless the error is a `Resync`
+    /// giving the number of bytes to skip to get to the next frame.
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<Self::In>>;
+
+    /// A default method available to be called when there are no more bytes
+    /// available to be read from the underlying I/O.
+    ///
+    /// This method defaults to calling `decode` and returns an error if
+    /// `Ok(None)` is returned. Typically this doesn't need to be implemented
+    /// unless the framing protocol differs near the end of the stream.
+    fn decode_eof(&mut self, buf: &mut EasyBuf) -> io::Result<Self::In> {
+        match try!(self.decode(buf)) {
+            Some(frame) => Ok(frame),
+            None => Err(io::Error::new(io::ErrorKind::UnexpectedEof,
+                                       "bytes remaining on stream")),
+        }
+    }
+
+    /// Encodes a frame into the buffer provided.
+    ///
+    /// This method will encode `msg` into the byte buffer provided by `buf`.
+    /// The `buf` provided is an internal buffer of the `Framed` instance and
+    /// will be written out when possible.
+    fn encode(&mut self, msg: Self::Out, buf: &mut Vec<u8>) -> io::Result<()>;
+
+    /// Resets the state this codec keeps between calls, such as that of a
+    /// partially decoded frame, to that of a new codec, keeping its
+    /// configuration.
+    ///
+    /// This is called by `Framed::reset` when the framing of a connection
+    /// restarts, for example after a STARTTLS upgrade or once a new version
+    /// of the protocol has been negotiated. The default implementation does
+    /// nothing, which suits codecs keeping no state.
+    fn reset(&mut self) {}
+}
+
+/// A decoding error after which the stream of frames can be resumed.
+///
+/// A codec which can find the next frame after a malformed one, for example
+/// by scanning for a synchronization marker, returns this error from
+/// `Codec::decode` or `Codec::decode_eof`, converted into an `io::Error` of
+/// the `InvalidData` kind, with the number of bytes to skip from the start of
+/// the buffer it was given. The framed adapters then yield the error and
+/// discard those bytes, including ones which haven't been read yet, so that
+/// polling them again resumes decoding at the next frame rather than failing
+/// again.
+///
+/// The errors can be handled without ending the stream of frames with
+/// `Stream::then`, as most other combinators stop at the first error.
+#[derive(Debug)]
+pub struct Resync {
+    skip: usize,
+    error: Box<Error + Send + Sync>,
+}
+
+impl Resync {
+    /// Creates an error skipping `skip` bytes, with a description of what was
+    /// wrong with them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `skip` is 0, as decoding would fail again.
+    pub fn new<E>(skip: usize, error: E) -> Resync
+        where E: Into<Box<Error + Send + Sync>>,
+    {
+        assert!(skip > 0, "a resync error must skip some bytes");
+        Resync {
+            skip: skip,
+            error: error.into(),
+        }
+    }
+
+    /// Returns the number of bytes skipped.
+    pub fn skip(&self) -> usize {
+        self.skip
+    }
+
+    /// Returns the description of what was wrong with the bytes skipped.
+    pub fn get_ref(&self) -> &(Error + Send + Sync + 'static) {
+        &*self.error
+    }
+}
+
+impl fmt::Display for Resync {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (skipping {} bytes)", self.error, self.skip)
+    }
+}
+
+impl Error for Resync {
+    fn description(&self) -> &str {
+        "malformed frame skipped"
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        Some(&*self.error)
+    }
+}
+
+impl From<Resync> for io::Error {
+    fn from(err: Resync) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+/// A unified `Stream` and `Sink` interface to an underlying `Io` object, using
+/// the `Codec` trait to encode and decode frames.
+///
+/// You can acquire a `Framed` instance by using the `Io::framed` adapter.
+pub struct Framed<T, C> {
+    upstream: T,
+    codec: C,
+    rd: ReadBuf,
+    wr: WriteBuf,
+}
+
+/// The parts of a `Framed`: its underlying I/O object and the contents of its
+/// buffers.
+///
+/// This is returned by `Framed::into_parts` and can be turned back into a
+/// `Framed` by `Framed::from_parts`, possibly with a different codec. It's
+/// used to switch protocols on a connection, for example after an HTTP
+/// `Upgrade` or `CONNECT`, where the peer may already have sent data for the
+/// new protocol which was read into the buffer of the old one.
+#[derive(Debug)]
+pub struct FramedParts<T> {
+    /// The underlying I/O object.
+    pub inner: T,
+    /// The data read from `inner` but not decoded yet.
+    pub read_buf: EasyBuf,
+    /// The encoded frames not written to `inner` yet.
+    pub write_buf: Vec<u8>,
+}
+
+/// A `Stream` of the frames decoded from an underlying reader with the
+/// `Codec` trait.
+///
+/// This is the reading half of `Framed`, for I/O objects which are only
+/// readable, such as the standard output of a child process or the
+/// `ReadHalf` of a split `Io` object. Only `Codec::decode` and
+/// `Codec::decode_eof` are used.
+pub struct FramedRead<T, C> {
+    upstream: T,
+    codec: C,
+    rd: ReadBuf,
+}
+
+/// A `Sink` of frames encoded to an underlying writer with the `Codec` trait.
+///
+/// This is the writing half of `Framed`, for I/O objects which are only
+/// writable, such as the standard input of a child process or the `WriteHalf`
+/// of a split `Io` object. Only `Codec::encode` is used.
+pub struct FramedWrite<T, C> {
+    upstream: T,
+    codec: C,
+    wr: WriteBuf,
+}
+
+/// The read buffer of the framed adapters and the state of decoding it.
+struct ReadBuf {
+    eof: bool,
+    is_readable: bool,
+    max_len: usize,
+    skip: usize,
+    buf: EasyBuf,
+    pool: Option<BufferPool>,
+}
+
+/// The write buffer of the framed adapters.
+struct WriteBuf {
+    // The frames encoded while nothing is being written, of which `pos`
+    // bytes have been written.
+    buf: Vec<u8>,
+    pos: usize,
+    // The frames encoded behind `buf` once it's partially written or large,
+    // rather than being copied along with it when it grows. They're written
+    // out together with `buf` with vectored writes.
+    queued: VecDeque<Vec<u8>>,
+    spare: Vec<Vec<u8>>,
+    len: usize,
+    // Where the frames not fully written yet end, counting from the first
+    // byte ever encoded, of which `encoded` there have been, and where the
+    // first of these frames starts.
+    ends: VecDeque<u64>,
+    encoded: u64,
+    frame_start: u64,
+    high_watermark: usize,
+    corked: bool,
+    pool: Option<BufferPool>,
+}
+
+impl ReadBuf {
+    fn new() -> ReadBuf {
+        ReadBuf {
+            eof: false,
+            is_readable: false,
+            max_len: usize::MAX,
+            skip: 0,
+            // Allocated by the first read.
+            buf: EasyBuf::with_capacity(0),
+            pool: None,
+        }
+    }
+
+    /// Returns the error of decoding, after noting the bytes to skip if the
+    /// codec can resume decoding.
+    fn decode_error(&mut self, err: io::Error) -> io::Error {
+        if let Some(resync) = err.get_ref().and_then(|e| e.downcast_ref::<Resync>()) {
+            debug!("skipping {} bytes of a malformed frame", resync.skip);
+            self.skip = resync.skip;
+        }
+        err
+    }
+
+    /// Discards the data buffered, along with any bytes left to skip.
+    fn clear(&mut self) {
+        let len = self.buf.len();
+        self.buf.drain_to(len);
+        self.skip = 0;
+        self.is_readable = false;
+        self.eof = false;
+    }
+
+    /// Decodes a frame from a copy of the data buffered, if there's a
+    /// complete one, leaving the buffer as it is.
+    fn peek<C: Codec>(&self, codec: &mut C) -> io::Result<Option<C::In>> {
+        let start = cmp::min(self.skip, self.buf.len());
+        let mut buf = self.buf.slice(start, self.buf.len());
+        codec.decode(&mut buf)
+    }
+
+    fn poll<R, C>(&mut self, upstream: &mut R, codec: &mut C)
+                  -> Poll<Option<C::In>, io::Error>
+        where R: Read,
+              C: Codec,
+    {
+        loop {
+            // If the read buffer has any pending data, then it could be
+            // possible that `decode` will return a new frame. We leave it to
+            // the decoder to optimize detecting that more data is required.
+            if self.is_readable {
+                // Discard what's left of a malformed frame first.
+                if self.skip > 0 {
+                    let n = cmp::min(self.skip, self.buf.len());
+                    self.buf.drain_to(n);
+                    self.skip -= n;
+                }
+                if self.eof {
+                    if self.buf.is_empty() {
+                        return Ok(Async::Ready(None))
+                    }
+                    return match codec.decode_eof(&mut self.buf) {
+                        Ok(frame) => Ok(Async::Ready(Some(frame))),
+                        Err(e) => Err(self.decode_error(e)),
+                    }
+                }
+                if self.skip == 0 {
+                    trace!("attempting to decode a frame");
+                    match codec.decode(&mut self.buf) {
+                        Ok(Some(frame)) => {
+                            trace!("frame decoded from buffer");
+                            return Ok(Async::Ready(Some(frame)))
+                        }
+                        Ok(None) => {}
+                        Err(e) => return Err(self.decode_error(e)),
+                    }
+                }
+                self.is_readable = false;
+            }
+
+            assert!(!self.eof);
+
+            // Otherwise, try to read more data and try again, as long as the
+            // frame being decoded stays within the limit.
+            if self.buf.len() >= self.max_len {
+                return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                          "frame exceeds the maximum read buffer size"))
+            }
+            if self.buf.capacity() == 0 {
+                self.buf = match self.pool {
+                    Some(ref pool) => {
+                        let mut buf = pool.get(INITIAL_CAPACITY);
+                        buf.clear();
+                        buf.into()
+                    }
+                    None => EasyBuf::new(),
+                };
+            }
+            let ret = {
+                let max = self.max_len;
+                let mut buf = self.buf.get_mut();
+                let len = buf.len();
+                buf.resize(len + cmp::min(INITIAL_CAPACITY, max - len), 0);
+                let ret = upstream.read(&mut buf[len..]);
+                let n = match ret {
+                    Ok(n) => n,
+                    Err(_) => 0,
+                };
+                buf.truncate(len + n);
+                ret
+            };
+            let n = try_nb!(ret);
+            trace!("read {} bytes", n);
+            if n == 0 {
+                self.eof = true;
+            }
+            self.is_readable = true;
+        }
+    }
+}
+
+impl WriteBuf {
+    fn new() -> WriteBuf {
+        // Allocated by the first frame sent.
+        WriteBuf::from_vec(Vec::new())
+    }
+
+    fn from_vec(buf: Vec<u8>) -> WriteBuf {
+        let mut ends = VecDeque::new();
+        if !buf.is_empty() {
+            ends.push_back(buf.len() as u64);
+        }
+        WriteBuf {
+            len: buf.len(),
+            ends: ends,
+            encoded: buf.len() as u64,
+            frame_start: 0,
+            buf: buf,
+            pos: 0,
+            queued: VecDeque::new(),
+            spare: Vec::new(),
+            high_watermark: BACKPRESSURE_BOUNDARY,
+            corked: false,
+            pool: None,
+        }
+    }
+
+    /// Discards the frames which haven't been written yet, except for the
+    /// rest of one which is partly written.
+    fn clear(&mut self) {
+        let written = self.encoded - self.len as u64;
+        match self.ends.front().cloned() {
+            // A frame is only ever encoded into a single buffer, so the
+            // rest of the partly written one is all in `buf`.
+            Some(end) if written > self.frame_start => {
+                let rest = (end - written) as usize;
+                self.buf.truncate(self.pos + rest);
+                self.len = rest;
+                self.ends.truncate(1);
+                self.encoded = end;
+            }
+            _ => {
+                self.buf.clear();
+                self.pos = 0;
+                self.len = 0;
+                self.ends.clear();
+                self.encoded = written;
+                self.frame_start = written;
+            }
+        }
+        for mut frames in self.queued.drain(..) {
+            if self.spare.len() < MAX_SPARE_BUFS {
+                frames.clear();
+                self.spare.push(frames);
+            }
+        }
+    }
+
+    /// Returns all the data not written yet, leaving this buffer empty.
+    fn take_all(&mut self) -> Vec<u8> {
+        let mut buf = mem::take(&mut self.buf);
+        buf.drain(..self.pos);
+        for frames in self.queued.drain(..) {
+            buf.extend_from_slice(&frames);
+        }
+        self.pos = 0;
+        self.len = 0;
+        self.ends.clear();
+        self.frame_start = self.encoded;
+        buf
+    }
+
+    fn start_send<W, C>(&mut self, upstream: &mut W, codec: &mut C, item: C::Out)
+                        -> StartSend<C::Out, io::Error>
+        where W: Write,
+              C: Codec,
+    {
+        // If the buffer is already over our backpressure boundary, then try
+        // to flush it. If after flushing it's *still* over the boundary then
+        // we reject the frame so the caller tries again later.
+        if self.len > self.high_watermark {
+            try!(self.poll_flush(upstream));
+            if self.len > self.high_watermark {
+                return Ok(AsyncSink::NotReady(item))
+            }
+        }
+
+        if self.buf.capacity() == 0 {
+            self.buf = match self.pool {
+                Some(ref pool) => {
+                    let mut buf = pool.get(INITIAL_CAPACITY);
+                    buf.clear();
+                    buf
+                }
+                None => Vec::with_capacity(INITIAL_CAPACITY),
+            };
+        }
+
+        // Frames are appended to the buffer being written only as long as
+        // none of it has been written and it's small, and otherwise to the
+        // last of the queued buffers under the same condition.
+        let buf = if self.queued.is_empty() &&
+                     self.pos == 0 &&
+                     self.buf.len() < INITIAL_CAPACITY {
+            &mut self.buf
+        } else {
+            let full = match self.queued.back() {
+                Some(frames) => frames.len() >= INITIAL_CAPACITY,
+                None => true,
+            };
+            if full {
+                let frames = self.spare.pop().unwrap_or_else(|| {
+                    Vec::with_capacity(INITIAL_CAPACITY)
+                });
+                self.queued.push_back(frames);
+            }
+            self.queued.back_mut().unwrap()
+        };
+        let before = buf.len();
+        if let Err(e) = codec.encode(item, buf) {
+            // Whatever the codec wrote before failing isn't a whole frame.
+            buf.truncate(before);
+            return Err(e)
+        }
+        self.len += buf.len() - before;
+        self.encoded += (buf.len() - before) as u64;
+        self.ends.push_back(self.encoded);
+        trace!("frame encoded; length={}", self.len);
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete<W: Write>(&mut self, upstream: &mut W) -> Poll<(), io::Error> {
+        // While corked the frames are only written once there's enough of
+        // them, or by an explicit flush.
+        if self.corked && self.len < self.high_watermark {
+            return Ok(Async::Ready(()))
+        }
+        self.poll_flush(upstream)
+    }
+
+    fn poll_flush<W: Write>(&mut self, upstream: &mut W) -> Poll<(), io::Error> {
+        trace!("flushing framed transport");
+
+        while self.len > 0 {
+            trace!("writing; remaining={}", self.len);
+            let n = if self.queued.is_empty() {
+                try_nb!(upstream.write(&self.buf[self.pos..]))
+            } else {
+                let mut bufs = [io::IoSlice::new(&[]); MAX_WRITE_BUFS];
+                bufs[0] = io::IoSlice::new(&self.buf[self.pos..]);
+                let mut count = 1;
+                for (slot, frames) in bufs[1..].iter_mut().zip(&self.queued) {
+                    *slot = io::IoSlice::new(frames);
+                    count += 1;
+                }
+                try_nb!(upstream.write_vectored(&bufs[..count]))
+            };
+            if n == 0 {
+                return Err(io::Error::new(io::ErrorKind::WriteZero,
+                                          "failed to write frame to transport"))
+            }
+            self.consume(n);
+        }
+
+        // Try flushing the underlying I/O
+        try_nb!(upstream.flush());
+
+        trace!("framed transport flushed");
+        Ok(Async::Ready(()))
+    }
+
+    /// Discards the first `n` bytes not written yet, once they've been
+    /// written.
+    fn consume(&mut self, mut n: usize) {
+        self.len -= n;
+        let written = self.encoded - self.len as u64;
+        while let Some(&end) = self.ends.front() {
+            if end > written {
+                break
+            }
+            self.frame_start = end;
+            self.ends.pop_front();
+        }
+        loop {
+            let left = self.buf.len() - self.pos;
+            if n < left {
+                self.pos += n;
+                return
+            }
+            n -= left;
+            self.buf.clear();
+            self.pos = 0;
+            match self.queued.pop_front() {
+                Some(frames) => {
+                    let written = mem::replace(&mut self.buf, frames);
+                    if self.spare.len() < MAX_SPARE_BUFS {
+                        self.spare.push(written);
+                    }
+                }
+                None => return,
+            }
+        }
+    }
+}
+
+impl Drop for ReadBuf {
+    fn drop(&mut self) {
+        if let Some(ref pool) = self.pool {
+            let buf = mem::replace(&mut self.buf, EasyBuf::with_capacity(0));
+            if let Some(buf) = buf.into_unique_vec() {
+                pool.put(buf);
+            }
+        }
+    }
+}
+
+impl Drop for WriteBuf {
+    fn drop(&mut self) {
+        if let Some(ref pool) = self.pool {
+            pool.put(mem::take(&mut self.buf));
+        }
+    }
+}
+
+impl<T: Io, C: Codec> Stream for Framed<T, C> {
+    type Item = C::In;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<C::In>, io::Error> {
+        self.rd.poll(&mut self.upstream, &mut self.codec)
+    }
+}
+
+impl<T: Io, C: Codec> Sink for Framed<T, C> {
+    type SinkItem = C::Out;
+    type SinkError = io::Error;
+
+    fn start_send(&mut self, item: C::Out) -> StartSend<C::Out, io::Error> {
+        self.wr.start_send(&mut self.upstream, &mut self.codec, item)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), io::Error> {
+        self.wr.poll_complete(&mut self.upstream)
+    }
+
+    fn close(&mut self) -> Poll<(), io::Error> {
+        // The frames held back by the cork are written out as well.
+        try_ready!(self.wr.poll_flush(&mut self.upstream));
+        AsyncWrite::shutdown(&mut self.upstream)
+    }
+}
+
+impl<T: Write, C> Framed<T, C> {
+    /// Writes out all the frames sent, even while corked.
+    ///
+    /// This is `Sink::poll_complete` as if the sink weren't corked.
+    pub fn poll_flush(&mut self) -> Poll<(), io::Error> {
+        self.wr.poll_flush(&mut self.upstream)
+    }
+}
+
+pub fn framed<T, C>(io: T, codec: C) -> Framed<T, C> {
+    Framed {
+        upstream: io,
+        codec: codec,
+        rd: ReadBuf::new(),
+        wr: WriteBuf::new(),
+    }
+}
+
+impl<T, C> Framed<T, C> {
+    /// Sets the maximum number of bytes buffered while decoding a frame.
+    ///
+    /// Once the read buffer holds `max` bytes without `Codec::decode` having
+    /// yielded a frame, the stream returns an `InvalidData` error instead of
+    /// reading more. This bounds the memory a peer can make this `Framed` use
+    /// whatever the codec, so codecs which don't enforce a maximum frame size
+    /// themselves can still be used with untrusted peers.
+    ///
+    /// There is no limit by default.
+    pub fn set_max_read_buffer(&mut self, max: usize) {
+        self.rd.max_len = max;
+    }
+
+    /// Sets the number of bytes of encoded frames buffered before writing
+    /// them is required.
+    ///
+    /// Once more than `max` bytes are buffered, sending a frame first tries
+    /// to write them out and is refused if it can't, so this is the most
+    /// memory used by the frames waiting to be written. It's 8KB by default.
+    /// A larger value lets more small frames be gathered into a single write.
+    pub fn set_write_high_watermark(&mut self, max: usize) {
+        self.wr.high_watermark = max;
+    }
+
+    /// Sets whether writing the encoded frames is held back until they reach
+    /// the write high watermark, or until `poll_flush` is called.
+    ///
+    /// By default `poll_complete` writes out all the frames sent, so sending
+    /// many small frames one after the other, with `Sink::send` for instance,
+    /// costs a write each. Once corked, `poll_complete` only writes the
+    /// frames once the high watermark is reached, and otherwise completes
+    /// immediately with the frames still buffered; a corked sink must then be
+    /// flushed with `poll_flush`, or uncorked, once done sending a batch.
+    /// `Sink::close`, and so `send_all` and `forward`, write out all the
+    /// frames whether or not the sink is corked.
+    pub fn set_corked(&mut self, corked: bool) {
+        self.wr.corked = corked;
+    }
+
+    /// Sets the pool the read and write buffers are taken from and handed
+    /// back to when this `Framed` is dropped, such as the pool of an event
+    /// loop returned by `Handle::buffer_pool`.
+    ///
+    /// The buffers are only taken from the pool when they're first needed,
+    /// so this should be called before using the `Framed`. By default they're
+    /// allocated.
+    pub fn set_buffer_pool(&mut self, pool: &BufferPool) {
+        self.rd.pool = Some(pool.clone());
+        self.wr.pool = Some(pool.clone());
+    }
+
+    /// Returns the data read from the underlying I/O stream but not decoded
+    /// into frames yet.
+    ///
+    /// This is what the next frames are decoded from, which is useful for
+    /// sniffing the protocol the peer speaks, or to report what it sent once
+    /// decoding has failed: the bytes of a malformed frame which the codec
+    /// skips with a `Resync` are still included until the next poll.
+    pub fn read_buffer(&self) -> &[u8] {
+        self.rd.buf.as_slice()
+    }
+
+    /// Decodes the next frame from the data already read, without consuming
+    /// it, returning `None` if the data buffered isn't a complete frame.
+    ///
+    /// No data is read by this method, and the frame is decoded again by the
+    /// next poll of the stream. `Codec::decode` is called on a copy of the
+    /// read buffer, so this is only meaningful for codecs which don't keep
+    /// any state of the data they've decoded between calls.
+    pub fn peek_frame(&mut self) -> io::Result<Option<C::In>>
+        where C: Codec,
+    {
+        self.rd.peek(&mut self.codec)
+    }
+
+    /// Restarts the framing of the underlying I/O stream, as if this `Framed`
+    /// had just been created.
+    ///
+    /// The codec is reset with `Codec::reset`, and the data read but not
+    /// decoded yet as well as the frames not written yet are discarded, so
+    /// the frames sent should be flushed first with `Sink::poll_complete` if
+    /// they're to reach the peer. Only the rest of a frame which is partly
+    /// written is kept, so the peer never receives a truncated frame. This lets a connection switch to a new
+    /// session of the same protocol, such as after a STARTTLS exchange or a
+    /// renegotiation of the version of the protocol, without being taken
+    /// apart, as long as the peer doesn't send data for the new session
+    /// before the switch has been acknowledged.
+    pub fn reset(&mut self)
+        where C: Codec,
+    {
+        self.codec.reset();
+        self.rd.clear();
+        self.wr.clear();
+    }
+
+    /// Returns a reference to the underlying I/O stream wrapped by `Framed`.
+    ///
+    /// Note that care should be taken to not tamper with the underlying stream
+    /// of data coming in as it may corrupt the stream of frames otherwise being
+    /// worked with.
+    pub fn get_ref(&self) -> &T {
+        &self.upstream
+    }
+
+    /// Returns a mutable reference to the underlying I/O stream wrapped by
+    /// `Framed`.
+    ///
+    /// Note that care should be taken to not tamper with the underlying stream
+    /// of data coming in as it may corrupt the stream of frames otherwise being
+    /// worked with.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.upstream
+    }
+
+    /// Returns a reference to the codec used to encode and decode frames.
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Returns a mutable reference to the codec used to encode and decode
+    /// frames, for example to reconfigure it or to set the hooks of a
+    /// `Hooked` codec.
+    pub fn codec_mut(&mut self) -> &mut C {
+        &mut self.codec
+    }
+
+    /// Consumes the `Framed`, returning its underlying I/O stream.
+    ///
+    /// Note that care should be taken to not tamper with the underlying stream
+    /// of data coming in as it may corrupt the stream of frames otherwise being
+    /// worked with.
+    pub fn into_inner(self) -> T {
+        self.upstream
+    }
+
+    /// Consumes the `Framed`, returning its underlying I/O stream along with
+    /// the data read but not decoded yet and the frames encoded but not
+    /// written yet.
+    ///
+    /// Unlike with `into_inner` no data is lost, so the I/O stream can be used
+    /// with another protocol, starting with the data in `read_buf`, once
+    /// `write_buf` has been written out. Any state kept by the codec, such as
+    /// a partially decoded frame header, is dropped along with it.
+    pub fn into_parts(mut self) -> FramedParts<T> {
+        FramedParts {
+            read_buf: mem::replace(&mut self.rd.buf, EasyBuf::with_capacity(0)),
+            write_buf: self.wr.take_all(),
+            inner: self.upstream,
+        }
+    }
+
+    /// Creates a `Framed` from the `parts` of another one, or from an I/O
+    /// stream and data already read from it, using `codec` to encode and
+    /// decode frames.
+    ///
+    /// The data in `parts.read_buf` is decoded before any more is read, and
+    /// the data in `parts.write_buf` is written out before any frame encoded
+    /// by `codec`.
+    pub fn from_parts(parts: FramedParts<T>, codec: C) -> Framed<T, C> {
+        let mut rd = ReadBuf::new();
+        rd.is_readable = !parts.read_buf.is_empty();
+        rd.buf = parts.read_buf;
+        Framed {
+            upstream: parts.inner,
+            codec: codec,
+            rd: rd,
+            wr: WriteBuf::from_vec(parts.write_buf),
+        }
+    }
+}
+
+impl<T: Read, C: Codec> Stream for FramedRead<T, C> {
+    type Item = C::In;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<C::In>, io::Error> {
+        self.rd.poll(&mut self.upstream, &mut self.codec)
+    }
+}
+
+impl<T, C> FramedRead<T, C> {
+    /// Creates a new `FramedRead` decoding the data read from `io` with
+    /// `codec`.
+    pub fn new(io: T, codec: C) -> FramedRead<T, C> {
+        FramedRead {
+            upstream: io,
+            codec: codec,
+            rd: ReadBuf::new(),
+        }
+    }
+
+    /// Sets the maximum number of bytes buffered while decoding a frame.
+    ///
+    /// See `Framed::set_max_read_buffer` for more details.
+    pub fn set_max_read_buffer(&mut self, max: usize) {
+        self.rd.max_len = max;
+    }
+
+    /// Sets the pool the read buffer is taken from and handed back to when
+    /// this `FramedRead` is dropped.
+    ///
+    /// See `Framed::set_buffer_pool` for more details.
+    pub fn set_buffer_pool(&mut self, pool: &BufferPool) {
+        self.rd.pool = Some(pool.clone());
+    }
+
+    /// Returns the data read from the underlying reader but not decoded into
+    /// frames yet.
+    ///
+    /// See `Framed::read_buffer` for more details.
+    pub fn read_buffer(&self) -> &[u8] {
+        self.rd.buf.as_slice()
+    }
+
+    /// Decodes the next frame from the data already read, without consuming
+    /// it.
+    ///
+    /// See `Framed::peek_frame` for more details.
+    pub fn peek_frame(&mut self) -> io::Result<Option<C::In>>
+        where C: Codec,
+    {
+        self.rd.peek(&mut self.codec)
+    }
+
+    /// Resets the codec and discards the data read but not decoded yet.
+    ///
+    /// See `Framed::reset` for more details.
+    pub fn reset(&mut self)
+        where C: Codec,
+    {
+        self.codec.reset();
+        self.rd.clear();
+    }
+
+    /// Returns a reference to the underlying reader wrapped by `FramedRead`.
+    pub fn get_ref(&self) -> &T {
+        &self.upstream
+    }
+
+    /// Returns a mutable reference to the underlying reader wrapped by
+    /// `FramedRead`.
+    ///
+    /// Note that care should be taken to not tamper with the underlying stream
+    /// of data coming in as it may corrupt the stream of frames otherwise being
+    /// worked with.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.upstream
+    }
+
+    /// Returns a reference to the codec used to decode frames.
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Returns a mutable reference to the codec used to decode frames.
+    pub fn codec_mut(&mut self) -> &mut C {
+        &mut self.codec
+    }
+
+    /// Consumes the `FramedRead`, returning its underlying reader.
+    ///
+    /// Any data already read but not yet decoded is lost.
+    pub fn into_inner(self) -> T {
+        self.upstream
+    }
+}
+
+impl<T: Write, C: Codec> Sink for FramedWrite<T, C> {
+    type SinkItem = C::Out;
+    type SinkError = io::Error;
+
+    fn start_send(&mut self, item: C::Out) -> StartSend<C::Out, io::Error> {
+        self.wr.start_send(&mut self.upstream, &mut self.codec, item)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), io::Error> {
+        self.wr.poll_complete(&mut self.upstream)
+    }
+
+    fn close(&mut self) -> Poll<(), io::Error> {
+        // The frames held back by the cork are written out as well. The
+        // writer is only `Write`, so unlike with `Framed` it isn't shut down.
+        self.wr.poll_flush(&mut self.upstream)
+    }
+}
+
+impl<T: Write, C> FramedWrite<T, C> {
+    /// Writes out all the frames sent, even while corked.
+    ///
+    /// This is `Sink::poll_complete` as if the sink weren't corked.
+    pub fn poll_flush(&mut self) -> Poll<(), io::Error> {
+        self.wr.poll_flush(&mut self.upstream)
+    }
+}
+
+impl<T, C> FramedWrite<T, C> {
+    /// Creates a new `FramedWrite` writing the frames encoded with `codec` to
+    /// `io`.
+    pub fn new(io: T, codec: C) -> FramedWrite<T, C> {
+        FramedWrite {
+            upstream: io,
+            codec: codec,
+            wr: WriteBuf::new(),
+        }
+    }
+
+    /// Sets the number of bytes of encoded frames buffered before writing
+    /// them is required.
+    ///
+    /// See `Framed::set_write_high_watermark` for more details.
+    pub fn set_write_high_watermark(&mut self, max: usize) {
+        self.wr.high_watermark = max;
+    }
+
+    /// Sets whether writing the encoded frames is held back until they reach
+    /// the write high watermark, or until `poll_flush` is called.
+    ///
+    /// See `Framed::set_corked` for more details.
+    pub fn set_corked(&mut self, corked: bool) {
+        self.wr.corked = corked;
+    }
+
+    /// Sets the pool the write buffer is taken from and handed back to when
+    /// this `FramedWrite` is dropped.
+    ///
+    /// See `Framed::set_buffer_pool` for more details.
+    pub fn set_buffer_pool(&mut self, pool: &BufferPool) {
+        self.wr.pool = Some(pool.clone());
+    }
+
+    /// Resets the codec and discards the frames not written yet.
+    ///
+    /// See `Framed::reset` for more details.
+    pub fn reset(&mut self)
+        where C: Codec,
+    {
+        self.codec.reset();
+        self.wr.clear();
+    }
+
+    /// Returns a reference to the underlying writer wrapped by `FramedWrite`.
+    pub fn get_ref(&self) -> &T {
+        &self.upstream
+    }
+
+    /// Returns a mutable reference to the underlying writer wrapped by
+    /// `FramedWrite`.
+    ///
+    /// Note that care should be taken to not write to the underlying writer
+    /// directly while frames are buffered, as the data written would be
+    /// interleaved with them.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.upstream
+    }
+
+    /// Returns a reference to the codec used to encode frames.
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Returns a mutable reference to the codec used to encode frames.
+    pub fn codec_mut(&mut self) -> &mut C {
+        &mut self.codec
+    }
+
+    /// Consumes the `FramedWrite`, returning its underlying writer.
+    ///
+    /// Any frames encoded but not yet written are lost, so the sink should be
+    /// flushed first.
+    pub fn into_inner(self) -> T {
+        self.upstream
+    }
+}
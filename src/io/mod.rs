@@ -3,7 +3,7 @@
 //! Contains various combinators to work with I/O objects and type definitions
 //! as well.
 
-use std::io::{self, Read, Write};
+use std::io::{self, Read, Seek, SeekFrom, Write};
 
 use futures::{BoxFuture, Async, Poll};
 use futures::stream::BoxStream;
@@ -31,33 +31,79 @@ macro_rules! try_nb {
     })
 }
 
+pub mod codec;
+
+mod buffer_pool;
+mod buffered;
+mod buffered_sink;
+mod chain;
+mod checksum;
 mod copy;
+mod copy_bidirectional;
+mod copy_exact;
+mod counted;
 mod flush;
+mod frame;
 mod read_exact;
+mod read_into;
 mod read_to_end;
+mod read_until;
+mod seek;
+#[cfg(unix)]
+mod splice;
 mod split;
+mod stdio;
+mod take;
+mod throttle;
+mod timeout;
+mod upgrade;
 mod window;
 mod write_all;
-pub use self::copy::{copy, Copy};
+mod write_all_vectored;
+pub use self::buffer_pool::BufferPool;
+pub use self::buffered::{BufReader, BufWriter};
+pub use self::buffered_sink::BufferedSink;
+pub use self::chain::{chain, Chain};
+pub use self::checksum::{Checksum, ChecksumReader, ChecksumWriter, Crc32};
+pub use self::copy::{copy, copy_with_buffer, Copy, CopyWithBuffer};
+pub use self::copy_bidirectional::{copy_bidirectional, CopyBidirectional};
+pub use self::copy_exact::{copy_exact, CopyExact};
+pub use self::counted::{Counted, Counters};
 pub use self::flush::{flush, Flush};
+pub use self::frame::{EasyBuf, EasyBufDrain, EasyBufMut, Codec, Framed, FramedParts};
+pub use self::frame::{FramedRead, FramedWrite, Resync};
 pub use self::read_exact::{read_exact, ReadExact};
-pub use self::read_to_end::{read_to_end, ReadToEnd};
-pub use self::split::{ReadHalf, WriteHalf};
+pub use self::read_into::{read_into, ReadInto};
+pub use self::read_to_end::{read_to_end, read_to_end_limited, ReadToEnd};
+pub use self::read_to_end::{ReadToEndLimited, Growth};
+pub use self::read_until::{read_until, ReadUntil};
+pub use self::seek::{seek, Seeking};
+#[cfg(unix)]
+pub use self::splice::{splice_copy, splice_bidirectional, Splice, SpliceCopy};
+#[cfg(unix)]
+pub use self::splice::SpliceBidirectional;
+pub use self::split::{ReadHalf, WriteHalf, SharedReadHalf, SharedWriteHalf};
+pub use self::take::{take, Take};
+pub use self::throttle::Throttle;
+pub use self::timeout::TimeoutIo;
+pub use self::stdio::{stdin, stdout, stderr, Stdin, Stdout, Stderr};
+pub use self::upgrade::{upgrade, Upgrade, Upgrading};
 pub use self::window::Window;
 pub use self::write_all::{write_all, WriteAll};
+pub use self::write_all_vectored::{write_all_vectored, WriteAllVectored};
 
-/// A trait for read/write I/O objects
+/// A trait for readable I/O objects.
 ///
-/// This trait represents I/O object which are readable and writable.
-/// Additionally, they're associated with the ability to test whether they're
-/// readable or writable.
+/// This trait represents I/O objects which are readable, with the ability to
+/// test whether they're readable. Objects which are writable as well
+/// implement `AsyncWrite` too, which makes them `Io` objects.
 ///
 /// Imporantly, the methods of this trait are intended to be used in conjuction
 /// with the current task of a future. Namely whenever any of them return a
 /// value that indicates "would block" the current future's task is arranged to
 /// receive a notification when the method would otherwise not indicate that it
 /// would block.
-pub trait Io: Read + Write {
+pub trait AsyncRead: Read {
     /// Tests to see if this I/O object may be readable.
     ///
     /// This method returns an `Async<()>` indicating whether the object
@@ -79,7 +125,18 @@ pub trait Io: Read + Write {
     fn poll_read(&mut self) -> Async<()> {
         Async::Ready(())
     }
+}
 
+/// A trait for writable I/O objects.
+///
+/// This trait represents I/O objects which are writable, with the ability to
+/// test whether they're writable and to flush and shut them down as futures
+/// would. Write-only objects, such as the standard input of a child process,
+/// only implement this trait.
+///
+/// As with `AsyncRead`, the methods of this trait are intended to be used in
+/// conjuction with the current task of a future.
+pub trait AsyncWrite: Write {
     /// Tests to see if this I/O object may be writable.
     ///
     /// This method returns an `Async<()>` indicating whether the object
@@ -102,21 +159,109 @@ pub trait Io: Read + Write {
         Async::Ready(())
     }
 
-    /// Helper method for splitting this read/write object into two halves.
+    /// Attempts to flush this object, returning `Async::NotReady` rather than
+    /// a "would block" error if it can't be yet.
     ///
-    /// The two halves returned implement the `Read` and `Write` traits,
-    /// respectively, but are only usable on the current task.
+    /// The default implementation calls `Write::flush`.
+    fn poll_flush(&mut self) -> Poll<(), io::Error> {
+        try_nb!(self.flush());
+        Ok(Async::Ready(()))
+    }
+
+    /// Attempts to shut down this object, flushing it first.
     ///
-    /// # Panics
+    /// Once this returns `Async::Ready` no more data should be written to the
+    /// object. For sockets this shuts down their writing half, so their peer
+    /// reads EOF, while other objects are only flushed by the default
+    /// implementation.
+    ///
+    /// Sockets also have an inherent `shutdown` method taking a `Shutdown`,
+    /// which takes precedence in method calls, so for them this one is
+    /// called as `AsyncWrite::shutdown(&mut socket)`.
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.poll_flush()
+    }
+}
+
+/// A trait for seekable I/O objects.
+///
+/// This is the counterpart of `Seek` for the I/O objects of this crate,
+/// returning `Async::NotReady` rather than a "would block" error if the
+/// object can't be seeked yet. Objects whose seeks complete immediately, such
+/// as `MmapReader`, can rely on the default implementation, while `fs::File`
+/// runs its seeks on the blocking pool. The `seek`
+/// function of this module returns a future for a seek.
+///
+/// As with `AsyncRead`, the methods of this trait are intended to be used in
+/// conjuction with the current task of a future.
+pub trait AsyncSeek: Seek {
+    /// Attempts to seek to the offset `pos`, returning the new position from
+    /// the start of the data.
+    ///
+    /// The default implementation calls `Seek::seek`.
+    fn poll_seek(&mut self, pos: SeekFrom) -> Poll<u64, io::Error> {
+        Ok(Async::Ready(try_nb!(self.seek(pos))))
+    }
+}
+
+/// A trait for read/write I/O objects
+///
+/// This trait represents I/O object which are readable and writable, that is
+/// which implement both `AsyncRead` and `AsyncWrite`, and is implemented for
+/// all of them. It provides helpers to use such objects as a whole.
+pub trait Io: AsyncRead + AsyncWrite {
+    /// Helper method for splitting this read/write object into two halves.
     ///
-    /// This method will panic if there is not currently an active future task.
+    /// The two halves returned implement the `AsyncRead` and `AsyncWrite`
+    /// traits, respectively. They share the object behind a lock, which each
+    /// operation on a half takes, so they can be used from different tasks.
+    /// Objects such as `TcpStream`, which are readable and writable through
+    /// `&Self`, can instead be split without any locking by `split_shared`.
+    ///
+    /// The halves can be put back together with `ReadHalf::unsplit`.
     fn split(self) -> (ReadHalf<Self>, WriteHalf<Self>)
         where Self: Sized
     {
         split::split(self)
     }
+
+    /// Splits this read/write object into two halves which share it without
+    /// any locking, by reading and writing through a shared reference to it.
+    ///
+    /// This is only available for objects such as `TcpStream` which are
+    /// readable and writable through `&Self`. Each half tracks the readiness
+    /// of its own direction, so the halves can be used from different tasks
+    /// without contending with or waking each other up.
+    ///
+    /// The halves can be put back together with `SharedReadHalf::unsplit`.
+    fn split_shared(self) -> (SharedReadHalf<Self>, SharedWriteHalf<Self>)
+        where Self: Sized,
+              for<'a> &'a Self: AsyncRead + AsyncWrite,
+    {
+        split::split_shared(self)
+    }
+
+    /// Provides a `Stream` and `Sink` interface for reading and writing to
+    /// this `Io` object, using `Codec` to encode and decode frames.
+    ///
+    /// Raw I/O objects work with byte sequences, but higher-level code
+    /// usually wants to batch these into meaningful chunks, called "frames".
+    /// This method layers framing on top of an I/O object, by using the
+    /// `Codec` trait to handle encoding and decoding of message frames. Note
+    /// that the incoming and outgoing frame types may be distinct.
+    ///
+    /// The returned `Framed` buffers the bytes read until `Codec::decode`
+    /// yields a complete frame, and buffers encoded frames until they can be
+    /// written out when the sink is flushed.
+    fn framed<C: Codec>(self, codec: C) -> Framed<Self, C>
+        where Self: Sized,
+    {
+        frame::framed(self, codec)
+    }
 }
 
+impl<T: AsyncRead + AsyncWrite> Io for T {}
+
 /// A trait for framed reading and writing.
 ///
 /// Most implementations of `FramedIo` are for doing protocol level
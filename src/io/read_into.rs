@@ -0,0 +1,79 @@
+use std::io::{self, Read};
+use std::mem;
+
+use futures::{Poll, Future};
+
+/// A future which reads some bytes into the spare capacity of a vector.
+///
+/// Created by the [`read_into`] function.
+///
+/// [`read_into`]: fn.read_into.html
+pub struct ReadInto<A> {
+    state: State<A>,
+}
+
+enum State<A> {
+    Reading {
+        a: A,
+        buf: Vec<u8>,
+    },
+    Empty,
+}
+
+/// Creates a future which will read some bytes from the I/O object `A`,
+/// appending them to `buf` without growing it beyond its capacity.
+///
+/// This is a single read, as with `Read::read`, with as many bytes as `buf`
+/// has spare capacity for, so variable-size data can be read without a
+/// fixed-size array to copy it out of. The returned future resolves to the
+/// I/O object, the buffer and the number of bytes appended to it, which is
+/// zero at EOF.
+///
+/// In the case of an error the buffer and the object will be discarded, with
+/// the error yielded.
+///
+/// # Panics
+///
+/// Panics if `buf` has no spare capacity, as a read of no bytes couldn't be
+/// told apart from EOF.
+pub fn read_into<A>(a: A, buf: Vec<u8>) -> ReadInto<A>
+    where A: Read,
+{
+    assert!(buf.len() < buf.capacity(), "read buffer has no spare capacity");
+    ReadInto {
+        state: State::Reading {
+            a: a,
+            buf: buf,
+        },
+    }
+}
+
+impl<A> Future for ReadInto<A>
+    where A: Read,
+{
+    type Item = (A, Vec<u8>, usize);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(A, Vec<u8>, usize), io::Error> {
+        let n = match self.state {
+            State::Reading { ref mut a, ref mut buf } => {
+                let len = buf.len();
+                let cap = buf.capacity();
+                buf.resize(cap, 0);
+                let ret = a.read(&mut buf[len..]);
+                let n = match ret {
+                    Ok(n) => n,
+                    Err(_) => 0,
+                };
+                buf.truncate(len + n);
+                try_nb!(ret)
+            }
+            State::Empty => panic!("poll a ReadInto after it's done"),
+        };
+
+        match mem::replace(&mut self.state, State::Empty) {
+            State::Reading { a, buf } => Ok((a, buf, n).into()),
+            State::Empty => panic!(),
+        }
+    }
+}
@@ -1,7 +1,8 @@
+use std::cmp;
 use std::io::{self, Read};
 use std::mem;
 
-use futures::{Poll, Future};
+use futures::{Async, Poll, Future};
 
 /// A future which can be used to easily read the entire contents of a stream
 /// into a vector.
@@ -62,3 +63,115 @@ impl<A> Future for ReadToEnd<A>
         }
     }
 }
+
+/// A future which reads the entire contents of a stream into a vector, failing
+/// if it's longer than a maximum size.
+///
+/// Created by the [`read_to_end_limited`] function.
+///
+/// [`read_to_end_limited`]: fn.read_to_end_limited.html
+pub struct ReadToEndLimited<A> {
+    state: State<A>,
+    max: usize,
+    read: usize,
+    growth: Growth,
+}
+
+/// How the buffer of a [`read_to_end_limited`] future grows as data is read.
+///
+/// [`read_to_end_limited`]: fn.read_to_end_limited.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Growth {
+    /// The capacity of the buffer is doubled each time it's full, starting
+    /// from 32 bytes, like `Read::read_to_end` does. This minimizes the
+    /// number of reallocations for large streams.
+    Double,
+    /// The capacity of the buffer is grown by this many bytes each time it's
+    /// full. This bounds the memory wasted by the capacity beyond the data
+    /// read, for example when slurping many small streams.
+    Fixed(usize),
+}
+
+/// Creates a future which will read all the bytes associated with the I/O
+/// object `A` into the buffer provided, as long as there are at most `max` of
+/// them.
+///
+/// This works like [`read_to_end`], except that the future fails with an
+/// `InvalidData` error as soon as more than `max` bytes are read, so reading
+/// from an untrusted peer can't use unbounded memory. The buffer never grows
+/// to hold more than `max` bytes beyond its original length, and `growth`
+/// determines how its capacity grows until then.
+///
+/// [`read_to_end`]: fn.read_to_end.html
+///
+/// # Panics
+///
+/// Panics if `growth` is `Growth::Fixed(0)`.
+pub fn read_to_end_limited<A>(a: A, buf: Vec<u8>, max: usize, growth: Growth)
+                              -> ReadToEndLimited<A>
+    where A: Read,
+{
+    assert!(growth != Growth::Fixed(0), "buffer growth must not be zero");
+    ReadToEndLimited {
+        state: State::Reading {
+            a: a,
+            buf: buf,
+        },
+        max: max,
+        read: 0,
+        growth: growth,
+    }
+}
+
+impl<A> Future for ReadToEndLimited<A>
+    where A: Read,
+{
+    type Item = (A, Vec<u8>);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(A, Vec<u8>), io::Error> {
+        match self.state {
+            State::Reading { ref mut a, ref mut buf } => {
+                loop {
+                    // Once the limit is reached the stream must be at EOF,
+                    // which is checked without growing the buffer.
+                    if self.read == self.max {
+                        if try_nb!(a.read(&mut [0])) == 0 {
+                            break
+                        }
+                        return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                                  "stream exceeds the maximum size"))
+                    }
+
+                    if buf.len() == buf.capacity() {
+                        let additional = match self.growth {
+                            Growth::Double => cmp::max(buf.capacity(), 32),
+                            Growth::Fixed(n) => n,
+                        };
+                        buf.reserve_exact(cmp::min(additional, self.max - self.read));
+                    }
+
+                    let len = buf.len();
+                    let avail = cmp::min(buf.capacity() - len, self.max - self.read);
+                    buf.resize(len + avail, 0);
+                    let ret = a.read(&mut buf[len..]);
+                    let n = match ret {
+                        Ok(n) => n,
+                        Err(_) => 0,
+                    };
+                    buf.truncate(len + n);
+                    if try_nb!(ret) == 0 {
+                        break
+                    }
+                    self.read += n;
+                }
+            }
+            State::Empty => panic!("poll ReadToEndLimited after it's done"),
+        }
+
+        match mem::replace(&mut self.state, State::Empty) {
+            State::Reading { a, buf } => Ok(Async::Ready((a, buf))),
+            State::Empty => unreachable!(),
+        }
+    }
+}
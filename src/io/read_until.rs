@@ -0,0 +1,88 @@
+use std::io::{self, Read};
+use std::mem;
+
+use futures::{Poll, Future};
+
+/// A future which can be used to easily read the contents of a stream into a
+/// vector until a delimiter is found.
+///
+/// Created by the [`read_until`] function.
+///
+/// [`read_until`]: fn.read_until.html
+pub struct ReadUntil<A, D> {
+    state: State<A, D>,
+}
+
+enum State<A, D> {
+    Reading {
+        a: A,
+        delim: D,
+        buf: Vec<u8>,
+    },
+    Empty,
+}
+
+/// Creates a future which will read the bytes of the I/O object `A` into the
+/// buffer provided until the delimiter `delim` is found.
+///
+/// The delimiter is a sequence of bytes, such as `b"\r\n"` or `[b'\n']` for a
+/// single byte. Data is read one byte at a time, so nothing following the
+/// delimiter is consumed from the object: this is meant for the first few
+/// lines of a protocol, such as an HTTP request head, before handing the
+/// object off to another parser or codec.
+///
+/// In the case of an error the buffer and the object will be discarded, with
+/// the error yielded. In the case of success the object and the buffer will
+/// be returned, with all data read from the stream appended to the buffer
+/// including the delimiter. If EOF is reached before the delimiter is found
+/// the buffer is returned as well, so whether it ends with the delimiter
+/// should be checked.
+///
+/// # Panics
+///
+/// Panics if `delim` is empty.
+pub fn read_until<A, D>(a: A, delim: D, buf: Vec<u8>) -> ReadUntil<A, D>
+    where A: Read,
+          D: AsRef<[u8]>,
+{
+    assert!(!delim.as_ref().is_empty(), "read_until delimiter must not be empty");
+    ReadUntil {
+        state: State::Reading {
+            a: a,
+            delim: delim,
+            buf: buf,
+        }
+    }
+}
+
+impl<A, D> Future for ReadUntil<A, D>
+    where A: Read,
+          D: AsRef<[u8]>,
+{
+    type Item = (A, Vec<u8>);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(A, Vec<u8>), io::Error> {
+        match self.state {
+            State::Reading { ref mut a, ref delim, ref mut buf } => {
+                let delim = delim.as_ref();
+                let mut byte = [0];
+                loop {
+                    if try_nb!(a.read(&mut byte)) == 0 {
+                        break
+                    }
+                    buf.push(byte[0]);
+                    if buf.ends_with(delim) {
+                        break
+                    }
+                }
+            }
+            State::Empty => panic!("poll a ReadUntil after it's done"),
+        }
+
+        match mem::replace(&mut self.state, State::Empty) {
+            State::Reading { a, buf, .. } => Ok((a, buf).into()),
+            State::Empty => unreachable!(),
+        }
+    }
+}
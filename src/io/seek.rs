@@ -0,0 +1,48 @@
+use std::io::{self, SeekFrom};
+
+use futures::{Poll, Future, Async};
+
+use io::AsyncSeek;
+
+/// A future used to seek an I/O object to an offset.
+///
+/// Resolves to the underlying I/O object and its new position once the seek
+/// is complete.
+///
+/// Created by the [`seek`] function.
+///
+/// [`seek`]: fn.seek.html
+pub struct Seeking<A> {
+    a: Option<A>,
+    pos: SeekFrom,
+}
+
+/// Creates a future which will seek an I/O object to the offset `pos` and
+/// then yield the object itself, along with its new position from the start
+/// of the data.
+///
+/// This function will consume the object provided if an error happens, and
+/// otherwise it will call `AsyncSeek::poll_seek` until the seek is complete.
+/// Together with `take` or `copy_exact` this serves a range of the data of
+/// an object, such as the range of a file requested by an HTTP client.
+pub fn seek<A>(a: A, pos: SeekFrom) -> Seeking<A>
+    where A: AsyncSeek,
+{
+    Seeking {
+        a: Some(a),
+        pos: pos,
+    }
+}
+
+impl<A> Future for Seeking<A>
+    where A: AsyncSeek,
+{
+    type Item = (A, u64);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(A, u64), io::Error> {
+        let n = try_ready!(self.a.as_mut().expect("poll a Seeking after it's done")
+                                 .poll_seek(self.pos));
+        Ok(Async::Ready((self.a.take().unwrap(), n)))
+    }
+}
@@ -0,0 +1,370 @@
+use std::cmp;
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use futures::{Async, Future, Poll};
+use mio;
+
+use io::AsyncWrite;
+use reactor::PollEvented;
+
+/// The most bytes moved through the pipe at once, the default capacity of a
+/// pipe on Linux, so that a pipe emptied by the previous write always has
+/// room for them.
+#[cfg(target_os = "linux")]
+const PIPE_LEN: usize = 64 * 1024;
+
+/// The size of the buffer copied through where splicing isn't possible.
+const BUF_LEN: usize = 2048;
+
+/// I/O objects whose data can be moved to and from a pipe by the kernel.
+///
+/// This is implemented by the TCP and Unix stream sockets of this crate, and
+/// by `PollEvented` for other objects backed by a file descriptor, so that
+/// `splice_copy` and `splice_bidirectional` can relay data between them with
+/// the `splice` system call of Linux, the data never being copied to
+/// userspace. Both methods take the descriptor of a nonblocking pipe, and
+/// behave like nonblocking reads and writes, returning a "would block" error
+/// and arranging for the current task to be notified when the object isn't
+/// ready.
+///
+/// On other platforms, or if the kernel refuses to splice the object, the
+/// methods return an error and the data is copied with `Read` and `Write`
+/// instead.
+pub trait Splice: Read + Write {
+    /// Moves up to `len` bytes read from this object into the pipe `pipe`,
+    /// returning how many were moved, zero at EOF.
+    fn splice_to(&mut self, pipe: RawFd, len: usize) -> io::Result<usize>;
+
+    /// Moves up to `len` bytes from the pipe `pipe` to this object, returning
+    /// how many were written.
+    fn splice_from(&mut self, pipe: RawFd, len: usize) -> io::Result<usize>;
+}
+
+impl<E> Splice for PollEvented<E>
+    where E: Read + Write + AsRawFd,
+{
+    fn splice_to(&mut self, pipe: RawFd, len: usize) -> io::Result<usize> {
+        if let Async::NotReady = self.poll_read() {
+            return Err(mio::would_block())
+        }
+        let r = splice(self.get_ref().as_raw_fd(), pipe, len);
+        if let Err(ref e) = r {
+            if e.kind() == io::ErrorKind::WouldBlock {
+                self.need_read();
+            }
+        }
+        r
+    }
+
+    fn splice_from(&mut self, pipe: RawFd, len: usize) -> io::Result<usize> {
+        if let Async::NotReady = self.poll_write() {
+            return Err(mio::would_block())
+        }
+        let r = splice(pipe, self.get_ref().as_raw_fd(), len);
+        if let Err(ref e) = r {
+            if e.kind() == io::ErrorKind::WouldBlock {
+                self.need_write();
+            }
+        }
+        r
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn splice(fd_in: RawFd, fd_out: RawFd, len: usize) -> io::Result<usize> {
+    ::fd::splice(fd_in, fd_out, len)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn splice(_fd_in: RawFd, _fd_out: RawFd, _len: usize) -> io::Result<usize> {
+    Err(io::Error::new(io::ErrorKind::Other, "splice is only supported on Linux"))
+}
+
+/// A future which will copy all data from a reader into a writer, splicing
+/// it through a pipe where possible.
+///
+/// Created by the [`splice_copy`] function, this future will resolve to the
+/// number of bytes copied or an error if one happens.
+///
+/// [`splice_copy`]: fn.splice_copy.html
+pub struct SpliceCopy<R, W> {
+    reader: R,
+    writer: W,
+    transfer: Transfer,
+}
+
+/// Creates a future which represents copying all the bytes from one socket
+/// to another without copying them to userspace.
+///
+/// This behaves like [`copy`], completing once `reader` has hit EOF and all
+/// the bytes have been written to and flushed from `writer`, but on Linux the
+/// bytes are moved from one socket to the other by the kernel through a
+/// pipe, which saves the CPU time `copy` spends copying them in and out of
+/// its buffer. Elsewhere, or if a pipe can't be created or the kernel refuses
+/// to splice the sockets, the bytes are copied through a buffer as with
+/// `copy`.
+///
+/// On success the number of bytes is returned and the `reader` and `writer`
+/// are consumed. On error the error is returned and the I/O objects are
+/// consumed as well.
+///
+/// [`copy`]: fn.copy.html
+pub fn splice_copy<R, W>(reader: R, writer: W) -> SpliceCopy<R, W>
+    where R: Splice,
+          W: Splice,
+{
+    SpliceCopy {
+        reader: reader,
+        writer: writer,
+        transfer: Transfer::new(),
+    }
+}
+
+impl<R, W> Future for SpliceCopy<R, W>
+    where R: Splice,
+          W: Splice,
+{
+    type Item = u64;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<u64, io::Error> {
+        try_ready!(self.transfer.poll(&mut self.reader, &mut self.writer));
+        try_nb!(self.writer.flush());
+        Ok(self.transfer.amt.into())
+    }
+}
+
+/// A future which copies data in both directions between two sockets,
+/// splicing it through pipes where possible.
+///
+/// Created by the [`splice_bidirectional`] function, this future will resolve
+/// to the number of bytes copied in each direction or an error if one
+/// happens.
+///
+/// [`splice_bidirectional`]: fn.splice_bidirectional.html
+pub struct SpliceBidirectional<A, B> {
+    a: A,
+    b: B,
+    a_to_b: Transfer,
+    b_to_a: Transfer,
+}
+
+/// Creates a future which copies all the bytes read from `a` to `b`, and all
+/// the bytes read from `b` to `a`, at the same time, without copying them to
+/// userspace.
+///
+/// This behaves like [`copy_bidirectional`], passing the EOF of each socket
+/// on to the other one with `AsyncWrite::shutdown`, but moves the
+/// bytes through pipes with the kernel on Linux, as [`splice_copy`] does.
+/// Each direction falls back to copying through a buffer on its own where
+/// splicing isn't possible.
+///
+/// [`copy_bidirectional`]: fn.copy_bidirectional.html
+/// [`splice_copy`]: fn.splice_copy.html
+pub fn splice_bidirectional<A, B>(a: A, b: B) -> SpliceBidirectional<A, B>
+    where A: Splice + AsyncWrite,
+          B: Splice + AsyncWrite,
+{
+    SpliceBidirectional {
+        a: a,
+        b: b,
+        a_to_b: Transfer::new(),
+        b_to_a: Transfer::new(),
+    }
+}
+
+impl<A, B> Future for SpliceBidirectional<A, B>
+    where A: Splice + AsyncWrite,
+          B: Splice + AsyncWrite,
+{
+    type Item = (u64, u64);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(u64, u64), io::Error> {
+        let a_to_b = try!(self.a_to_b.poll_half_close(&mut self.a, &mut self.b));
+        let b_to_a = try!(self.b_to_a.poll_half_close(&mut self.b, &mut self.a));
+        match (a_to_b, b_to_a) {
+            (Async::Ready(()), Async::Ready(())) => {
+                Ok(Async::Ready((self.a_to_b.amt, self.b_to_a.amt)))
+            }
+            _ => Ok(Async::NotReady),
+        }
+    }
+}
+
+/// The state of copying data in one direction.
+struct Transfer {
+    mode: Mode,
+    amt: u64,
+    read_done: bool,
+    done: bool,
+}
+
+enum Mode {
+    /// Nothing has been tried yet.
+    Start,
+    /// Splicing through a pipe holding `len` bytes.
+    #[cfg(target_os = "linux")]
+    Pipe {
+        rd: ::fd::OwnedFd,
+        wr: ::fd::OwnedFd,
+        len: usize,
+    },
+    /// Copying through a buffer, as `copy` does.
+    Buffer {
+        buf: Box<[u8]>,
+        pos: usize,
+        cap: usize,
+    },
+}
+
+impl Transfer {
+    fn new() -> Transfer {
+        Transfer {
+            mode: Mode::Start,
+            amt: 0,
+            read_done: false,
+            done: false,
+        }
+    }
+
+    /// Moves the data until the reader hits EOF and all of it has been
+    /// written, without flushing the writer.
+    fn poll<R, W>(&mut self, reader: &mut R, writer: &mut W) -> Poll<(), io::Error>
+        where R: Splice,
+              W: Splice,
+    {
+        loop {
+            match self.mode {
+                Mode::Start => self.mode = Mode::pipe(),
+                #[cfg(target_os = "linux")]
+                Mode::Pipe { ref rd, ref wr, ref mut len } => {
+                    if *len == 0 && !self.read_done {
+                        match reader.splice_to(wr.as_raw_fd(), PIPE_LEN) {
+                            Ok(0) => self.read_done = true,
+                            Ok(n) => *len = n,
+                            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                                return Ok(Async::NotReady)
+                            }
+                            // The kernel can't splice the reader, so fall
+                            // back to a buffer while the pipe is empty.
+                            Err(ref e) if e.raw_os_error() == Some(::libc::EINVAL) => {
+                                debug!("splice refused, copying through a buffer");
+                                self.mode = Mode::buffer();
+                                continue
+                            }
+                            Err(e) => return Err(e),
+                        }
+                    }
+
+                    let mut fallback = None;
+                    while *len > 0 {
+                        let i = match writer.splice_from(rd.as_raw_fd(), *len) {
+                            Ok(i) => i,
+                            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                                return Ok(Async::NotReady)
+                            }
+                            // The kernel can't splice to the writer, so the
+                            // data is taken back out of the pipe to be
+                            // written from a buffer.
+                            Err(ref e) if e.raw_os_error() == Some(::libc::EINVAL) => {
+                                debug!("splice refused, copying through a buffer");
+                                let cap = *len;
+                                let mut buf = vec![0; cmp::max(cap, BUF_LEN)];
+                                try!((&*rd).read_exact(&mut buf[..cap]));
+                                fallback = Some(Mode::Buffer {
+                                    buf: buf.into_boxed_slice(),
+                                    pos: 0,
+                                    cap: cap,
+                                });
+                                break
+                            }
+                            Err(e) => return Err(e),
+                        };
+                        if i == 0 {
+                            return Err(io::Error::new(io::ErrorKind::WriteZero,
+                                                      "write zero byte into writer"))
+                        }
+                        *len -= i;
+                        self.amt += i as u64;
+                    }
+
+                    if let Some(mode) = fallback {
+                        self.mode = mode;
+                        continue
+                    }
+                    if self.read_done {
+                        return Ok(Async::Ready(()))
+                    }
+                }
+                Mode::Buffer { ref mut buf, ref mut pos, ref mut cap } => {
+                    if *pos == *cap && !self.read_done {
+                        let n = try_nb!(reader.read(buf));
+                        if n == 0 {
+                            self.read_done = true;
+                        } else {
+                            *pos = 0;
+                            *cap = n;
+                        }
+                    }
+
+                    while *pos < *cap {
+                        let i = try_nb!(writer.write(&buf[*pos..*cap]));
+                        if i == 0 {
+                            return Err(io::Error::new(io::ErrorKind::WriteZero,
+                                                      "write zero byte into writer"))
+                        }
+                        *pos += i;
+                        self.amt += i as u64;
+                    }
+
+                    if *pos == *cap && self.read_done {
+                        return Ok(Async::Ready(()))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves the data, then flushes the writer and shuts down its writing
+    /// half to pass the EOF on.
+    fn poll_half_close<R, W>(&mut self, reader: &mut R, writer: &mut W)
+                             -> Poll<(), io::Error>
+        where R: Splice,
+              W: Splice + AsyncWrite,
+    {
+        if !self.done {
+            try_ready!(self.poll(reader, writer));
+            try_ready!(writer.shutdown());
+            self.done = true;
+        }
+        Ok(Async::Ready(()))
+    }
+}
+
+impl Mode {
+    #[cfg(target_os = "linux")]
+    fn pipe() -> Mode {
+        match ::fd::pipe() {
+            Ok((rd, wr)) => Mode::Pipe { rd: rd, wr: wr, len: 0 },
+            Err(e) => {
+                debug!("failed to create a pipe to splice through: {}", e);
+                Mode::buffer()
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn pipe() -> Mode {
+        Mode::buffer()
+    }
+
+    fn buffer() -> Mode {
+        Mode::Buffer {
+            buf: vec![0; BUF_LEN].into_boxed_slice(),
+            pos: 0,
+            cap: 0,
+        }
+    }
+}
@@ -1,35 +1,172 @@
-use std::cell::RefCell;
 use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex};
 
-use futures::task::TaskRc;
+use futures::{Async, Poll};
+
+use io::{AsyncRead, AsyncWrite};
 
 /// The readable half of an object returned from `Io::split`.
 pub struct ReadHalf<T> {
-    handle: TaskRc<RefCell<T>>,
+    io: Arc<Mutex<T>>,
 }
 
-/// The readable half of an object returned from `Io::split`.
+/// The writable half of an object returned from `Io::split`.
 pub struct WriteHalf<T> {
-    handle: TaskRc<RefCell<T>>,
+    io: Arc<Mutex<T>>,
+}
+
+/// The readable half of an object returned from `Io::split_shared`.
+pub struct SharedReadHalf<T> {
+    io: Arc<T>,
+}
+
+/// The writable half of an object returned from `Io::split_shared`.
+pub struct SharedWriteHalf<T> {
+    io: Arc<T>,
+}
+
+pub fn split<T>(t: T) -> (ReadHalf<T>, WriteHalf<T>) {
+    let io = Arc::new(Mutex::new(t));
+    (ReadHalf { io: io.clone() }, WriteHalf { io: io })
+}
+
+pub fn split_shared<T>(t: T) -> (SharedReadHalf<T>, SharedWriteHalf<T>) {
+    let io = Arc::new(t);
+    (SharedReadHalf { io: io.clone() }, SharedWriteHalf { io: io })
 }
 
-pub fn split<T: Read + Write>(t: T) -> (ReadHalf<T>, WriteHalf<T>) {
-    let rc = TaskRc::new(RefCell::new(t));
-    (ReadHalf { handle: rc.clone() }, WriteHalf { handle: rc })
+impl<T> ReadHalf<T> {
+    /// Puts this half back together with the `WriteHalf` split off with it,
+    /// returning the original object.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `other` wasn't split from the same object.
+    pub fn unsplit(self, other: WriteHalf<T>) -> T {
+        assert!(Arc::ptr_eq(&self.io, &other.io),
+                "unsplit halves of different objects");
+        drop(other);
+        match Arc::try_unwrap(self.io) {
+            Ok(io) => io.into_inner().unwrap(),
+            Err(_) => unreachable!(),
+        }
+    }
 }
 
 impl<T: Read> Read for ReadHalf<T> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.handle.with(|t| t.borrow_mut().read(buf))
+        self.io.lock().unwrap().read(buf)
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for ReadHalf<T> {
+    fn poll_read(&mut self) -> Async<()> {
+        self.io.lock().unwrap().poll_read()
     }
 }
 
 impl<T: Write> Write for WriteHalf<T> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.handle.with(|t| t.borrow_mut().write(buf))
+        self.io.lock().unwrap().write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        self.io.lock().unwrap().write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.io.lock().unwrap().flush()
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for WriteHalf<T> {
+    fn poll_write(&mut self) -> Async<()> {
+        self.io.lock().unwrap().poll_write()
+    }
+
+    fn poll_flush(&mut self) -> Poll<(), io::Error> {
+        self.io.lock().unwrap().poll_flush()
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.io.lock().unwrap().shutdown()
+    }
+}
+
+impl<T> SharedReadHalf<T> {
+    /// Returns a reference to the object this half was split from.
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    /// Puts this half back together with the `SharedWriteHalf` split off
+    /// with it, returning the original object.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `other` wasn't split from the same object.
+    pub fn unsplit(self, other: SharedWriteHalf<T>) -> T {
+        assert!(Arc::ptr_eq(&self.io, &other.io),
+                "unsplit halves of different objects");
+        drop(other);
+        match Arc::try_unwrap(self.io) {
+            Ok(io) => io,
+            Err(_) => unreachable!(),
+        }
+    }
+}
+
+impl<T> SharedWriteHalf<T> {
+    /// Returns a reference to the object this half was split from.
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+}
+
+impl<T> Read for SharedReadHalf<T>
+    where for<'a> &'a T: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&*self.io).read(buf)
+    }
+}
+
+impl<T> AsyncRead for SharedReadHalf<T>
+    where for<'a> &'a T: AsyncRead,
+{
+    fn poll_read(&mut self) -> Async<()> {
+        (&*self.io).poll_read()
+    }
+}
+
+impl<T> Write for SharedWriteHalf<T>
+    where for<'a> &'a T: Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&*self.io).write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        (&*self.io).write_vectored(bufs)
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        self.handle.with(|t| t.borrow_mut().flush())
+        (&*self.io).flush()
+    }
+}
+
+impl<T> AsyncWrite for SharedWriteHalf<T>
+    where for<'a> &'a T: AsyncWrite,
+{
+    fn poll_write(&mut self) -> Async<()> {
+        (&*self.io).poll_write()
+    }
+
+    fn poll_flush(&mut self) -> Poll<(), io::Error> {
+        (&*self.io).poll_flush()
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        (&*self.io).shutdown()
     }
 }
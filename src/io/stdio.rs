@@ -0,0 +1,246 @@
+use std::io::{self, Read, Write};
+use std::sync::mpsc;
+use std::thread;
+
+use futures::Async;
+use futures::stream::Stream;
+use mio;
+
+use channel::{self, Receiver};
+use io::{AsyncRead, AsyncWrite};
+use reactor::Handle;
+
+/// The amount of data read from the process's standard input at a time.
+const READ_SIZE: usize = 8 * 1024;
+
+/// The amount of written data which may be in flight to standard output or
+/// standard error before writes start to return "would block".
+const MAX_PENDING: usize = 64 * 1024;
+
+/// A handle to the standard input of the current process, associated with an
+/// event loop.
+///
+/// Created by the `stdin` function, reads from this handle return "would
+/// block" errors until data is available, scheduling the current task to be
+/// notified when it is.
+pub struct Stdin {
+    rx: Receiver<io::Result<Vec<u8>>>,
+    buf: Vec<u8>,
+    pos: usize,
+    eof: bool,
+}
+
+/// A handle to the standard output of the current process, associated with an
+/// event loop.
+///
+/// Created by the `stdout` function. Writes are queued and return immediately
+/// unless too much data is already waiting to be written, in which case a
+/// "would block" error is returned and the current task is notified once more
+/// can be accepted. Flushing waits for all queued data to be written.
+pub struct Stdout {
+    inner: Writer,
+}
+
+/// A handle to the standard error of the current process, associated with an
+/// event loop.
+///
+/// Created by the `stderr` function, this behaves in the same way as `Stdout`.
+pub struct Stderr {
+    inner: Writer,
+}
+
+struct Writer {
+    tx: mpsc::Sender<Vec<u8>>,
+    acks: Receiver<io::Result<usize>>,
+    pending: usize,
+    error: Option<io::Error>,
+}
+
+/// Creates a handle to the standard input of the current process, associated
+/// with the event loop `handle` points to.
+///
+/// Standard input isn't placed into nonblocking mode, as that would also
+/// affect any other process sharing it (such as the shell this process was
+/// started from), and on some platforms the console can't be polled at all.
+/// Instead it's read from by a helper thread which hands the data over to the
+/// event loop. Only one `Stdin` should be used at a time, since each one
+/// reads from the process's standard input separately.
+pub fn stdin(handle: &Handle) -> io::Result<Stdin> {
+    let (tx, rx) = try!(channel::sync_channel(1, handle));
+    try!(thread::Builder::new().name("tokio-stdin".to_string()).spawn(move || {
+        let stdin = io::stdin();
+        let mut stdin = stdin.lock();
+        loop {
+            let mut buf = vec![0; READ_SIZE];
+            let res = match stdin.read(&mut buf) {
+                Ok(n) => {
+                    buf.truncate(n);
+                    Ok(buf)
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => Err(e),
+            };
+            let done = match res {
+                Ok(ref buf) => buf.is_empty(),
+                Err(_) => true,
+            };
+            if tx.send(res).is_err() || done {
+                break
+            }
+        }
+    }));
+    Ok(Stdin {
+        rx: rx,
+        buf: Vec::new(),
+        pos: 0,
+        eof: false,
+    })
+}
+
+/// Creates a handle to the standard output of the current process,
+/// associated with the event loop `handle` points to.
+///
+/// As with `stdin`, the data written is handed off to a helper thread which
+/// performs the blocking writes.
+pub fn stdout(handle: &Handle) -> io::Result<Stdout> {
+    Writer::new("tokio-stdout", handle, io::stdout).map(|w| Stdout { inner: w })
+}
+
+/// Creates a handle to the standard error of the current process,
+/// associated with the event loop `handle` points to.
+///
+/// As with `stdin`, the data written is handed off to a helper thread which
+/// performs the blocking writes.
+pub fn stderr(handle: &Handle) -> io::Result<Stderr> {
+    Writer::new("tokio-stderr", handle, io::stderr).map(|w| Stderr { inner: w })
+}
+
+impl Read for Stdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            if self.eof {
+                return Ok(0)
+            }
+            match try!(self.rx.poll()) {
+                Async::Ready(Some(Ok(data))) => {
+                    self.eof = data.is_empty();
+                    self.buf = data;
+                    self.pos = 0;
+                }
+                Async::Ready(Some(Err(e))) => {
+                    self.eof = true;
+                    return Err(e)
+                }
+                Async::Ready(None) => self.eof = true,
+                Async::NotReady => return Err(mio::would_block()),
+            }
+        }
+        let n = try!((&self.buf[self.pos..]).read(buf));
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl AsyncRead for Stdin {}
+
+impl Writer {
+    fn new<F, W>(name: &str, handle: &Handle, stream: F) -> io::Result<Writer>
+        where F: FnOnce() -> W + Send + 'static,
+              W: Write,
+    {
+        let (tx, rx) = mpsc::channel::<Vec<u8>>();
+        let (ack_tx, acks) = try!(channel::channel(handle));
+        try!(thread::Builder::new().name(name.to_string()).spawn(move || {
+            let mut stream = stream();
+            for buf in rx {
+                let res = stream.write_all(&buf).and_then(|()| stream.flush());
+                if ack_tx.send(res.map(|()| buf.len())).is_err() {
+                    break
+                }
+            }
+        }));
+        Ok(Writer {
+            tx: tx,
+            acks: acks,
+            pending: 0,
+            error: None,
+        })
+    }
+
+    /// Processes acknowledgements of writes completed by the helper thread,
+    /// returning whether any more are outstanding.
+    fn poll_acks(&mut self) -> io::Result<Async<()>> {
+        while self.pending > 0 {
+            match try!(self.acks.poll()) {
+                Async::Ready(Some(Ok(n))) => self.pending -= n,
+                Async::Ready(Some(Err(e))) => {
+                    self.pending = 0;
+                    self.error = Some(e);
+                }
+                Async::Ready(None) => {
+                    self.pending = 0;
+                    self.error = Some(io::Error::new(io::ErrorKind::Other,
+                                                     "writer thread has exited"));
+                }
+                Async::NotReady => return Ok(Async::NotReady),
+            }
+        }
+        Ok(Async::Ready(()))
+    }
+
+    fn take_error(&mut self) -> io::Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        try!(self.poll_acks());
+        try!(self.take_error());
+        if self.pending >= MAX_PENDING {
+            return Err(mio::would_block())
+        }
+        if self.tx.send(buf.to_vec()).is_err() {
+            return Err(io::Error::new(io::ErrorKind::Other,
+                                      "writer thread has exited"))
+        }
+        self.pending += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let ready = try!(self.poll_acks());
+        try!(self.take_error());
+        match ready {
+            Async::Ready(()) => Ok(()),
+            Async::NotReady => Err(mio::would_block()),
+        }
+    }
+}
+
+impl Write for Stdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Write for Stderr {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl AsyncWrite for Stdout {}
+
+impl AsyncWrite for Stderr {}
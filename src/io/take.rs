@@ -0,0 +1,110 @@
+use std::cmp;
+use std::io::{self, Read, Write};
+
+use futures::{Async, Poll};
+
+use io::{AsyncRead, AsyncWrite};
+
+/// An adapter which reads at most a limited number of bytes from a reader.
+///
+/// Created by the [`take`] function.
+///
+/// [`take`]: fn.take.html
+#[derive(Debug)]
+pub struct Take<R> {
+    inner: R,
+    limit: u64,
+}
+
+/// Creates an adapter which reads at most `limit` bytes from `reader`, after
+/// which it reads EOF.
+///
+/// This is like `Read::take`, except that writes are passed through to
+/// `reader`, so taking from an `Io` object gives an `Io` object. This is what
+/// reading a body with a known length, such as one given by a
+/// `Content-Length` header, needs: the `Take` can be read to its end, or
+/// handed to a codec, and then unwrapped with `into_inner` to read what
+/// follows the body.
+pub fn take<R: Read>(reader: R, limit: u64) -> Take<R> {
+    Take {
+        inner: reader,
+        limit: limit,
+    }
+}
+
+impl<R> Take<R> {
+    /// Returns the number of bytes which can still be read before EOF.
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// Sets the number of bytes which can be read before EOF.
+    pub fn set_limit(&mut self, limit: u64) {
+        self.limit = limit;
+    }
+
+    /// Gets a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying reader.
+    ///
+    /// Care should be taken to avoid reading from the underlying reader
+    /// directly, as the data read wouldn't count towards the limit.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Consumes the `Take`, returning the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: Read> Read for Take<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.limit == 0 {
+            return Ok(0)
+        }
+        let max = cmp::min(buf.len() as u64, self.limit) as usize;
+        let n = try!(self.inner.read(&mut buf[..max]));
+        self.limit -= n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Write> Write for Take<R> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<R: AsyncRead> AsyncRead for Take<R> {
+    fn poll_read(&mut self) -> Async<()> {
+        // EOF can be read right away once the limit is reached.
+        if self.limit == 0 {
+            Async::Ready(())
+        } else {
+            self.inner.poll_read()
+        }
+    }
+}
+
+impl<R: AsyncWrite> AsyncWrite for Take<R> {
+    fn poll_write(&mut self) -> Async<()> {
+        self.inner.poll_write()
+    }
+
+    fn poll_flush(&mut self) -> Poll<(), io::Error> {
+        self.inner.poll_flush()
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.inner.shutdown()
+    }
+}
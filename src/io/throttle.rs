@@ -0,0 +1,221 @@
+use std::cmp;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+use futures::{Async, Future, Poll};
+
+use io::{AsyncRead, AsyncWrite};
+use reactor::{Handle, Timeout};
+
+/// An I/O object which limits the rate at which bytes are read from and
+/// written to another one.
+///
+/// Each direction of a `Throttle` has a bucket of tokens, one per byte, which
+/// is refilled at the configured rate in bytes per second and holds a tenth
+/// of a second's worth of them. Reads and writes are truncated to the tokens
+/// available, and when the bucket is empty they return a "would block" error
+/// and a timer is started on the event loop to wake up the task once tokens
+/// are available again. Bursts are thereby limited as well as the average
+/// rate.
+///
+/// The rates of both directions are unlimited by default. A `Throttle` can be
+/// used with any of the futures of this module, as well as with
+/// `Io::framed`.
+pub struct Throttle<T> {
+    inner: T,
+    handle: Handle,
+    read: Bucket,
+    write: Bucket,
+}
+
+/// The token bucket of one direction of a `Throttle`.
+struct Bucket {
+    rate: Option<u64>,
+    tokens: f64,
+    last: Instant,
+    timeout: Option<Timeout>,
+}
+
+impl<T> Throttle<T> {
+    /// Wraps `inner`, with timers created on the event loop of `handle`.
+    ///
+    /// No rates are set initially, so `set_read_rate` and `set_write_rate`
+    /// should be called to configure them.
+    pub fn new(inner: T, handle: &Handle) -> Throttle<T> {
+        Throttle {
+            inner: inner,
+            handle: handle.clone(),
+            read: Bucket::new(),
+            write: Bucket::new(),
+        }
+    }
+
+    /// Returns the read rate of this object in bytes per second, if any.
+    pub fn read_rate(&self) -> Option<u64> {
+        self.read.rate
+    }
+
+    /// Sets the read rate of this object in bytes per second.
+    ///
+    /// The bucket of read tokens starts out full, and `None` removes the
+    /// limit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is `Some(0)`.
+    pub fn set_read_rate(&mut self, rate: Option<u64>) {
+        self.read.set(rate)
+    }
+
+    /// Returns the write rate of this object in bytes per second, if any.
+    pub fn write_rate(&self) -> Option<u64> {
+        self.write.rate
+    }
+
+    /// Sets the write rate of this object in bytes per second.
+    ///
+    /// The bucket of write tokens starts out full, and `None` removes the
+    /// limit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `rate` is `Some(0)`.
+    pub fn set_write_rate(&mut self, rate: Option<u64>) {
+        self.write.set(rate)
+    }
+
+    /// Gets a reference to the underlying I/O object.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying I/O object.
+    ///
+    /// Reads and writes made directly on the underlying object aren't
+    /// limited.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes this `Throttle`, returning the underlying I/O object.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl Bucket {
+    fn new() -> Bucket {
+        Bucket {
+            rate: None,
+            tokens: 0.0,
+            last: Instant::now(),
+            timeout: None,
+        }
+    }
+
+    fn set(&mut self, rate: Option<u64>) {
+        assert!(rate != Some(0), "rate must be greater than zero");
+        self.rate = rate;
+        self.tokens = rate.map(capacity).unwrap_or(0.0);
+        self.last = Instant::now();
+        self.timeout = None;
+    }
+
+    /// Returns how many of `want` bytes may be transferred now, or a "would
+    /// block" error if none may be, in which case the current task is
+    /// notified once some may be.
+    fn acquire(&mut self, want: usize, handle: &Handle) -> io::Result<usize> {
+        let rate = match self.rate {
+            Some(rate) if want > 0 => rate,
+            _ => return Ok(want),
+        };
+        loop {
+            let now = Instant::now();
+            let elapsed = now - self.last;
+            let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+            self.tokens = (self.tokens + secs * rate as f64).min(capacity(rate));
+            self.last = now;
+            if self.tokens >= 1.0 {
+                self.timeout = None;
+                return Ok((want as f64).min(self.tokens) as usize)
+            }
+
+            // Wait for the next token to come in.
+            if self.timeout.is_none() {
+                let nanos = ((1.0 - self.tokens) / rate as f64 * 1e9) as u64 + 1;
+                let dur = Duration::new(nanos / 1_000_000_000,
+                                        (nanos % 1_000_000_000) as u32);
+                self.timeout = Some(try!(Timeout::new(dur, handle)));
+            }
+            match try!(self.timeout.as_mut().unwrap().poll()) {
+                Async::Ready(()) => self.timeout = None,
+                Async::NotReady => {
+                    return Err(io::Error::new(io::ErrorKind::WouldBlock,
+                                              "rate limit exceeded"))
+                }
+            }
+        }
+    }
+
+    /// Takes the tokens of `n` bytes transferred out of the bucket.
+    fn consume(&mut self, n: usize) {
+        if self.rate.is_some() {
+            self.tokens -= n as f64;
+        }
+    }
+}
+
+/// The number of tokens a bucket refilled at `rate` holds.
+fn capacity(rate: u64) -> f64 {
+    cmp::max(rate / 10, 1) as f64
+}
+
+impl<T: Read> Read for Throttle<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = try!(self.read.acquire(buf.len(), &self.handle));
+        let n = try!(self.inner.read(&mut buf[..len]));
+        self.read.consume(n);
+        Ok(n)
+    }
+}
+
+impl<T: Write> Write for Throttle<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = try!(self.write.acquire(buf.len(), &self.handle));
+        let n = try!(self.inner.write(&buf[..len]));
+        self.write.consume(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for Throttle<T> {
+    fn poll_read(&mut self) -> Async<()> {
+        self.inner.poll_read()
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for Throttle<T> {
+    fn poll_write(&mut self) -> Async<()> {
+        self.inner.poll_write()
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        try_nb!(self.flush());
+        self.inner.shutdown()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Throttle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Throttle")
+         .field("inner", &self.inner)
+         .field("read_rate", &self.read.rate)
+         .field("write_rate", &self.write.rate)
+         .finish()
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use futures::{Async, Future, Poll};
+
+use io::{AsyncRead, AsyncWrite};
+use reactor::{Handle, Timeout};
+
+/// An I/O object which fails reads and writes which make no progress for too
+/// long.
+///
+/// A `TimeoutIo` wraps another I/O object and, once a read or a write of it
+/// would block, starts a timer on the event loop. If the timer fires before
+/// the operation makes progress then the operation fails with an error of the
+/// `TimedOut` kind. Any successful read resets the read timer, and any
+/// successful write resets the write timer, so the timeouts bound the time
+/// spent waiting on a peer rather than the total time of a transfer.
+///
+/// The read and write timeouts are independent and both disabled by default.
+/// As the timers are only checked while an operation is pending, a
+/// `TimeoutIo` can be used with any of the futures of this module, as well as
+/// with `Io::framed`.
+pub struct TimeoutIo<T> {
+    inner: T,
+    handle: Handle,
+    read: Timer,
+    write: Timer,
+}
+
+/// The timeout of one direction of a `TimeoutIo`.
+struct Timer {
+    dur: Option<Duration>,
+    timeout: Option<Timeout>,
+}
+
+impl<T> TimeoutIo<T> {
+    /// Wraps `inner`, with timers created on the event loop of `handle`.
+    ///
+    /// No timeouts are set initially, so `set_read_timeout` and
+    /// `set_write_timeout` should be called to configure them.
+    pub fn new(inner: T, handle: &Handle) -> TimeoutIo<T> {
+        TimeoutIo {
+            inner: inner,
+            handle: handle.clone(),
+            read: Timer::new(),
+            write: Timer::new(),
+        }
+    }
+
+    /// Returns the read timeout of this object, if any.
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read.dur
+    }
+
+    /// Sets the read timeout of this object.
+    ///
+    /// A pending read restarts waiting for the new duration, and `None`
+    /// disables the timeout.
+    pub fn set_read_timeout(&mut self, dur: Option<Duration>) {
+        self.read.set(dur)
+    }
+
+    /// Returns the write timeout of this object, if any.
+    pub fn write_timeout(&self) -> Option<Duration> {
+        self.write.dur
+    }
+
+    /// Sets the write timeout of this object.
+    ///
+    /// A pending write restarts waiting for the new duration, and `None`
+    /// disables the timeout.
+    pub fn set_write_timeout(&mut self, dur: Option<Duration>) {
+        self.write.set(dur)
+    }
+
+    /// Gets a reference to the underlying I/O object.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Gets a mutable reference to the underlying I/O object.
+    ///
+    /// Reads and writes made directly on the underlying object don't reset
+    /// the timers.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes this `TimeoutIo`, returning the underlying I/O object.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl Timer {
+    fn new() -> Timer {
+        Timer {
+            dur: None,
+            timeout: None,
+        }
+    }
+
+    fn set(&mut self, dur: Option<Duration>) {
+        self.dur = dur;
+        self.timeout = None;
+    }
+
+    /// Handles the result of an operation, starting the timer if it would
+    /// block and failing the operation if the timer has fired.
+    fn check<R>(&mut self, res: io::Result<R>, handle: &Handle) -> io::Result<R> {
+        match res {
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            res => {
+                self.timeout = None;
+                return res
+            }
+        }
+        let dur = match self.dur {
+            Some(dur) => dur,
+            None => return res,
+        };
+        if self.timeout.is_none() {
+            self.timeout = Some(try!(Timeout::new(dur, handle)));
+        }
+        match try!(self.timeout.as_mut().unwrap().poll()) {
+            Async::Ready(()) => {
+                self.timeout = None;
+                Err(io::Error::new(io::ErrorKind::TimedOut, "I/O operation timed out"))
+            }
+            Async::NotReady => res,
+        }
+    }
+}
+
+impl<T: Read> Read for TimeoutIo<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let res = self.inner.read(buf);
+        self.read.check(res, &self.handle)
+    }
+}
+
+impl<T: Write> Write for TimeoutIo<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let res = self.inner.write(buf);
+        self.write.check(res, &self.handle)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let res = self.inner.flush();
+        self.write.check(res, &self.handle)
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for TimeoutIo<T> {
+    fn poll_read(&mut self) -> Async<()> {
+        self.inner.poll_read()
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for TimeoutIo<T> {
+    fn poll_write(&mut self) -> Async<()> {
+        self.inner.poll_write()
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        try_nb!(self.flush());
+        self.inner.shutdown()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TimeoutIo<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TimeoutIo")
+         .field("inner", &self.inner)
+         .field("read_timeout", &self.read.dur)
+         .field("write_timeout", &self.write.dur)
+         .finish()
+    }
+}
@@ -0,0 +1,101 @@
+use std::io;
+
+use futures::{Async, Future, IntoFuture, Poll};
+
+use io::Io;
+
+/// A trait for asynchronously transforming one I/O object into another.
+///
+/// This is the integration point for protocols which wrap an existing
+/// transport, the canonical example being a TLS handshake turning a
+/// `TcpStream` into an encrypted stream. A TLS library implements this trait
+/// for its connector or acceptor type, and the result can then be used
+/// anywhere an `Io` object is expected, for example with [`upgrade`] directly
+/// after connecting.
+///
+/// Upgrades are performed through a shared reference so one configuration,
+/// such as a TLS context, can be used to upgrade many I/O objects. Closures
+/// returning a future of the upgraded object implement this trait as well.
+///
+/// [`upgrade`]: fn.upgrade.html
+pub trait Upgrade<T> {
+    /// The I/O object produced by the upgrade.
+    type Output: Io;
+
+    /// The future performing the upgrade.
+    type Future: Future<Item=Self::Output, Error=io::Error>;
+
+    /// Starts upgrading `io`, returning a future which resolves to the
+    /// upgraded I/O object once it's complete.
+    fn upgrade(&self, io: T) -> Self::Future;
+}
+
+impl<T, F, R> Upgrade<T> for F
+    where F: Fn(T) -> R,
+          R: IntoFuture<Error=io::Error>,
+          R::Item: Io,
+{
+    type Output = R::Item;
+    type Future = R::Future;
+
+    fn upgrade(&self, io: T) -> R::Future {
+        self(io).into_future()
+    }
+}
+
+/// A future which resolves an I/O object and then upgrades it.
+///
+/// Created by the [`upgrade`] function.
+///
+/// [`upgrade`]: fn.upgrade.html
+pub struct Upgrading<F, U>
+    where F: Future,
+          U: Upgrade<F::Item>,
+{
+    state: State<F, U>,
+}
+
+enum State<F, U>
+    where F: Future,
+          U: Upgrade<F::Item>,
+{
+    First(F, U),
+    Second(U::Future),
+}
+
+/// Creates a future which waits for the I/O object produced by `future`, such
+/// as a connecting `TcpStream`, and then upgrades it with `upgrader`.
+///
+/// The returned future resolves to the upgraded I/O object, or to the first
+/// error encountered while producing or upgrading it.
+pub fn upgrade<F, U>(future: F, upgrader: U) -> Upgrading<F, U>
+    where F: Future<Error=io::Error>,
+          U: Upgrade<F::Item>,
+{
+    Upgrading {
+        state: State::First(future, upgrader),
+    }
+}
+
+impl<F, U> Future for Upgrading<F, U>
+    where F: Future<Error=io::Error>,
+          U: Upgrade<F::Item>,
+{
+    type Item = U::Output;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<U::Output, io::Error> {
+        loop {
+            let next = match self.state {
+                State::First(ref mut future, ref upgrader) => {
+                    match try!(future.poll()) {
+                        Async::Ready(io) => upgrader.upgrade(io),
+                        Async::NotReady => return Ok(Async::NotReady),
+                    }
+                }
+                State::Second(ref mut future) => return future.poll(),
+            };
+            self.state = State::Second(next);
+        }
+    }
+}
@@ -10,8 +10,12 @@ use std::ops;
 /// underlying buffer of bytes.
 ///
 /// A `Window<T>` wraps an underlying buffer, `T`, and has configurable
-/// start/end indexes to alter the behavior of the `AsRef<[u8]>` implementation
-/// that this type carries.
+/// start/end indexes to alter the behavior of the `AsRef<[u8]>` and
+/// `AsMut<[u8]>` implementations that this type carries. The buffer can be
+/// anything which is `AsRef<[u8]>`, owned like a `Vec<u8>` or borrowed like a
+/// `&mut [u8]`, so a window can also be moved over one part of a larger
+/// buffer after another, for example to read into or write out of it in
+/// pieces.
 ///
 /// This type can be particularly useful when working with the `write_all`
 /// combinator in this crate. Data can be sliced via `Window`, consumed by
@@ -43,6 +47,10 @@ impl<T: AsRef<[u8]>> Window<T> {
 
     /// Gets a mutable reference to the underlying buffer inside of this
     /// `Window`.
+    ///
+    /// The window isn't changed if the buffer is resized through this
+    /// reference: `grow_to_end` includes data appended to the buffer, and the
+    /// window must not be left past the end of a buffer which is shrunk.
     pub fn get_mut(&mut self) -> &mut T {
         &mut self.inner
     }
@@ -73,7 +81,7 @@ impl<T: AsRef<[u8]>> Window<T> {
     /// This method will panic if `start` is out of bounds for the underlying
     /// slice or if it comes after the `end` configured in this window.
     pub fn set_start(&mut self, start: usize) -> &mut Window<T> {
-        assert!(start < self.inner.as_ref().len());
+        assert!(start <= self.inner.as_ref().len());
         assert!(start <= self.range.end);
         self.range.start = start;
         self
@@ -86,21 +94,59 @@ impl<T: AsRef<[u8]>> Window<T> {
     /// # Panics
     ///
     /// This method will panic if `end` is out of bounds for the underlying
-    /// slice or if it comes after the `end` configured in this window.
+    /// slice or if it comes before the `start` configured in this window.
     pub fn set_end(&mut self, end: usize) -> &mut Window<T> {
-        assert!(end < self.inner.as_ref().len());
+        assert!(end <= self.inner.as_ref().len());
         assert!(self.range.start <= end);
         self.range.end = end;
         self
     }
 
-    // TODO: how about a generic set() method along the lines of:
-    //
-    //       buffer.set(..3)
-    //             .set(0..2)
-    //             .set(4..)
-    //
-    // etc.
+    /// Changes both indexes of this window to the range specified, such as
+    /// `2..5`, `3..` or `..`, which are relative to the underlying buffer.
+    ///
+    /// Returns the windows back to chain multiple calls to this method.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if `range` is out of bounds for the underlying
+    /// slice or if its start comes after its end.
+    pub fn set_range<R>(&mut self, range: R) -> &mut Window<T>
+        where R: ops::RangeBounds<usize>,
+    {
+        let len = self.inner.as_ref().len();
+        let start = match range.start_bound() {
+            ops::Bound::Included(&n) => n,
+            ops::Bound::Excluded(&n) => n + 1,
+            ops::Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            ops::Bound::Included(&n) => n + 1,
+            ops::Bound::Excluded(&n) => n,
+            ops::Bound::Unbounded => len,
+        };
+        assert!(end <= len);
+        assert!(start <= end);
+        self.range = start..end;
+        self
+    }
+
+    /// Moves the end index of this window to the end of the underlying
+    /// buffer, growing the window to include any data the buffer has been
+    /// extended with since the window was created.
+    ///
+    /// Returns the windows back to chain multiple calls to this method.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the buffer has shrunk to before the `start`
+    /// configured in this window.
+    pub fn grow_to_end(&mut self) -> &mut Window<T> {
+        let len = self.inner.as_ref().len();
+        assert!(self.range.start <= len);
+        self.range.end = len;
+        self
+    }
 }
 
 impl<T: AsRef<[u8]>> AsRef<[u8]> for Window<T> {
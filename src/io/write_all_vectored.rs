@@ -0,0 +1,116 @@
+use std::cmp;
+use std::io::{self, IoSlice, Write};
+use std::mem;
+
+use futures::{Poll, Future};
+
+/// The most buffers passed to a single vectored write.
+const MAX_BUFS: usize = 64;
+
+/// A future used to write a list of buffers entirely to a writer.
+///
+/// Created by the [`write_all_vectored`] function.
+///
+/// [`write_all_vectored`]: fn.write_all_vectored.html
+pub struct WriteAllVectored<A, B> {
+    state: State<A, B>,
+}
+
+enum State<A, B> {
+    Writing {
+        a: A,
+        bufs: Vec<B>,
+        idx: usize,
+        pos: usize,
+    },
+    Empty,
+}
+
+/// Creates a future that will write the buffers of `bufs`, one after the
+/// other, entirely to the `a` object.
+///
+/// This is like [`write_all`] for data split across several buffers, such as
+/// a header and a body, without concatenating them first. The buffers are
+/// written with `Write::write_vectored`, which is a single `writev` call for
+/// the sockets and pipes of this crate, and the progress within the list is
+/// kept across partial writes.
+///
+/// The returned future will not complete until all the data has been written,
+/// and on success the object and the list of buffers are returned.
+///
+/// [`write_all`]: fn.write_all.html
+pub fn write_all_vectored<A, B>(a: A, bufs: Vec<B>) -> WriteAllVectored<A, B>
+    where A: Write,
+          B: AsRef<[u8]>,
+{
+    WriteAllVectored {
+        state: State::Writing {
+            a: a,
+            bufs: bufs,
+            idx: 0,
+            pos: 0,
+        },
+    }
+}
+
+fn zero_write() -> io::Error {
+    io::Error::new(io::ErrorKind::WriteZero, "zero-length write")
+}
+
+impl<A, B> Future for WriteAllVectored<A, B>
+    where A: Write,
+          B: AsRef<[u8]>,
+{
+    type Item = (A, Vec<B>);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(A, Vec<B>), io::Error> {
+        match self.state {
+            State::Writing { ref mut a, ref bufs, ref mut idx, ref mut pos } => {
+                loop {
+                    // Skip past the buffers which are done, including empty
+                    // ones.
+                    while *idx < bufs.len() && *pos == bufs[*idx].as_ref().len() {
+                        *idx += 1;
+                        *pos = 0;
+                    }
+                    if *idx == bufs.len() {
+                        break
+                    }
+
+                    let mut n = {
+                        let rest = &bufs[*idx..];
+                        let mut slices = Vec::with_capacity(cmp::min(rest.len(), MAX_BUFS));
+                        slices.push(IoSlice::new(&rest[0].as_ref()[*pos..]));
+                        for buf in rest[1..].iter().take(MAX_BUFS - 1) {
+                            slices.push(IoSlice::new(buf.as_ref()));
+                        }
+                        try_nb!(a.write_vectored(&slices))
+                    };
+                    if n == 0 {
+                        return Err(zero_write())
+                    }
+
+                    // Advance through the buffers written, possibly stopping
+                    // in the middle of one.
+                    while n > 0 {
+                        let remaining = bufs[*idx].as_ref().len() - *pos;
+                        if n < remaining {
+                            *pos += n;
+                            break
+                        }
+                        n -= remaining;
+                        *idx += 1;
+                        *pos = 0;
+                    }
+                }
+            }
+            State::Empty => panic!("poll a WriteAllVectored after it's done"),
+        }
+
+        match mem::replace(&mut self.state, State::Empty) {
+            State::Writing { a, bufs, .. } => Ok((a, bufs).into()),
+            State::Empty => panic!(),
+        }
+    }
+}
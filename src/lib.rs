@@ -72,8 +72,8 @@
 //!     // We use the `io::copy` future to copy all data from the
 //!     // reading half onto the writing half.
 //!     let done = socket.incoming().for_each(|(socket, addr)| {
-//!         let pair = futures::lazy(|| Ok(socket.split()));
-//!         let amt = pair.and_then(|(reader, writer)| copy(reader, writer));
+//!         let (reader, writer) = socket.split();
+//!         let amt = copy(reader, writer);
 //!
 //!         // Once all that is done we print out how much we wrote, and then
 //!         // critically we *spawn* this future which allows it to run
@@ -105,11 +105,27 @@ extern crate scoped_tls;
 #[macro_use]
 extern crate log;
 
+#[cfg(unix)]
+extern crate libc;
+
+#[cfg(feature = "flate2")]
+extern crate flate2;
+
 #[macro_use]
 pub mod io;
 
 mod mpsc_queue;
 mod heap;
+#[cfg(unix)]
+mod fd;
+mod pool;
 pub mod channel;
+pub mod fs;
 pub mod net;
+#[cfg(unix)]
+pub mod process;
+#[cfg(unix)]
+pub mod pty;
 pub mod reactor;
+#[cfg(unix)]
+pub mod signal;
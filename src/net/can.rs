@@ -0,0 +1,335 @@
+//! Linux SocketCAN sockets, integrated with the event loop.
+
+use std::ffi::CString;
+use std::fmt;
+use std::io;
+use std::mem;
+use std::os::unix::prelude::*;
+
+use futures::Async;
+use libc::{self, c_int};
+use mio;
+
+use fd::{self, EventedFd, OwnedFd};
+use net::sockopt::{self, cvt};
+use reactor::{Handle, PollEvented};
+
+/// An I/O object representing a raw CAN socket bound to a CAN interface.
+///
+/// Each call to `send` transmits a single `CanFrame` on the bus and each call
+/// to `recv` receives one, so a CAN bus can be driven from the event loop
+/// like any other socket. By default every frame seen on the interface is
+/// received, which can be narrowed down with `set_filters`.
+pub struct CanSocket {
+    io: PollEvented<EventedFd<OwnedFd>>,
+}
+
+/// A classic CAN frame, carrying up to 8 bytes of data.
+#[derive(Clone, Copy)]
+pub struct CanFrame {
+    frame: libc::can_frame,
+}
+
+/// A filter for the frames received by a `CanSocket`.
+///
+/// A frame matches the filter when the bits of its identifier selected by the
+/// mask are equal to those of the filter's identifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CanFilter {
+    id: u32,
+    mask: u32,
+}
+
+impl CanSocket {
+    /// Creates a new raw CAN socket bound to the interface named `ifname`,
+    /// such as `can0` or `vcan0`, associated with the event loop `handle`
+    /// points to.
+    pub fn open(ifname: &str, handle: &Handle) -> io::Result<CanSocket> {
+        let name = match CString::new(ifname) {
+            Ok(name) => name,
+            Err(_) => {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          "interface name contains a nul byte"))
+            }
+        };
+        let ifindex = unsafe { libc::if_nametoindex(name.as_ptr()) };
+        if ifindex == 0 {
+            return Err(io::Error::last_os_error())
+        }
+
+        let raw = try!(cvt(unsafe {
+            libc::socket(libc::PF_CAN, libc::SOCK_RAW, libc::CAN_RAW)
+        }));
+        let socket = unsafe { OwnedFd::new(raw) };
+        try!(fd::set_nonblocking_cloexec(raw));
+
+        let mut addr: libc::sockaddr_can = unsafe { mem::zeroed() };
+        addr.can_family = libc::AF_CAN as libc::sa_family_t;
+        addr.can_ifindex = ifindex as c_int;
+        try!(cvt(unsafe {
+            libc::bind(raw,
+                       &addr as *const _ as *const libc::sockaddr,
+                       mem::size_of::<libc::sockaddr_can>() as libc::socklen_t)
+        }));
+        let io = try!(PollEvented::new(EventedFd::new(socket), handle));
+        Ok(CanSocket { io: io })
+    }
+
+    /// Test whether this socket is ready to be read or not.
+    ///
+    /// If the socket is *not* readable then the current task is scheduled to
+    /// get a notification when the socket does become readable.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Test whether this socket is ready to be written to or not.
+    ///
+    /// If the socket is *not* writable then the current task is scheduled to
+    /// get a notification when the socket does become writable.
+    pub fn poll_write(&self) -> Async<()> {
+        self.io.poll_write()
+    }
+
+    /// Returns the token this socket is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
+    /// Transmits `frame` on the bus.
+    ///
+    /// If the socket isn't writable, for example because the transmit queue
+    /// of the interface is full, then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the
+    /// socket is writable again.
+    pub fn send(&self, frame: &CanFrame) -> io::Result<()> {
+        if let Async::NotReady = self.io.poll_write() {
+            return Err(mio::would_block())
+        }
+        let n = unsafe {
+            libc::write(self.as_raw_fd(),
+                        &frame.frame as *const _ as *const _,
+                        mem::size_of::<libc::can_frame>())
+        };
+        if n == -1 {
+            let e = io::Error::last_os_error();
+            if e.kind() == io::ErrorKind::WouldBlock {
+                self.io.need_write();
+            }
+            return Err(e)
+        }
+        Ok(())
+    }
+
+    /// Receives a frame from the bus.
+    ///
+    /// If no frame is available then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the
+    /// socket is readable again.
+    pub fn recv(&self) -> io::Result<CanFrame> {
+        if let Async::NotReady = self.io.poll_read() {
+            return Err(mio::would_block())
+        }
+        let mut frame: libc::can_frame = unsafe { mem::zeroed() };
+        let n = unsafe {
+            libc::read(self.as_raw_fd(),
+                       &mut frame as *mut _ as *mut _,
+                       mem::size_of::<libc::can_frame>())
+        };
+        if n == -1 {
+            let e = io::Error::last_os_error();
+            if e.kind() == io::ErrorKind::WouldBlock {
+                self.io.need_read();
+            }
+            return Err(e)
+        }
+        if n as usize != mem::size_of::<libc::can_frame>() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                      "received an incomplete CAN frame"))
+        }
+        Ok(CanFrame { frame: frame })
+    }
+
+    /// Sets the filters for the frames received by this socket, replacing
+    /// any previous ones.
+    ///
+    /// A frame is received if it matches any of the filters, so an empty list
+    /// of filters stops all frames from being received.
+    pub fn set_filters(&self, filters: &[CanFilter]) -> io::Result<()> {
+        let filters = filters.iter().map(|f| {
+            let mut filter: libc::can_filter = unsafe { mem::zeroed() };
+            filter.can_id = f.id;
+            filter.can_mask = f.mask;
+            filter
+        }).collect::<Vec<_>>();
+        try!(cvt(unsafe {
+            libc::setsockopt(self.as_raw_fd(),
+                             libc::SOL_CAN_RAW,
+                             libc::CAN_RAW_FILTER,
+                             filters.as_ptr() as *const _,
+                             mem::size_of_val(&filters[..]) as libc::socklen_t)
+        }));
+        Ok(())
+    }
+
+    /// Sets the mask of the error classes, such as bus-off or controller
+    /// problems, for which error frames are received by this socket.
+    ///
+    /// No error frames are received by default.
+    pub fn set_error_filter(&self, mask: u32) -> io::Result<()> {
+        sockopt::set(self.as_raw_fd(), libc::SOL_CAN_RAW,
+                     libc::CAN_RAW_ERR_FILTER, mask)
+    }
+
+    /// Sets whether the frames sent by this socket are looped back to the
+    /// other sockets on the same interface and host, which is the default.
+    pub fn set_loopback(&self, loopback: bool) -> io::Result<()> {
+        sockopt::set(self.as_raw_fd(), libc::SOL_CAN_RAW,
+                     libc::CAN_RAW_LOOPBACK, loopback as c_int)
+    }
+
+    /// Sets whether the frames sent by this socket are received by it as well
+    /// when loopback is enabled, which isn't the default.
+    pub fn set_recv_own_msgs(&self, recv: bool) -> io::Result<()> {
+        sockopt::set(self.as_raw_fd(), libc::SOL_CAN_RAW,
+                     libc::CAN_RAW_RECV_OWN_MSGS, recv as c_int)
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        let err: c_int = try!(sockopt::get(self.as_raw_fd(), libc::SOL_SOCKET,
+                                           libc::SO_ERROR));
+        if err == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(io::Error::from_raw_os_error(err)))
+        }
+    }
+}
+
+impl fmt::Debug for CanSocket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CanSocket")
+         .field("fd", &self.as_raw_fd())
+         .finish()
+    }
+}
+
+impl AsRawFd for CanSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl CanFrame {
+    /// Creates a data frame with the identifier `id` carrying `data`.
+    ///
+    /// Identifiers which fit in 11 bits are sent in the standard frame
+    /// format, and larger ones of up to 29 bits in the extended format. An
+    /// error is returned for larger identifiers or more than 8 bytes of data.
+    pub fn new(id: u32, data: &[u8]) -> io::Result<CanFrame> {
+        if data.len() > libc::CAN_MAX_DLEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "CAN frames carry at most 8 bytes"))
+        }
+        let mut frame = try!(CanFrame::with_id(id));
+        frame.frame.can_dlc = data.len() as u8;
+        frame.frame.data[..data.len()].copy_from_slice(data);
+        Ok(frame)
+    }
+
+    /// Creates a remote transmission request for `len` bytes of data from
+    /// the node sending frames with the identifier `id`.
+    pub fn new_remote(id: u32, len: usize) -> io::Result<CanFrame> {
+        if len > libc::CAN_MAX_DLEN {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "CAN frames carry at most 8 bytes"))
+        }
+        let mut frame = try!(CanFrame::with_id(id));
+        frame.frame.can_id |= libc::CAN_RTR_FLAG;
+        frame.frame.can_dlc = len as u8;
+        Ok(frame)
+    }
+
+    fn with_id(id: u32) -> io::Result<CanFrame> {
+        let can_id = if id <= libc::CAN_SFF_MASK {
+            id
+        } else if id <= libc::CAN_EFF_MASK {
+            id | libc::CAN_EFF_FLAG
+        } else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "CAN identifiers are at most 29 bits"))
+        };
+        let mut frame: libc::can_frame = unsafe { mem::zeroed() };
+        frame.can_id = can_id;
+        Ok(CanFrame { frame: frame })
+    }
+
+    /// Returns the identifier of this frame, without the flags sent along
+    /// with it.
+    ///
+    /// For error frames this is the class of the error instead.
+    pub fn id(&self) -> u32 {
+        if self.is_extended() {
+            self.frame.can_id & libc::CAN_EFF_MASK
+        } else {
+            self.frame.can_id & libc::CAN_SFF_MASK
+        }
+    }
+
+    /// Returns whether this frame uses the extended, 29 bit, frame format.
+    pub fn is_extended(&self) -> bool {
+        self.frame.can_id & libc::CAN_EFF_FLAG != 0
+    }
+
+    /// Returns whether this frame is a remote transmission request.
+    pub fn is_remote(&self) -> bool {
+        self.frame.can_id & libc::CAN_RTR_FLAG != 0
+    }
+
+    /// Returns whether this frame is an error frame, which are only received
+    /// once enabled with `CanSocket::set_error_filter`.
+    pub fn is_error(&self) -> bool {
+        self.frame.can_id & libc::CAN_ERR_FLAG != 0
+    }
+
+    /// Returns the data carried by this frame, which is empty for remote
+    /// transmission requests.
+    pub fn data(&self) -> &[u8] {
+        if self.is_remote() {
+            return &[]
+        }
+        let len = (self.frame.can_dlc as usize).min(libc::CAN_MAX_DLEN);
+        &self.frame.data[..len]
+    }
+}
+
+impl fmt::Debug for CanFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CanFrame")
+         .field("id", &self.id())
+         .field("extended", &self.is_extended())
+         .field("remote", &self.is_remote())
+         .field("error", &self.is_error())
+         .field("data", &self.data())
+         .finish()
+    }
+}
+
+impl CanFilter {
+    /// Creates a filter matching the frames whose identifier has the same
+    /// bits as `id` where `mask` is set.
+    ///
+    /// The `CAN_EFF_FLAG` and `CAN_RTR_FLAG` bits may be included in `id` and
+    /// `mask` to also match on the frame format and remote requests.
+    pub fn new(id: u32, mask: u32) -> CanFilter {
+        CanFilter { id: id, mask: mask }
+    }
+
+    /// Creates a filter matching the frames which the filter created by
+    /// `CanFilter::new(id, mask)` doesn't match.
+    pub fn inverted(id: u32, mask: u32) -> CanFilter {
+        CanFilter { id: id | libc::CAN_INV_FILTER, mask: mask }
+    }
+}
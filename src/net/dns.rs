@@ -0,0 +1,39 @@
+//! Name resolution performed off the event loop.
+//!
+//! The system resolver, `getaddrinfo`, only has a blocking interface, so the
+//! lookups here are run on the crate's pool of helper threads.
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+
+use futures::Future;
+
+use io::IoFuture;
+use pool::{self, Blocking};
+use reactor::Handle;
+
+/// Looks up the addresses of the host `name`, returning a future which
+/// resolves to the list of addresses found.
+///
+/// The name may also be an IP address literal. As no service is given, the
+/// port of each address returned is 0.
+///
+/// The handle identifies the event loop the lookup is made on behalf of. The
+/// lookup itself is performed with the system resolver on a helper thread, so
+/// it never blocks that event loop while waiting on the network.
+pub fn lookup_host(name: &str, _handle: &Handle) -> IoFuture<Vec<SocketAddr>> {
+    let name = name.to_string();
+    pool::run(move || collect((&name[..], 0).to_socket_addrs())).boxed()
+}
+
+/// Resolves `host`, in the `host:port` form, on the blocking pool.
+pub fn resolve(host: &str) -> Blocking<Vec<SocketAddr>> {
+    let host = host.to_string();
+    pool::run(move || collect(host.to_socket_addrs()))
+}
+
+fn collect<I>(addrs: io::Result<I>) -> io::Result<Vec<SocketAddr>>
+    where I: Iterator<Item=SocketAddr>,
+{
+    addrs.map(|addrs| addrs.collect())
+}
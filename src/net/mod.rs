@@ -3,9 +3,46 @@
 //! This module contains the TCP/UDP networking types, similar to the standard
 //! library, which can be used to implement networking protocols.
 
+mod dns;
+mod proxy;
 mod tcp;
 mod udp;
+#[cfg(unix)]
+mod sockaddr;
+#[cfg(unix)]
+mod sockopt;
+#[cfg(unix)]
+mod raw;
+#[cfg(unix)]
+mod unix;
+#[cfg(target_os = "linux")]
+mod can;
+#[cfg(target_os = "linux")]
+mod netlink;
+#[cfg(target_os = "linux")]
+mod vsock;
 
+pub use self::dns::lookup_host;
+pub use self::proxy::{socks5_connect, http_connect};
 pub use self::tcp::{TcpStream, TcpStreamNew};
 pub use self::tcp::{TcpListener, Incoming};
-pub use self::udp::UdpSocket;
+pub use self::udp::{UdpSocket, UdpCodec, UdpFramed, Malformed, UdpPair};
+pub use self::udp::{SendDgram, RecvDgram};
+#[cfg(target_os = "linux")]
+pub use self::udp::PacketInfo;
+#[cfg(unix)]
+pub use self::unix::{UnixStream, UnixListener, UnixIncoming, UnixDatagram};
+#[cfg(unix)]
+pub use self::unix::{UnixSeqpacket, UnixSeqpacketListener, UnixSeqpacketIncoming};
+#[cfg(unix)]
+pub use self::unix::UCred;
+#[cfg(unix)]
+pub use self::raw::RawSocket;
+#[cfg(target_os = "linux")]
+pub use self::can::{CanSocket, CanFrame, CanFilter};
+#[cfg(target_os = "linux")]
+pub use self::netlink::NetlinkSocket;
+#[cfg(target_os = "linux")]
+pub use self::vsock::{VsockStream, VsockStreamNew, VsockListener, VsockIncoming};
+#[cfg(target_os = "linux")]
+pub use self::vsock::VsockAddr;
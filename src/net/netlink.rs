@@ -0,0 +1,223 @@
+//! Linux netlink sockets, integrated with the event loop.
+
+use std::fmt;
+use std::io;
+use std::mem;
+use std::os::unix::prelude::*;
+
+use futures::Async;
+use libc::{self, c_int};
+use mio;
+
+use fd::{self, EventedFd, OwnedFd};
+use net::sockopt::{self, cvt};
+use reactor::{Handle, PollEvented};
+
+/// An I/O object representing a netlink socket, used to communicate with the
+/// kernel (or with other processes) over one of its netlink families.
+///
+/// For example a socket of the `NETLINK_ROUTE` family which has joined the
+/// `RTNLGRP_LINK` and `RTNLGRP_IPV4_IFADDR` groups is notified of changes to
+/// network interfaces and their addresses, while one of the
+/// `NETLINK_KOBJECT_UEVENT` family receives device events. The constants for
+/// families and groups, and the message formats themselves, are defined by the
+/// kernel and are available from the `libc` crate.
+///
+/// Netlink sockets preserve message boundaries, each call to `recv` reading a
+/// single datagram which may contain several netlink messages.
+pub struct NetlinkSocket {
+    io: PollEvented<EventedFd<OwnedFd>>,
+}
+
+impl NetlinkSocket {
+    /// Creates a new netlink socket of the family `protocol`, bound to a port
+    /// ID chosen by the kernel and subscribed to the multicast groups in the
+    /// bitmask `groups`, associated with the event loop `handle` points to.
+    ///
+    /// The legacy `groups` bitmask can only express the first 32 groups, so
+    /// `add_membership` should be used to join later ones.
+    pub fn bind(protocol: c_int, groups: u32, handle: &Handle)
+                -> io::Result<NetlinkSocket> {
+        let raw = try!(cvt(unsafe {
+            libc::socket(libc::AF_NETLINK, libc::SOCK_RAW, protocol)
+        }));
+        let socket = unsafe { OwnedFd::new(raw) };
+        try!(fd::set_nonblocking_cloexec(raw));
+
+        let addr = sockaddr_nl(0, groups);
+        try!(cvt(unsafe {
+            libc::bind(raw,
+                       &addr as *const _ as *const libc::sockaddr,
+                       mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t)
+        }));
+        let io = try!(PollEvented::new(EventedFd::new(socket), handle));
+        Ok(NetlinkSocket { io: io })
+    }
+
+    /// Test whether this socket is ready to be read or not.
+    ///
+    /// If the socket is *not* readable then the current task is scheduled to
+    /// get a notification when the socket does become readable.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Test whether this socket is ready to be written to or not.
+    ///
+    /// If the socket is *not* writable then the current task is scheduled to
+    /// get a notification when the socket does become writable.
+    pub fn poll_write(&self) -> Async<()> {
+        self.io.poll_write()
+    }
+
+    /// Returns the token this socket is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
+    /// Returns the port ID this socket is bound to, which is used as the
+    /// address of the socket by the kernel and other processes.
+    pub fn port_id(&self) -> io::Result<u32> {
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t;
+        try!(cvt(unsafe {
+            libc::getsockname(self.as_raw_fd(),
+                              &mut addr as *mut _ as *mut libc::sockaddr,
+                              &mut len)
+        }));
+        Ok(addr.nl_pid)
+    }
+
+    /// Subscribes this socket to the multicast group `group`.
+    ///
+    /// Unlike the bitmask passed to `bind`, `group` is the number of the
+    /// group, such as `RTNLGRP_LINK`.
+    pub fn add_membership(&self, group: u32) -> io::Result<()> {
+        sockopt::set(self.as_raw_fd(), libc::SOL_NETLINK,
+                     libc::NETLINK_ADD_MEMBERSHIP, group as c_int)
+    }
+
+    /// Unsubscribes this socket from the multicast group `group`.
+    pub fn drop_membership(&self, group: u32) -> io::Result<()> {
+        sockopt::set(self.as_raw_fd(), libc::SOL_NETLINK,
+                     libc::NETLINK_DROP_MEMBERSHIP, group as c_int)
+    }
+
+    /// Sends `buf` to the kernel. On success, returns the number of bytes
+    /// written.
+    ///
+    /// If the socket isn't writable then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is writable again.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.send_to(buf, 0, 0)
+    }
+
+    /// Sends `buf` to the socket with the port ID `port_id` and to the
+    /// multicast groups in the bitmask `groups`. A port ID of 0 addresses the
+    /// kernel. On success, returns the number of bytes written.
+    ///
+    /// If the socket isn't writable then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is writable again.
+    pub fn send_to(&self, buf: &[u8], port_id: u32, groups: u32)
+                   -> io::Result<usize> {
+        if let Async::NotReady = self.io.poll_write() {
+            return Err(mio::would_block())
+        }
+        let addr = sockaddr_nl(port_id, groups);
+        let n = unsafe {
+            libc::sendto(self.as_raw_fd(),
+                         buf.as_ptr() as *const _,
+                         buf.len(),
+                         0,
+                         &addr as *const _ as *const libc::sockaddr,
+                         mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t)
+        };
+        if n == -1 {
+            let e = io::Error::last_os_error();
+            if e.kind() == io::ErrorKind::WouldBlock {
+                self.io.need_write();
+            }
+            return Err(e)
+        }
+        Ok(n as usize)
+    }
+
+    /// Receives a datagram from the socket. On success, returns the number of
+    /// bytes read.
+    ///
+    /// If the datagram is longer than `buf` the excess is discarded.
+    ///
+    /// If no datagram is available then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is readable again.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.recv_from(buf).map(|p| p.0)
+    }
+
+    /// Receives a datagram from the socket. On success, returns the number of
+    /// bytes read and the port ID of the sender, which is 0 for messages from
+    /// the kernel.
+    ///
+    /// If no datagram is available then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is readable again.
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, u32)> {
+        if let Async::NotReady = self.io.poll_read() {
+            return Err(mio::would_block())
+        }
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        let mut len = mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t;
+        let n = unsafe {
+            libc::recvfrom(self.as_raw_fd(),
+                           buf.as_mut_ptr() as *mut _,
+                           buf.len(),
+                           0,
+                           &mut addr as *mut _ as *mut libc::sockaddr,
+                           &mut len)
+        };
+        if n == -1 {
+            let e = io::Error::last_os_error();
+            if e.kind() == io::ErrorKind::WouldBlock {
+                self.io.need_read();
+            }
+            return Err(e)
+        }
+        Ok((n as usize, addr.nl_pid))
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        let err: c_int = try!(sockopt::get(self.as_raw_fd(), libc::SOL_SOCKET,
+                                           libc::SO_ERROR));
+        if err == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(io::Error::from_raw_os_error(err)))
+        }
+    }
+}
+
+fn sockaddr_nl(port_id: u32, groups: u32) -> libc::sockaddr_nl {
+    let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    addr.nl_pid = port_id;
+    addr.nl_groups = groups;
+    addr
+}
+
+impl fmt::Debug for NetlinkSocket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NetlinkSocket")
+         .field("fd", &self.as_raw_fd())
+         .finish()
+    }
+}
+
+impl AsRawFd for NetlinkSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
@@ -0,0 +1,206 @@
+//! Connecting through SOCKS5 and HTTP proxies.
+//!
+//! Both protocols tunnel a TCP connection: a connection is made to the proxy,
+//! it's asked to connect to the target, and once it agrees the same
+//! connection carries the data to and from the target.
+
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+
+use futures::Future;
+use futures::future::{self, Loop};
+
+use io::{read_exact, write_all, IoFuture};
+use net::TcpStream;
+use reactor::Handle;
+
+/// The longest response to a `CONNECT` request accepted, to bound the memory
+/// used by a misbehaving proxy.
+const MAX_RESPONSE: usize = 8 * 1024;
+
+/// Connects to `target` through the SOCKS5 proxy at `proxy`, returning a
+/// future which resolves to the tunneled stream.
+///
+/// The target is given in the `host:port` form, and the host may be a domain
+/// name, which is then resolved by the proxy rather than locally. Only proxies
+/// not requiring authentication are supported.
+pub fn socks5_connect(proxy: &SocketAddr, target: &str, handle: &Handle)
+                      -> IoFuture<TcpStream> {
+    let request = match socks5_request(target) {
+        Ok(request) => request,
+        Err(e) => return future::err(e).boxed(),
+    };
+    TcpStream::connect(proxy, handle).and_then(|stream| {
+        // Offer only the "no authentication required" method.
+        write_all(stream, [5, 1, 0])
+    }).and_then(|(stream, _)| {
+        read_exact(stream, [0; 2])
+    }).and_then(|(stream, reply)| {
+        if reply[0] != 5 {
+            return Err(invalid_data("invalid SOCKS5 proxy response"))
+        }
+        if reply[1] != 0 {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied,
+                                      "SOCKS5 proxy requires authentication"))
+        }
+        Ok(stream)
+    }).and_then(move |stream| {
+        write_all(stream, request)
+    }).and_then(|(stream, _)| {
+        read_exact(stream, [0; 4])
+    }).and_then(|(stream, reply)| {
+        if reply[0] != 5 {
+            return future::err(invalid_data("invalid SOCKS5 proxy response")).boxed()
+        }
+        if reply[1] != 0 {
+            return future::err(socks5_error(reply[1])).boxed()
+        }
+        // The reply ends with the address the proxy bound, which is of no use
+        // here but has to be consumed before the tunneled data.
+        match reply[3] {
+            1 => skip(stream, 4 + 2),
+            4 => skip(stream, 16 + 2),
+            3 => {
+                read_exact(stream, [0; 1]).and_then(|(stream, len)| {
+                    skip(stream, len[0] as usize + 2)
+                }).boxed()
+            }
+            _ => future::err(invalid_data("invalid SOCKS5 address type")).boxed(),
+        }
+    }).boxed()
+}
+
+/// Connects to `target` through the HTTP proxy at `proxy` using a `CONNECT`
+/// request, returning a future which resolves to the tunneled stream.
+///
+/// The target is given in the `host:port` form and is passed to the proxy
+/// unchanged, so a domain name is resolved by the proxy rather than locally.
+/// Targets containing whitespace or control characters are refused. Any
+/// status other than `2xx` in the proxy's response is returned as an error.
+pub fn http_connect(proxy: &SocketAddr, target: &str, handle: &Handle)
+                    -> IoFuture<TcpStream> {
+    if let Err(e) = split_host_port(target) {
+        return future::err(e).boxed()
+    }
+    let request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", target);
+    TcpStream::connect(proxy, handle).and_then(|stream| {
+        write_all(stream, request.into_bytes())
+    }).and_then(|(stream, _)| {
+        // Read the response a byte at a time so nothing sent by the target
+        // after it is consumed.
+        future::loop_fn((stream, Vec::new()), |(stream, mut response)| {
+            read_exact(stream, [0; 1]).and_then(move |(stream, byte)| {
+                response.push(byte[0]);
+                if response.ends_with(b"\r\n\r\n") {
+                    Ok(Loop::Break((stream, response)))
+                } else if response.len() >= MAX_RESPONSE {
+                    Err(invalid_data("HTTP proxy response too long"))
+                } else {
+                    Ok(Loop::Continue((stream, response)))
+                }
+            })
+        })
+    }).and_then(|(stream, response)| {
+        let status = {
+            let line = response.split(|b| *b == b'\r').next().unwrap();
+            let line = String::from_utf8_lossy(line);
+            let mut parts = line.splitn(3, ' ');
+            let version = parts.next().unwrap_or("");
+            let code = parts.next().unwrap_or("");
+            if !version.starts_with("HTTP/1.") || code.len() != 3 {
+                return Err(invalid_data("invalid HTTP proxy response"))
+            }
+            if code.starts_with('2') {
+                None
+            } else {
+                Some(line.to_string())
+            }
+        };
+        match status {
+            None => Ok(stream),
+            Some(line) => {
+                Err(io::Error::new(io::ErrorKind::Other,
+                                   format!("HTTP proxy refused to connect: {}",
+                                           line)))
+            }
+        }
+    }).boxed()
+}
+
+/// Builds the SOCKS5 `CONNECT` request for `target`.
+fn socks5_request(target: &str) -> io::Result<Vec<u8>> {
+    let (host, port) = try!(split_host_port(target));
+    let mut request = vec![5, 1, 0];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(1);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(4);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            if host.is_empty() || host.len() > 255 {
+                return Err(invalid_input("invalid SOCKS5 target host name"))
+            }
+            request.push(3);
+            request.push(host.len() as u8);
+            request.extend_from_slice(host.as_bytes());
+        }
+    }
+    request.push((port >> 8) as u8);
+    request.push(port as u8);
+    Ok(request)
+}
+
+/// Splits `target` into its host, without the brackets around an IPv6
+/// address, and port.
+fn split_host_port(target: &str) -> io::Result<(&str, u16)> {
+    let i = match target.rfind(':') {
+        Some(i) => i,
+        None => return Err(invalid_input("target must be in the host:port form")),
+    };
+    let port = match target[i + 1..].parse() {
+        Ok(port) => port,
+        Err(_) => return Err(invalid_input("invalid target port")),
+    };
+    let host = &target[..i];
+    // The target ends up verbatim in the `CONNECT` request line, so anything
+    // that could end the line or header early is refused.
+    if host.chars().any(|c| c.is_control() || c.is_whitespace()) {
+        return Err(invalid_input("invalid target host"))
+    }
+    if host.starts_with('[') && host.ends_with(']') {
+        Ok((&host[1..host.len() - 1], port))
+    } else {
+        Ok((host, port))
+    }
+}
+
+/// Reads and discards `len` bytes from `stream`.
+fn skip(stream: TcpStream, len: usize) -> IoFuture<TcpStream> {
+    read_exact(stream, vec![0; len]).map(|(stream, _)| stream).boxed()
+}
+
+fn socks5_error(code: u8) -> io::Error {
+    let (kind, msg) = match code {
+        2 => (io::ErrorKind::PermissionDenied, "connection not allowed by ruleset"),
+        3 => (io::ErrorKind::Other, "network unreachable"),
+        4 => (io::ErrorKind::Other, "host unreachable"),
+        5 => (io::ErrorKind::ConnectionRefused, "connection refused"),
+        6 => (io::ErrorKind::TimedOut, "TTL expired"),
+        7 => (io::ErrorKind::Other, "command not supported"),
+        8 => (io::ErrorKind::Other, "address type not supported"),
+        _ => (io::ErrorKind::Other, "general SOCKS server failure"),
+    };
+    io::Error::new(kind, format!("SOCKS5 proxy error: {}", msg))
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn invalid_input(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
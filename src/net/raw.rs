@@ -0,0 +1,276 @@
+//! Raw IP sockets, integrated with the event loop.
+
+use std::fmt;
+use std::io;
+use std::mem;
+use std::net::SocketAddr;
+use std::os::unix::prelude::*;
+
+use futures::Async;
+use libc::{self, c_int};
+use mio;
+
+use fd::{self, EventedFd, OwnedFd};
+use net::sockaddr;
+use net::sockopt::{self, cvt};
+use reactor::{Handle, PollEvented};
+
+/// An I/O object representing a raw IP socket.
+///
+/// Raw sockets send and receive packets of a particular IP protocol directly,
+/// without any transport layer in between, which allows protocols such as
+/// ICMP (used by ping and traceroute) or custom ones to be implemented on top
+/// of the event loop. Creating them generally requires elevated privileges,
+/// such as `CAP_NET_RAW` on Linux.
+///
+/// For IPv4 sockets the packets received include the IP header, while the
+/// packets sent don't unless `set_header_included` has been enabled. For IPv6
+/// sockets the IP header is never included. The port of the socket addresses
+/// passed to and returned from this type is unused and is always 0.
+pub struct RawSocket {
+    io: PollEvented<EventedFd<OwnedFd>>,
+}
+
+impl RawSocket {
+    /// Creates a new raw socket of the address family `domain` (`AF_INET` or
+    /// `AF_INET6`) for the IP protocol number `protocol`, associated with the
+    /// event loop `handle` points to.
+    pub fn new(domain: c_int, protocol: c_int, handle: &Handle)
+               -> io::Result<RawSocket> {
+        let raw = try!(cvt(unsafe {
+            libc::socket(domain, libc::SOCK_RAW, protocol)
+        }));
+        let socket = unsafe { OwnedFd::new(raw) };
+        try!(fd::set_nonblocking_cloexec(raw));
+        let io = try!(PollEvented::new(EventedFd::new(socket), handle));
+        Ok(RawSocket { io: io })
+    }
+
+    /// Creates a new raw socket for sending and receiving ICMP packets over
+    /// IPv4, associated with the event loop `handle` points to.
+    pub fn icmpv4(handle: &Handle) -> io::Result<RawSocket> {
+        RawSocket::new(libc::AF_INET, libc::IPPROTO_ICMP, handle)
+    }
+
+    /// Creates a new raw socket for sending and receiving ICMPv6 packets,
+    /// associated with the event loop `handle` points to.
+    pub fn icmpv6(handle: &Handle) -> io::Result<RawSocket> {
+        RawSocket::new(libc::AF_INET6, libc::IPPROTO_ICMPV6, handle)
+    }
+
+    /// Binds this socket to the local address `addr`, limiting the packets
+    /// received to those sent to it.
+    pub fn bind(&self, addr: &SocketAddr) -> io::Result<()> {
+        let (storage, len) = sockaddr::from_socket_addr(addr);
+        try!(cvt(unsafe {
+            libc::bind(self.as_raw_fd(),
+                       &storage as *const _ as *const libc::sockaddr,
+                       len)
+        }));
+        Ok(())
+    }
+
+    /// Connects this socket to the remote address `addr`, setting the default
+    /// destination for `send` and limiting the packets received to those sent
+    /// from it.
+    pub fn connect(&self, addr: &SocketAddr) -> io::Result<()> {
+        let (storage, len) = sockaddr::from_socket_addr(addr);
+        try!(cvt(unsafe {
+            libc::connect(self.as_raw_fd(),
+                          &storage as *const _ as *const libc::sockaddr,
+                          len)
+        }));
+        Ok(())
+    }
+
+    /// Returns the local address this socket is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let mut len = mem::size_of_val(&storage) as libc::socklen_t;
+        try!(cvt(unsafe {
+            libc::getsockname(self.as_raw_fd(),
+                              &mut storage as *mut _ as *mut libc::sockaddr,
+                              &mut len)
+        }));
+        sockaddr::to_socket_addr(&storage, len)
+    }
+
+    /// Test whether this socket is ready to be read or not.
+    ///
+    /// If the socket is *not* readable then the current task is scheduled to
+    /// get a notification when the socket does become readable.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Test whether this socket is ready to be written to or not.
+    ///
+    /// If the socket is *not* writable then the current task is scheduled to
+    /// get a notification when the socket does become writable.
+    pub fn poll_write(&self) -> Async<()> {
+        self.io.poll_write()
+    }
+
+    /// Returns the token this socket is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
+    /// Sends the packet `buf` to the address `target`. On success, returns the
+    /// number of bytes written.
+    ///
+    /// If the socket isn't writable then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is writable again.
+    pub fn send_to(&self, buf: &[u8], target: &SocketAddr) -> io::Result<usize> {
+        let (storage, len) = sockaddr::from_socket_addr(target);
+        self.write(|fd| unsafe {
+            libc::sendto(fd,
+                         buf.as_ptr() as *const _,
+                         buf.len(),
+                         0,
+                         &storage as *const _ as *const libc::sockaddr,
+                         len)
+        })
+    }
+
+    /// Sends the packet `buf` to the address previously set via `connect`. On
+    /// success, returns the number of bytes written.
+    ///
+    /// If the socket isn't writable then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is writable again.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.write(|fd| unsafe {
+            libc::send(fd, buf.as_ptr() as *const _, buf.len(), 0)
+        })
+    }
+
+    /// Receives a packet from the socket. On success, returns the number of
+    /// bytes read and the address it came from.
+    ///
+    /// If the packet is longer than `buf` the excess is discarded.
+    ///
+    /// If no packet is available then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is readable again.
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let mut len = mem::size_of_val(&storage) as libc::socklen_t;
+        let n = try!(self.read(|fd| unsafe {
+            libc::recvfrom(fd,
+                           buf.as_mut_ptr() as *mut _,
+                           buf.len(),
+                           0,
+                           &mut storage as *mut _ as *mut libc::sockaddr,
+                           &mut len)
+        }));
+        let addr = try!(sockaddr::to_socket_addr(&storage, len));
+        Ok((n, addr))
+    }
+
+    /// Receives a packet from the socket. On success, returns the number of
+    /// bytes read.
+    ///
+    /// If no packet is available then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is readable again.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read(|fd| unsafe {
+            libc::recv(fd, buf.as_mut_ptr() as *mut _, buf.len(), 0)
+        })
+    }
+
+    /// Sets the value of the `IP_HDRINCL` option on this IPv4 socket.
+    ///
+    /// When enabled, the packets passed to `send` and `send_to` must start
+    /// with an IP header filled in by the caller.
+    pub fn set_header_included(&self, included: bool) -> io::Result<()> {
+        sockopt::set(self.as_raw_fd(), libc::IPPROTO_IP, libc::IP_HDRINCL,
+                     included as c_int)
+    }
+
+    /// Gets the value of the `IP_HDRINCL` option on this IPv4 socket.
+    pub fn header_included(&self) -> io::Result<bool> {
+        let included: c_int = try!(sockopt::get(self.as_raw_fd(),
+                                                libc::IPPROTO_IP,
+                                                libc::IP_HDRINCL));
+        Ok(included != 0)
+    }
+
+    /// Sets the value of the `IP_TTL` option on this IPv4 socket, the time to
+    /// live of the packets sent from it.
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        sockopt::set(self.as_raw_fd(), libc::IPPROTO_IP, libc::IP_TTL,
+                     ttl as c_int)
+    }
+
+    /// Gets the value of the `IP_TTL` option on this IPv4 socket.
+    pub fn ttl(&self) -> io::Result<u32> {
+        let ttl: c_int = try!(sockopt::get(self.as_raw_fd(), libc::IPPROTO_IP,
+                                           libc::IP_TTL));
+        Ok(ttl as u32)
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        let err: c_int = try!(sockopt::get(self.as_raw_fd(), libc::SOL_SOCKET,
+                                           libc::SO_ERROR));
+        if err == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(io::Error::from_raw_os_error(err)))
+        }
+    }
+
+    fn read<F>(&self, f: F) -> io::Result<usize>
+        where F: FnOnce(RawFd) -> libc::ssize_t,
+    {
+        if let Async::NotReady = self.io.poll_read() {
+            return Err(mio::would_block())
+        }
+        match f(self.as_raw_fd()) {
+            -1 => {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::WouldBlock {
+                    self.io.need_read();
+                }
+                Err(e)
+            }
+            n => Ok(n as usize),
+        }
+    }
+
+    fn write<F>(&self, f: F) -> io::Result<usize>
+        where F: FnOnce(RawFd) -> libc::ssize_t,
+    {
+        if let Async::NotReady = self.io.poll_write() {
+            return Err(mio::would_block())
+        }
+        match f(self.as_raw_fd()) {
+            -1 => {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::WouldBlock {
+                    self.io.need_write();
+                }
+                Err(e)
+            }
+            n => Ok(n as usize),
+        }
+    }
+}
+
+impl fmt::Debug for RawSocket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RawSocket")
+         .field("fd", &self.as_raw_fd())
+         .finish()
+    }
+}
+
+impl AsRawFd for RawSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
@@ -0,0 +1,68 @@
+//! Conversions between `std::net::SocketAddr` and the raw `sockaddr` family
+//! of types used when calling into `libc` directly.
+
+use std::io;
+use std::mem;
+use std::net::{SocketAddr, SocketAddrV4, SocketAddrV6, Ipv4Addr, Ipv6Addr};
+
+use libc::{self, sockaddr_storage, socklen_t};
+
+/// Converts a socket address into a `sockaddr_storage` and the length of the
+/// address stored within it.
+pub fn from_socket_addr(addr: &SocketAddr) -> (sockaddr_storage, socklen_t) {
+    unsafe {
+        let mut storage: sockaddr_storage = mem::zeroed();
+        let len = match *addr {
+            SocketAddr::V4(ref a) => {
+                let sin = &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in);
+                sin.sin_family = libc::AF_INET as libc::sa_family_t;
+                sin.sin_port = a.port().to_be();
+                sin.sin_addr = libc::in_addr {
+                    s_addr: u32::from(*a.ip()).to_be(),
+                };
+                mem::size_of::<libc::sockaddr_in>()
+            }
+            SocketAddr::V6(ref a) => {
+                let sin6 = &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6);
+                sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+                sin6.sin6_port = a.port().to_be();
+                sin6.sin6_flowinfo = a.flowinfo();
+                sin6.sin6_scope_id = a.scope_id();
+                sin6.sin6_addr.s6_addr = a.ip().octets();
+                mem::size_of::<libc::sockaddr_in6>()
+            }
+        };
+        (storage, len as socklen_t)
+    }
+}
+
+/// Converts a `sockaddr_storage` filled in by the kernel back into a socket
+/// address, returning an error for address families other than IPv4/IPv6.
+pub fn to_socket_addr(storage: &sockaddr_storage, len: socklen_t)
+                      -> io::Result<SocketAddr> {
+    match storage.ss_family as libc::c_int {
+        libc::AF_INET => {
+            assert!(len as usize >= mem::size_of::<libc::sockaddr_in>());
+            let sin = unsafe {
+                &*(storage as *const _ as *const libc::sockaddr_in)
+            };
+            let ip = Ipv4Addr::from(u32::from_be(sin.sin_addr.s_addr));
+            let port = u16::from_be(sin.sin_port);
+            Ok(SocketAddr::V4(SocketAddrV4::new(ip, port)))
+        }
+        libc::AF_INET6 => {
+            assert!(len as usize >= mem::size_of::<libc::sockaddr_in6>());
+            let sin6 = unsafe {
+                &*(storage as *const _ as *const libc::sockaddr_in6)
+            };
+            let ip = Ipv6Addr::from(sin6.sin6_addr.s6_addr);
+            let port = u16::from_be(sin6.sin6_port);
+            Ok(SocketAddr::V6(SocketAddrV6::new(ip,
+                                                port,
+                                                sin6.sin6_flowinfo,
+                                                sin6.sin6_scope_id)))
+        }
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                "invalid address family")),
+    }
+}
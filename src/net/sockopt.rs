@@ -0,0 +1,43 @@
+//! Thin wrappers around `setsockopt` and `getsockopt` for socket options that
+//! `mio` doesn't expose itself.
+
+use std::io;
+use std::mem;
+use std::os::unix::prelude::*;
+
+use libc::{self, c_int, c_void, socklen_t};
+
+pub fn set<T: Copy>(fd: RawFd, level: c_int, name: c_int, val: T)
+                    -> io::Result<()> {
+    unsafe {
+        let payload = &val as *const T as *const c_void;
+        try!(cvt(libc::setsockopt(fd,
+                                  level,
+                                  name,
+                                  payload,
+                                  mem::size_of::<T>() as socklen_t)));
+    }
+    Ok(())
+}
+
+pub fn get<T: Copy>(fd: RawFd, level: c_int, name: c_int) -> io::Result<T> {
+    unsafe {
+        let mut slot: T = mem::zeroed();
+        let mut len = mem::size_of::<T>() as socklen_t;
+        try!(cvt(libc::getsockopt(fd,
+                                  level,
+                                  name,
+                                  &mut slot as *mut T as *mut c_void,
+                                  &mut len)));
+        assert_eq!(len as usize, mem::size_of::<T>());
+        Ok(slot)
+    }
+}
+
+pub fn cvt(t: c_int) -> io::Result<c_int> {
+    if t == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(t)
+    }
+}
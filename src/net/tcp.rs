@@ -7,7 +7,8 @@ use futures::stream::Stream;
 use futures::{self, Future, failed, Poll, Async};
 use mio;
 
-use io::{Io, IoFuture, IoStream};
+use io::{AsyncRead, AsyncWrite, IoFuture, IoStream};
+use net::dns;
 use reactor::{Handle, PollEvented};
 
 /// An I/O object representing a TCP socket listening for incoming connections.
@@ -16,6 +17,16 @@ use reactor::{Handle, PollEvented};
 /// various forms of processing.
 pub struct TcpListener {
     io: PollEvented<mio::tcp::TcpListener>,
+    accept_options: StreamOptions,
+}
+
+/// Socket options which a `TcpListener` applies to each stream it accepts.
+#[derive(Clone, Default)]
+struct StreamOptions {
+    nodelay: Option<bool>,
+    keepalive_ms: Option<Option<u32>>,
+    recv_buffer_size: Option<usize>,
+    send_buffer_size: Option<usize>,
 }
 
 /// Stream returned by the `TcpListener::incoming` function representing the
@@ -71,7 +82,7 @@ impl TcpListener {
     fn new(listener: mio::tcp::TcpListener, handle: &Handle)
            -> io::Result<TcpListener> {
         let io = try!(PollEvented::new(listener, handle));
-        Ok(TcpListener { io: io })
+        Ok(TcpListener { io: io, accept_options: StreamOptions::default() })
     }
 
     /// Test whether this socket is ready to be read or not.
@@ -79,6 +90,12 @@ impl TcpListener {
         self.io.poll_read()
     }
 
+    /// Returns the token this listener is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
     /// Returns the local address that this listener is bound to.
     ///
     /// This can be useful, for example, when binding to port 0 to figure out
@@ -91,7 +108,10 @@ impl TcpListener {
     /// accepts.
     ///
     /// This method returns an implementation of the `Stream` trait which
-    /// resolves to the sockets the are accepted on this listener.
+    /// resolves to the sockets the are accepted on this listener. A socket
+    /// which the `set_accept_*` options can't be applied to is closed, with a
+    /// warning logged, and skipped rather than ending the stream with an
+    /// error.
     pub fn incoming(self) -> Incoming {
         struct MyIncoming {
             inner: TcpListener,
@@ -105,13 +125,23 @@ impl TcpListener {
                 if let Async::NotReady = self.inner.io.poll_read() {
                     return Ok(Async::NotReady)
                 }
-                match self.inner.io.get_ref().accept() {
-                    Ok(pair) => Ok(Async::Ready(Some(pair))),
-                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
-                        self.inner.io.need_read();
-                        Ok(Async::NotReady)
+                loop {
+                    match self.inner.io.get_ref().accept() {
+                        Ok((tcp, addr)) => {
+                            let opts = &self.inner.accept_options;
+                            if let Err(e) = opts.apply(&tcp) {
+                                warn!("dropping connection from {}: {}",
+                                      addr, e);
+                                continue
+                            }
+                            return Ok(Async::Ready(Some((tcp, addr))))
+                        }
+                        Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                            self.inner.io.need_read();
+                            return Ok(Async::NotReady)
+                        }
+                        Err(e) => return Err(e)
                     }
-                    Err(e) => Err(e)
                 }
             }
         }
@@ -133,6 +163,55 @@ impl TcpListener {
         }
     }
 
+    /// Configures the `TCP_NODELAY` option to be set on all sockets accepted
+    /// by this listener.
+    ///
+    /// The option is applied before the accepted `TcpStream` is yielded from
+    /// the `incoming` stream. For more information about this option, see
+    /// [`TcpStream::set_nodelay`][link].
+    ///
+    /// [link]: struct.TcpStream.html#method.set_nodelay
+    pub fn set_accept_nodelay(&mut self, nodelay: bool) -> &mut TcpListener {
+        self.accept_options.nodelay = Some(nodelay);
+        self
+    }
+
+    /// Configures the keepalive option to be set on all sockets accepted by
+    /// this listener.
+    ///
+    /// The option is applied before the accepted `TcpStream` is yielded from
+    /// the `incoming` stream. For more information about this option, see
+    /// [`TcpStream::set_keepalive_ms`][link].
+    ///
+    /// [link]: struct.TcpStream.html#method.set_keepalive_ms
+    pub fn set_accept_keepalive_ms(&mut self, keepalive: Option<u32>)
+                                   -> &mut TcpListener {
+        self.accept_options.keepalive_ms = Some(keepalive);
+        self
+    }
+
+    /// Configures the `SO_RCVBUF` option to be set on all sockets accepted by
+    /// this listener.
+    ///
+    /// The option is applied before the accepted `TcpStream` is yielded from
+    /// the `incoming` stream.
+    pub fn set_accept_recv_buffer_size(&mut self, size: usize)
+                                       -> &mut TcpListener {
+        self.accept_options.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Configures the `SO_SNDBUF` option to be set on all sockets accepted by
+    /// this listener.
+    ///
+    /// The option is applied before the accepted `TcpStream` is yielded from
+    /// the `incoming` stream.
+    pub fn set_accept_send_buffer_size(&mut self, size: usize)
+                                       -> &mut TcpListener {
+        self.accept_options.send_buffer_size = Some(size);
+        self
+    }
+
     /// Sets the value for the `IP_TTL` option on this socket.
     ///
     /// This value sets the time-to-live field that is used in every packet sent
@@ -172,6 +251,24 @@ impl TcpListener {
     }
 }
 
+impl StreamOptions {
+    fn apply(&self, tcp: &mio::tcp::TcpStream) -> io::Result<()> {
+        if let Some(nodelay) = self.nodelay {
+            try!(tcp.set_nodelay(nodelay));
+        }
+        if let Some(keepalive) = self.keepalive_ms {
+            try!(tcp.set_keepalive_ms(keepalive));
+        }
+        if let Some(size) = self.recv_buffer_size {
+            try!(tcp.set_recv_buffer_size(size));
+        }
+        if let Some(size) = self.send_buffer_size {
+            try!(tcp.set_send_buffer_size(size));
+        }
+        Ok(())
+    }
+}
+
 impl fmt::Debug for TcpListener {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.io.get_ref().fmt(f)
@@ -224,6 +321,26 @@ impl TcpStream {
         TcpStreamNew { inner: future }
     }
 
+    /// Create a new TCP stream connected to `host`, given in the `host:port`
+    /// form, such as `"example.com:80"`.
+    ///
+    /// The host name is resolved with `lookup_host`, off the event loop, and
+    /// the addresses found are then tried in turn until a connection to one of
+    /// them succeeds. If none do, the error from the last attempt is returned.
+    pub fn connect_host(host: &str, handle: &Handle) -> IoFuture<TcpStream> {
+        let remote = handle.remote().clone();
+        dns::resolve(host).and_then(move |addrs| {
+            let (tx, rx) = futures::oneshot();
+            remote.spawn(move |handle| {
+                connect_any(addrs, handle).then(|res| {
+                    tx.complete(res);
+                    Ok(())
+                })
+            });
+            rx.then(|r| r.expect("shouldn't be canceled"))
+        }).boxed()
+    }
+
     fn new(connected_stream: mio::tcp::TcpStream, handle: &Handle)
            -> IoFuture<TcpStream> {
         let tcp = PollEvented::new(connected_stream, handle);
@@ -279,6 +396,12 @@ impl TcpStream {
         self.io.poll_write()
     }
 
+    /// Returns the token this stream is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
     /// Returns the local address that this stream is bound to.
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
         self.io.get_ref().local_addr()
@@ -372,19 +495,30 @@ impl Write for TcpStream {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
         self.io.write(buf)
     }
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        (&*self).write_vectored(bufs)
+    }
     fn flush(&mut self) -> io::Result<()> {
         self.io.flush()
     }
 }
 
-impl Io for TcpStream {
+impl AsyncRead for TcpStream {
     fn poll_read(&mut self) -> Async<()> {
         <TcpStream>::poll_read(self)
     }
+}
 
+impl AsyncWrite for TcpStream {
     fn poll_write(&mut self) -> Async<()> {
         <TcpStream>::poll_write(self)
     }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        try_ready!(self.poll_flush());
+        try!(<TcpStream>::shutdown(self, Shutdown::Write));
+        Ok(Async::Ready(()))
+    }
 }
 
 impl<'a> Read for &'a TcpStream {
@@ -398,19 +532,45 @@ impl<'a> Write for &'a TcpStream {
         (&self.io).write(buf)
     }
 
+    // mio doesn't forward vectored writes to the socket, so `writev` is
+    // called directly where it's available.
+    #[cfg(unix)]
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        use std::os::unix::io::AsRawFd;
+
+        if let Async::NotReady = self.io.poll_write() {
+            return Err(mio::would_block())
+        }
+        let r = ::fd::writev(self.as_raw_fd(), bufs);
+        if let Err(ref e) = r {
+            if e.kind() == io::ErrorKind::WouldBlock {
+                self.io.need_write();
+            }
+        }
+        r
+    }
+
     fn flush(&mut self) -> io::Result<()> {
         (&self.io).flush()
     }
 }
 
-impl<'a> Io for &'a TcpStream {
+impl<'a> AsyncRead for &'a TcpStream {
     fn poll_read(&mut self) -> Async<()> {
         <TcpStream>::poll_read(self)
     }
+}
 
+impl<'a> AsyncWrite for &'a TcpStream {
     fn poll_write(&mut self) -> Async<()> {
         <TcpStream>::poll_write(self)
     }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        try_ready!(self.poll_flush());
+        try!(<TcpStream>::shutdown(self, Shutdown::Write));
+        Ok(Async::Ready(()))
+    }
 }
 
 impl fmt::Debug for TcpStream {
@@ -419,6 +579,28 @@ impl fmt::Debug for TcpStream {
     }
 }
 
+/// Connects to each of `addrs` in turn, resolving to the first stream which
+/// connects successfully.
+fn connect_any(addrs: Vec<SocketAddr>, handle: &Handle)
+               -> Box<Future<Item=TcpStream, Error=io::Error>> {
+    let mut addrs = addrs.into_iter();
+    let mut future: Box<Future<Item=_, Error=_>> = match addrs.next() {
+        Some(addr) => Box::new(TcpStream::connect(&addr, handle)),
+        None => {
+            let e = io::Error::new(io::ErrorKind::Other,
+                                   "host resolved to no addresses");
+            return Box::new(failed(e))
+        }
+    };
+    for addr in addrs {
+        let handle = handle.clone();
+        future = Box::new(future.or_else(move |_| {
+            TcpStream::connect(&addr, &handle)
+        }));
+    }
+    future
+}
+
 impl Future for TcpStreamNew {
     type Item = TcpStream;
     type Error = io::Error;
@@ -461,7 +643,13 @@ impl Future for TcpStreamConnect {
 
 #[cfg(unix)]
 mod sys {
+    use std::io;
     use std::os::unix::prelude::*;
+
+    use libc;
+
+    use io::Splice;
+    use net::sockopt;
     use super::{TcpStream, TcpListener};
 
     impl AsRawFd for TcpStream {
@@ -470,6 +658,67 @@ mod sys {
         }
     }
 
+    impl Splice for TcpStream {
+        fn splice_to(&mut self, pipe: RawFd, len: usize) -> io::Result<usize> {
+            self.io.splice_to(pipe, len)
+        }
+
+        fn splice_from(&mut self, pipe: RawFd, len: usize) -> io::Result<usize> {
+            self.io.splice_from(pipe, len)
+        }
+    }
+
+    impl TcpStream {
+        /// Sets the value of the `IP_TOS` option for this socket.
+        ///
+        /// This value sets the type-of-service field of every IPv4 packet
+        /// sent from this socket, the upper six bits of which are the DSCP
+        /// value used to mark traffic for QoS treatment.
+        pub fn set_tos(&self, tos: u32) -> io::Result<()> {
+            sockopt::set(self.as_raw_fd(),
+                         libc::IPPROTO_IP,
+                         libc::IP_TOS,
+                         tos as libc::c_int)
+        }
+
+        /// Gets the value of the `IP_TOS` option for this socket.
+        ///
+        /// For more information about this option, see
+        /// [`set_tos`][link].
+        ///
+        /// [link]: #method.set_tos
+        pub fn tos(&self) -> io::Result<u32> {
+            let tos: libc::c_int = try!(sockopt::get(self.as_raw_fd(),
+                                                     libc::IPPROTO_IP,
+                                                     libc::IP_TOS));
+            Ok(tos as u32)
+        }
+
+        /// Sets the value of the `IPV6_TCLASS` option for this socket.
+        ///
+        /// This is the IPv6 equivalent of `IP_TOS`, setting the traffic class
+        /// field of every IPv6 packet sent from this socket.
+        pub fn set_traffic_class(&self, tclass: u32) -> io::Result<()> {
+            sockopt::set(self.as_raw_fd(),
+                         libc::IPPROTO_IPV6,
+                         libc::IPV6_TCLASS,
+                         tclass as libc::c_int)
+        }
+
+        /// Gets the value of the `IPV6_TCLASS` option for this socket.
+        ///
+        /// For more information about this option, see
+        /// [`set_traffic_class`][link].
+        ///
+        /// [link]: #method.set_traffic_class
+        pub fn traffic_class(&self) -> io::Result<u32> {
+            let tclass: libc::c_int = try!(sockopt::get(self.as_raw_fd(),
+                                                        libc::IPPROTO_IPV6,
+                                                        libc::IPV6_TCLASS));
+            Ok(tclass as u32)
+        }
+    }
+
     impl AsRawFd for TcpListener {
         fn as_raw_fd(&self) -> RawFd {
             self.io.get_ref().as_raw_fd()
@@ -0,0 +1,268 @@
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+
+use futures::{Async, Poll, Stream, Sink, StartSend, AsyncSink};
+
+use net::UdpSocket;
+
+/// Encoding of frames via buffers.
+///
+/// This trait is used when constructing an instance of `UdpFramed` and provides
+/// the `In` and `Out` types which are decoded and encoded from the socket,
+/// respectively.
+///
+/// Because UDP is a connectionless protocol, the `decode` method receives the
+/// address where data came from and the `encode` method is also responsible for
+/// determining the remote host to which the datagram should be sent
+///
+/// The trait itself is implemented on a type that can track state for decoding
+/// or encoding, which is particularly useful for streaming parsers. In many
+/// cases, though, this type will simply be a unit struct (e.g. `struct
+/// HttpCodec`).
+pub trait UdpCodec {
+    /// The type of decoded frames.
+    type In;
+
+    /// The type of frames to be encoded.
+    type Out;
+
+    /// Attempts to decode a frame from the provided buffer of bytes.
+    ///
+    /// This method is called by `UdpFramed` on a single datagram which has been
+    /// read from a socket. The `buf` argument contains the data that was
+    /// received from the remote address, and `src` is the address the data
+    /// came from. Note that typically this method should require the entire
+    /// contents of `buf` to be valid or otherwise return an error with
+    /// trailing data.
+    ///
+    /// Finally, if the bytes in the buffer are malformed then an error is
+    /// returned indicating why. By default this informs `UdpFramed` that the
+    /// stream is now corrupt and should be terminated, but the framed adapter
+    /// can also be configured to skip or yield malformed datagrams.
+    fn decode(&mut self, src: &SocketAddr, buf: &[u8]) -> io::Result<Self::In>;
+
+    /// Encodes a frame into the buffer provided.
+    ///
+    /// This method will encode `msg` into the byte buffer provided by `buf`.
+    /// The `buf` provided is an internal buffer of the `UdpFramed` instance and
+    /// will be written out when possible.
+    ///
+    /// The encode method also determines the destination to which the buffer
+    /// should be directed, which will be returned as a `SocketAddr`.
+    fn encode(&mut self, msg: Self::Out, buf: &mut Vec<u8>) -> SocketAddr;
+}
+
+/// A unified `Stream` and `Sink` interface to an underlying `UdpSocket`, using
+/// the `UdpCodec` trait to encode and decode frames.
+///
+/// You can acquire a `UdpFramed` instance by using the `UdpSocket::framed`
+/// adapter.
+pub struct UdpFramed<C: UdpCodec> {
+    socket: UdpSocket,
+    codec: C,
+    rd: Vec<u8>,
+    wr: Vec<u8>,
+    out_addr: SocketAddr,
+    flushed: bool,
+    policy: DecodePolicy<C::In>,
+}
+
+/// What a `UdpFramed` does with a datagram its codec failed to decode.
+enum DecodePolicy<T> {
+    Propagate,
+    Skip,
+    Yield(fn(Malformed) -> T),
+}
+
+/// A datagram which a `UdpCodec` failed to decode.
+///
+/// This is handed to the stream's item type when a `UdpFramed` is configured
+/// with `yield_malformed`, preserving the source address and data of the
+/// datagram alongside the decoding error.
+pub struct Malformed {
+    addr: SocketAddr,
+    data: Vec<u8>,
+    error: io::Error,
+}
+
+impl Malformed {
+    /// Returns the address the malformed datagram was received from.
+    pub fn addr(&self) -> &SocketAddr {
+        &self.addr
+    }
+
+    /// Returns the contents of the malformed datagram.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Returns the error the codec returned when decoding the datagram.
+    pub fn error(&self) -> &io::Error {
+        &self.error
+    }
+
+    /// Consumes this value, returning the datagram's contents and the error
+    /// the codec returned when decoding it.
+    pub fn into_parts(self) -> (Vec<u8>, io::Error) {
+        (self.data, self.error)
+    }
+}
+
+impl fmt::Debug for Malformed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Malformed")
+         .field("addr", &self.addr)
+         .field("len", &self.data.len())
+         .field("error", &self.error)
+         .finish()
+    }
+}
+
+impl<C: UdpCodec> Stream for UdpFramed<C> {
+    type Item = C::In;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<C::In>, io::Error> {
+        loop {
+            let (n, addr) = try_nb!(self.socket.recv_from(&mut self.rd));
+            trace!("received {} bytes, decoding", n);
+            let err = match self.codec.decode(&addr, &self.rd[..n]) {
+                Ok(frame) => {
+                    trace!("frame decoded from buffer");
+                    return Ok(Async::Ready(Some(frame)))
+                }
+                Err(e) => e,
+            };
+            match self.policy {
+                DecodePolicy::Propagate => return Err(err),
+                DecodePolicy::Skip => {
+                    debug!("skipping malformed datagram from {}: {}", addr, err);
+                }
+                DecodePolicy::Yield(into_frame) => {
+                    let malformed = Malformed {
+                        addr: addr,
+                        data: self.rd[..n].to_vec(),
+                        error: err,
+                    };
+                    return Ok(Async::Ready(Some(into_frame(malformed))))
+                }
+            }
+        }
+    }
+}
+
+impl<C: UdpCodec> Sink for UdpFramed<C> {
+    type SinkItem = C::Out;
+    type SinkError = io::Error;
+
+    fn start_send(&mut self, item: C::Out) -> StartSend<C::Out, io::Error> {
+        trace!("sending frame");
+
+        if !self.flushed {
+            match try!(self.poll_complete()) {
+                Async::Ready(()) => {},
+                Async::NotReady => return Ok(AsyncSink::NotReady(item)),
+            }
+        }
+
+        self.out_addr = self.codec.encode(item, &mut self.wr);
+        self.flushed = false;
+        trace!("frame encoded; length={}", self.wr.len());
+
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), io::Error> {
+        if self.flushed {
+            return Ok(Async::Ready(()))
+        }
+
+        trace!("flushing frame; length={}", self.wr.len());
+        let n = try_nb!(self.socket.send_to(&self.wr, &self.out_addr));
+        trace!("written {}", n);
+
+        let wrote_all = n == self.wr.len();
+        self.wr.clear();
+        self.flushed = true;
+
+        if wrote_all {
+            Ok(Async::Ready(()))
+        } else {
+            Err(io::Error::new(io::ErrorKind::Other,
+                               "failed to write entire datagram to socket"))
+        }
+    }
+}
+
+pub fn new<C: UdpCodec>(socket: UdpSocket, codec: C) -> UdpFramed<C> {
+    UdpFramed {
+        socket: socket,
+        codec: codec,
+        out_addr: "0.0.0.0:0".parse().unwrap(),
+        rd: vec![0; 64 * 1024],
+        wr: Vec::with_capacity(8 * 1024),
+        flushed: true,
+        policy: DecodePolicy::Propagate,
+    }
+}
+
+impl<C: UdpCodec> UdpFramed<C> {
+    /// Configures this stream to return an error when a datagram fails to
+    /// decode.
+    ///
+    /// This is the default policy. The error returned by `UdpCodec::decode`
+    /// is returned from the stream, which typically terminates it.
+    pub fn propagate_malformed(&mut self) {
+        self.policy = DecodePolicy::Propagate;
+    }
+
+    /// Configures this stream to discard datagrams which fail to decode,
+    /// logging them at the debug level.
+    ///
+    /// The stream continues with the next datagram received from the socket,
+    /// so a single peer sending garbage can't terminate it.
+    pub fn skip_malformed(&mut self) {
+        self.policy = DecodePolicy::Skip;
+    }
+
+    /// Configures this stream to yield datagrams which fail to decode as items
+    /// of the stream.
+    ///
+    /// Each malformed datagram is packaged up, along with its source address
+    /// and the decoding error, in a `Malformed` value which is then converted
+    /// into the codec's `In` type through its `From` implementation.
+    pub fn yield_malformed(&mut self)
+        where C::In: From<Malformed>,
+    {
+        self.policy = DecodePolicy::Yield(C::In::from);
+    }
+
+    /// Returns a reference to the underlying I/O stream wrapped by `Framed`.
+    ///
+    /// Note that care should be taken to not tamper with the underlying stream
+    /// of data coming in as it may corrupt the stream of frames otherwise being
+    /// worked with.
+    pub fn get_ref(&self) -> &UdpSocket {
+        &self.socket
+    }
+
+    /// Returns a mutable reference to the underlying I/O stream wrapped by
+    /// `Framed`.
+    ///
+    /// Note that care should be taken to not tamper with the underlying stream
+    /// of data coming in as it may corrupt the stream of frames otherwise being
+    /// worked with.
+    pub fn get_mut(&mut self) -> &mut UdpSocket {
+        &mut self.socket
+    }
+
+    /// Consumes the `Framed`, returning its underlying I/O stream.
+    ///
+    /// Note that care should be taken to not tamper with the underlying stream
+    /// of data coming in as it may corrupt the stream of frames otherwise being
+    /// worked with.
+    pub fn into_inner(self) -> UdpSocket {
+        self.socket
+    }
+}
@@ -0,0 +1,1114 @@
+use std::io;
+use std::net::{self, SocketAddr, Ipv4Addr, Ipv6Addr};
+use std::fmt;
+
+use futures::{Async, Future, Poll};
+use mio;
+
+use reactor::{Handle, PollEvented};
+
+mod frame;
+mod pair;
+pub use self::frame::{UdpFramed, UdpCodec, Malformed};
+pub use self::pair::UdpPair;
+#[cfg(target_os = "linux")]
+pub use self::sys::PacketInfo;
+
+/// An I/O object representing a UDP socket.
+pub struct UdpSocket {
+    io: PollEvented<mio::udp::UdpSocket>,
+}
+
+impl UdpSocket {
+    /// Create a new UDP socket bound to the specified address.
+    ///
+    /// This function will create a new UDP socket and attempt to bind it to the
+    /// `addr` provided. If the result is `Ok`, the socket has successfully bound.
+    pub fn bind(addr: &SocketAddr, handle: &Handle) -> io::Result<UdpSocket> {
+        let udp = try!(mio::udp::UdpSocket::bind(addr));
+        UdpSocket::new(udp, handle)
+    }
+
+    fn new(socket: mio::udp::UdpSocket, handle: &Handle) -> io::Result<UdpSocket> {
+        let io = try!(PollEvented::new(socket, handle));
+        Ok(UdpSocket { io: io })
+    }
+
+    /// Creates a new `UdpSocket` from the previously bound socket provided.
+    ///
+    /// The socket given will be registered with the event loop that `handle` is
+    /// associated with. This function requires that `socket` has previously
+    /// been bound to an address to work correctly.
+    ///
+    /// This can be used in conjunction with net2's `UdpBuilder` interface to
+    /// configure a socket before it's handed off, such as setting options like
+    /// `reuse_address` or binding to multiple addresses.
+    pub fn from_socket(socket: net::UdpSocket,
+                       handle: &Handle) -> io::Result<UdpSocket> {
+        let udp = try!(mio::udp::UdpSocket::from_socket(socket));
+        UdpSocket::new(udp, handle)
+    }
+
+    /// Provides a `Stream` and `Sink` interface for reading and writing to this
+    /// `UdpSocket` object, using the provided `UdpCodec` to read and write the
+    /// raw data.
+    ///
+    /// Raw UDP sockets work with datagrams, but higher-level code usually
+    /// wants to batch these into meaningful chunks, called "frames". This
+    /// method layers framing on top of this socket by using the `UdpCodec`
+    /// trait to handle encoding and decoding of messages frames. Note that
+    /// the incoming and outgoing frame types may be distinct.
+    ///
+    /// This function returns a *single* object that is both `Stream` and
+    /// `Sink`; grouping this into a single object is often useful for layering
+    /// things which require both read and write access to the underlying
+    /// object.
+    ///
+    /// If you want to work more directly with the streams and sink, consider
+    /// calling `split` on the `UdpFramed` returned by this method, which will
+    /// break them into separate objects, allowing them to interact more
+    /// easily.
+    pub fn framed<C: UdpCodec>(self, codec: C) -> UdpFramed<C> {
+        frame::new(self, codec)
+    }
+
+    /// Returns the local address that this stream is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().local_addr()
+    }
+
+    /// Test whether this socket is ready to be read or not.
+    ///
+    /// If the socket is *not* readable then the current task is scheduled to
+    /// get a notification when the socket does become readable. That is, this
+    /// is only suitable for calling in a `Future::poll` method and will
+    /// automatically handle ensuring a retry once the socket is readable again.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Test whether this socket is writey to be written to or not.
+    ///
+    /// If the socket is *not* writable then the current task is scheduled to
+    /// get a notification when the socket does become writable. That is, this
+    /// is only suitable for calling in a `Future::poll` method and will
+    /// automatically handle ensuring a retry once the socket is writable again.
+    pub fn poll_write(&self) -> Async<()> {
+        self.io.poll_write()
+    }
+
+    /// Returns the token this socket is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
+    /// Connects the UDP socket setting the default destination for `send` and
+    /// limiting packets that are read via `recv` to the address specified in
+    /// `addr`.
+    ///
+    /// Connected sockets also receive ICMP errors, such as "port unreachable",
+    /// for datagrams they send, which are reported as errors from subsequent
+    /// calls to `send` or `recv`.
+    pub fn connect(&self, addr: &SocketAddr) -> io::Result<()> {
+        self.io.get_ref().connect(*addr)
+    }
+
+    /// Sends data on the socket to the address previously bound via
+    /// `connect`. On success, returns the number of bytes written.
+    ///
+    /// If the socket isn't writable then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is writable again.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        if let Async::NotReady = self.io.poll_write() {
+            return Err(mio::would_block())
+        }
+        match self.io.get_ref().send(buf) {
+            Ok(Some(n)) => Ok(n),
+            Ok(None) => {
+                self.io.need_write();
+                Err(mio::would_block())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Receives data from the socket previously bound via `connect`. On
+    /// success, returns the number of bytes read.
+    ///
+    /// If no datagram is available then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is readable again.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Async::NotReady = self.io.poll_read() {
+            return Err(mio::would_block())
+        }
+        match self.io.get_ref().recv(buf) {
+            Ok(Some(n)) => Ok(n),
+            Ok(None) => {
+                self.io.need_read();
+                Err(mio::would_block())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Sends data on the socket to the given address. On success, returns the
+    /// number of bytes written.
+    ///
+    /// Address type can be any implementor of `ToSocketAddrs` trait. See its
+    /// documentation for concrete examples.
+    pub fn send_to(&self, buf: &[u8], target: &SocketAddr) -> io::Result<usize> {
+        if let Async::NotReady = self.io.poll_write() {
+            return Err(mio::would_block())
+        }
+        match self.io.get_ref().send_to(buf, target) {
+            Ok(Some(n)) => Ok(n),
+            Ok(None) => {
+                self.io.need_write();
+                Err(mio::would_block())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Creates a future that will write the entire contents of the buffer
+    /// `buf` provided as a datagram to this socket.
+    ///
+    /// The returned future will return after data has been written to the
+    /// outbound socket.  The future will resolve to the stream as well as the
+    /// buffer (for reuse if needed).
+    ///
+    /// Any error which happens during writing will cause both the stream and
+    /// the buffer to get destroyed. Note that failure to write the entire
+    /// buffer is considered an error for the purposes of sending a datagram.
+    ///
+    /// The `buf` parameter here only requires the `AsRef<[u8]>` trait, which
+    /// should be broadly applicable to accepting data which can be converted
+    /// to a slice.  The `Window` struct is also available in this crate to
+    /// provide a different window into a slice if necessary.
+    pub fn send_dgram<T>(self, buf: T, addr: SocketAddr) -> SendDgram<T>
+        where T: AsRef<[u8]>,
+    {
+        SendDgram(Some((self, buf, addr)))
+    }
+
+    /// Sends a batch of datagrams on the socket, each to its paired address.
+    ///
+    /// On success returns the number of datagrams from the front of `msgs`
+    /// which were accepted by the kernel. This is always at least 1 and may be
+    /// fewer than `msgs.len()` if the socket's send buffer filled up, in which
+    /// case the remaining datagrams should be resubmitted once the socket is
+    /// writable again.
+    ///
+    /// On Linux this is implemented with a single `sendmmsg` call. On other
+    /// platforms datagrams are sent one at a time until the socket would block.
+    ///
+    /// If no datagram could be sent then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is writable again. An empty `msgs` is refused with an "invalid input"
+    /// error.
+    pub fn send_many(&self, msgs: &[(&[u8], SocketAddr)]) -> io::Result<usize> {
+        if msgs.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "no datagrams to send"))
+        }
+        if let Async::NotReady = self.io.poll_write() {
+            return Err(mio::would_block())
+        }
+        #[cfg(target_os = "linux")]
+        let r = sys::send_many(self, msgs);
+        #[cfg(not(target_os = "linux"))]
+        let r = self.send_one_at_a_time(msgs);
+        if let Err(ref e) = r {
+            if e.kind() == io::ErrorKind::WouldBlock {
+                self.io.need_write();
+            }
+        }
+        r
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn send_one_at_a_time(&self, msgs: &[(&[u8], SocketAddr)])
+                          -> io::Result<usize> {
+        let mut amt = 0;
+        for &(buf, ref addr) in msgs {
+            match self.io.get_ref().send_to(buf, addr) {
+                Ok(Some(_)) => amt += 1,
+                Ok(None) if amt == 0 => return Err(mio::would_block()),
+                Ok(None) => {
+                    self.io.need_write();
+                    break
+                }
+                Err(e) => {
+                    if amt == 0 {
+                        return Err(e)
+                    }
+                    break
+                }
+            }
+        }
+        Ok(amt)
+    }
+
+    /// Receives data from the socket. On success, returns the number of bytes
+    /// read and the address from whence the data came.
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        if let Async::NotReady = self.io.poll_read() {
+            return Err(mio::would_block())
+        }
+        match self.io.get_ref().recv_from(buf) {
+            Ok(Some(n)) => Ok(n),
+            Ok(None) => {
+                self.io.need_read();
+                Err(mio::would_block())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Receives a batch of datagrams from the socket, one into each of the
+    /// buffers in `bufs`.
+    ///
+    /// For each datagram received the number of bytes read and the address
+    /// whence the data came is appended to `results`, in the same order as
+    /// the buffers they were read into. On success the number of datagrams
+    /// received is returned, which is always at least 1 and may be fewer than
+    /// the number of buffers provided.
+    ///
+    /// On Linux this is implemented with a single `recvmmsg` call, pulling as
+    /// many datagrams as possible out of the kernel per readiness event. On
+    /// other platforms datagrams are read one at a time until the socket
+    /// would block.
+    ///
+    /// If no datagram is available then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is readable again. An empty `bufs` is refused with an "invalid input"
+    /// error.
+    pub fn recv_many(&self,
+                     bufs: &mut [&mut [u8]],
+                     results: &mut Vec<(usize, SocketAddr)>)
+                     -> io::Result<usize> {
+        if bufs.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "no buffers to receive into"))
+        }
+        if let Async::NotReady = self.io.poll_read() {
+            return Err(mio::would_block())
+        }
+        #[cfg(target_os = "linux")]
+        let r = sys::recv_many(self, bufs, results);
+        #[cfg(not(target_os = "linux"))]
+        let r = self.recv_one_at_a_time(bufs, results);
+        if let Err(ref e) = r {
+            if e.kind() == io::ErrorKind::WouldBlock {
+                self.io.need_read();
+            }
+        }
+        r
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn recv_one_at_a_time(&self,
+                          bufs: &mut [&mut [u8]],
+                          results: &mut Vec<(usize, SocketAddr)>)
+                          -> io::Result<usize> {
+        let mut amt = 0;
+        for buf in bufs.iter_mut() {
+            match self.io.get_ref().recv_from(buf) {
+                Ok(Some(pair)) => {
+                    results.push(pair);
+                    amt += 1;
+                }
+                Ok(None) if amt == 0 => return Err(mio::would_block()),
+                Ok(None) => {
+                    self.io.need_read();
+                    break
+                }
+                Err(e) => {
+                    if amt == 0 {
+                        return Err(e)
+                    }
+                    break
+                }
+            }
+        }
+        Ok(amt)
+    }
+
+    /// Creates a future that receive a datagram to be written to the buffer
+    /// provided.
+    ///
+    /// The returned future will return after a datagram has been received on
+    /// this socket. The future will resolve to the socket, the buffer, the
+    /// amount of data read, and the address the data was received from.
+    ///
+    /// An error during reading will cause the socket and buffer to get
+    /// destroyed and the socket will be returned.
+    ///
+    /// The `buf` parameter here only requires the `AsMut<[u8]>` trait, which
+    /// should be broadly applicable to accepting data which can be converted
+    /// to a slice.  The `Window` struct is also available in this crate to
+    /// provide a different window into a slice if necessary.
+    pub fn recv_dgram<T>(self, buf: T) -> RecvDgram<T>
+        where T: AsMut<[u8]>,
+    {
+        RecvDgram(Some((self, buf)))
+    }
+
+    /// Gets the value of the `SO_BROADCAST` option for this socket.
+    ///
+    /// For more information about this option, see
+    /// [`set_broadcast`][link].
+    ///
+    /// [link]: #method.set_broadcast
+    pub fn broadcast(&self) -> io::Result<bool> {
+        self.io.get_ref().broadcast()
+    }
+
+    /// Sets the value of the `SO_BROADCAST` option for this socket.
+    ///
+    /// When enabled, this socket is allowed to send packets to a broadcast
+    /// address.
+    pub fn set_broadcast(&self, on: bool) -> io::Result<()> {
+        self.io.get_ref().set_broadcast(on)
+    }
+
+    /// Gets the value of the `IP_MULTICAST_LOOP` option for this socket.
+    ///
+    /// For more information about this option, see
+    /// [`set_multicast_loop_v4`][link].
+    ///
+    /// [link]: #method.set_multicast_loop_v4
+    pub fn multicast_loop_v4(&self) -> io::Result<bool> {
+        self.io.get_ref().multicast_loop_v4()
+    }
+
+    /// Sets the value of the `IP_MULTICAST_LOOP` option for this socket.
+    ///
+    /// If enabled, multicast packets will be looped back to the local socket.
+    /// Note that this may not have any affect on IPv6 sockets.
+    pub fn set_multicast_loop_v4(&self, on: bool) -> io::Result<()> {
+        self.io.get_ref().set_multicast_loop_v4(on)
+    }
+
+    /// Gets the value of the `IP_MULTICAST_TTL` option for this socket.
+    ///
+    /// For more information about this option, see
+    /// [`set_multicast_ttl_v4`][link].
+    ///
+    /// [link]: #method.set_multicast_ttl_v4
+    pub fn multicast_ttl_v4(&self) -> io::Result<u32> {
+        self.io.get_ref().multicast_ttl_v4()
+    }
+
+    /// Sets the value of the `IP_MULTICAST_TTL` option for this socket.
+    ///
+    /// Indicates the time-to-live value of outgoing multicast packets for
+    /// this socket. The default value is 1 which means that multicast packets
+    /// don't leave the local network unless explicitly requested.
+    ///
+    /// Note that this may not have any affect on IPv6 sockets.
+    pub fn set_multicast_ttl_v4(&self, ttl: u32) -> io::Result<()> {
+        self.io.get_ref().set_multicast_ttl_v4(ttl)
+    }
+
+    /// Gets the value of the `IPV6_MULTICAST_LOOP` option for this socket.
+    ///
+    /// For more information about this option, see
+    /// [`set_multicast_loop_v6`][link].
+    ///
+    /// [link]: #method.set_multicast_loop_v6
+    pub fn multicast_loop_v6(&self) -> io::Result<bool> {
+        self.io.get_ref().multicast_loop_v6()
+    }
+
+    /// Sets the value of the `IPV6_MULTICAST_LOOP` option for this socket.
+    ///
+    /// Controls whether this socket sees the multicast packets it sends itself.
+    /// Note that this may not have any affect on IPv4 sockets.
+    pub fn set_multicast_loop_v6(&self, on: bool) -> io::Result<()> {
+        self.io.get_ref().set_multicast_loop_v6(on)
+    }
+
+    /// Gets the value of the `IP_TTL` option for this socket.
+    ///
+    /// For more information about this option, see [`set_ttl`][link].
+    ///
+    /// [link]: #method.set_ttl
+    pub fn ttl(&self) -> io::Result<u32> {
+        self.io.get_ref().ttl()
+    }
+
+    /// Sets the value for the `IP_TTL` option on this socket.
+    ///
+    /// This value sets the time-to-live field that is used in every packet sent
+    /// from this socket.
+    pub fn set_ttl(&self, ttl: u32) -> io::Result<()> {
+        self.io.get_ref().set_ttl(ttl)
+    }
+
+    /// Executes an operation of the `IP_ADD_MEMBERSHIP` type.
+    ///
+    /// This function specifies a new multicast group for this socket to join.
+    /// The address must be a valid multicast address, and `interface` is the
+    /// address of the local interface with which the system should join the
+    /// multicast group. If it's equal to `INADDR_ANY` then an appropriate
+    /// interface is chosen by the system.
+    pub fn join_multicast_v4(&self,
+                             multiaddr: &Ipv4Addr,
+                             interface: &Ipv4Addr) -> io::Result<()> {
+        self.io.get_ref().join_multicast_v4(multiaddr, interface)
+    }
+
+    /// Executes an operation of the `IPV6_ADD_MEMBERSHIP` type.
+    ///
+    /// This function specifies a new multicast group for this socket to join.
+    /// The address must be a valid multicast address, and `interface` is the
+    /// index of the interface to join/leave (or 0 to indicate any interface).
+    pub fn join_multicast_v6(&self,
+                             multiaddr: &Ipv6Addr,
+                             interface: u32) -> io::Result<()> {
+        self.io.get_ref().join_multicast_v6(multiaddr, interface)
+    }
+
+    /// Executes an operation of the `IP_DROP_MEMBERSHIP` type.
+    ///
+    /// For more information about this option, see
+    /// [`join_multicast_v4`][link].
+    ///
+    /// [link]: #method.join_multicast_v4
+    pub fn leave_multicast_v4(&self,
+                              multiaddr: &Ipv4Addr,
+                              interface: &Ipv4Addr) -> io::Result<()> {
+        self.io.get_ref().leave_multicast_v4(multiaddr, interface)
+    }
+
+    /// Executes an operation of the `IPV6_DROP_MEMBERSHIP` type.
+    ///
+    /// For more information about this option, see
+    /// [`join_multicast_v6`][link].
+    ///
+    /// [link]: #method.join_multicast_v6
+    pub fn leave_multicast_v6(&self,
+                              multiaddr: &Ipv6Addr,
+                              interface: u32) -> io::Result<()> {
+        self.io.get_ref().leave_multicast_v6(multiaddr, interface)
+    }
+}
+
+impl fmt::Debug for UdpSocket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.io.get_ref().fmt(f)
+    }
+}
+
+/// A future used to write the entire contents of some data to a UDP socket.
+///
+/// This is created by the `UdpSocket::send_dgram` method.
+pub struct SendDgram<T>(Option<(UdpSocket, T, SocketAddr)>);
+
+fn incomplete_write(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, reason)
+}
+
+impl<T> Future for SendDgram<T>
+    where T: AsRef<[u8]>,
+{
+    type Item = (UdpSocket, T);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(UdpSocket, T), io::Error> {
+        {
+            let (ref sock, ref buf, ref addr) =
+                *self.0.as_ref().expect("SendDgram polled after completion");
+            let n = try_nb!(sock.send_to(buf.as_ref(), addr));
+            if n != buf.as_ref().len() {
+                return Err(incomplete_write("failed to send entire message \
+                                             in datagram"))
+            }
+        }
+
+        let (sock, buf, _addr) = self.0.take().unwrap();
+        Ok(Async::Ready((sock, buf)))
+    }
+}
+
+/// A future used to receive a datagram from a UDP socket.
+///
+/// This is created by the `UdpSocket::recv_dgram` method.
+pub struct RecvDgram<T>(Option<(UdpSocket, T)>);
+
+impl<T> Future for RecvDgram<T>
+    where T: AsMut<[u8]>,
+{
+    type Item = (UdpSocket, T, usize, SocketAddr);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Self::Item, io::Error> {
+        let (n, addr) = {
+            let (ref socket, ref mut buf) =
+                *self.0.as_mut().expect("RecvDgram polled after completion");
+
+            try_nb!(socket.recv_from(buf.as_mut()))
+        };
+
+        let (socket, buf) = self.0.take().unwrap();
+        Ok(Async::Ready((socket, buf, n, addr)))
+    }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::io;
+    use std::mem;
+    use std::net::{self, Ipv4Addr, SocketAddr};
+    #[cfg(target_os = "linux")]
+    use std::net::{IpAddr, Ipv6Addr};
+    use std::os::unix::prelude::*;
+
+    use futures::Async;
+    use libc;
+    use mio;
+
+    use net::{sockaddr, sockopt};
+    use reactor::Handle;
+    use super::UdpSocket;
+
+    impl AsRawFd for UdpSocket {
+        fn as_raw_fd(&self) -> RawFd {
+            self.io.get_ref().as_raw_fd()
+        }
+    }
+
+    impl UdpSocket {
+        /// Creates a new `UdpSocket` from a raw file descriptor, registering it
+        /// with the event loop that `handle` is associated with.
+        ///
+        /// The descriptor must refer to a bound UDP socket, for example one
+        /// inherited from a parent process or created by another library. Any
+        /// options already configured on it are left untouched, and it's placed
+        /// into nonblocking mode as required by the event loop.
+        ///
+        /// # Safety
+        ///
+        /// Ownership of `fd` is transferred to the returned socket, which will
+        /// close it when dropped, so nothing else may close it afterwards.
+        pub unsafe fn from_raw_fd(fd: RawFd, handle: &Handle)
+                                  -> io::Result<UdpSocket> {
+            UdpSocket::from_socket(net::UdpSocket::from_raw_fd(fd), handle)
+        }
+
+        /// Deregisters this socket from the event loop `handle` points to,
+        /// returning the underlying socket as a `std::net::UdpSocket`.
+        ///
+        /// All socket options configured on this socket are preserved. Note
+        /// that the returned socket is still in nonblocking mode, so
+        /// `set_nonblocking(false)` should be called on it if it's to be used
+        /// with blocking I/O.
+        ///
+        /// This will return an error if `handle` doesn't point to the event
+        /// loop this socket was created on.
+        pub fn into_std(self, handle: &Handle) -> io::Result<net::UdpSocket> {
+            try!(self.io.deregister(handle));
+            let fd = try!(sockopt::cvt(unsafe {
+                libc::fcntl(self.as_raw_fd(), libc::F_DUPFD_CLOEXEC, 0)
+            }));
+            Ok(unsafe { net::UdpSocket::from_raw_fd(fd) })
+        }
+
+        /// Receives data from the socket without removing it from the input
+        /// queue. On success, returns the number of bytes read and the address
+        /// from whence the data came.
+        ///
+        /// Successive calls return the same datagram until it's consumed by
+        /// `recv_from` or a similar method, which allows inspecting a header
+        /// before deciding where to read the datagram into.
+        ///
+        /// If no datagram is available then a "would block" error is returned
+        /// and the current task is scheduled to receive a notification when
+        /// the socket is readable again.
+        pub fn peek_from(&self, buf: &mut [u8])
+                         -> io::Result<(usize, SocketAddr)> {
+            if let Async::NotReady = self.io.poll_read() {
+                return Err(mio::would_block())
+            }
+            unsafe {
+                let mut storage: libc::sockaddr_storage = mem::zeroed();
+                let mut len =
+                    mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+                let n = libc::recvfrom(self.as_raw_fd(),
+                                       buf.as_mut_ptr() as *mut libc::c_void,
+                                       buf.len(),
+                                       libc::MSG_PEEK,
+                                       &mut storage as *mut _ as *mut _,
+                                       &mut len);
+                if n < 0 {
+                    let err = io::Error::last_os_error();
+                    if err.kind() == io::ErrorKind::WouldBlock {
+                        self.io.need_read();
+                    }
+                    return Err(err)
+                }
+                let addr = try!(sockaddr::to_socket_addr(&storage, len));
+                Ok((n as usize, addr))
+            }
+        }
+
+        /// Gets the value of the `IP_MULTICAST_IF` option for this socket.
+        ///
+        /// For more information about this option, see
+        /// [`set_multicast_if_v4`][link].
+        ///
+        /// [link]: #method.set_multicast_if_v4
+        pub fn multicast_if_v4(&self) -> io::Result<Ipv4Addr> {
+            let addr: libc::in_addr = try!(sockopt::get(self.as_raw_fd(),
+                                                        libc::IPPROTO_IP,
+                                                        libc::IP_MULTICAST_IF));
+            Ok(Ipv4Addr::from(u32::from_be(addr.s_addr)))
+        }
+
+        /// Sets the value of the `IP_MULTICAST_IF` option for this socket.
+        ///
+        /// Specifies the address of the local interface through which outgoing
+        /// multicast packets are sent. If it's equal to `INADDR_ANY` then the
+        /// system picks an appropriate interface.
+        ///
+        /// Note that this may not have any affect on IPv6 sockets.
+        pub fn set_multicast_if_v4(&self, interface: &Ipv4Addr)
+                                   -> io::Result<()> {
+            let addr = libc::in_addr {
+                s_addr: u32::from(*interface).to_be(),
+            };
+            sockopt::set(self.as_raw_fd(),
+                         libc::IPPROTO_IP,
+                         libc::IP_MULTICAST_IF,
+                         addr)
+        }
+
+        /// Gets the value of the `IPV6_MULTICAST_IF` option for this socket.
+        ///
+        /// For more information about this option, see
+        /// [`set_multicast_if_v6`][link].
+        ///
+        /// [link]: #method.set_multicast_if_v6
+        pub fn multicast_if_v6(&self) -> io::Result<u32> {
+            let idx: libc::c_uint = try!(sockopt::get(self.as_raw_fd(),
+                                                      libc::IPPROTO_IPV6,
+                                                      libc::IPV6_MULTICAST_IF));
+            Ok(idx as u32)
+        }
+
+        /// Sets the value of the `IPV6_MULTICAST_IF` option for this socket.
+        ///
+        /// Specifies the index of the interface through which outgoing
+        /// multicast packets are sent, or 0 to let the system pick an
+        /// interface.
+        ///
+        /// Note that this may not have any affect on IPv4 sockets.
+        pub fn set_multicast_if_v6(&self, interface: u32) -> io::Result<()> {
+            sockopt::set(self.as_raw_fd(),
+                         libc::IPPROTO_IPV6,
+                         libc::IPV6_MULTICAST_IF,
+                         interface as libc::c_uint)
+        }
+
+        /// Gets the value of the `IPV6_MULTICAST_HOPS` option for this socket.
+        ///
+        /// For more information about this option, see
+        /// [`set_multicast_hops_v6`][link].
+        ///
+        /// [link]: #method.set_multicast_hops_v6
+        pub fn multicast_hops_v6(&self) -> io::Result<u32> {
+            let hops: libc::c_int = try!(sockopt::get(self.as_raw_fd(),
+                                                      libc::IPPROTO_IPV6,
+                                                      libc::IPV6_MULTICAST_HOPS));
+            Ok(hops as u32)
+        }
+
+        /// Sets the value of the `IPV6_MULTICAST_HOPS` option for this socket.
+        ///
+        /// Indicates the hop limit of outgoing multicast packets for this
+        /// socket, the IPv6 counterpart of `set_multicast_ttl_v4`. The default
+        /// value is 1 which means that multicast packets don't leave the local
+        /// network unless explicitly requested.
+        ///
+        /// Note that this may not have any affect on IPv4 sockets.
+        pub fn set_multicast_hops_v6(&self, hops: u32) -> io::Result<()> {
+            sockopt::set(self.as_raw_fd(),
+                         libc::IPPROTO_IPV6,
+                         libc::IPV6_MULTICAST_HOPS,
+                         hops as libc::c_int)
+        }
+
+        /// Gets the value of the `IPV6_UNICAST_HOPS` option for this socket.
+        ///
+        /// For more information about this option, see
+        /// [`set_unicast_hops_v6`][link].
+        ///
+        /// [link]: #method.set_unicast_hops_v6
+        pub fn unicast_hops_v6(&self) -> io::Result<u32> {
+            let hops: libc::c_int = try!(sockopt::get(self.as_raw_fd(),
+                                                      libc::IPPROTO_IPV6,
+                                                      libc::IPV6_UNICAST_HOPS));
+            Ok(hops as u32)
+        }
+
+        /// Sets the value of the `IP_TOS` option for this socket.
+        ///
+        /// This value sets the type-of-service field of every IPv4 packet
+        /// sent from this socket, the upper six bits of which are the DSCP
+        /// value used to mark traffic for QoS treatment.
+        pub fn set_tos(&self, tos: u32) -> io::Result<()> {
+            sockopt::set(self.as_raw_fd(),
+                         libc::IPPROTO_IP,
+                         libc::IP_TOS,
+                         tos as libc::c_int)
+        }
+
+        /// Gets the value of the `IP_TOS` option for this socket.
+        ///
+        /// For more information about this option, see
+        /// [`set_tos`][link].
+        ///
+        /// [link]: #method.set_tos
+        pub fn tos(&self) -> io::Result<u32> {
+            let tos: libc::c_int = try!(sockopt::get(self.as_raw_fd(),
+                                                     libc::IPPROTO_IP,
+                                                     libc::IP_TOS));
+            Ok(tos as u32)
+        }
+
+        /// Sets the value of the `IPV6_TCLASS` option for this socket.
+        ///
+        /// This is the IPv6 equivalent of `IP_TOS`, setting the traffic class
+        /// field of every IPv6 packet sent from this socket.
+        pub fn set_traffic_class(&self, tclass: u32) -> io::Result<()> {
+            sockopt::set(self.as_raw_fd(),
+                         libc::IPPROTO_IPV6,
+                         libc::IPV6_TCLASS,
+                         tclass as libc::c_int)
+        }
+
+        /// Gets the value of the `IPV6_TCLASS` option for this socket.
+        ///
+        /// For more information about this option, see
+        /// [`set_traffic_class`][link].
+        ///
+        /// [link]: #method.set_traffic_class
+        pub fn traffic_class(&self) -> io::Result<u32> {
+            let tclass: libc::c_int = try!(sockopt::get(self.as_raw_fd(),
+                                                        libc::IPPROTO_IPV6,
+                                                        libc::IPV6_TCLASS));
+            Ok(tclass as u32)
+        }
+
+        /// Sets the value of the `IPV6_UNICAST_HOPS` option for this socket.
+        ///
+        /// This value sets the hop limit field that is used in every unicast
+        /// packet sent from this socket, the IPv6 counterpart of `set_ttl`.
+        ///
+        /// Note that this may not have any affect on IPv4 sockets.
+        pub fn set_unicast_hops_v6(&self, hops: u32) -> io::Result<()> {
+            sockopt::set(self.as_raw_fd(),
+                         libc::IPPROTO_IPV6,
+                         libc::IPV6_UNICAST_HOPS,
+                         hops as libc::c_int)
+        }
+    }
+
+    /// Destination information for a datagram received via
+    /// `UdpSocket::recv_from_pktinfo`.
+    ///
+    /// This records which local address a datagram was sent to and which
+    /// interface it arrived on, which is required for a socket bound to a
+    /// wildcard address to know where a peer believes it is talking to.
+    #[cfg(target_os = "linux")]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct PacketInfo {
+        dst: IpAddr,
+        ifindex: u32,
+    }
+
+    #[cfg(target_os = "linux")]
+    impl PacketInfo {
+        /// Returns the destination address of the datagram.
+        pub fn dst_addr(&self) -> IpAddr {
+            self.dst
+        }
+
+        /// Returns the index of the interface the datagram arrived on.
+        pub fn interface_index(&self) -> u32 {
+            self.ifindex
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    impl UdpSocket {
+        /// Sets whether destination information is recorded for datagrams
+        /// received on this socket.
+        ///
+        /// This enables the `IP_PKTINFO` option on IPv4 sockets and the
+        /// `IPV6_RECVPKTINFO` option on IPv6 sockets, which must be set for
+        /// `recv_from_pktinfo` to return any `PacketInfo`.
+        pub fn set_recv_pktinfo(&self, on: bool) -> io::Result<()> {
+            let on = on as libc::c_int;
+            match try!(self.local_addr()) {
+                SocketAddr::V4(..) => {
+                    sockopt::set(self.as_raw_fd(),
+                                 libc::IPPROTO_IP,
+                                 libc::IP_PKTINFO,
+                                 on)
+                }
+                SocketAddr::V6(..) => {
+                    sockopt::set(self.as_raw_fd(),
+                                 libc::IPPROTO_IPV6,
+                                 libc::IPV6_RECVPKTINFO,
+                                 on)
+                }
+            }
+        }
+
+        /// Receives data from the socket along with the destination address and
+        /// interface of the datagram.
+        ///
+        /// On success, returns the number of bytes read, the address from
+        /// whence the data came, and the datagram's `PacketInfo`. The packet
+        /// information is only available once `set_recv_pktinfo` has been
+        /// enabled on this socket, otherwise `None` is returned in its place.
+        ///
+        /// If no datagram is available then a "would block" error is returned
+        /// and the current task is scheduled to receive a notification when
+        /// the socket is readable again.
+        pub fn recv_from_pktinfo(&self, buf: &mut [u8])
+                                 -> io::Result<(usize, SocketAddr, Option<PacketInfo>)> {
+            let mut info = None;
+            let (n, addr) = try!(recv_msg(self, buf, |level, ty, data| unsafe {
+                match (level, ty) {
+                    (libc::IPPROTO_IP, libc::IP_PKTINFO) => {
+                        let pi = &*(data as *const libc::in_pktinfo);
+                        let dst = u32::from_be(pi.ipi_addr.s_addr);
+                        info = Some(PacketInfo {
+                            dst: IpAddr::V4(Ipv4Addr::from(dst)),
+                            ifindex: pi.ipi_ifindex as u32,
+                        });
+                    }
+                    (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO) => {
+                        let pi = &*(data as *const libc::in6_pktinfo);
+                        let dst = Ipv6Addr::from(pi.ipi6_addr.s6_addr);
+                        info = Some(PacketInfo {
+                            dst: IpAddr::V6(dst),
+                            ifindex: pi.ipi6_ifindex,
+                        });
+                    }
+                    _ => {}
+                }
+            }));
+            Ok((n, addr, info))
+        }
+
+        /// Sets the segment size used for UDP segmentation offload (the
+        /// `UDP_SEGMENT` option) on this socket.
+        ///
+        /// When set to a nonzero value, each buffer passed to a send method is
+        /// split by the kernel (or the network card) into datagrams of
+        /// `size` bytes, with only the final datagram possibly being shorter.
+        /// This allows sending many datagrams to the same destination with a
+        /// single system call. Setting the size to 0 disables segmentation.
+        ///
+        /// This requires Linux 4.18 or later.
+        pub fn set_gso_segment_size(&self, size: u16) -> io::Result<()> {
+            sockopt::set(self.as_raw_fd(),
+                         libc::SOL_UDP,
+                         libc::UDP_SEGMENT,
+                         size as libc::c_int)
+        }
+
+        /// Gets the value of the `UDP_SEGMENT` option on this socket.
+        ///
+        /// For more information about this option, see
+        /// [`set_gso_segment_size`][link].
+        ///
+        /// [link]: #method.set_gso_segment_size
+        pub fn gso_segment_size(&self) -> io::Result<u16> {
+            let size: libc::c_int = try!(sockopt::get(self.as_raw_fd(),
+                                                      libc::SOL_UDP,
+                                                      libc::UDP_SEGMENT));
+            Ok(size as u16)
+        }
+
+        /// Sets whether generic receive offload (the `UDP_GRO` option) is
+        /// enabled on this socket.
+        ///
+        /// When enabled the kernel may coalesce multiple datagrams from the
+        /// same source into a single buffer delivered by one receive call. Use
+        /// `recv_from_gro` to learn the size of the segments each such buffer
+        /// is made up of.
+        ///
+        /// This requires Linux 5.0 or later.
+        pub fn set_gro(&self, on: bool) -> io::Result<()> {
+            sockopt::set(self.as_raw_fd(),
+                         libc::SOL_UDP,
+                         libc::UDP_GRO,
+                         on as libc::c_int)
+        }
+
+        /// Receives data from the socket, along with the segment size of any
+        /// datagrams coalesced by generic receive offload.
+        ///
+        /// On success, returns the number of bytes read, the address from
+        /// whence the data came, and the size of each segment in `buf` if the
+        /// kernel coalesced several datagrams into it. The last segment may be
+        /// shorter than the others. If `None` is returned then `buf` holds a
+        /// single datagram.
+        ///
+        /// If no datagram is available then a "would block" error is returned
+        /// and the current task is scheduled to receive a notification when
+        /// the socket is readable again.
+        pub fn recv_from_gro(&self, buf: &mut [u8])
+                             -> io::Result<(usize, SocketAddr, Option<usize>)> {
+            let mut segment = None;
+            let (n, addr) = try!(recv_msg(self, buf, |level, ty, data| unsafe {
+                if level == libc::SOL_UDP && ty == libc::UDP_GRO {
+                    segment = Some(*(data as *const libc::c_int) as usize);
+                }
+            }));
+            Ok((n, addr, segment))
+        }
+    }
+
+    /// Receives a single datagram with `recvmsg`, handing each control message
+    /// which arrives with it to `cmsg` as its level, type, and data.
+    #[cfg(target_os = "linux")]
+    fn recv_msg<F>(socket: &UdpSocket, buf: &mut [u8], mut cmsg: F)
+                   -> io::Result<(usize, SocketAddr)>
+        where F: FnMut(libc::c_int, libc::c_int, *const u8),
+    {
+        if let Async::NotReady = socket.io.poll_read() {
+            return Err(mio::would_block())
+        }
+        unsafe {
+            let mut storage: libc::sockaddr_storage = mem::zeroed();
+            // Large enough for the handful of small control messages the
+            // options on this socket can generate.
+            let mut control = [0u64; 16];
+            let mut iov = libc::iovec {
+                iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+                iov_len: buf.len(),
+            };
+            let mut msg: libc::msghdr = mem::zeroed();
+            msg.msg_name = &mut storage as *mut _ as *mut libc::c_void;
+            msg.msg_namelen =
+                mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+            msg.msg_iov = &mut iov;
+            msg.msg_iovlen = 1;
+            msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+            msg.msg_controllen = mem::size_of_val(&control) as _;
+
+            let n = libc::recvmsg(socket.as_raw_fd(), &mut msg, 0);
+            if n < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::WouldBlock {
+                    socket.io.need_read();
+                }
+                return Err(err)
+            }
+            let addr = try!(sockaddr::to_socket_addr(&storage, msg.msg_namelen));
+
+            let mut hdr = libc::CMSG_FIRSTHDR(&msg);
+            while !hdr.is_null() {
+                cmsg((*hdr).cmsg_level,
+                     (*hdr).cmsg_type,
+                     libc::CMSG_DATA(hdr) as *const u8);
+                hdr = libc::CMSG_NXTHDR(&msg, hdr);
+            }
+            Ok((n as usize, addr))
+        }
+    }
+
+    /// Maximum number of datagrams transferred by a single `recvmmsg` or
+    /// `sendmmsg` call, bounding the scratch space kept on the stack.
+    #[cfg(target_os = "linux")]
+    const MAX_BATCH: usize = 64;
+
+    #[cfg(target_os = "linux")]
+    pub fn recv_many(socket: &UdpSocket,
+                     bufs: &mut [&mut [u8]],
+                     results: &mut Vec<(usize, SocketAddr)>)
+                     -> io::Result<usize> {
+        use std::cmp;
+        use std::ptr;
+
+        let n = cmp::min(bufs.len(), MAX_BATCH);
+        unsafe {
+            let mut addrs: [libc::sockaddr_storage; MAX_BATCH] = mem::zeroed();
+            let mut iovecs: [libc::iovec; MAX_BATCH] = mem::zeroed();
+            let mut msgs: [libc::mmsghdr; MAX_BATCH] = mem::zeroed();
+            for i in 0..n {
+                iovecs[i].iov_base = bufs[i].as_mut_ptr() as *mut libc::c_void;
+                iovecs[i].iov_len = bufs[i].len();
+                let hdr = &mut msgs[i].msg_hdr;
+                hdr.msg_name = &mut addrs[i] as *mut _ as *mut libc::c_void;
+                hdr.msg_namelen =
+                    mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+                hdr.msg_iov = &mut iovecs[i];
+                hdr.msg_iovlen = 1;
+            }
+            let amt = try!(sockopt::cvt(libc::recvmmsg(socket.as_raw_fd(),
+                                                       msgs.as_mut_ptr(),
+                                                       n as libc::c_uint,
+                                                       0,
+                                                       ptr::null_mut())));
+            for i in 0..amt as usize {
+                let addr = try!(sockaddr::to_socket_addr(&addrs[i],
+                                                         msgs[i].msg_hdr.msg_namelen));
+                results.push((msgs[i].msg_len as usize, addr));
+            }
+            Ok(amt as usize)
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn send_many(socket: &UdpSocket, msgs: &[(&[u8], SocketAddr)])
+                     -> io::Result<usize> {
+        use std::cmp;
+
+        let n = cmp::min(msgs.len(), MAX_BATCH);
+        unsafe {
+            let mut addrs: [libc::sockaddr_storage; MAX_BATCH] = mem::zeroed();
+            let mut iovecs: [libc::iovec; MAX_BATCH] = mem::zeroed();
+            let mut hdrs: [libc::mmsghdr; MAX_BATCH] = mem::zeroed();
+            for (i, &(buf, ref addr)) in msgs[..n].iter().enumerate() {
+                let (storage, len) = sockaddr::from_socket_addr(addr);
+                addrs[i] = storage;
+                iovecs[i].iov_base = buf.as_ptr() as *mut libc::c_void;
+                iovecs[i].iov_len = buf.len();
+                let hdr = &mut hdrs[i].msg_hdr;
+                hdr.msg_name = &mut addrs[i] as *mut _ as *mut libc::c_void;
+                hdr.msg_namelen = len;
+                hdr.msg_iov = &mut iovecs[i];
+                hdr.msg_iovlen = 1;
+            }
+            let amt = try!(sockopt::cvt(libc::sendmmsg(socket.as_raw_fd(),
+                                                       hdrs.as_mut_ptr(),
+                                                       n as libc::c_uint,
+                                                       0)));
+            Ok(amt as usize)
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    // TODO: let's land these upstream with mio and then we can add them here.
+    //
+    // use std::os::windows::prelude::*;
+    // use super::UdpSocket;
+    //
+    // impl AsRawHandle for UdpSocket {
+    //     fn as_raw_handle(&self) -> RawHandle {
+    //         self.io.get_ref().as_raw_handle()
+    //     }
+    // }
+}
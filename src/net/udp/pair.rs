@@ -0,0 +1,171 @@
+use std::collections::VecDeque;
+use std::io;
+use std::net::SocketAddr;
+
+use futures::{Future, Poll};
+
+use net::UdpSocket;
+use reactor::Handle;
+
+/// A builder for a pair of loopback `UdpSocket`s connected to one another,
+/// primarily intended for testing datagram protocols.
+///
+/// By default the two sockets are simply connected directly to each other.
+/// When any of the impairment knobs are configured the sockets are instead
+/// both connected to a relay socket running on the event loop, which forwards
+/// datagrams between them and applies the configured loss, duplication and
+/// reordering. These impairments are applied deterministically, based on the
+/// sequence number of each datagram within its direction, so tests exercising
+/// them are reproducible.
+///
+/// Note that when a relay is in use the address datagrams appear to come from
+/// is the relay's rather than that of the other socket in the pair. The relay
+/// runs for as long as the event loop does.
+#[derive(Clone, Debug, Default)]
+pub struct UdpPair {
+    drop_every: Option<usize>,
+    duplicate_every: Option<usize>,
+    reorder_every: Option<usize>,
+}
+
+impl UdpPair {
+    /// Creates a new builder for a pair of sockets with no impairments.
+    pub fn new() -> UdpPair {
+        UdpPair::default()
+    }
+
+    /// Drops every `n`th datagram sent in each direction.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `n` is 0.
+    pub fn drop_every(&mut self, n: usize) -> &mut UdpPair {
+        assert!(n > 0, "impairment interval must be nonzero");
+        self.drop_every = Some(n);
+        self
+    }
+
+    /// Delivers every `n`th datagram sent in each direction twice.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `n` is 0.
+    pub fn duplicate_every(&mut self, n: usize) -> &mut UdpPair {
+        assert!(n > 0, "impairment interval must be nonzero");
+        self.duplicate_every = Some(n);
+        self
+    }
+
+    /// Holds back every `n`th datagram sent in each direction, delivering it
+    /// just after the datagram which follows it.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if `n` is 0.
+    pub fn reorder_every(&mut self, n: usize) -> &mut UdpPair {
+        assert!(n > 0, "impairment interval must be nonzero");
+        self.reorder_every = Some(n);
+        self
+    }
+
+    /// Creates the pair of sockets, registering them (and the relay, if one
+    /// is needed) with the event loop `handle` is associated with.
+    pub fn build(&self, handle: &Handle) -> io::Result<(UdpSocket, UdpSocket)> {
+        let localhost = "127.0.0.1:0".parse().unwrap();
+        let a = try!(UdpSocket::bind(&localhost, handle));
+        let b = try!(UdpSocket::bind(&localhost, handle));
+        let a_addr = try!(a.local_addr());
+        let b_addr = try!(b.local_addr());
+
+        if self.drop_every.is_none() &&
+           self.duplicate_every.is_none() &&
+           self.reorder_every.is_none() {
+            try!(a.connect(&b_addr));
+            try!(b.connect(&a_addr));
+            return Ok((a, b))
+        }
+
+        let socket = try!(UdpSocket::bind(&localhost, handle));
+        let relay_addr = try!(socket.local_addr());
+        try!(a.connect(&relay_addr));
+        try!(b.connect(&relay_addr));
+        let relay = Relay {
+            socket: socket,
+            config: self.clone(),
+            addrs: [a_addr, b_addr],
+            buf: vec![0; 64 * 1024],
+            received: [0, 0],
+            held: [None, None],
+            out: VecDeque::new(),
+        };
+        handle.spawn(relay.map_err(|e| debug!("udp pair relay failed: {}", e)));
+        Ok((a, b))
+    }
+}
+
+struct Relay {
+    socket: UdpSocket,
+    config: UdpPair,
+    addrs: [SocketAddr; 2],
+    buf: Vec<u8>,
+    received: [usize; 2],
+    held: [Option<Vec<u8>>; 2],
+    out: VecDeque<(Vec<u8>, SocketAddr)>,
+}
+
+fn every(interval: Option<usize>, seq: usize) -> bool {
+    interval.map(|n| seq % n == 0).unwrap_or(false)
+}
+
+impl Future for Relay {
+    type Item = ();
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<(), io::Error> {
+        loop {
+            while let Some(&(ref data, ref addr)) = self.out.front() {
+                try_nb!(self.socket.send_to(data, addr));
+                self.out.pop_front();
+            }
+
+            let (n, addr) = try_nb!(self.socket.recv_from(&mut self.buf));
+            let dir = match self.addrs.iter().position(|a| *a == addr) {
+                Some(dir) => dir,
+                None => continue,
+            };
+            let to = self.addrs[1 - dir];
+            self.received[dir] += 1;
+            let seq = self.received[dir];
+            if every(self.config.drop_every, seq) {
+                trace!("dropping datagram {} from {}", seq, addr);
+                continue
+            }
+
+            let data = self.buf[..n].to_vec();
+            if every(self.config.duplicate_every, seq) {
+                trace!("duplicating datagram {} from {}", seq, addr);
+                self.out.push_back((data.clone(), to));
+            }
+            if every(self.config.reorder_every, seq) && self.held[dir].is_none() {
+                trace!("holding back datagram {} from {}", seq, addr);
+                self.held[dir] = Some(data);
+                continue
+            }
+            self.out.push_back((data, to));
+            if let Some(held) = self.held[dir].take() {
+                self.out.push_back((held, to));
+            }
+        }
+    }
+}
+
+impl UdpSocket {
+    /// Creates a pair of loopback sockets connected to one another and
+    /// registered with the event loop `handle` is associated with.
+    ///
+    /// This is a shorthand for `UdpPair::new().build(handle)`; see `UdpPair`
+    /// for creating pairs which simulate an unreliable network.
+    pub fn pair(handle: &Handle) -> io::Result<(UdpSocket, UdpSocket)> {
+        UdpPair::new().build(handle)
+    }
+}
@@ -0,0 +1,252 @@
+use std::fmt;
+use std::io;
+use std::net::Shutdown;
+use std::os::unix::net::{self, SocketAddr};
+use std::os::unix::prelude::*;
+use std::path::Path;
+
+use futures::Async;
+use libc;
+use mio;
+
+use fd::EventedFd;
+use reactor::{Handle, PollEvented};
+use super::{is_wouldblock, scm};
+
+/// An I/O object representing a Unix domain datagram socket.
+pub struct UnixDatagram {
+    io: PollEvented<EventedFd<net::UnixDatagram>>,
+}
+
+impl UnixDatagram {
+    /// Creates a new Unix datagram socket bound to the path specified,
+    /// associated with the event loop `handle` points to.
+    ///
+    /// The socket file at `path` is created by this function and must not
+    /// already exist. On Linux, a path starting with a null byte instead binds
+    /// to a name in the abstract namespace.
+    pub fn bind<P: AsRef<Path>>(path: P, handle: &Handle)
+                                -> io::Result<UnixDatagram> {
+        let fd = try!(super::socket(libc::SOCK_DGRAM));
+        let socket = unsafe { net::UnixDatagram::from_raw_fd(fd) };
+        try!(super::bind(fd, path.as_ref()));
+        let io = try!(PollEvented::new(EventedFd::new(socket), handle));
+        Ok(UnixDatagram { io: io })
+    }
+
+    /// Creates a new Unix datagram socket which isn't bound to any address,
+    /// associated with the event loop `handle` points to.
+    ///
+    /// Such a socket can send datagrams, but can only receive replies to them
+    /// if the peer's socket is connected back to it.
+    pub fn unbound(handle: &Handle) -> io::Result<UnixDatagram> {
+        let socket = try!(net::UnixDatagram::unbound());
+        UnixDatagram::from_datagram(socket, handle)
+    }
+
+    /// Creates an unnamed pair of connected datagram sockets, both associated
+    /// with the event loop `handle` points to.
+    pub fn pair(handle: &Handle) -> io::Result<(UnixDatagram, UnixDatagram)> {
+        let (a, b) = try!(super::socketpair(libc::SOCK_DGRAM));
+        let (a, b) = unsafe {
+            (net::UnixDatagram::from_raw_fd(a), net::UnixDatagram::from_raw_fd(b))
+        };
+        let a = try!(PollEvented::new(EventedFd::new(a), handle));
+        let b = try!(PollEvented::new(EventedFd::new(b), handle));
+        Ok((UnixDatagram { io: a }, UnixDatagram { io: b }))
+    }
+
+    /// Creates a new `UnixDatagram` from the standard library's socket,
+    /// registering it with the event loop `handle` points to.
+    ///
+    /// The socket is placed into nonblocking mode.
+    pub fn from_datagram(socket: net::UnixDatagram, handle: &Handle)
+                         -> io::Result<UnixDatagram> {
+        try!(socket.set_nonblocking(true));
+        let io = try!(PollEvented::new(EventedFd::new(socket), handle));
+        Ok(UnixDatagram { io: io })
+    }
+
+    /// Test whether this socket is ready to be read or not.
+    ///
+    /// If the socket is *not* readable then the current task is scheduled to
+    /// get a notification when the socket does become readable. That is, this
+    /// is only suitable for calling in a `Future::poll` method and will
+    /// automatically handle ensuring a retry once the socket is readable again.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Test whether this socket is writey to be written to or not.
+    ///
+    /// If the socket is *not* writable then the current task is scheduled to
+    /// get a notification when the socket does become writable. That is, this
+    /// is only suitable for calling in a `Future::poll` method and will
+    /// automatically handle ensuring a retry once the socket is writable again.
+    pub fn poll_write(&self) -> Async<()> {
+        self.io.poll_write()
+    }
+
+    /// Returns the token this socket is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
+    /// Returns the address this socket is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().get_ref().local_addr()
+    }
+
+    /// Returns the address of this socket's peer, as set by `connect`.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().get_ref().peer_addr()
+    }
+
+    /// Connects the socket to the path specified, setting the default
+    /// destination for `send` and limiting datagrams read via `recv` to those
+    /// sent from that path.
+    ///
+    /// On Linux, a path starting with a null byte names an address in the
+    /// abstract namespace.
+    pub fn connect<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        super::connect(self.as_raw_fd(), path.as_ref())
+    }
+
+    /// Sends data on the socket to the socket bound to `path`. On success,
+    /// returns the number of bytes written.
+    ///
+    /// On Linux, a path starting with a null byte names an address in the
+    /// abstract namespace.
+    ///
+    /// If the socket isn't writable then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is writable again.
+    pub fn send_to<P: AsRef<Path>>(&self, buf: &[u8], path: P)
+                                   -> io::Result<usize> {
+        let (addr, len) = try!(super::sockaddr_un(path.as_ref()));
+        self.write(|s| {
+            let n = unsafe {
+                libc::sendto(s.as_raw_fd(),
+                             buf.as_ptr() as *const _,
+                             buf.len(),
+                             0,
+                             &addr as *const _ as *const libc::sockaddr,
+                             len)
+            };
+            if n == -1 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(n as usize)
+            }
+        })
+    }
+
+    /// Receives data from the socket. On success, returns the number of bytes
+    /// read and the address from whence the data came.
+    ///
+    /// If no datagram is available then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is readable again.
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.read(|s| s.recv_from(buf))
+    }
+
+    /// Sends data on the socket to the peer previously set via `connect`. On
+    /// success, returns the number of bytes written.
+    ///
+    /// If the socket isn't writable then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is writable again.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.write(|s| s.send(buf))
+    }
+
+    /// Receives data from the peer previously set via `connect`. On success,
+    /// returns the number of bytes read.
+    ///
+    /// If no datagram is available then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is readable again.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read(|s| s.recv(buf))
+    }
+
+    /// Sends `buf` to the peer previously set via `connect` as a single
+    /// datagram, along with the file descriptors in `fds`, which are duplicated
+    /// into the receiving process as `SCM_RIGHTS` ancillary data. On success,
+    /// returns the number of bytes written.
+    ///
+    /// If the socket isn't writable then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is writable again.
+    pub fn send_with_fds(&self, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        self.write(|s| scm::send_with_fds(s.as_raw_fd(), buf, fds))
+    }
+
+    /// Receives a datagram into `buf`, storing any file descriptors sent along
+    /// with it in `fds`. On success, returns the number of bytes read and the
+    /// number of descriptors received.
+    ///
+    /// The received descriptors are owned by the caller and are marked
+    /// close-on-exec. Any descriptors which don't fit in `fds` are closed.
+    ///
+    /// If no datagram is available then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is readable again.
+    pub fn recv_with_fds(&self, buf: &mut [u8], fds: &mut [RawFd])
+                         -> io::Result<(usize, usize)> {
+        self.read(|s| scm::recv_with_fds(s.as_raw_fd(), buf, fds))
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.io.get_ref().get_ref().take_error()
+    }
+
+    /// Shuts down the read, write, or both halves of this socket.
+    ///
+    /// This function will cause all pending and future I/O on the specified
+    /// portions to return immediately with an appropriate value.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.io.get_ref().get_ref().shutdown(how)
+    }
+
+    fn read<F, R>(&self, f: F) -> io::Result<R>
+        where F: FnOnce(&net::UnixDatagram) -> io::Result<R>,
+    {
+        if let Async::NotReady = self.io.poll_read() {
+            return Err(mio::would_block())
+        }
+        let r = f(self.io.get_ref().get_ref());
+        if is_wouldblock(&r) {
+            self.io.need_read();
+        }
+        r
+    }
+
+    fn write<F, R>(&self, f: F) -> io::Result<R>
+        where F: FnOnce(&net::UnixDatagram) -> io::Result<R>,
+    {
+        if let Async::NotReady = self.io.poll_write() {
+            return Err(mio::would_block())
+        }
+        let r = f(self.io.get_ref().get_ref());
+        if is_wouldblock(&r) {
+            self.io.need_write();
+        }
+        r
+    }
+}
+
+impl fmt::Debug for UnixDatagram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.io.get_ref().get_ref().fmt(f)
+    }
+}
+
+impl AsRawFd for UnixDatagram {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
@@ -0,0 +1,121 @@
+//! Unix domain sockets, integrated with the event loop.
+
+use std::io;
+use std::mem;
+use std::os::unix::prelude::*;
+use std::path::Path;
+
+use libc::{self, c_int, c_char};
+
+use fd;
+use net::sockopt::cvt;
+
+mod datagram;
+mod scm;
+mod seqpacket;
+mod stream;
+mod ucred;
+pub use self::datagram::UnixDatagram;
+pub use self::seqpacket::{UnixSeqpacket, UnixSeqpacketListener};
+pub use self::seqpacket::UnixSeqpacketIncoming;
+pub use self::stream::{UnixStream, UnixListener, UnixIncoming};
+pub use self::ucred::UCred;
+
+/// Creates a new Unix domain socket of the type `ty`, in nonblocking mode and
+/// marked close-on-exec.
+fn socket(ty: c_int) -> io::Result<RawFd> {
+    let fd = try!(cvt(unsafe { libc::socket(libc::AF_UNIX, ty, 0) }));
+    if let Err(e) = fd::set_nonblocking_cloexec(fd) {
+        unsafe { libc::close(fd); }
+        return Err(e)
+    }
+    Ok(fd)
+}
+
+/// Creates a connected pair of Unix domain sockets of the type `ty`, in
+/// nonblocking mode and marked close-on-exec.
+fn socketpair(ty: c_int) -> io::Result<(RawFd, RawFd)> {
+    let mut fds = [0; 2];
+    try!(cvt(unsafe { libc::socketpair(libc::AF_UNIX, ty, 0, fds.as_mut_ptr()) }));
+    for fd in fds.iter() {
+        if let Err(e) = fd::set_nonblocking_cloexec(*fd) {
+            unsafe {
+                libc::close(fds[0]);
+                libc::close(fds[1]);
+            }
+            return Err(e)
+        }
+    }
+    Ok((fds[0], fds[1]))
+}
+
+/// Converts `path` into a `sockaddr_un` and the length of the address within
+/// it, in the same way as the standard library.
+///
+/// On Linux a path starting with a null byte names an address in the abstract
+/// namespace, which isn't tied to the filesystem. Such names are used as-is,
+/// without a trailing null byte, and may contain further null bytes.
+fn sockaddr_un(path: &Path) -> io::Result<(libc::sockaddr_un, libc::socklen_t)> {
+    let mut addr: libc::sockaddr_un = unsafe { mem::zeroed() };
+    addr.sun_family = libc::AF_UNIX as libc::sa_family_t;
+
+    let bytes = path.as_os_str().as_bytes();
+    let abstract_name = cfg!(target_os = "linux") && bytes.first() == Some(&0);
+    if abstract_name {
+        if bytes.len() > addr.sun_path.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "abstract name must not be longer \
+                                       than SUN_LEN"))
+        }
+    } else {
+        if bytes.contains(&0) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "paths may not contain interior null \
+                                       bytes"))
+        }
+        if bytes.len() >= addr.sun_path.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "path must be shorter than SUN_LEN"))
+        }
+    }
+    for (dst, src) in addr.sun_path.iter_mut().zip(bytes) {
+        *dst = *src as c_char;
+    }
+
+    let base = &addr as *const _ as usize;
+    let path = &addr.sun_path as *const _ as usize;
+    let mut len = path - base + bytes.len();
+    if !bytes.is_empty() && !abstract_name {
+        len += 1;
+    }
+    Ok((addr, len as libc::socklen_t))
+}
+
+/// Issues a nonblocking `connect` of the socket `fd` to `path`.
+fn connect(fd: RawFd, path: &Path) -> io::Result<()> {
+    let (addr, len) = try!(sockaddr_un(path));
+    let ret = unsafe {
+        libc::connect(fd, &addr as *const _ as *const libc::sockaddr, len)
+    };
+    match cvt(ret) {
+        Ok(_) => Ok(()),
+        Err(ref e) if e.raw_os_error() == Some(libc::EINPROGRESS) => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Binds the socket `fd` to `path`.
+fn bind(fd: RawFd, path: &Path) -> io::Result<()> {
+    let (addr, len) = try!(sockaddr_un(path));
+    try!(cvt(unsafe {
+        libc::bind(fd, &addr as *const _ as *const libc::sockaddr, len)
+    }));
+    Ok(())
+}
+
+fn is_wouldblock<T>(r: &io::Result<T>) -> bool {
+    match *r {
+        Ok(_) => false,
+        Err(ref e) => e.kind() == io::ErrorKind::WouldBlock,
+    }
+}
@@ -0,0 +1,114 @@
+//! Passing file descriptors as `SCM_RIGHTS` ancillary data.
+
+use std::io;
+use std::mem;
+use std::os::unix::prelude::*;
+use std::ptr;
+
+use libc::{self, c_int};
+
+use fd;
+
+/// Sends `buf` on the socket `sock`, along with duplicates of the descriptors
+/// in `fds`.
+pub fn send_with_fds(sock: RawFd, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+    let fds_len = mem::size_of_val(fds);
+    let mut control = control_buf(fds_len);
+
+    let mut iov = libc::iovec {
+        iov_base: buf.as_ptr() as *mut _,
+        iov_len: buf.len(),
+    };
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    if !fds.is_empty() {
+        msg.msg_control = control.as_mut_ptr() as *mut _;
+        msg.msg_controllen = (control.len() * mem::size_of::<u64>()) as _;
+        unsafe {
+            let cmsg = libc::CMSG_FIRSTHDR(&msg);
+            (*cmsg).cmsg_level = libc::SOL_SOCKET;
+            (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsg).cmsg_len = libc::CMSG_LEN(fds_len as u32) as _;
+            ptr::copy_nonoverlapping(fds.as_ptr() as *const u8,
+                                     libc::CMSG_DATA(cmsg),
+                                     fds_len);
+        }
+    }
+
+    let n = unsafe { libc::sendmsg(sock, &msg, 0) };
+    if n == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(n as usize)
+    }
+}
+
+/// Receives data from the socket `sock` into `buf`, storing any descriptors
+/// sent along with it in `fds`.
+///
+/// Returns the number of bytes and the number of descriptors received. The
+/// received descriptors are marked close-on-exec, and any which don't fit in
+/// `fds` are closed.
+pub fn recv_with_fds(sock: RawFd, buf: &mut [u8], fds: &mut [RawFd])
+                     -> io::Result<(usize, usize)> {
+    let mut control = control_buf(mem::size_of_val(fds));
+
+    let mut iov = libc::iovec {
+        iov_base: buf.as_mut_ptr() as *mut _,
+        iov_len: buf.len(),
+    };
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    if !fds.is_empty() {
+        msg.msg_control = control.as_mut_ptr() as *mut _;
+        msg.msg_controllen = (control.len() * mem::size_of::<u64>()) as _;
+    }
+
+    let n = unsafe { libc::recvmsg(sock, &mut msg, RECV_FLAGS) };
+    if n == -1 {
+        return Err(io::Error::last_os_error())
+    }
+
+    let mut received = 0;
+    unsafe {
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET &&
+               (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let data = libc::CMSG_DATA(cmsg);
+                let header = data as usize - cmsg as usize;
+                let count = ((*cmsg).cmsg_len as usize - header) /
+                            mem::size_of::<c_int>();
+                for i in 0..count {
+                    let ptr = (data as *const c_int).add(i);
+                    let raw = ptr::read_unaligned(ptr);
+                    if received < fds.len() {
+                        if !cfg!(any(target_os = "linux", target_os = "android")) {
+                            drop(fd::set_cloexec(raw));
+                        }
+                        fds[received] = raw;
+                        received += 1;
+                    } else {
+                        libc::close(raw);
+                    }
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+    }
+    Ok((n as usize, received))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const RECV_FLAGS: c_int = libc::MSG_CMSG_CLOEXEC;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const RECV_FLAGS: c_int = 0;
+
+/// Allocates a suitably aligned buffer for a control message carrying `len`
+/// bytes of data.
+fn control_buf(len: usize) -> Vec<u64> {
+    let space = unsafe { libc::CMSG_SPACE(len as u32) } as usize;
+    vec![0; space.div_ceil(mem::size_of::<u64>())]
+}
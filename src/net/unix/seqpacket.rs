@@ -0,0 +1,291 @@
+//! Unix domain sockets of the `SOCK_SEQPACKET` type.
+//!
+//! The standard library has no types for these sockets, but as the system
+//! calls involved are the same as for the other kinds of Unix sockets its
+//! `UnixListener` and `UnixDatagram` types are used internally to own and
+//! operate on the underlying descriptors.
+
+use std::fmt;
+use std::io;
+use std::net::Shutdown;
+use std::os::unix::net::{self, SocketAddr};
+use std::os::unix::prelude::*;
+use std::path::Path;
+
+use futures::stream::Stream;
+use futures::{self, Future, Poll, Async};
+use libc;
+use mio;
+
+use fd::{self, EventedFd};
+use io::IoStream;
+use net::sockopt::cvt;
+use reactor::{Handle, PollEvented};
+use super::is_wouldblock;
+
+/// An I/O object representing a Unix domain sequenced-packet socket listening
+/// for incoming connections.
+pub struct UnixSeqpacketListener {
+    io: PollEvented<EventedFd<net::UnixListener>>,
+}
+
+/// Stream returned by the `UnixSeqpacketListener::incoming` function
+/// representing the stream of sockets received from a listener.
+pub struct UnixSeqpacketIncoming {
+    inner: IoStream<(UnixSeqpacket, SocketAddr)>,
+}
+
+/// An I/O object representing a Unix domain sequenced-packet socket connected
+/// to a peer.
+///
+/// Like a stream socket, a sequenced-packet socket is connection-oriented and
+/// delivers data reliably and in order. Unlike a stream socket, the boundaries
+/// of the messages written with `send` are preserved, each call to `recv`
+/// reading exactly one message.
+pub struct UnixSeqpacket {
+    io: PollEvented<EventedFd<net::UnixDatagram>>,
+}
+
+impl UnixSeqpacketListener {
+    /// Creates a new sequenced-packet listener bound to the path specified,
+    /// associated with the event loop `handle` points to.
+    ///
+    /// The socket file at `path` is created by this function and must not
+    /// already exist.
+    pub fn bind<P: AsRef<Path>>(path: P, handle: &Handle)
+                                -> io::Result<UnixSeqpacketListener> {
+        let fd = try!(super::socket(libc::SOCK_SEQPACKET));
+        let listener = unsafe { net::UnixListener::from_raw_fd(fd) };
+        try!(super::bind(fd, path.as_ref()));
+        try!(cvt(unsafe { libc::listen(fd, 128) }));
+        let io = try!(PollEvented::new(EventedFd::new(listener), handle));
+        Ok(UnixSeqpacketListener { io: io })
+    }
+
+    /// Test whether this socket is ready to be read or not.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Returns the token this listener is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
+    /// Returns the local address that this listener is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().get_ref().local_addr()
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.io.get_ref().get_ref().take_error()
+    }
+
+    /// Consumes this listener, returning a stream of the sockets this listener
+    /// accepts.
+    pub fn incoming(self) -> UnixSeqpacketIncoming {
+        struct MyIncoming {
+            inner: UnixSeqpacketListener,
+        }
+
+        impl Stream for MyIncoming {
+            type Item = (net::UnixDatagram, SocketAddr);
+            type Error = io::Error;
+
+            fn poll(&mut self) -> Poll<Option<Self::Item>, io::Error> {
+                if let Async::NotReady = self.inner.io.poll_read() {
+                    return Ok(Async::NotReady)
+                }
+                match self.inner.io.get_ref().get_ref().accept() {
+                    Ok((socket, addr)) => {
+                        let fd = socket.into_raw_fd();
+                        let socket = unsafe {
+                            net::UnixDatagram::from_raw_fd(fd)
+                        };
+                        try!(fd::set_nonblocking_cloexec(fd));
+                        Ok(Async::Ready(Some((socket, addr))))
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        self.inner.io.need_read();
+                        Ok(Async::NotReady)
+                    }
+                    Err(e) => Err(e)
+                }
+            }
+        }
+
+        let remote = self.io.remote().clone();
+        let stream = MyIncoming { inner: self };
+        UnixSeqpacketIncoming {
+            inner: stream.and_then(move |(socket, addr)| {
+                let (tx, rx) = futures::oneshot();
+                remote.spawn(move |handle| {
+                    let res = PollEvented::new(EventedFd::new(socket), handle);
+                    tx.complete(res.map(move |io| {
+                        (UnixSeqpacket { io: io }, addr)
+                    }));
+                    Ok(())
+                });
+                rx.then(|r| r.expect("shouldn't be canceled"))
+            }).boxed(),
+        }
+    }
+}
+
+impl fmt::Debug for UnixSeqpacketListener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UnixSeqpacketListener")
+         .field("fd", &self.as_raw_fd())
+         .finish()
+    }
+}
+
+impl AsRawFd for UnixSeqpacketListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl Stream for UnixSeqpacketIncoming {
+    type Item = (UnixSeqpacket, SocketAddr);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, io::Error> {
+        self.inner.poll()
+    }
+}
+
+impl UnixSeqpacket {
+    /// Connects to the sequenced-packet listener bound to `path`, associating
+    /// the returned socket with the event loop `handle` points to.
+    ///
+    /// As with `UnixStream::connect`, the connection is initiated without
+    /// blocking.
+    pub fn connect<P: AsRef<Path>>(path: P, handle: &Handle)
+                                   -> io::Result<UnixSeqpacket> {
+        let fd = try!(super::socket(libc::SOCK_SEQPACKET));
+        let socket = unsafe { net::UnixDatagram::from_raw_fd(fd) };
+        try!(super::connect(fd, path.as_ref()));
+        UnixSeqpacket::new(socket, handle)
+    }
+
+    /// Creates an unnamed pair of connected sequenced-packet sockets, both
+    /// associated with the event loop `handle` points to.
+    pub fn pair(handle: &Handle) -> io::Result<(UnixSeqpacket, UnixSeqpacket)> {
+        let (a, b) = try!(super::socketpair(libc::SOCK_SEQPACKET));
+        let (a, b) = unsafe {
+            (net::UnixDatagram::from_raw_fd(a), net::UnixDatagram::from_raw_fd(b))
+        };
+        let a = try!(UnixSeqpacket::new(a, handle));
+        let b = try!(UnixSeqpacket::new(b, handle));
+        Ok((a, b))
+    }
+
+    fn new(socket: net::UnixDatagram, handle: &Handle)
+           -> io::Result<UnixSeqpacket> {
+        let io = try!(PollEvented::new(EventedFd::new(socket), handle));
+        Ok(UnixSeqpacket { io: io })
+    }
+
+    /// Test whether this socket is ready to be read or not.
+    ///
+    /// If the socket is *not* readable then the current task is scheduled to
+    /// get a notification when the socket does become readable. That is, this
+    /// is only suitable for calling in a `Future::poll` method and will
+    /// automatically handle ensuring a retry once the socket is readable again.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Test whether this socket is ready to be written to or not.
+    ///
+    /// If the socket is *not* writable then the current task is scheduled to
+    /// get a notification when the socket does become writable. That is, this
+    /// is only suitable for calling in a `Future::poll` method and will
+    /// automatically handle ensuring a retry once the socket is writable again.
+    pub fn poll_write(&self) -> Async<()> {
+        self.io.poll_write()
+    }
+
+    /// Returns the token this socket is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
+    /// Returns the socket address of the local half of this connection.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().get_ref().local_addr()
+    }
+
+    /// Returns the socket address of the remote half of this connection.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().get_ref().peer_addr()
+    }
+
+    /// Sends `buf` to the peer as a single message. On success, returns the
+    /// number of bytes written, which is always the whole message.
+    ///
+    /// If the socket isn't writable then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is writable again.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        if let Async::NotReady = self.io.poll_write() {
+            return Err(mio::would_block())
+        }
+        let r = self.io.get_ref().get_ref().send(buf);
+        if is_wouldblock(&r) {
+            self.io.need_write();
+        }
+        r
+    }
+
+    /// Receives a single message from the peer into `buf`. On success,
+    /// returns the number of bytes read, with 0 indicating that the peer has
+    /// closed the connection.
+    ///
+    /// If the message is longer than `buf` the excess is discarded.
+    ///
+    /// If no message is available then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is readable again.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Async::NotReady = self.io.poll_read() {
+            return Err(mio::would_block())
+        }
+        let r = self.io.get_ref().get_ref().recv(buf);
+        if is_wouldblock(&r) {
+            self.io.need_read();
+        }
+        r
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.io.get_ref().get_ref().take_error()
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    ///
+    /// This function will cause all pending and future I/O on the specified
+    /// portions to return immediately with an appropriate value.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.io.get_ref().get_ref().shutdown(how)
+    }
+}
+
+impl fmt::Debug for UnixSeqpacket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UnixSeqpacket")
+         .field("fd", &self.as_raw_fd())
+         .finish()
+    }
+}
+
+impl AsRawFd for UnixSeqpacket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
@@ -0,0 +1,412 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::{self, SocketAddr};
+use std::os::unix::prelude::*;
+use std::path::Path;
+
+use futures::stream::Stream;
+use futures::{self, Future, Poll, Async};
+use libc;
+use mio;
+
+use fd::EventedFd;
+use io::{AsyncRead, AsyncWrite, IoStream, Splice};
+use net::sockopt::cvt;
+use reactor::{Handle, PollEvented};
+use super::{is_wouldblock, scm};
+use super::ucred::{self, UCred};
+
+/// An I/O object representing a Unix domain socket listening for incoming
+/// connections.
+///
+/// This object can be converted into a stream of incoming connections for
+/// various forms of processing.
+pub struct UnixListener {
+    io: PollEvented<EventedFd<net::UnixListener>>,
+}
+
+/// Stream returned by the `UnixListener::incoming` function representing the
+/// stream of sockets received from a listener.
+pub struct UnixIncoming {
+    inner: IoStream<(UnixStream, SocketAddr)>,
+}
+
+impl UnixListener {
+    /// Creates a new Unix domain socket listener bound to the path specified,
+    /// associated with the event loop `handle` points to.
+    ///
+    /// The socket file at `path` is created by this function and must not
+    /// already exist. On Linux, a path starting with a null byte instead binds
+    /// to a name in the abstract namespace.
+    pub fn bind<P: AsRef<Path>>(path: P, handle: &Handle)
+                                -> io::Result<UnixListener> {
+        let fd = try!(super::socket(libc::SOCK_STREAM));
+        let listener = unsafe { net::UnixListener::from_raw_fd(fd) };
+        try!(super::bind(fd, path.as_ref()));
+        try!(cvt(unsafe { libc::listen(fd, 128) }));
+        let io = try!(PollEvented::new(EventedFd::new(listener), handle));
+        Ok(UnixListener { io: io })
+    }
+
+    /// Creates a new `UnixListener` from the standard library's listener,
+    /// registering it with the event loop `handle` points to.
+    ///
+    /// This can be used to accept connections on a listener which was created
+    /// elsewhere, for example one inherited from a parent process. The
+    /// listener is placed into nonblocking mode.
+    pub fn from_listener(listener: net::UnixListener, handle: &Handle)
+                         -> io::Result<UnixListener> {
+        try!(listener.set_nonblocking(true));
+        let io = try!(PollEvented::new(EventedFd::new(listener), handle));
+        Ok(UnixListener { io: io })
+    }
+
+    /// Test whether this socket is ready to be read or not.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Returns the token this listener is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
+    /// Returns the local address that this listener is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().get_ref().local_addr()
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.io.get_ref().get_ref().take_error()
+    }
+
+    /// Consumes this listener, returning a stream of the sockets this listener
+    /// accepts.
+    ///
+    /// This method returns an implementation of the `Stream` trait which
+    /// resolves to the sockets the are accepted on this listener.
+    pub fn incoming(self) -> UnixIncoming {
+        struct MyIncoming {
+            inner: UnixListener,
+        }
+
+        impl Stream for MyIncoming {
+            type Item = (net::UnixStream, SocketAddr);
+            type Error = io::Error;
+
+            fn poll(&mut self) -> Poll<Option<Self::Item>, io::Error> {
+                if let Async::NotReady = self.inner.io.poll_read() {
+                    return Ok(Async::NotReady)
+                }
+                match self.inner.io.get_ref().get_ref().accept() {
+                    Ok((stream, addr)) => {
+                        try!(stream.set_nonblocking(true));
+                        Ok(Async::Ready(Some((stream, addr))))
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        self.inner.io.need_read();
+                        Ok(Async::NotReady)
+                    }
+                    Err(e) => Err(e)
+                }
+            }
+        }
+
+        let remote = self.io.remote().clone();
+        let stream = MyIncoming { inner: self };
+        UnixIncoming {
+            inner: stream.and_then(move |(stream, addr)| {
+                let (tx, rx) = futures::oneshot();
+                remote.spawn(move |handle| {
+                    let res = PollEvented::new(EventedFd::new(stream), handle);
+                    tx.complete(res.map(move |io| {
+                        (UnixStream { io: io }, addr)
+                    }));
+                    Ok(())
+                });
+                rx.then(|r| r.expect("shouldn't be canceled"))
+            }).boxed(),
+        }
+    }
+}
+
+impl fmt::Debug for UnixListener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.io.get_ref().get_ref().fmt(f)
+    }
+}
+
+impl AsRawFd for UnixListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl Stream for UnixIncoming {
+    type Item = (UnixStream, SocketAddr);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, io::Error> {
+        self.inner.poll()
+    }
+}
+
+/// An I/O object representing a Unix domain stream socket connected to a
+/// peer.
+///
+/// A Unix stream can either be created by connecting to a path or by accepting
+/// a connection from a listener.
+pub struct UnixStream {
+    io: PollEvented<EventedFd<net::UnixStream>>,
+}
+
+impl UnixStream {
+    /// Connects to the Unix domain socket bound to `path`, associating the
+    /// returned stream with the event loop `handle` points to.
+    ///
+    /// The connection is initiated without blocking. Connections to Unix
+    /// sockets are generally established immediately, but if that's not the
+    /// case then the stream simply won't become writable until it is, and any
+    /// error connecting is returned from the first read or write.
+    ///
+    /// On Linux, a path starting with a null byte connects to a name in the
+    /// abstract namespace.
+    pub fn connect<P: AsRef<Path>>(path: P, handle: &Handle)
+                                   -> io::Result<UnixStream> {
+        let fd = try!(super::socket(libc::SOCK_STREAM));
+        let stream = unsafe { net::UnixStream::from_raw_fd(fd) };
+        try!(super::connect(fd, path.as_ref()));
+        UnixStream::new(stream, handle)
+    }
+
+    /// Creates an unnamed pair of connected streams, both associated with the
+    /// event loop `handle` points to.
+    ///
+    /// This is useful for communicating with a child process, which can be
+    /// handed one end of the pair, or between tasks and in tests.
+    pub fn pair(handle: &Handle) -> io::Result<(UnixStream, UnixStream)> {
+        let (a, b) = try!(super::socketpair(libc::SOCK_STREAM));
+        let (a, b) = unsafe {
+            (net::UnixStream::from_raw_fd(a), net::UnixStream::from_raw_fd(b))
+        };
+        let a = try!(UnixStream::new(a, handle));
+        let b = try!(UnixStream::new(b, handle));
+        Ok((a, b))
+    }
+
+    /// Creates a new `UnixStream` from the standard library's stream,
+    /// registering it with the event loop `handle` points to.
+    ///
+    /// The stream is placed into nonblocking mode.
+    pub fn from_stream(stream: net::UnixStream, handle: &Handle)
+                       -> io::Result<UnixStream> {
+        try!(stream.set_nonblocking(true));
+        UnixStream::new(stream, handle)
+    }
+
+    fn new(stream: net::UnixStream, handle: &Handle) -> io::Result<UnixStream> {
+        let io = try!(PollEvented::new(EventedFd::new(stream), handle));
+        Ok(UnixStream { io: io })
+    }
+
+    /// Test whether this socket is ready to be read or not.
+    ///
+    /// If the socket is *not* readable then the current task is scheduled to
+    /// get a notification when the socket does become readable. That is, this
+    /// is only suitable for calling in a `Future::poll` method and will
+    /// automatically handle ensuring a retry once the socket is readable again.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Test whether this socket is writey to be written to or not.
+    ///
+    /// If the socket is *not* writable then the current task is scheduled to
+    /// get a notification when the socket does become writable. That is, this
+    /// is only suitable for calling in a `Future::poll` method and will
+    /// automatically handle ensuring a retry once the socket is writable again.
+    pub fn poll_write(&self) -> Async<()> {
+        self.io.poll_write()
+    }
+
+    /// Returns the token this stream is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
+    /// Returns the socket address of the local half of this connection.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().get_ref().local_addr()
+    }
+
+    /// Returns the socket address of the remote half of this connection.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        self.io.get_ref().get_ref().peer_addr()
+    }
+
+    /// Sends `buf` on this connection along with the file descriptors in
+    /// `fds`, which are duplicated into the receiving process as `SCM_RIGHTS`
+    /// ancillary data. On success, returns the number of bytes written.
+    ///
+    /// The descriptors are attached to the first byte written, so if only
+    /// part of `buf` was sent the remainder should be sent without them.
+    ///
+    /// If the socket isn't writable then a "would block" error is returned and
+    /// the current task is scheduled to receive a notification when the socket
+    /// is writable again.
+    pub fn send_with_fds(&self, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        if let Async::NotReady = self.io.poll_write() {
+            return Err(mio::would_block())
+        }
+        let r = scm::send_with_fds(self.as_raw_fd(), buf, fds);
+        if is_wouldblock(&r) {
+            self.io.need_write();
+        }
+        r
+    }
+
+    /// Receives data from this connection into `buf`, storing any file
+    /// descriptors sent along with it in `fds`. On success, returns the number
+    /// of bytes read and the number of descriptors received.
+    ///
+    /// The received descriptors are owned by the caller and are marked
+    /// close-on-exec. Any descriptors which don't fit in `fds` are closed.
+    ///
+    /// If no data is available then a "would block" error is returned and the
+    /// current task is scheduled to receive a notification when the socket is
+    /// readable again.
+    pub fn recv_with_fds(&self, buf: &mut [u8], fds: &mut [RawFd])
+                         -> io::Result<(usize, usize)> {
+        if let Async::NotReady = self.io.poll_read() {
+            return Err(mio::would_block())
+        }
+        let r = scm::recv_with_fds(self.as_raw_fd(), buf, fds);
+        if is_wouldblock(&r) {
+            self.io.need_read();
+        }
+        r
+    }
+
+    /// Returns the credentials of the process which created the remote half
+    /// of this connection.
+    ///
+    /// This uses the `SO_PEERCRED` option on Linux and `getpeereid` on other
+    /// platforms, where the peer's process ID isn't available.
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        ucred::peer_cred(self.as_raw_fd())
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.io.get_ref().get_ref().take_error()
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    ///
+    /// This function will cause all pending and future I/O on the specified
+    /// portions to return immediately with an appropriate value (see the
+    /// documentation of `Shutdown`).
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.io.get_ref().get_ref().shutdown(how)
+    }
+}
+
+impl Read for UnixStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.io.read(buf)
+    }
+}
+
+impl Write for UnixStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.io.write(buf)
+    }
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        self.io.write_vectored(bufs)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.io.flush()
+    }
+}
+
+impl AsyncRead for UnixStream {
+    fn poll_read(&mut self) -> Async<()> {
+        <UnixStream>::poll_read(self)
+    }
+}
+
+impl AsyncWrite for UnixStream {
+    fn poll_write(&mut self) -> Async<()> {
+        <UnixStream>::poll_write(self)
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        try_ready!(self.poll_flush());
+        try!(<UnixStream>::shutdown(self, Shutdown::Write));
+        Ok(Async::Ready(()))
+    }
+}
+
+impl<'a> Read for &'a UnixStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.io).read(buf)
+    }
+}
+
+impl<'a> Write for &'a UnixStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.io).write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        (&self.io).write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.io).flush()
+    }
+}
+
+impl<'a> AsyncRead for &'a UnixStream {
+    fn poll_read(&mut self) -> Async<()> {
+        <UnixStream>::poll_read(self)
+    }
+}
+
+impl<'a> AsyncWrite for &'a UnixStream {
+    fn poll_write(&mut self) -> Async<()> {
+        <UnixStream>::poll_write(self)
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        try_ready!(self.poll_flush());
+        try!(<UnixStream>::shutdown(self, Shutdown::Write));
+        Ok(Async::Ready(()))
+    }
+}
+
+impl fmt::Debug for UnixStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.io.get_ref().get_ref().fmt(f)
+    }
+}
+
+impl AsRawFd for UnixStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl Splice for UnixStream {
+    fn splice_to(&mut self, pipe: RawFd, len: usize) -> io::Result<usize> {
+        self.io.splice_to(pipe, len)
+    }
+
+    fn splice_from(&mut self, pipe: RawFd, len: usize) -> io::Result<usize> {
+        self.io.splice_from(pipe, len)
+    }
+}
@@ -0,0 +1,62 @@
+//! Credentials of the process on the other end of a Unix domain socket.
+
+use std::io;
+use std::os::unix::prelude::*;
+
+use libc;
+
+/// The credentials of the process which connected a Unix domain socket, as
+/// returned by `UnixStream::peer_cred`.
+///
+/// These are captured by the kernel when the connection is established, so
+/// they can't be spoofed by the peer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UCred {
+    uid: u32,
+    gid: u32,
+    pid: Option<i32>,
+}
+
+impl UCred {
+    /// Returns the effective user ID of the peer.
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Returns the effective group ID of the peer.
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// Returns the process ID of the peer, on platforms which report it.
+    pub fn pid(&self) -> Option<i32> {
+        self.pid
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn peer_cred(fd: RawFd) -> io::Result<UCred> {
+    use net::sockopt;
+
+    let cred: libc::ucred = try!(sockopt::get(fd, libc::SOL_SOCKET,
+                                              libc::SO_PEERCRED));
+    Ok(UCred {
+        uid: cred.uid as u32,
+        gid: cred.gid as u32,
+        pid: Some(cred.pid as i32),
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub fn peer_cred(fd: RawFd) -> io::Result<UCred> {
+    let mut uid = 0;
+    let mut gid = 0;
+    if unsafe { libc::getpeereid(fd, &mut uid, &mut gid) } == -1 {
+        return Err(io::Error::last_os_error())
+    }
+    Ok(UCred {
+        uid: uid as u32,
+        gid: gid as u32,
+        pid: None,
+    })
+}
@@ -0,0 +1,431 @@
+//! Linux `AF_VSOCK` sockets, integrated with the event loop.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::net::Shutdown;
+use std::os::unix::prelude::*;
+
+use futures::stream::Stream;
+use futures::{self, Future, Poll, Async, failed};
+use libc::{self, c_int};
+use mio;
+
+use fd::{self, EventedFd, OwnedFd};
+use io::{AsyncRead, AsyncWrite, IoFuture, IoStream};
+use net::sockopt::{self, cvt};
+use reactor::{Handle, PollEvented};
+
+/// The address of a vsock socket, made up of a context ID identifying the
+/// virtual machine (or the host) and a port number.
+///
+/// Well known context IDs include `VMADDR_CID_HOST` for the host and
+/// `VMADDR_CID_ANY` for binding to any context ID, both available from the
+/// `libc` crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VsockAddr {
+    cid: u32,
+    port: u32,
+}
+
+impl VsockAddr {
+    /// Creates a new address from a context ID and a port.
+    pub fn new(cid: u32, port: u32) -> VsockAddr {
+        VsockAddr { cid: cid, port: port }
+    }
+
+    /// Returns the context ID of this address.
+    pub fn cid(&self) -> u32 {
+        self.cid
+    }
+
+    /// Returns the port of this address.
+    pub fn port(&self) -> u32 {
+        self.port
+    }
+
+    fn to_raw(self) -> libc::sockaddr_vm {
+        let mut addr: libc::sockaddr_vm = unsafe { mem::zeroed() };
+        addr.svm_family = libc::AF_VSOCK as libc::sa_family_t;
+        addr.svm_cid = self.cid;
+        addr.svm_port = self.port;
+        addr
+    }
+
+    fn from_raw(addr: &libc::sockaddr_vm) -> VsockAddr {
+        VsockAddr::new(addr.svm_cid, addr.svm_port)
+    }
+}
+
+fn socket() -> io::Result<OwnedFd> {
+    let raw = try!(cvt(unsafe {
+        libc::socket(libc::AF_VSOCK, libc::SOCK_STREAM, 0)
+    }));
+    let socket = unsafe { OwnedFd::new(raw) };
+    try!(fd::set_nonblocking_cloexec(raw));
+    Ok(socket)
+}
+
+fn sockname(fd: RawFd, peer: bool) -> io::Result<VsockAddr> {
+    let mut addr: libc::sockaddr_vm = unsafe { mem::zeroed() };
+    let mut len = mem::size_of_val(&addr) as libc::socklen_t;
+    let addr_ptr = &mut addr as *mut _ as *mut libc::sockaddr;
+    try!(cvt(unsafe {
+        if peer {
+            libc::getpeername(fd, addr_ptr, &mut len)
+        } else {
+            libc::getsockname(fd, addr_ptr, &mut len)
+        }
+    }));
+    Ok(VsockAddr::from_raw(&addr))
+}
+
+fn take_error(fd: RawFd) -> io::Result<Option<io::Error>> {
+    let err: c_int = try!(sockopt::get(fd, libc::SOL_SOCKET, libc::SO_ERROR));
+    if err == 0 {
+        Ok(None)
+    } else {
+        Ok(Some(io::Error::from_raw_os_error(err)))
+    }
+}
+
+/// An I/O object representing a vsock socket listening for incoming
+/// connections.
+pub struct VsockListener {
+    io: PollEvented<EventedFd<OwnedFd>>,
+}
+
+/// Stream returned by the `VsockListener::incoming` function representing the
+/// stream of sockets received from a listener.
+pub struct VsockIncoming {
+    inner: IoStream<(VsockStream, VsockAddr)>,
+}
+
+impl VsockListener {
+    /// Creates a new vsock listener bound to the address specified,
+    /// associated with the event loop `handle` points to.
+    pub fn bind(addr: &VsockAddr, handle: &Handle) -> io::Result<VsockListener> {
+        let socket = try!(socket());
+        let raw = addr.to_raw();
+        try!(cvt(unsafe {
+            libc::bind(socket.as_raw_fd(),
+                       &raw as *const _ as *const libc::sockaddr,
+                       mem::size_of_val(&raw) as libc::socklen_t)
+        }));
+        try!(cvt(unsafe { libc::listen(socket.as_raw_fd(), 128) }));
+        let io = try!(PollEvented::new(EventedFd::new(socket), handle));
+        Ok(VsockListener { io: io })
+    }
+
+    /// Test whether this socket is ready to be read or not.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Returns the token this listener is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
+    /// Returns the local address that this listener is bound to.
+    pub fn local_addr(&self) -> io::Result<VsockAddr> {
+        sockname(self.as_raw_fd(), false)
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        take_error(self.as_raw_fd())
+    }
+
+    /// Consumes this listener, returning a stream of the sockets this listener
+    /// accepts.
+    pub fn incoming(self) -> VsockIncoming {
+        struct MyIncoming {
+            inner: VsockListener,
+        }
+
+        impl Stream for MyIncoming {
+            type Item = (OwnedFd, VsockAddr);
+            type Error = io::Error;
+
+            fn poll(&mut self) -> Poll<Option<Self::Item>, io::Error> {
+                if let Async::NotReady = self.inner.io.poll_read() {
+                    return Ok(Async::NotReady)
+                }
+                let mut addr: libc::sockaddr_vm = unsafe { mem::zeroed() };
+                let mut len = mem::size_of_val(&addr) as libc::socklen_t;
+                let raw = unsafe {
+                    libc::accept(self.inner.as_raw_fd(),
+                                 &mut addr as *mut _ as *mut libc::sockaddr,
+                                 &mut len)
+                };
+                if raw == -1 {
+                    let e = io::Error::last_os_error();
+                    if e.kind() == io::ErrorKind::WouldBlock {
+                        self.inner.io.need_read();
+                        return Ok(Async::NotReady)
+                    }
+                    return Err(e)
+                }
+                let socket = unsafe { OwnedFd::new(raw) };
+                try!(fd::set_nonblocking_cloexec(raw));
+                Ok(Async::Ready(Some((socket, VsockAddr::from_raw(&addr)))))
+            }
+        }
+
+        let remote = self.io.remote().clone();
+        let stream = MyIncoming { inner: self };
+        VsockIncoming {
+            inner: stream.and_then(move |(socket, addr)| {
+                let (tx, rx) = futures::oneshot();
+                remote.spawn(move |handle| {
+                    let res = PollEvented::new(EventedFd::new(socket), handle);
+                    tx.complete(res.map(move |io| {
+                        (VsockStream { io: io }, addr)
+                    }));
+                    Ok(())
+                });
+                rx.then(|r| r.expect("shouldn't be canceled"))
+            }).boxed(),
+        }
+    }
+}
+
+impl fmt::Debug for VsockListener {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VsockListener")
+         .field("fd", &self.as_raw_fd())
+         .finish()
+    }
+}
+
+impl AsRawFd for VsockListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl Stream for VsockIncoming {
+    type Item = (VsockStream, VsockAddr);
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<Self::Item>, io::Error> {
+        self.inner.poll()
+    }
+}
+
+/// An I/O object representing a vsock stream connected to a peer.
+pub struct VsockStream {
+    io: PollEvented<EventedFd<OwnedFd>>,
+}
+
+/// Future returned by `VsockStream::connect` which will resolve to a
+/// `VsockStream` when the stream is connected.
+pub struct VsockStreamNew {
+    inner: IoFuture<VsockStream>,
+}
+
+enum VsockStreamConnect {
+    Waiting(VsockStream),
+    Empty,
+}
+
+impl VsockStream {
+    /// Creates a new vsock stream connected to the address specified.
+    ///
+    /// The returned future will be resolved once the stream has successfully
+    /// connected, or with the error encountered while doing so.
+    pub fn connect(addr: &VsockAddr, handle: &Handle) -> VsockStreamNew {
+        let inner = match VsockStream::start_connect(addr, handle) {
+            Ok(stream) => VsockStreamConnect::Waiting(stream).boxed(),
+            Err(e) => failed(e).boxed(),
+        };
+        VsockStreamNew { inner: inner }
+    }
+
+    fn start_connect(addr: &VsockAddr, handle: &Handle) -> io::Result<VsockStream> {
+        let socket = try!(socket());
+        let raw = addr.to_raw();
+        let ret = unsafe {
+            libc::connect(socket.as_raw_fd(),
+                          &raw as *const _ as *const libc::sockaddr,
+                          mem::size_of_val(&raw) as libc::socklen_t)
+        };
+        match cvt(ret) {
+            Ok(_) => {}
+            Err(ref e) if e.raw_os_error() == Some(libc::EINPROGRESS) => {}
+            Err(e) => return Err(e),
+        }
+        let io = try!(PollEvented::new(EventedFd::new(socket), handle));
+        Ok(VsockStream { io: io })
+    }
+
+    /// Test whether this socket is ready to be read or not.
+    ///
+    /// If the socket is *not* readable then the current task is scheduled to
+    /// get a notification when the socket does become readable.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Test whether this socket is ready to be written to or not.
+    ///
+    /// If the socket is *not* writable then the current task is scheduled to
+    /// get a notification when the socket does become writable.
+    pub fn poll_write(&self) -> Async<()> {
+        self.io.poll_write()
+    }
+
+    /// Returns the token this stream is registered with the event loop under,
+    /// as with `PollEvented::token`.
+    pub fn token(&self) -> mio::Token {
+        self.io.token()
+    }
+
+    /// Returns the socket address of the local half of this connection.
+    pub fn local_addr(&self) -> io::Result<VsockAddr> {
+        sockname(self.as_raw_fd(), false)
+    }
+
+    /// Returns the socket address of the remote half of this connection.
+    pub fn peer_addr(&self) -> io::Result<VsockAddr> {
+        sockname(self.as_raw_fd(), true)
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        take_error(self.as_raw_fd())
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        let how = match how {
+            Shutdown::Read => libc::SHUT_RD,
+            Shutdown::Write => libc::SHUT_WR,
+            Shutdown::Both => libc::SHUT_RDWR,
+        };
+        try!(cvt(unsafe { libc::shutdown(self.as_raw_fd(), how) }));
+        Ok(())
+    }
+}
+
+impl Future for VsockStreamNew {
+    type Item = VsockStream;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<VsockStream, io::Error> {
+        self.inner.poll()
+    }
+}
+
+impl Future for VsockStreamConnect {
+    type Item = VsockStream;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<VsockStream, io::Error> {
+        {
+            let stream = match *self {
+                VsockStreamConnect::Waiting(ref s) => s,
+                VsockStreamConnect::Empty => {
+                    panic!("can't poll vsock stream twice")
+                }
+            };
+
+            // As with TCP, the connection has been established (or has
+            // failed) once the socket is writable.
+            if let Async::NotReady = stream.io.poll_write() {
+                return Ok(Async::NotReady)
+            }
+            if let Some(e) = try!(stream.take_error()) {
+                return Err(e)
+            }
+        }
+        match mem::replace(self, VsockStreamConnect::Empty) {
+            VsockStreamConnect::Waiting(stream) => Ok(Async::Ready(stream)),
+            VsockStreamConnect::Empty => panic!(),
+        }
+    }
+}
+
+impl Read for VsockStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.io.read(buf)
+    }
+}
+
+impl Write for VsockStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.io.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.io.flush()
+    }
+}
+
+impl AsyncRead for VsockStream {
+    fn poll_read(&mut self) -> Async<()> {
+        <VsockStream>::poll_read(self)
+    }
+}
+
+impl AsyncWrite for VsockStream {
+    fn poll_write(&mut self) -> Async<()> {
+        <VsockStream>::poll_write(self)
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        try_ready!(self.poll_flush());
+        try!(<VsockStream>::shutdown(self, Shutdown::Write));
+        Ok(Async::Ready(()))
+    }
+}
+
+impl<'a> Read for &'a VsockStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        (&self.io).read(buf)
+    }
+}
+
+impl<'a> Write for &'a VsockStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.io).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.io).flush()
+    }
+}
+
+impl<'a> AsyncRead for &'a VsockStream {
+    fn poll_read(&mut self) -> Async<()> {
+        <VsockStream>::poll_read(self)
+    }
+}
+
+impl<'a> AsyncWrite for &'a VsockStream {
+    fn poll_write(&mut self) -> Async<()> {
+        <VsockStream>::poll_write(self)
+    }
+
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        try_ready!(self.poll_flush());
+        try!(<VsockStream>::shutdown(self, Shutdown::Write));
+        Ok(Async::Ready(()))
+    }
+}
+
+impl fmt::Debug for VsockStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VsockStream")
+         .field("fd", &self.as_raw_fd())
+         .finish()
+    }
+}
+
+impl AsRawFd for VsockStream {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
@@ -0,0 +1,141 @@
+//! A pool of threads for running blocking operations off the event loop.
+//!
+//! Some operations, such as file I/O or name resolution, have no nonblocking
+//! equivalent on all platforms. These are instead shipped off to a lazily
+//! started, process-wide pool of helper threads, completing a future once
+//! they've finished. Threads are spawned on demand, up to a fixed limit,
+//! whenever no idle thread is available to pick up a new job.
+
+use std::io;
+use std::panic;
+use std::ptr;
+use std::sync::{Arc, Mutex, Once, ONCE_INIT};
+use std::sync::mpsc::{self, Sender, Receiver};
+use std::thread;
+
+use futures::{self, Future, Oneshot, Poll, Async};
+
+const MAX_THREADS: usize = 32;
+
+trait FnBox: Send {
+    fn call_box(self: Box<Self>);
+}
+
+impl<F: FnOnce() + Send> FnBox for F {
+    fn call_box(self: Box<Self>) {
+        (*self)()
+    }
+}
+
+type Job = Box<FnBox>;
+
+struct Pool {
+    tx: Mutex<Sender<Job>>,
+    rx: Arc<Mutex<Receiver<Job>>>,
+    state: Mutex<State>,
+}
+
+struct State {
+    threads: usize,
+    idle: usize,
+    queued: usize,
+}
+
+static INIT: Once = ONCE_INIT;
+static mut POOL: *const Pool = ptr::null();
+
+fn pool() -> &'static Pool {
+    INIT.call_once(|| {
+        let (tx, rx) = mpsc::channel();
+        let pool = Pool {
+            tx: Mutex::new(tx),
+            rx: Arc::new(Mutex::new(rx)),
+            state: Mutex::new(State { threads: 0, idle: 0, queued: 0 }),
+        };
+        unsafe {
+            POOL = Box::into_raw(Box::new(pool));
+        }
+    });
+    unsafe { &*POOL }
+}
+
+impl Pool {
+    fn execute(&'static self, job: Job) {
+        {
+            // Each job is either handed to an idle thread, to a newly spawned
+            // one, or left queued for the next thread to finish its job.
+            let mut state = self.state.lock().unwrap();
+            if state.idle > 0 {
+                state.idle -= 1;
+            } else if state.threads < MAX_THREADS {
+                let spawned = thread::Builder::new()
+                    .name("tokio-blocking".to_string())
+                    .spawn(move || self.work());
+                match spawned {
+                    Ok(_) => state.threads += 1,
+                    Err(e) => {
+                        debug!("failed to spawn blocking pool thread: {}", e);
+                        state.queued += 1;
+                    }
+                }
+            } else {
+                state.queued += 1;
+            }
+        }
+        self.tx.lock().unwrap().send(job).unwrap();
+    }
+
+    fn work(&self) {
+        loop {
+            let job = match self.rx.lock().unwrap().recv() {
+                Ok(job) => job,
+                Err(_) => break,
+            };
+
+            // A panicking job drops its oneshot sender, failing the
+            // `Blocking` future, but mustn't take the thread down with it.
+            let job = panic::AssertUnwindSafe(job);
+            if panic::catch_unwind(move || job.0.call_box()).is_err() {
+                debug!("blocking pool job panicked");
+            }
+
+            let mut state = self.state.lock().unwrap();
+            if state.queued > 0 {
+                state.queued -= 1;
+            } else {
+                state.idle += 1;
+            }
+        }
+    }
+}
+
+/// A future for the result of an operation running on the blocking pool.
+pub struct Blocking<T> {
+    rx: Oneshot<io::Result<T>>,
+}
+
+/// Runs `f` on the blocking pool, returning a future for its result.
+pub fn run<F, T>(f: F) -> Blocking<T>
+    where F: FnOnce() -> io::Result<T> + Send + 'static,
+          T: Send + 'static,
+{
+    let (tx, rx) = futures::oneshot();
+    pool().execute(Box::new(move || {
+        tx.complete(f());
+    }));
+    Blocking { rx: rx }
+}
+
+impl<T> Future for Blocking<T> {
+    type Item = T;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<T, io::Error> {
+        match self.rx.poll() {
+            Ok(Async::Ready(res)) => res.map(Async::Ready),
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(_) => Err(io::Error::new(io::ErrorKind::Other,
+                                         "blocking operation panicked")),
+        }
+    }
+}
@@ -0,0 +1,416 @@
+//! Asynchronous child processes.
+//!
+//! This module provides the `CommandExt` extension trait, which allows a child
+//! process configured with the standard library's `Command` type to be spawned
+//! with its stdio pipes, and its exit status, managed by an event loop.
+//!
+//! On Linux the exit of a child is learned about through a pidfd registered
+//! with the event loop. Elsewhere, or on kernels without pidfd support, it's
+//! learned about through `SIGCHLD` instead, so creating a `Child` installs a
+//! handler for that signal as with the `signal` module.
+//!
+//! The Unix specific configuration of the standard library's
+//! `std::os::unix::process::CommandExt` trait, such as `process_group`, `uid`,
+//! `gid` and `pre_exec`, is applied when spawning with `spawn_async` as well.
+//! Together with `Child::signal_group` and `Child::set_kill_on_drop` this is
+//! enough for a supervisor to run sandboxed children, and whole trees of
+//! processes, from an event loop.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::os::unix::prelude::*;
+use std::process::{self, Command, ExitStatus, Output};
+
+use futures::{Future, Poll, Async, failed, finished};
+use futures::stream::Stream;
+use libc::{self, c_int};
+
+#[cfg(target_os = "linux")]
+use fd::OwnedFd;
+use fd::{self, EventedFd};
+use io::{read_to_end, AsyncRead, AsyncWrite, IoFuture};
+use reactor::{Handle, PollEvented};
+use signal::{self, Signal};
+
+/// Extensions to the standard library's `Command` type for spawning child
+/// processes on an event loop.
+pub trait CommandExt {
+    /// Executes the command as a child process, associating it with the event
+    /// loop `handle` points to.
+    ///
+    /// Any stdio handles configured as `Stdio::piped()` are registered with
+    /// the event loop and are available through the `stdin`, `stdout` and
+    /// `stderr` methods of the returned `Child`, which is itself a future
+    /// resolving to the exit status of the process.
+    fn spawn_async(&mut self, handle: &Handle) -> io::Result<Child>;
+}
+
+impl CommandExt for Command {
+    fn spawn_async(&mut self, handle: &Handle) -> io::Result<Child> {
+        let mut child = try!(self.spawn());
+        // An exit notification arriving before the `Exit` is created isn't
+        // lost, as `Child::poll` always checks the status of the child first.
+        let exit = try!(Exit::new(&child, handle));
+        let stdin = try!(pipe(child.stdin.take(), handle));
+        let stdout = try!(pipe(child.stdout.take(), handle));
+        let stderr = try!(pipe(child.stderr.take(), handle));
+        Ok(Child {
+            child: child,
+            exit: exit,
+            kill_on_drop: false,
+            stdin: stdin.map(|io| ChildStdin { io: io }),
+            stdout: stdout.map(|io| ChildStdout { io: io }),
+            stderr: stderr.map(|io| ChildStderr { io: io }),
+        })
+    }
+}
+
+fn pipe<T: AsRawFd>(io: Option<T>, handle: &Handle)
+                    -> io::Result<Option<PollEvented<EventedFd<T>>>> {
+    match io {
+        Some(io) => {
+            try!(fd::set_nonblocking_cloexec(io.as_raw_fd()));
+            PollEvented::new(EventedFd::new(io), handle).map(Some)
+        }
+        None => Ok(None),
+    }
+}
+
+/// The source of notifications that a child may have exited.
+enum Exit {
+    #[cfg(target_os = "linux")]
+    Pidfd(PollEvented<EventedFd<OwnedFd>>),
+    Sigchld(Signal),
+}
+
+impl Exit {
+    fn new(child: &process::Child, handle: &Handle) -> io::Result<Exit> {
+        if let Some(exit) = try!(Exit::pidfd(child, handle)) {
+            return Ok(exit)
+        }
+        Signal::new(signal::SIGCHLD, handle).map(Exit::Sigchld)
+    }
+
+    /// Opens a pidfd for `child`, returning `None` if the kernel doesn't
+    /// support them.
+    #[cfg(target_os = "linux")]
+    fn pidfd(child: &process::Child, handle: &Handle) -> io::Result<Option<Exit>> {
+        let raw = unsafe {
+            libc::syscall(libc::SYS_pidfd_open, child.id() as libc::pid_t, 0)
+        };
+        if raw == -1 {
+            return Ok(None)
+        }
+        let pidfd = unsafe { OwnedFd::new(raw as c_int) };
+        try!(fd::set_nonblocking_cloexec(raw as c_int));
+        let io = try!(PollEvented::new(EventedFd::new(pidfd), handle));
+        Ok(Some(Exit::Pidfd(io)))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn pidfd(_child: &process::Child, _handle: &Handle)
+             -> io::Result<Option<Exit>> {
+        Ok(None)
+    }
+
+    /// Waits for the next notification, returning `NotReady` if there isn't
+    /// one yet.
+    fn poll(&mut self) -> Poll<(), io::Error> {
+        match *self {
+            #[cfg(target_os = "linux")]
+            Exit::Pidfd(ref io) => {
+                if let Async::NotReady = io.poll_read() {
+                    return Ok(Async::NotReady)
+                }
+                // The caller checks whether the child has exited after each
+                // notification, so the readiness is always cleared: if the
+                // child is still running the next poll waits for another.
+                io.need_read();
+                Ok(Async::Ready(()))
+            }
+            Exit::Sigchld(ref mut sigchld) => {
+                match try!(sigchld.poll()) {
+                    Async::Ready(Some(_)) => Ok(Async::Ready(())),
+                    Async::Ready(None) => {
+                        Err(io::Error::new(io::ErrorKind::Other,
+                                           "SIGCHLD stream ended"))
+                    }
+                    Async::NotReady => Ok(Async::NotReady),
+                }
+            }
+        }
+    }
+}
+
+/// A child process spawned with `CommandExt::spawn_async`.
+///
+/// This type is a future resolving to the exit status of the child. By
+/// default dropping a `Child` does not kill the underlying process, nor does
+/// it wait for it to exit, but `set_kill_on_drop` can be used to kill it.
+pub struct Child {
+    child: process::Child,
+    exit: Exit,
+    kill_on_drop: bool,
+    stdin: Option<ChildStdin>,
+    stdout: Option<ChildStdout>,
+    stderr: Option<ChildStderr>,
+}
+
+impl Child {
+    /// Returns the OS-assigned process identifier of the child.
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Forces the child to exit, by sending it `SIGKILL`.
+    ///
+    /// The exit status is still resolved by this future once the child has
+    /// actually exited.
+    pub fn kill(&mut self) -> io::Result<()> {
+        self.child.kill()
+    }
+
+    /// Sends the signal `signum` to the child, such as `SIGTERM` to ask it to
+    /// exit gracefully.
+    ///
+    /// Nothing is sent if the child has already been reaped, so the signal
+    /// can't reach another process which has reused its ID.
+    pub fn signal(&mut self, signum: c_int) -> io::Result<()> {
+        if try!(self.child.try_wait()).is_some() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "process has already exited"))
+        }
+        if unsafe { libc::kill(self.child.id() as libc::pid_t, signum) } == -1 {
+            return Err(io::Error::last_os_error())
+        }
+        Ok(())
+    }
+
+    /// Sends the signal `signum` to the process group led by the child.
+    ///
+    /// The child only leads a process group of its own if it was spawned as
+    /// one, such as with `process_group(0)` or by calling `setsid` in a
+    /// `pre_exec` hook, in which case this reaches the processes it has
+    /// spawned in turn as well.
+    pub fn signal_group(&mut self, signum: c_int) -> io::Result<()> {
+        if try!(self.child.try_wait()).is_some() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "process has already exited"))
+        }
+        if unsafe { libc::killpg(self.child.id() as libc::pid_t, signum) } == -1 {
+            return Err(io::Error::last_os_error())
+        }
+        Ok(())
+    }
+
+    /// Kills the child, returning a future which resolves to its exit status
+    /// once it has actually exited.
+    ///
+    /// Unlike `kill`, it isn't an error for the child to have already exited,
+    /// in which case the future resolves to the status it exited with.
+    pub fn kill_and_wait(mut self) -> IoFuture<ExitStatus> {
+        if let Err(e) = self.child.try_wait().and_then(|status| {
+            match status {
+                Some(_) => Ok(()),
+                None => self.child.kill(),
+            }
+        }) {
+            return failed(e).boxed()
+        }
+        self.boxed()
+    }
+
+    /// Sets whether the child is killed when this `Child` is dropped before
+    /// it has exited, which is `false` by default.
+    ///
+    /// This ensures a child doesn't outlive the task supervising it, for
+    /// example when that task is itself canceled or times out.
+    pub fn set_kill_on_drop(&mut self, kill: bool) {
+        self.kill_on_drop = kill;
+    }
+
+    /// Returns the handle for writing to the child's standard input, if it
+    /// was configured as a pipe.
+    ///
+    /// The handle can be taken out of the option to be moved elsewhere, and
+    /// dropping it closes the child's standard input.
+    pub fn stdin(&mut self) -> &mut Option<ChildStdin> {
+        &mut self.stdin
+    }
+
+    /// Returns the handle for reading from the child's standard output, if it
+    /// was configured as a pipe.
+    pub fn stdout(&mut self) -> &mut Option<ChildStdout> {
+        &mut self.stdout
+    }
+
+    /// Returns the handle for reading from the child's standard error, if it
+    /// was configured as a pipe.
+    pub fn stderr(&mut self) -> &mut Option<ChildStderr> {
+        &mut self.stderr
+    }
+
+    /// Returns a future which closes the child's standard input, reads all of
+    /// its standard output and error and waits for it to exit, collecting
+    /// everything into an `Output`.
+    pub fn wait_with_output(mut self) -> IoFuture<Output> {
+        drop(self.stdin.take());
+        let stdout = match self.stdout.take() {
+            Some(io) => read_to_end(io, Vec::new()).map(|p| p.1).boxed(),
+            None => finished(Vec::new()).boxed(),
+        };
+        let stderr = match self.stderr.take() {
+            Some(io) => read_to_end(io, Vec::new()).map(|p| p.1).boxed(),
+            None => finished(Vec::new()).boxed(),
+        };
+        self.join3(stdout, stderr).map(|(status, stdout, stderr)| {
+            Output {
+                status: status,
+                stdout: stdout,
+                stderr: stderr,
+            }
+        }).boxed()
+    }
+}
+
+impl Future for Child {
+    type Item = ExitStatus;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<ExitStatus, io::Error> {
+        loop {
+            if let Some(status) = try!(self.child.try_wait()) {
+                return Ok(Async::Ready(status))
+            }
+            try_ready!(self.exit.poll());
+        }
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        if !self.kill_on_drop {
+            return
+        }
+        if let Ok(None) = self.child.try_wait() {
+            drop(self.child.kill());
+            // Reap the child if it has already exited, which it usually
+            // won't have had time to do yet.
+            drop(self.child.try_wait());
+        }
+    }
+}
+
+impl fmt::Debug for Child {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Child")
+         .field("id", &self.child.id())
+         .finish()
+    }
+}
+
+/// A handle to a child process's standard input, registered with an event
+/// loop.
+pub struct ChildStdin {
+    io: PollEvented<EventedFd<process::ChildStdin>>,
+}
+
+/// A handle to a child process's standard output, registered with an event
+/// loop.
+pub struct ChildStdout {
+    io: PollEvented<EventedFd<process::ChildStdout>>,
+}
+
+/// A handle to a child process's standard error, registered with an event
+/// loop.
+pub struct ChildStderr {
+    io: PollEvented<EventedFd<process::ChildStderr>>,
+}
+
+impl ChildStdin {
+    /// Test whether this pipe is ready to be written to or not.
+    ///
+    /// If the pipe is *not* writable then the current task is scheduled to
+    /// get a notification when it does become writable.
+    pub fn poll_write(&self) -> Async<()> {
+        self.io.poll_write()
+    }
+}
+
+impl Write for ChildStdin {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.io.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.io.flush()
+    }
+}
+
+impl AsyncWrite for ChildStdin {
+    fn poll_write(&mut self) -> Async<()> {
+        <ChildStdin>::poll_write(self)
+    }
+}
+
+impl AsRawFd for ChildStdin {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl ChildStdout {
+    /// Test whether this pipe is ready to be read or not.
+    ///
+    /// If the pipe is *not* readable then the current task is scheduled to
+    /// get a notification when it does become readable.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+}
+
+impl Read for ChildStdout {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.io.read(buf)
+    }
+}
+
+impl AsyncRead for ChildStdout {
+    fn poll_read(&mut self) -> Async<()> {
+        <ChildStdout>::poll_read(self)
+    }
+}
+
+impl AsRawFd for ChildStdout {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl ChildStderr {
+    /// Test whether this pipe is ready to be read or not.
+    ///
+    /// If the pipe is *not* readable then the current task is scheduled to
+    /// get a notification when it does become readable.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+}
+
+impl Read for ChildStderr {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.io.read(buf)
+    }
+}
+
+impl AsyncRead for ChildStderr {
+    fn poll_read(&mut self) -> Async<()> {
+        <ChildStderr>::poll_read(self)
+    }
+}
+
+impl AsRawFd for ChildStderr {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
@@ -0,0 +1,309 @@
+//! Asynchronous pseudo-terminals.
+//!
+//! A pseudo-terminal is a pair of connected character devices: the slave side
+//! behaves like a real terminal for the program attached to it, typically a
+//! shell in a child process, while the master side, registered with an event
+//! loop here, is used by the terminal emulator, multiplexer or remote-shell
+//! server driving it.
+//!
+//! To attach a child process to the slave side, pass duplicates of it as the
+//! child's stdio with `Stdio::from(slave.try_clone()?.into_std())`. For the
+//! pseudo-terminal to also become the child's controlling terminal, the child
+//! needs to start a new session and acquire it with `TIOCSCTTY` before
+//! executing the program.
+
+use std::ffi::{CStr, OsStr};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::mem;
+use std::os::unix::prelude::*;
+use std::path::{Path, PathBuf};
+
+use futures::Async;
+use libc;
+
+use fd::{self, cvt, EventedFd, OwnedFd};
+use io::{AsyncRead, AsyncWrite};
+use reactor::{Handle, PollEvented};
+
+/// The master side of a pseudo-terminal, registered with an event loop.
+///
+/// The data written to the master is the input of the terminal, and the data
+/// read from it is the terminal's output. Once every handle to the slave side
+/// has been closed, reads from the master return end of file.
+pub struct PtyMaster {
+    io: PollEvented<EventedFd<OwnedFd>>,
+    path: PathBuf,
+}
+
+/// The slave side of a pseudo-terminal.
+///
+/// The slave is an ordinary, blocking terminal device intended to be handed
+/// to a child process, so it isn't associated with an event loop.
+pub struct PtySlave {
+    file: File,
+}
+
+/// The size of a terminal window, in character cells.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowSize {
+    rows: u16,
+    cols: u16,
+}
+
+impl WindowSize {
+    /// Creates a window size of `rows` rows and `cols` columns.
+    pub fn new(rows: u16, cols: u16) -> WindowSize {
+        WindowSize { rows: rows, cols: cols }
+    }
+
+    /// Returns the number of rows.
+    pub fn rows(&self) -> u16 {
+        self.rows
+    }
+
+    /// Returns the number of columns.
+    pub fn cols(&self) -> u16 {
+        self.cols
+    }
+}
+
+impl PtyMaster {
+    /// Opens a new pseudo-terminal, returning its master side associated with
+    /// the event loop `handle` points to and its slave side.
+    pub fn open(handle: &Handle) -> io::Result<(PtyMaster, PtySlave)> {
+        let raw = try!(cvt(unsafe {
+            libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY)
+        }));
+        let master = unsafe { OwnedFd::new(raw) };
+        try!(fd::set_nonblocking_cloexec(raw));
+        try!(cvt(unsafe { libc::grantpt(raw) }));
+        try!(cvt(unsafe { libc::unlockpt(raw) }));
+        let path = try!(ptsname(raw));
+
+        let file = try!(OpenOptions::new()
+                            .read(true)
+                            .write(true)
+                            .custom_flags(libc::O_NOCTTY)
+                            .open(&path));
+        let io = try!(PollEvented::new(EventedFd::new(master), handle));
+        let master = PtyMaster { io: io, path: path };
+        Ok((master, PtySlave { file: file }))
+    }
+
+    /// Returns the path of the slave device of this pseudo-terminal, such as
+    /// `/dev/pts/3`.
+    pub fn slave_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Sets the window size of this pseudo-terminal.
+    ///
+    /// The foreground process group of the terminal is sent `SIGWINCH` when
+    /// the size changes, which is how programs such as shells and editors
+    /// learn about resizes.
+    pub fn set_window_size(&self, size: WindowSize) -> io::Result<()> {
+        set_window_size(self.as_raw_fd(), size)
+    }
+
+    /// Returns the window size of this pseudo-terminal.
+    pub fn window_size(&self) -> io::Result<WindowSize> {
+        window_size(self.as_raw_fd())
+    }
+
+    /// Test whether this pseudo-terminal is ready to be read or not.
+    ///
+    /// If it is *not* readable then the current task is scheduled to get a
+    /// notification when it does become readable.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Test whether this pseudo-terminal is ready to be written to or not.
+    ///
+    /// If it is *not* writable then the current task is scheduled to get a
+    /// notification when it does become writable.
+    pub fn poll_write(&self) -> Async<()> {
+        self.io.poll_write()
+    }
+}
+
+impl PtySlave {
+    /// Creates a new handle to the slave side sharing the same underlying
+    /// device, such as to use it as more than one of a child's stdio handles.
+    pub fn try_clone(&self) -> io::Result<PtySlave> {
+        self.file.try_clone().map(|file| PtySlave { file: file })
+    }
+
+    /// Sets the window size of this pseudo-terminal.
+    pub fn set_window_size(&self, size: WindowSize) -> io::Result<()> {
+        set_window_size(self.as_raw_fd(), size)
+    }
+
+    /// Returns the window size of this pseudo-terminal.
+    pub fn window_size(&self) -> io::Result<WindowSize> {
+        window_size(self.as_raw_fd())
+    }
+
+    /// Consumes this slave, returning the underlying device as a `File`.
+    pub fn into_std(self) -> File {
+        self.file
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn ptsname(fd: RawFd) -> io::Result<PathBuf> {
+    let mut buf = [0 as libc::c_char; 128];
+    let rc = unsafe { libc::ptsname_r(fd, buf.as_mut_ptr(), buf.len()) };
+    if rc != 0 {
+        return Err(io::Error::from_raw_os_error(rc))
+    }
+    let name = unsafe { CStr::from_ptr(buf.as_ptr()) };
+    Ok(PathBuf::from(OsStr::from_bytes(name.to_bytes())))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn ptsname(fd: RawFd) -> io::Result<PathBuf> {
+    // `ptsname_r` isn't universally available, so fall back to `ptsname`,
+    // copying its result out of the static buffer straight away.
+    let name = unsafe { libc::ptsname(fd) };
+    if name.is_null() {
+        return Err(io::Error::last_os_error())
+    }
+    let name = unsafe { CStr::from_ptr(name) };
+    Ok(PathBuf::from(OsStr::from_bytes(name.to_bytes())))
+}
+
+fn set_window_size(fd: RawFd, size: WindowSize) -> io::Result<()> {
+    let ws = libc::winsize {
+        ws_row: size.rows,
+        ws_col: size.cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    try!(cvt(unsafe { libc::ioctl(fd, libc::TIOCSWINSZ, &ws) }));
+    Ok(())
+}
+
+fn window_size(fd: RawFd) -> io::Result<WindowSize> {
+    let mut ws: libc::winsize = unsafe { mem::zeroed() };
+    try!(cvt(unsafe { libc::ioctl(fd, libc::TIOCGWINSZ, &mut ws) }));
+    Ok(WindowSize::new(ws.ws_row, ws.ws_col))
+}
+
+/// On Linux reading the master once the slave has been closed fails with
+/// `EIO` rather than returning end of file, so translate it.
+fn eof_on_eio(res: io::Result<usize>) -> io::Result<usize> {
+    match res {
+        Err(ref e) if e.raw_os_error() == Some(libc::EIO) => Ok(0),
+        res => res,
+    }
+}
+
+impl Read for PtyMaster {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        eof_on_eio(self.io.read(buf))
+    }
+}
+
+impl Write for PtyMaster {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.io.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.io.flush()
+    }
+}
+
+impl AsyncRead for PtyMaster {
+    fn poll_read(&mut self) -> Async<()> {
+        <PtyMaster>::poll_read(self)
+    }
+}
+
+impl AsyncWrite for PtyMaster {
+    fn poll_write(&mut self) -> Async<()> {
+        <PtyMaster>::poll_write(self)
+    }
+}
+
+impl<'a> Read for &'a PtyMaster {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        eof_on_eio((&self.io).read(buf))
+    }
+}
+
+impl<'a> Write for &'a PtyMaster {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        (&self.io).write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (&self.io).flush()
+    }
+}
+
+impl<'a> AsyncRead for &'a PtyMaster {
+    fn poll_read(&mut self) -> Async<()> {
+        <PtyMaster>::poll_read(self)
+    }
+}
+
+impl<'a> AsyncWrite for &'a PtyMaster {
+    fn poll_write(&mut self) -> Async<()> {
+        <PtyMaster>::poll_write(self)
+    }
+}
+
+impl AsRawFd for PtyMaster {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl fmt::Debug for PtyMaster {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PtyMaster")
+         .field("fd", &self.as_raw_fd())
+         .field("slave", &self.path)
+         .finish()
+    }
+}
+
+impl Read for PtySlave {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Write for PtySlave {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl AsRawFd for PtySlave {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+impl IntoRawFd for PtySlave {
+    fn into_raw_fd(self) -> RawFd {
+        self.file.into_raw_fd()
+    }
+}
+
+impl fmt::Debug for PtySlave {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PtySlave")
+         .field("fd", &self.as_raw_fd())
+         .finish()
+    }
+}
@@ -0,0 +1,154 @@
+//! Support for Linux's `eventfd` counters as a source of events.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::prelude::*;
+use std::sync::Arc;
+
+use futures::{Async, Poll};
+use futures::stream::Stream;
+use libc;
+
+use fd::{self, EventedFd};
+use reactor::{Handle, PollEvented};
+
+/// A Linux `eventfd` counter registered with an event loop.
+///
+/// An eventfd is a kernel-maintained 64-bit counter: writes add to it, and a
+/// read returns its current value and resets it to zero. This makes it a
+/// cheap way to wake up an event loop from other threads, or from foreign
+/// code which signals readiness through an eventfd.
+///
+/// This type is a stream of the counter's values, each item being the sum of
+/// everything added to the counter since it was last read.
+pub struct EventFd {
+    io: PollEvented<EventedFd<File>>,
+}
+
+/// A handle for adding to the counter of an `EventFd` from any thread.
+///
+/// Created by `EventFd::notifier`, notifiers can be cloned and sent to other
+/// threads.
+#[derive(Clone)]
+pub struct Notifier {
+    file: Arc<File>,
+}
+
+impl EventFd {
+    /// Creates a new eventfd with an initial counter value of `init`,
+    /// associated with the event loop `handle` points to.
+    pub fn new(init: u32, handle: &Handle) -> io::Result<EventFd> {
+        let flags = libc::EFD_NONBLOCK | libc::EFD_CLOEXEC;
+        let raw = unsafe { libc::eventfd(init, flags) };
+        if raw == -1 {
+            return Err(io::Error::last_os_error())
+        }
+        unsafe { EventFd::from_raw_fd(raw, handle) }
+    }
+
+    /// Creates a new `EventFd` from an existing eventfd file descriptor,
+    /// taking ownership of it.
+    ///
+    /// The descriptor is placed into nonblocking mode. To use an eventfd which
+    /// is owned by other code, pass a `dup` of it instead, keeping in mind
+    /// that the nonblocking flag is shared between duplicated descriptors.
+    ///
+    /// # Safety
+    ///
+    /// The descriptor must be a valid eventfd which nothing else will close.
+    pub unsafe fn from_raw_fd(fd: RawFd, handle: &Handle) -> io::Result<EventFd> {
+        let file = File::from_raw_fd(fd);
+        try!(fd::set_nonblocking_cloexec(fd));
+        let io = try!(PollEvented::new(EventedFd::new(file), handle));
+        Ok(EventFd { io: io })
+    }
+
+    /// Test whether the counter is nonzero or not.
+    ///
+    /// If the counter is zero then the current task is scheduled to get a
+    /// notification when it becomes nonzero.
+    pub fn poll_read(&self) -> Async<()> {
+        self.io.poll_read()
+    }
+
+    /// Reads the value of the counter and resets it to zero.
+    ///
+    /// If the counter is zero a "would block" error is returned and the
+    /// current task is scheduled to be notified once it's nonzero.
+    pub fn read(&self) -> io::Result<u64> {
+        let mut buf = [0; 8];
+        let n = try!((&self.io).read(&mut buf));
+        debug_assert_eq!(n, 8);
+        Ok(u64::from_ne_bytes(buf))
+    }
+
+    /// Adds `n` to the counter, waking up anything waiting for it to become
+    /// nonzero.
+    ///
+    /// If this would overflow the counter a "would block" error is returned and
+    /// the current task is scheduled to be notified once it's been read.
+    pub fn write(&self, n: u64) -> io::Result<()> {
+        let buf = n.to_ne_bytes();
+        let written = try!((&self.io).write(&buf));
+        debug_assert_eq!(written, 8);
+        Ok(())
+    }
+
+    /// Creates a handle which can be used to add to this counter from other
+    /// threads.
+    pub fn notifier(&self) -> io::Result<Notifier> {
+        let file = try!(self.io.get_ref().get_ref().try_clone());
+        Ok(Notifier { file: Arc::new(file) })
+    }
+}
+
+impl Stream for EventFd {
+    type Item = u64;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<u64>, io::Error> {
+        match self.read() {
+            Ok(n) => Ok(Async::Ready(Some(n))),
+            Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                Ok(Async::NotReady)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl AsRawFd for EventFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl fmt::Debug for EventFd {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EventFd")
+         .field("fd", &self.as_raw_fd())
+         .finish()
+    }
+}
+
+impl Notifier {
+    /// Adds `n` to the counter of the associated `EventFd`.
+    ///
+    /// If this would overflow the counter a "would block" error is returned,
+    /// as the counter hasn't been read in a long time.
+    pub fn notify(&self, n: u64) -> io::Result<()> {
+        let buf = n.to_ne_bytes();
+        let written = try!((&*self.file).write(&buf));
+        debug_assert_eq!(written, 8);
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Notifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Notifier")
+         .field("fd", &self.file.as_raw_fd())
+         .finish()
+    }
+}
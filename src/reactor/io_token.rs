@@ -5,7 +5,7 @@ use std::io;
 use futures::task;
 use mio;
 
-use reactor::{Message, Remote, Handle, Direction};
+use reactor::{Message, Remote, Handle, Direction, source_token};
 
 /// A token that identifies an active timeout.
 pub struct IoToken {
@@ -24,17 +24,23 @@ impl IoToken {
     /// and a token which can be used to send more messages to the event loop.
     ///
     /// The token returned is then passed in turn to each of the methods below
-    /// to interact with notifications on the I/O object itself.
+    /// to interact with notifications on the I/O object itself. The source is
+    /// registered for the readiness in `interest`, with the options `opts`.
     ///
     /// # Panics
     ///
     /// The returned future will panic if the event loop this handle is
     /// associated with has gone away, or if there is an error communicating
     /// with the event loop.
-    pub fn new(source: &mio::Evented, handle: &Handle) -> io::Result<IoToken> {
+    pub fn new(source: &mio::Evented,
+               interest: mio::Ready,
+               opts: mio::PollOpt,
+               handle: &Handle) -> io::Result<IoToken> {
         match handle.inner.upgrade() {
             Some(inner) => {
-                let (ready, token) = try!(inner.borrow_mut().add_source(source));
+                let (ready, token) = try!(inner.borrow_mut().add_source(source,
+                                                                        interest,
+                                                                        opts));
                 Ok(IoToken { token: token, readiness: ready })
             }
             None => Err(io::Error::new(io::ErrorKind::Other, "event loop gone")),
@@ -60,6 +66,30 @@ impl IoToken {
         self.readiness.swap(0, Ordering::SeqCst)
     }
 
+    /// Returns the `mio` token the source is registered under.
+    pub fn mio_token(&self) -> mio::Token {
+        source_token(self.token)
+    }
+
+    /// Changes the readiness `source`, registered under this token, is polled
+    /// for to `interest`, and its options to `opts`.
+    ///
+    /// Unlike the other methods this is carried out right away rather than
+    /// sent as a message, so `handle` has to point to the event loop this
+    /// token was created on.
+    pub fn reregister(&self,
+                      source: &mio::Evented,
+                      interest: mio::Ready,
+                      opts: mio::PollOpt,
+                      handle: &Handle) -> io::Result<()> {
+        match handle.inner.upgrade() {
+            Some(inner) => {
+                inner.borrow_mut().reregister_source(source, self.token, interest, opts)
+            }
+            None => Err(io::Error::new(io::ErrorKind::Other, "event loop gone")),
+        }
+    }
+
     /// Schedule the current future task to receive a notification when the
     /// corresponding I/O object is readable.
     ///
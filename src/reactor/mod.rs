@@ -3,6 +3,11 @@
 //! This module contains the `Core` type which is the reactor for all I/O
 //! happening in `tokio-core`. This reactor (or event loop) is used to run
 //! futures, schedule tasks, issue I/O requests, etc.
+//!
+//! The reactor is readiness based: it's built on `mio`, and so on `epoll`,
+//! `kqueue` or IOCP depending on the platform, and every I/O object is
+//! registered with it through `PollEvented` to learn when a nonblocking
+//! system call is worth retrying.
 
 use std::cell::RefCell;
 use std::io::{self, ErrorKind};
@@ -18,6 +23,7 @@ use mio;
 use slab::Slab;
 
 use heap::{Heap, Slot};
+use io::BufferPool;
 
 mod channel;
 mod io_token;
@@ -26,8 +32,14 @@ use self::channel::{Sender, Receiver, channel};
 
 mod poll_evented;
 mod timeout;
-pub use self::poll_evented::PollEvented;
+#[cfg(target_os = "linux")]
+mod eventfd;
+pub use self::poll_evented::{PollEvented, Detached, ReadinessStream};
 pub use self::timeout::Timeout;
+#[cfg(target_os = "linux")]
+pub use self::eventfd::{EventFd, Notifier};
+#[cfg(unix)]
+pub use fd::EventedFd;
 
 static NEXT_LOOP_ID: AtomicUsize = ATOMIC_USIZE_INIT;
 scoped_thread_local!(static CURRENT_LOOP: Core);
@@ -46,6 +58,7 @@ pub struct Core {
     tx: Sender<Message>,
     rx: Receiver<Message>,
     inner: Rc<RefCell<Inner>>,
+    buffers: BufferPool,
 
     // Used for determining when the future passed to `run` is ready. Once the
     // registration is passed to `io` above we never touch it again, just keep
@@ -89,6 +102,7 @@ pub struct Remote {
 pub struct Handle {
     remote: Remote,
     inner: Weak<RefCell<Inner>>,
+    buffers: BufferPool,
 }
 
 struct ScheduledIo {
@@ -146,6 +160,7 @@ impl Core {
             rx: rx,
             _future_registration: future_pair.0,
             future_readiness: Arc::new(MySetReadiness(future_pair.1)),
+            buffers: BufferPool::new(),
 
             inner: Rc::new(RefCell::new(Inner {
                 id: NEXT_LOOP_ID.fetch_add(1, Ordering::Relaxed),
@@ -168,6 +183,7 @@ impl Core {
         Handle {
             remote: self.remote(),
             inner: Rc::downgrade(&self.inner),
+            buffers: self.buffers.clone(),
         }
     }
 
@@ -303,14 +319,26 @@ impl Core {
         let mut writer = None;
         let mut inner = self.inner.borrow_mut();
         if let Some(io) = inner.io_dispatch.get_mut(token) {
-            if ready.is_readable() {
+            // A hang up or error isn't necessarily accompanied by readable or
+            // writable readiness (notably for pipes), but any I/O attempted
+            // will no longer block so both directions are woken up.
+            //
+            // The task waiting to read is also the one waiting in
+            // `PollEvented::poll_ready` for any readiness other than write
+            // readiness, such as the priority readiness of Unix, so it's
+            // woken up for all of it, and all of it is recorded.
+            let hup = is_hup_or_error(&ready);
+            let mut bits = ready.as_usize();
+            if hup {
+                bits |= 1 | 2;
+            }
+            if !(ready - mio::Ready::writable()).is_empty() {
                 reader = io.reader.take();
-                io.readiness.fetch_or(1, Ordering::Relaxed);
             }
-            if ready.is_writable() {
+            if ready.is_writable() || hup {
                 writer = io.writer.take();
-                io.readiness.fetch_or(2, Ordering::Relaxed);
             }
+            io.readiness.fetch_or(bits, Ordering::Relaxed);
         }
         drop(inner);
         // TODO: don't notify the same task twice
@@ -408,8 +436,30 @@ impl Core {
     }
 }
 
+/// Returns the token the I/O source at `index` in the dispatch slab is
+/// registered under.
+fn source_token(index: usize) -> mio::Token {
+    mio::Token(TOKEN_START + index * 2)
+}
+
+#[cfg(unix)]
+fn is_hup_or_error(ready: &mio::Ready) -> bool {
+    use mio::unix::UnixReady;
+
+    let ready = UnixReady::from(*ready);
+    ready.is_hup() || ready.is_error()
+}
+
+#[cfg(windows)]
+fn is_hup_or_error(_ready: &mio::Ready) -> bool {
+    false
+}
+
 impl Inner {
-    fn add_source(&mut self, source: &mio::Evented)
+    fn add_source(&mut self,
+                  source: &mio::Evented,
+                  interest: mio::Ready,
+                  opts: mio::PollOpt)
                   -> io::Result<(Arc<AtomicUsize>, usize)> {
         debug!("adding a new I/O source");
         let sched = ScheduledIo {
@@ -423,12 +473,29 @@ impl Inner {
         }
         let entry = self.io_dispatch.vacant_entry().unwrap();
         try!(self.io.register(source,
-                              mio::Token(TOKEN_START + entry.index() * 2),
-                              mio::Ready::readable() | mio::Ready::writable(),
-                              mio::PollOpt::edge()));
+                              source_token(entry.index()),
+                              interest,
+                              opts));
         Ok((sched.readiness.clone(), entry.insert(sched).index()))
     }
 
+    fn reregister_source(&mut self,
+                         source: &mio::Evented,
+                         token: usize,
+                         interest: mio::Ready,
+                         opts: mio::PollOpt) -> io::Result<()> {
+        debug!("reregistering I/O source: {}", token);
+        self.io.reregister(source,
+                           source_token(token),
+                           interest,
+                           opts)
+    }
+
+    fn deregister_source(&mut self, source: &mio::Evented) -> io::Result<()> {
+        debug!("deregistering an I/O source");
+        self.io.deregister(source)
+    }
+
     fn drop_source(&mut self, token: usize) {
         debug!("dropping I/O source: {}", token);
         self.io_dispatch.remove(token).unwrap();
@@ -438,11 +505,11 @@ impl Inner {
                 -> Option<Task> {
         debug!("scheduling direction for: {}", token);
         let sched = self.io_dispatch.get_mut(token).unwrap();
-        let (slot, bit) = match dir {
-            Direction::Read => (&mut sched.reader, 1),
+        let (slot, bits) = match dir {
+            Direction::Read => (&mut sched.reader, !2),
             Direction::Write => (&mut sched.writer, 2),
         };
-        if sched.readiness.load(Ordering::SeqCst) & bit != 0 {
+        if sched.readiness.load(Ordering::SeqCst) & bits != 0 {
             *slot = None;
             Some(wake)
         } else {
@@ -576,6 +643,14 @@ impl Handle {
         };
         inner.borrow_mut().spawn(Box::new(f));
     }
+
+    /// Returns the pool of byte buffers of this event loop.
+    ///
+    /// The I/O objects and futures of the event loop can take their buffers
+    /// from this pool, without locking, instead of allocating them.
+    pub fn buffer_pool(&self) -> &BufferPool {
+        &self.buffers
+    }
 }
 
 impl TimeoutState {
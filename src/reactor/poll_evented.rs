@@ -7,12 +7,18 @@
 //! underlying I/O primitive.
 
 use std::io::{self, Read, Write};
+use std::mem;
+#[cfg(unix)]
+use std::os::unix::io::AsRawFd;
+use std::ptr;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
-use futures::Async;
+use futures::{Async, Poll, Stream};
 use mio;
 
-use io::Io;
+#[cfg(unix)]
+use fd::EventedFd;
+use io::{AsyncRead, AsyncWrite};
 use reactor::{Handle, Remote};
 use reactor::io_token::IoToken;
 
@@ -28,6 +34,16 @@ use reactor::io_token::IoToken;
 /// readable/writable, then it will continue to do so until the `need_read` or
 /// `need_write` methods are called.
 ///
+/// By default the source is registered for both read and write readiness.
+/// `PollEvented::with_interest` registers it for less, and the registration
+/// can be changed later on with `set_interest`, so for example a server can
+/// leave its idle connections registered only for read readiness and add
+/// write readiness while it has data queued for them.
+///
+/// Sources are registered edge-triggered unless created with
+/// `PollEvented::with_options`, which can register them level-triggered
+/// instead.
+///
 /// That is, this object is typically wrapped in another form of I/O object.
 /// It's the responsibility of the wrapper to inform the readiness stream when a
 /// "would block" I/O event is seen. The readiness stream will then take care of
@@ -36,6 +52,8 @@ pub struct PollEvented<E> {
     token: IoToken,
     handle: Remote,
     readiness: AtomicUsize,
+    interest: AtomicUsize,
+    opts: mio::PollOpt,
     io: E,
 }
 
@@ -46,13 +64,191 @@ impl<E: mio::Evented> PollEvented<E> {
     /// This method returns a future which will resolve to the readiness stream
     /// when it's ready.
     pub fn new(io: E, handle: &Handle) -> io::Result<PollEvented<E>> {
+        PollEvented::with_interest(io, handle,
+                                   mio::Ready::readable() | mio::Ready::writable())
+    }
+
+    /// Creates a new readiness stream for `io` which is only registered for
+    /// write readiness.
+    ///
+    /// This is for sources which are only ever written to, such as the
+    /// writing end of a pipe, sparing the event loop the read readiness of
+    /// the source, which nothing waits for. It's the same as `with_interest`
+    /// with write readiness only. Hang ups and errors are still reported,
+    /// so a write to a pipe whose reading end is closed fails rather than
+    /// waiting forever.
+    pub fn new_write_only(io: E, handle: &Handle) -> io::Result<PollEvented<E>> {
+        PollEvented::with_interest(io, handle, mio::Ready::writable())
+    }
+
+    /// Creates a new readiness stream for `io` which is only registered for
+    /// the readiness in `interest`.
+    ///
+    /// The event loop doesn't watch for the readiness left out, so a task
+    /// blocked in `poll_read` or `poll_write` on a source not registered for
+    /// the corresponding readiness only wakes up once it's added with
+    /// `set_interest`, or on a hang up or an error.
+    pub fn with_interest(io: E, handle: &Handle, interest: mio::Ready)
+                         -> io::Result<PollEvented<E>> {
+        PollEvented::with_options(io, handle, interest, mio::PollOpt::edge())
+    }
+
+    /// Creates a new readiness stream for `io` which is registered for the
+    /// readiness in `interest` with the options `opts`, edge-triggered or
+    /// level-triggered.
+    ///
+    /// An edge-triggered source is only reported when it becomes ready, so
+    /// `need_read` and `need_write` must only be called once a "would block"
+    /// error has been seen, or the task could wait for readiness the source
+    /// already has. This is what the I/O objects of this crate do.
+    ///
+    /// A level-triggered source is reported for as long as it's ready, so
+    /// `need_read` and `need_write` can be called at any time, for example
+    /// after reading only part of the data available, and the task is woken
+    /// up again if the source is still ready. The event loop is woken up on
+    /// every turn while the source is ready, whether or not a task is waiting
+    /// for it, so level-triggered sources are best registered only for the
+    /// readiness waited for at the time, changing it with `set_interest`.
+    ///
+    /// Either can be combined with `PollOpt::oneshot`, in which case the
+    /// source is disabled after each readiness notification, and nothing
+    /// more is reported for it until it's armed again with `rearm`. This is
+    /// for when a ready source is handed off to be dealt with elsewhere, its
+    /// readiness not being delivered again in the meantime.
+    pub fn with_options(io: E,
+                        handle: &Handle,
+                        interest: mio::Ready,
+                        opts: mio::PollOpt) -> io::Result<PollEvented<E>> {
         Ok(PollEvented {
-            token: try!(IoToken::new(&io, handle)),
+            token: try!(IoToken::new(&io, interest, opts, handle)),
             handle: handle.remote().clone(),
             readiness: AtomicUsize::new(0),
+            interest: AtomicUsize::new(interest.as_usize()),
+            opts: opts,
             io: io,
         })
     }
+
+    /// Changes the readiness this source is registered for with the event
+    /// loop `handle` points to.
+    ///
+    /// Readiness which is added is reported as soon as the I/O object has
+    /// it, even if it already did before this call. As with `deregister`,
+    /// an error is returned if `handle` points to another event loop than
+    /// the one this source was created on.
+    pub fn set_interest(&self, handle: &Handle, interest: mio::Ready) -> io::Result<()> {
+        try!(self.check_handle(handle));
+        try!(self.token.reregister(&self.io, interest, self.opts, handle));
+        self.interest.store(interest.as_usize(), Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Removes the underlying I/O object from the set of sources polled by
+    /// the event loop `handle` points to.
+    ///
+    /// No further readiness notifications are delivered for this source once
+    /// this returns, which allows the I/O object to be handed off elsewhere
+    /// without the event loop continuing to track it. Tasks waiting for the
+    /// object to become ready aren't woken up anymore.
+    ///
+    /// The event loop has to be reached through a `Handle`, so this is called
+    /// on the thread running it. An error is returned if `handle` points to
+    /// another event loop than the one this source was created on.
+    pub fn deregister(&self, handle: &Handle) -> io::Result<()> {
+        try!(self.check_handle(handle));
+        match handle.inner.upgrade() {
+            Some(inner) => inner.borrow_mut().deregister_source(&self.io),
+            None => Ok(()),
+        }
+    }
+
+    /// Deregisters the underlying I/O object from the event loop `handle`
+    /// points to, and returns it.
+    ///
+    /// The object can then be registered with another event loop by creating
+    /// a new `PollEvented` for it, or `detach` used instead to register it
+    /// the same way again, or, once put back in blocking mode, used with
+    /// blocking I/O. As with `deregister`, an error is returned if
+    /// `handle` points to another event loop than the one this source was
+    /// created on, in which case the object is dropped.
+    pub fn into_inner(self, handle: &Handle) -> io::Result<E> {
+        try!(self.deregister(handle));
+        self.token.drop_source(&self.handle);
+        // The source has been dropped from the event loop already, so the
+        // fields are moved out without running our destructor.
+        unsafe {
+            let token = ptr::read(&self.token);
+            let remote = ptr::read(&self.handle);
+            let io = ptr::read(&self.io);
+            mem::forget(self);
+            drop((token, remote));
+            Ok(io)
+        }
+    }
+
+    /// Arms this source again after a readiness notification, when it's
+    /// registered with `PollOpt::oneshot`.
+    ///
+    /// The source is registered again for the same readiness, which is then
+    /// reported as soon as the I/O object has it, including readiness it had
+    /// while disabled. Arming a source not registered with
+    /// `PollOpt::oneshot` has no effect. As with `deregister`, an error is
+    /// returned if `handle` points to another event loop than the one this
+    /// source was created on.
+    pub fn rearm(&self, handle: &Handle) -> io::Result<()> {
+        try!(self.check_handle(handle));
+        self.token.reregister(&self.io, self.interest(), self.opts, handle)
+    }
+
+    /// Deregisters this source from the event loop `handle` points to,
+    /// keeping what's needed to register it with another one.
+    ///
+    /// This is `into_inner` keeping the readiness the source is registered
+    /// for and its options, so that `Detached::attach` registers the source
+    /// with the other event loop the same way. The `Detached` source can be
+    /// sent to the thread running the other event loop if the I/O object is
+    /// `Send`, which allows connections to be rebalanced between the event
+    /// loops of several threads. Readiness the I/O object has is reported
+    /// again once it's attached, so none is lost in the move.
+    ///
+    /// As with `deregister`, an error is returned if `handle` points to
+    /// another event loop than the one this source was created on, in which
+    /// case the object is dropped.
+    pub fn detach(self, handle: &Handle) -> io::Result<Detached<E>> {
+        let interest = self.interest();
+        let opts = self.opts;
+        let io = try!(self.into_inner(handle));
+        Ok(Detached {
+            io: io,
+            interest: interest,
+            opts: opts,
+        })
+    }
+
+    fn check_handle(&self, handle: &Handle) -> io::Result<()> {
+        if handle.remote().id != self.handle.id {
+            return Err(io::Error::new(io::ErrorKind::Other,
+                                      "source registered with another event loop"))
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+impl<T: AsRawFd> PollEvented<EventedFd<T>> {
+    /// Creates a new readiness stream for the file descriptor of `io`,
+    /// wrapping it in an `EventedFd`.
+    ///
+    /// This registers any object backed by a file descriptor, such as a
+    /// descriptor created by a C library or a device node opened as a `File`,
+    /// without an implementation of `mio::Evented` for it. The object is
+    /// owned by the returned `PollEvented`, so its descriptor stays open for
+    /// as long as it's registered, and is reached through `get_ref` and
+    /// `get_mut` as with `EventedFd`. The descriptor is expected to be in
+    /// nonblocking mode already.
+    pub fn from_fd(io: T, handle: &Handle) -> io::Result<PollEvented<EventedFd<T>>> {
+        PollEvented::new(EventedFd::new(io), handle)
+    }
 }
 
 impl<E> PollEvented<E> {
@@ -96,6 +292,50 @@ impl<E> PollEvented<E> {
         }
     }
 
+    /// Tests to see if this source has any of the readiness in `mask`,
+    /// returning the part of it which it has.
+    ///
+    /// Any readiness `mio` reports can be waited for, such as the priority
+    /// readiness of Unix, `UnixReady::priority`, for out-of-band data and
+    /// other exceptional conditions, provided the source has been registered
+    /// for it with `with_interest` or `set_interest`.
+    ///
+    /// This includes the hang up and error readiness of Unix, `UnixReady::hup`
+    /// and `UnixReady::error`, which are always polled for whatever the
+    /// source is registered for. A hang up is
+    /// seen when the other end of a pipe is closed or a connection is reset,
+    /// so it can be waited for on an idle connection without attempting a
+    /// read, whereas a peer merely shutting down its writing half makes a
+    /// socket readable, reads then returning EOF. Once seen, readiness is
+    /// reported until it's cleared with `clear_read_ready` or
+    /// `clear_write_ready`.
+    ///
+    /// If none of `mask` is ready then `NotReady` is returned and the current
+    /// task is scheduled to receive a notification when the source is ready,
+    /// as with `poll_read` and `poll_write`, whose notifications are shared
+    /// with this method.
+    pub fn poll_ready(&self, mask: mio::Ready) -> Async<mio::Ready> {
+        let bits = mask.as_usize();
+        let ready = self.readiness.load(Ordering::SeqCst) & bits;
+        if ready != 0 {
+            return Async::Ready(mio::Ready::from_usize(ready))
+        }
+        self.readiness.fetch_or(self.token.take_readiness(), Ordering::SeqCst);
+        let ready = self.readiness.load(Ordering::SeqCst) & bits;
+        if ready != 0 {
+            return Async::Ready(mio::Ready::from_usize(ready))
+        }
+        // The task waiting to read is woken up for all the readiness but
+        // write readiness, and both tasks for a hang up or an error.
+        if mask.is_writable() {
+            self.token.schedule_write(&self.handle);
+        }
+        if !(mask - mio::Ready::writable()).is_empty() {
+            self.token.schedule_read(&self.handle);
+        }
+        Async::NotReady
+    }
+
     /// Indicates to this source of events that the corresponding I/O object is
     /// no longer readable, but it needs to be.
     ///
@@ -106,10 +346,9 @@ impl<E> PollEvented<E> {
     ///
     /// The flag indicating that this stream is readable is unset and the
     /// current task is scheduled to receive a notification when the stream is
-    /// then again readable.
+    /// then again readable. This is `clear_read_ready` for read readiness.
     pub fn need_read(&self) {
-        self.readiness.fetch_and(!1, Ordering::SeqCst);
-        self.token.schedule_read(&self.handle)
+        self.clear_read_ready(mio::Ready::readable())
     }
 
     /// Indicates to this source of events that the corresponding I/O object is
@@ -122,12 +361,89 @@ impl<E> PollEvented<E> {
     ///
     /// The flag indicating that this stream is writable is unset and the
     /// current task is scheduled to receive a notification when the stream is
-    /// then again writable.
+    /// then again writable. This is the same as `clear_write_ready`.
     pub fn need_write(&self) {
+        self.clear_write_ready()
+    }
+
+    /// Clears the readiness in `mask`, which the I/O object no longer has,
+    /// scheduling the current task to receive a notification when the source
+    /// is ready to be read from again.
+    ///
+    /// This generalizes `need_read` to all the readiness but write readiness,
+    /// so that a wrapper which has handled a part of the readiness reported
+    /// by `poll_ready`, say a hang up, waits for that part only to be
+    /// reported again, leaving the rest, say read readiness, set. The rest of
+    /// the readiness reported with a hang up can be cleared by later calls.
+    ///
+    /// Like `need_read`, this is only safe to call from the context of a
+    /// future's task.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mask` contains write readiness, which is cleared with
+    /// `clear_write_ready` instead.
+    pub fn clear_read_ready(&self, mask: mio::Ready) {
+        assert!(!mask.is_writable(), "cannot clear write readiness");
+        self.readiness.fetch_and(!mask.as_usize(), Ordering::SeqCst);
+        self.token.schedule_read(&self.handle)
+    }
+
+    /// Clears the write readiness of this source, scheduling the current task
+    /// to receive a notification when the source is writable again.
+    ///
+    /// This is the write side counterpart of `clear_read_ready`, and the same
+    /// as `need_write`.
+    pub fn clear_write_ready(&self) {
         self.readiness.fetch_and(!2, Ordering::SeqCst);
         self.token.schedule_write(&self.handle)
     }
 
+    /// Returns a stream of the readiness in `mask` this source is reported
+    /// to have.
+    ///
+    /// Each item is the part of `mask` which is ready, as returned by
+    /// `poll_ready`. The readiness yielded is taken to have been handled by
+    /// the time the stream is polled again, and is cleared, so the next item
+    /// is the readiness reported after that. The source being edge-triggered
+    /// by default, a readable item has to be followed by reads until one
+    /// would block, and a writable item by writes, for another one to be
+    /// yielded.
+    ///
+    /// This allows a source to be driven with the stream combinators, such
+    /// as `for_each`, rather than with a future of its own. The stream never
+    /// ends, nor yields an error.
+    pub fn readiness_stream<'a>(&'a self, mask: mio::Ready) -> ReadinessStream<'a, E> {
+        ReadinessStream {
+            io: self,
+            mask: mask,
+            last: mio::Ready::empty(),
+        }
+    }
+
+    /// Returns the readiness this source is registered for.
+    pub fn interest(&self) -> mio::Ready {
+        mio::Ready::from_usize(self.interest.load(Ordering::SeqCst))
+    }
+
+    /// Returns the options this source is registered with.
+    pub fn opts(&self) -> mio::PollOpt {
+        self.opts
+    }
+
+    /// Returns the token this source is registered with the event loop
+    /// under.
+    ///
+    /// This is the token `mio` reports the readiness of the source with, and
+    /// on Linux the data of the events `epoll_wait` returns for it, so the
+    /// events seen by tracing tools such as `strace` can be traced back to
+    /// the I/O objects they are for. Tokens are unique among the sources
+    /// registered with an event loop, but the token of a source is reused
+    /// once it's dropped.
+    pub fn token(&self) -> mio::Token {
+        self.token.mio_token()
+    }
+
     /// Returns a reference to the event loop handle that this readiness stream
     /// is associated with.
     pub fn remote(&self) -> &Remote {
@@ -147,6 +463,80 @@ impl<E> PollEvented<E> {
     }
 }
 
+/// An I/O object deregistered from an event loop by `PollEvented::detach`,
+/// along with the readiness it was registered for and its options.
+pub struct Detached<E> {
+    io: E,
+    interest: mio::Ready,
+    opts: mio::PollOpt,
+}
+
+impl<E: mio::Evented> Detached<E> {
+    /// Registers the I/O object with the event loop `handle` points to, for
+    /// the readiness and with the options it was registered with before.
+    ///
+    /// If the object can't be registered the error is returned and the
+    /// object is dropped.
+    pub fn attach(self, handle: &Handle) -> io::Result<PollEvented<E>> {
+        PollEvented::with_options(self.io, handle, self.interest, self.opts)
+    }
+}
+
+impl<E> Detached<E> {
+    /// Returns the readiness the I/O object was registered for.
+    pub fn interest(&self) -> mio::Ready {
+        self.interest
+    }
+
+    /// Returns the options the I/O object was registered with.
+    pub fn opts(&self) -> mio::PollOpt {
+        self.opts
+    }
+
+    /// Returns a reference to the I/O object.
+    pub fn get_ref(&self) -> &E {
+        &self.io
+    }
+
+    /// Returns a mutable reference to the I/O object.
+    pub fn get_mut(&mut self) -> &mut E {
+        &mut self.io
+    }
+
+    /// Consumes this value, returning the I/O object.
+    pub fn into_inner(self) -> E {
+        self.io
+    }
+}
+
+/// A stream of the readiness of a `PollEvented`.
+///
+/// Created by the `PollEvented::readiness_stream` method.
+pub struct ReadinessStream<'a, E: 'a> {
+    io: &'a PollEvented<E>,
+    mask: mio::Ready,
+    last: mio::Ready,
+}
+
+impl<'a, E> Stream for ReadinessStream<'a, E> {
+    type Item = mio::Ready;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<mio::Ready>, io::Error> {
+        // The readiness yielded last has been handled, so it's cleared before
+        // waiting for more, `poll_ready` scheduling the task.
+        let last = mem::replace(&mut self.last, mio::Ready::empty());
+        self.io.readiness.fetch_and(!last.as_usize(), Ordering::SeqCst);
+        match self.io.poll_ready(self.mask) {
+            Async::Ready(ready) => {
+                self.last = ready;
+                Ok(Async::Ready(Some(ready)))
+            }
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
+
 impl<E: Read> Read for PollEvented<E> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         if let Async::NotReady = self.poll_read() {
@@ -172,6 +562,17 @@ impl<E: Write> Write for PollEvented<E> {
         return r
     }
 
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        if let Async::NotReady = self.poll_write() {
+            return Err(mio::would_block())
+        }
+        let r = self.get_mut().write_vectored(bufs);
+        if is_wouldblock(&r) {
+            self.need_write();
+        }
+        return r
+    }
+
     fn flush(&mut self) -> io::Result<()> {
         if let Async::NotReady = self.poll_write() {
             return Err(mio::would_block())
@@ -184,11 +585,13 @@ impl<E: Write> Write for PollEvented<E> {
     }
 }
 
-impl<E: Read + Write> Io for PollEvented<E> {
+impl<E: Read> AsyncRead for PollEvented<E> {
     fn poll_read(&mut self) -> Async<()> {
         <PollEvented<E>>::poll_read(self)
     }
+}
 
+impl<E: Write> AsyncWrite for PollEvented<E> {
     fn poll_write(&mut self) -> Async<()> {
         <PollEvented<E>>::poll_write(self)
     }
@@ -223,6 +626,17 @@ impl<'a, E> Write for &'a PollEvented<E>
         return r
     }
 
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        if let Async::NotReady = self.poll_write() {
+            return Err(mio::would_block())
+        }
+        let r = self.get_ref().write_vectored(bufs);
+        if is_wouldblock(&r) {
+            self.need_write();
+        }
+        return r
+    }
+
     fn flush(&mut self) -> io::Result<()> {
         if let Async::NotReady = self.poll_write() {
             return Err(mio::would_block())
@@ -235,13 +649,17 @@ impl<'a, E> Write for &'a PollEvented<E>
     }
 }
 
-impl<'a, E> Io for &'a PollEvented<E>
-    where &'a E: Read + Write,
+impl<'a, E> AsyncRead for &'a PollEvented<E>
+    where &'a E: Read,
 {
     fn poll_read(&mut self) -> Async<()> {
         <PollEvented<E>>::poll_read(self)
     }
+}
 
+impl<'a, E> AsyncWrite for &'a PollEvented<E>
+    where &'a E: Write,
+{
     fn poll_write(&mut self) -> Async<()> {
         <PollEvented<E>>::poll_write(self)
     }
@@ -0,0 +1,244 @@
+//! Unix signal handling integrated with the event loop.
+//!
+//! This module contains the `Signal` type, a stream of notifications that a
+//! particular signal has been delivered to the process. Signals are received
+//! through a process-wide "self-pipe": the installed signal handler records
+//! which signal arrived and writes a byte to the pipe, the read half of which
+//! is registered with each event loop that has a `Signal` stream. Whichever
+//! stream notices the pipe is readable first dispatches the pending signals
+//! to every `Signal` stream interested in them, regardless of which `Core`
+//! that stream is associated with.
+//!
+//! Once a handler for a signal has been installed it's never removed, so the
+//! default action for that signal (such as terminating the process on
+//! `SIGINT`) no longer happens even after all `Signal` streams for it have
+//! been dropped.
+//!
+//! On Linux the `SignalFd` type is also available, receiving signals through a
+//! `signalfd` instead of a handler, which preserves details such as the
+//! process which sent each signal.
+
+use std::fs::File;
+use std::io::{self, Read};
+use std::mem;
+use std::os::unix::prelude::*;
+use std::ptr;
+use std::sync::{Mutex, Once, ONCE_INIT};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use futures::Poll;
+use futures::stream::Stream;
+use libc::{self, c_int};
+
+use channel::{self, Sender, Receiver};
+use fd::{self, EventedFd};
+use reactor::{Handle, PollEvented};
+
+pub use libc::{SIGALRM, SIGCHLD, SIGHUP, SIGINT, SIGPIPE, SIGQUIT, SIGTERM};
+pub use libc::{SIGUSR1, SIGUSR2, SIGWINCH};
+
+#[cfg(target_os = "linux")]
+mod signalfd;
+#[cfg(target_os = "linux")]
+pub use self::signalfd::{SignalFd, SigInfo};
+
+const MAX_SIGNUM: usize = 64;
+
+struct Globals {
+    read: RawFd,
+    write: RawFd,
+    signals: Vec<SignalInfo>,
+}
+
+struct SignalInfo {
+    pending: AtomicBool,
+    installed: AtomicBool,
+    recipients: Mutex<Vec<Sender<c_int>>>,
+}
+
+static INIT: Once = ONCE_INIT;
+static mut GLOBALS: *const Globals = ptr::null();
+
+fn globals() -> io::Result<&'static Globals> {
+    let mut err = None;
+    INIT.call_once(|| {
+        match Globals::new() {
+            Ok(globals) => unsafe {
+                GLOBALS = Box::into_raw(Box::new(globals));
+            },
+            Err(e) => err = Some(e),
+        }
+    });
+    if let Some(e) = err {
+        return Err(e)
+    }
+    unsafe {
+        if GLOBALS.is_null() {
+            Err(io::Error::new(io::ErrorKind::Other,
+                               "failed to initialize signal handling"))
+        } else {
+            Ok(&*GLOBALS)
+        }
+    }
+}
+
+impl Globals {
+    fn new() -> io::Result<Globals> {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+            return Err(io::Error::last_os_error())
+        }
+        for raw in fds.iter() {
+            try!(fd::set_nonblocking_cloexec(*raw));
+        }
+        Ok(Globals {
+            read: fds[0],
+            write: fds[1],
+            signals: (0..MAX_SIGNUM + 1).map(|_| {
+                SignalInfo {
+                    pending: AtomicBool::new(false),
+                    installed: AtomicBool::new(false),
+                    recipients: Mutex::new(Vec::new()),
+                }
+            }).collect(),
+        })
+    }
+
+    /// Delivers all pending signals to the streams which are interested in
+    /// them, forgetting about streams which have since been dropped.
+    fn broadcast(&self) {
+        for (signum, info) in self.signals.iter().enumerate() {
+            if !info.pending.swap(false, Ordering::SeqCst) {
+                continue
+            }
+            let mut recipients = info.recipients.lock().unwrap();
+            recipients.retain(|tx| tx.send(signum as c_int).is_ok());
+        }
+    }
+}
+
+extern "C" fn handler(signum: c_int) {
+    unsafe {
+        // The `write` below may clobber `errno`, which the code interrupted
+        // by the signal could be just about to look at.
+        let errno = errno_location();
+        let saved = *errno;
+        let globals = &*GLOBALS;
+        globals.signals[signum as usize].pending.store(true, Ordering::SeqCst);
+        let byte = 1u8;
+        libc::write(globals.write, &byte as *const u8 as *const _, 1);
+        *errno = saved;
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "emscripten",
+          target_os = "dragonfly"))]
+unsafe fn errno_location() -> *mut c_int {
+    libc::__errno_location()
+}
+
+#[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+unsafe fn errno_location() -> *mut c_int {
+    libc::__errno()
+}
+
+#[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd"))]
+unsafe fn errno_location() -> *mut c_int {
+    libc::__error()
+}
+
+fn install(signum: c_int) -> io::Result<()> {
+    unsafe {
+        let mut action: libc::sigaction = mem::zeroed();
+        let handler: extern "C" fn(c_int) = handler;
+        action.sa_sigaction = handler as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        if libc::sigaction(signum, &action, ptr::null_mut()) == -1 {
+            return Err(io::Error::last_os_error())
+        }
+    }
+    Ok(())
+}
+
+/// A stream of notifications that a particular signal has been delivered to
+/// this process.
+///
+/// Each item of the stream is the number of the signal which was received.
+/// Note that signals are coalesced, so several deliveries of the signal in
+/// quick succession may only be reported as a single item.
+pub struct Signal {
+    globals: &'static Globals,
+    pipe: PollEvented<EventedFd<File>>,
+    rx: Receiver<c_int>,
+}
+
+impl Signal {
+    /// Creates a new stream of notifications for the signal `signum`,
+    /// associated with the event loop `handle` points to.
+    ///
+    /// The first time a stream is created for a given signal a handler for it
+    /// is installed for the whole process, replacing any previous handler.
+    ///
+    /// An error is returned for signals which can't or shouldn't be handled
+    /// this way, such as `SIGKILL` or `SIGSEGV`.
+    pub fn new(signum: c_int, handle: &Handle) -> io::Result<Signal> {
+        if signum <= 0 || signum as usize > MAX_SIGNUM ||
+           FORBIDDEN.contains(&signum) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                      "invalid or unsupported signal number"))
+        }
+        let globals = try!(globals());
+
+        let raw = unsafe { libc::dup(globals.read) };
+        if raw == -1 {
+            return Err(io::Error::last_os_error())
+        }
+        let file = unsafe { File::from_raw_fd(raw) };
+        try!(fd::set_nonblocking_cloexec(raw));
+        let pipe = try!(PollEvented::new(EventedFd::new(file), handle));
+        let (tx, rx) = try!(channel::channel(handle));
+
+        let info = &globals.signals[signum as usize];
+        let mut recipients = info.recipients.lock().unwrap();
+        if !info.installed.load(Ordering::SeqCst) {
+            try!(install(signum));
+            info.installed.store(true, Ordering::SeqCst);
+        }
+        recipients.push(tx);
+
+        Ok(Signal { globals: globals, pipe: pipe, rx: rx })
+    }
+
+    fn drain(&mut self) -> io::Result<()> {
+        let mut buf = [0; 128];
+        loop {
+            match self.pipe.read(&mut buf) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        self.globals.broadcast();
+        Ok(())
+    }
+}
+
+const FORBIDDEN: &'static [c_int] = &[
+    libc::SIGILL,
+    libc::SIGFPE,
+    libc::SIGKILL,
+    libc::SIGSEGV,
+    libc::SIGSTOP,
+];
+
+impl Stream for Signal {
+    type Item = c_int;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<c_int>, io::Error> {
+        try!(self.drain());
+        self.rx.poll()
+    }
+}
@@ -0,0 +1,226 @@
+//! Linux signal delivery through a `signalfd`.
+
+use std::cell::RefCell;
+use std::io::{self, Read};
+use std::mem;
+use std::os::unix::prelude::*;
+use std::ptr;
+use std::slice;
+use std::thread::{self, ThreadId};
+
+use futures::{Async, Poll};
+use futures::stream::Stream;
+use libc::{self, c_int};
+
+use fd::{cvt, EventedFd, OwnedFd};
+use reactor::{Handle, PollEvented};
+
+use super::FORBIDDEN;
+
+/// A stream of signals received through a Linux `signalfd`, along with the
+/// details the kernel records about each of them.
+///
+/// Unlike `Signal`, no signal handler is involved: the signals are blocked
+/// instead, so they stay pending until read from the descriptor registered
+/// with the event loop. This preserves information such as the process which
+/// sent the signal, and signals aren't coalesced with other streams.
+///
+/// Creating a `SignalFd` blocks its signals in the calling thread only. A
+/// signal directed at the whole process is delivered to any thread which
+/// doesn't block it, which then handles it as usual rather than through the
+/// `signalfd`, so the signals should also be blocked in every other thread.
+/// This is most easily done by creating the `SignalFd` on the main thread
+/// before any other threads are spawned, as new threads inherit the signal
+/// mask of the thread creating them.
+///
+/// Once the last `SignalFd` of a thread for a signal is dropped, on the thread
+/// it was created on, the signal is unblocked again in that thread, unless it
+/// was already blocked when the first of them was created. Meanwhile a
+/// `Signal` stream for the same signal only sees the deliveries to other
+/// threads.
+pub struct SignalFd {
+    io: PollEvented<EventedFd<OwnedFd>>,
+    // The signals this stream holds blocked, and the thread they're blocked
+    // in.
+    blocked: Vec<c_int>,
+    thread: ThreadId,
+}
+
+thread_local! {
+    // How many `SignalFd` streams of this thread hold each signal blocked.
+    static BLOCKED: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) }
+}
+
+/// Information about a signal received by a `SignalFd`.
+#[derive(Clone, Copy, Debug)]
+pub struct SigInfo {
+    signo: c_int,
+    code: i32,
+    pid: u32,
+    uid: u32,
+    status: i32,
+}
+
+impl SignalFd {
+    /// Creates a new stream of the signals in `signals`, associated with the
+    /// event loop `handle` points to.
+    ///
+    /// An error is returned for signals which can't or shouldn't be handled
+    /// this way, such as `SIGKILL` or `SIGSEGV`.
+    pub fn new(signals: &[c_int], handle: &Handle) -> io::Result<SignalFd> {
+        let mut mask: libc::sigset_t = unsafe { mem::zeroed() };
+        unsafe {
+            libc::sigemptyset(&mut mask);
+        }
+        for &signum in signals {
+            if FORBIDDEN.contains(&signum) ||
+               unsafe { libc::sigaddset(&mut mask, signum) } == -1 {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          "invalid or unsupported signal number"))
+            }
+        }
+
+        let raw = try!(cvt(unsafe {
+            libc::signalfd(-1, &mask, libc::SFD_NONBLOCK | libc::SFD_CLOEXEC)
+        }));
+        let fd = unsafe { OwnedFd::new(raw) };
+        let io = try!(PollEvented::new(EventedFd::new(fd), handle));
+
+        let mut old: libc::sigset_t = unsafe { mem::zeroed() };
+        let rc = unsafe {
+            libc::pthread_sigmask(libc::SIG_BLOCK, &mask, &mut old)
+        };
+        if rc != 0 {
+            return Err(io::Error::from_raw_os_error(rc))
+        }
+        let blocked = BLOCKED.with(|counts| {
+            let mut counts = counts.borrow_mut();
+            let mut blocked = Vec::new();
+            for &signum in signals {
+                let i = signum as usize;
+                if counts.len() <= i {
+                    counts.resize(i + 1, 0);
+                }
+                // Signals blocked by someone else are left to them.
+                if counts[i] > 0 || unsafe { libc::sigismember(&old, signum) } == 0 {
+                    counts[i] += 1;
+                    blocked.push(signum);
+                }
+            }
+            blocked
+        });
+        Ok(SignalFd {
+            io: io,
+            blocked: blocked,
+            thread: thread::current().id(),
+        })
+    }
+}
+
+impl Drop for SignalFd {
+    fn drop(&mut self) {
+        if self.thread != thread::current().id() {
+            debug!("SignalFd dropped on another thread, signals stay blocked");
+            return
+        }
+        let mut mask: libc::sigset_t = unsafe { mem::zeroed() };
+        unsafe {
+            libc::sigemptyset(&mut mask);
+        }
+        let mut unblock = false;
+        BLOCKED.with(|counts| {
+            let mut counts = counts.borrow_mut();
+            for &signum in &self.blocked {
+                counts[signum as usize] -= 1;
+                if counts[signum as usize] == 0 {
+                    unsafe {
+                        libc::sigaddset(&mut mask, signum);
+                    }
+                    unblock = true;
+                }
+            }
+        });
+        if !unblock {
+            return
+        }
+
+        // Signals still pending would otherwise get their default action
+        // as soon as they're unblocked. Only those being unblocked are read,
+        // the others being left to the streams still holding them.
+        let fd = self.io.get_ref().as_raw_fd();
+        unsafe {
+            libc::signalfd(fd, &mask, 0);
+        }
+        let mut info: libc::signalfd_siginfo = unsafe { mem::zeroed() };
+        let len = mem::size_of_val(&info);
+        while unsafe { libc::read(fd, &mut info as *mut _ as *mut _, len) } > 0 {}
+
+        unsafe {
+            libc::pthread_sigmask(libc::SIG_UNBLOCK, &mask, ptr::null_mut());
+        }
+    }
+}
+
+impl Stream for SignalFd {
+    type Item = SigInfo;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Option<SigInfo>, io::Error> {
+        let mut info: libc::signalfd_siginfo = unsafe { mem::zeroed() };
+        let n = {
+            let buf = unsafe {
+                slice::from_raw_parts_mut(&mut info as *mut _ as *mut u8,
+                                          mem::size_of_val(&info))
+            };
+            try_nb!(self.io.read(buf))
+        };
+        if n != mem::size_of_val(&info) {
+            return Err(io::Error::new(io::ErrorKind::Other,
+                                      "short read from signalfd"))
+        }
+        Ok(Async::Ready(Some(SigInfo {
+            signo: info.ssi_signo as c_int,
+            code: info.ssi_code,
+            pid: info.ssi_pid,
+            uid: info.ssi_uid,
+            status: info.ssi_status,
+        })))
+    }
+}
+
+impl AsRawFd for SignalFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.io.get_ref().as_raw_fd()
+    }
+}
+
+impl SigInfo {
+    /// Returns the number of the signal.
+    pub fn signo(&self) -> c_int {
+        self.signo
+    }
+
+    /// Returns the `si_code` of the signal, describing why it was sent, such
+    /// as `SI_USER` for one sent with `kill` or `CLD_EXITED` for a `SIGCHLD`
+    /// generated by a child exiting.
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    /// Returns the ID of the process which sent the signal, or of the child
+    /// whose status changed for `SIGCHLD`.
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    /// Returns the real user ID of the process which sent the signal.
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Returns the exit status, or the number of the terminating signal, of
+    /// the child for `SIGCHLD`.
+    pub fn status(&self) -> i32 {
+        self.status
+    }
+}
@@ -0,0 +1,114 @@
+extern crate env_logger;
+#[macro_use]
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::{self, Cursor, Read, Write};
+use std::net;
+use std::thread;
+
+use futures::Future;
+use futures::future::poll_fn;
+use tokio_core::io::{flush, read_until, write_all, BufReader, BufWriter};
+use tokio_core::net::TcpStream;
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+/// Counts the calls made to an underlying reader or writer.
+struct Counted<T> {
+    inner: T,
+    calls: usize,
+}
+
+impl<T: Read> Read for Counted<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.calls += 1;
+        self.inner.read(buf)
+    }
+}
+
+impl<T: Write> Write for Counted<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.calls += 1;
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[test]
+fn reader_batches_reads() {
+    let inner = Counted { inner: Cursor::new(b"one\ntwo\n".to_vec()), calls: 0 };
+    let reader = BufReader::new(inner);
+    let (reader, one) = t!(read_until(reader, [b'\n'], Vec::new()).wait());
+    let (reader, two) = t!(read_until(reader, [b'\n'], Vec::new()).wait());
+    assert_eq!(one, b"one\n");
+    assert_eq!(two, b"two\n");
+    assert_eq!(reader.get_ref().calls, 1);
+}
+
+#[test]
+fn writer_batches_writes() {
+    let inner = Counted { inner: Vec::new(), calls: 0 };
+    let mut writer = BufWriter::with_capacity(16, inner);
+    for _ in 0..5 {
+        t!(writer.write_all(b"abc"));
+    }
+    assert_eq!(writer.get_ref().calls, 0);
+    assert_eq!(writer.buffer(), b"abcabcabcabcabc");
+
+    // Overflowing the buffer writes it out, and large writes bypass it.
+    t!(writer.write_all(b"de"));
+    assert_eq!(writer.get_ref().calls, 1);
+    t!(writer.write_all(&[b'f'; 32]));
+    assert_eq!(writer.get_ref().calls, 3);
+
+    let writer = t!(flush(writer).wait());
+    assert_eq!(writer.get_ref().calls, 3);
+    assert_eq!(writer.buffer(), b"");
+    assert_eq!(writer.get_ref().inner.len(), 15 + 2 + 32);
+}
+
+#[test]
+fn socket() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let (mut s, _) = t!(srv.accept());
+        let mut buf = [0; 6];
+        t!(s.read_exact(&mut buf));
+        assert_eq!(&buf, b"ping\r\n");
+        t!(s.write_all(b"pong\r\n"));
+    });
+
+    let stream = TcpStream::connect(&addr, &l.handle());
+    let pong = stream.and_then(|s| {
+        write_all(BufWriter::new(s), b"ping\r\n")
+    }).and_then(|(s, _)| {
+        flush(s)
+    }).and_then(|s| {
+        let mut reader = BufReader::new(s.into_inner());
+        poll_fn(move || {
+            let n = {
+                let buf = try_ready!(reader.poll_fill_buf());
+                assert!(!buf.is_empty());
+                buf.len()
+            };
+            reader.consume(n);
+            Ok::<_, io::Error>(n.into())
+        })
+    });
+    let n = t!(l.run(pong));
+    assert!(n > 0 && n <= 6);
+    t.join().unwrap();
+}
@@ -0,0 +1,81 @@
+extern crate futures;
+extern crate tokio_core;
+
+use futures::{Async, AsyncSink, Poll, Sink, StartSend};
+use tokio_core::io::BufferedSink;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+/// A sink accepting items only while it has room, and completing only once
+/// flushed is set.
+struct Slow {
+    items: Vec<u32>,
+    room: usize,
+    flushed: bool,
+}
+
+impl Sink for Slow {
+    type SinkItem = u32;
+    type SinkError = ();
+
+    fn start_send(&mut self, item: u32) -> StartSend<u32, ()> {
+        if self.room == 0 {
+            return Ok(AsyncSink::NotReady(item))
+        }
+        self.room -= 1;
+        self.items.push(item);
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), ()> {
+        if self.flushed {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+#[test]
+fn backpressure() {
+    let slow = Slow { items: Vec::new(), room: 1, flushed: false };
+    let mut sink = BufferedSink::new(slow, 2);
+
+    // Items are queued up to the capacity of the sink.
+    assert_eq!(t!(sink.start_send(1)), AsyncSink::Ready);
+    assert_eq!(t!(sink.start_send(2)), AsyncSink::Ready);
+    assert_eq!(sink.queued(), 2);
+
+    // Once it's full room is made by sending items on to the inner sink,
+    // which only takes one.
+    assert_eq!(t!(sink.start_send(3)), AsyncSink::Ready);
+    assert_eq!(sink.get_ref().items, [1]);
+    assert_eq!(t!(sink.start_send(4)), AsyncSink::NotReady(4));
+    assert_eq!(sink.queued(), 2);
+
+    assert_eq!(t!(sink.poll_complete()), Async::NotReady);
+    sink.get_mut().room = 10;
+    assert_eq!(t!(sink.poll_complete()), Async::NotReady);
+    assert_eq!(sink.get_ref().items, [1, 2, 3]);
+    assert_eq!(sink.queued(), 0);
+    sink.get_mut().flushed = true;
+    assert_eq!(t!(sink.poll_complete()), Async::Ready(()));
+}
+
+#[test]
+fn into_inner() {
+    let slow = Slow { items: Vec::new(), room: 0, flushed: true };
+    let mut sink = BufferedSink::new(slow, 3);
+    assert_eq!(t!(sink.start_send(1)), AsyncSink::Ready);
+    assert_eq!(t!(sink.start_send(2)), AsyncSink::Ready);
+    assert_eq!(t!(sink.close()), Async::NotReady);
+
+    let (slow, queue) = sink.into_inner();
+    assert!(slow.items.is_empty());
+    assert_eq!(queue.into_iter().collect::<Vec<_>>(), [1, 2]);
+}
@@ -0,0 +1,69 @@
+#![cfg(target_os = "linux")]
+
+extern crate futures;
+#[macro_use]
+extern crate tokio_core;
+
+use std::io;
+
+use tokio_core::net::{CanFilter, CanFrame, CanSocket};
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn frames() {
+    let frame = t!(CanFrame::new(0x123, b"data"));
+    assert_eq!(frame.id(), 0x123);
+    assert!(!frame.is_extended());
+    assert!(!frame.is_remote());
+    assert_eq!(frame.data(), b"data");
+
+    let frame = t!(CanFrame::new(0x12345, b""));
+    assert_eq!(frame.id(), 0x12345);
+    assert!(frame.is_extended());
+
+    let frame = t!(CanFrame::new_remote(0x7ff, 4));
+    assert!(frame.is_remote());
+    assert_eq!(frame.data(), b"");
+
+    assert!(CanFrame::new(0x123, &[0; 9]).is_err());
+    assert!(CanFrame::new(0x2000_0000, b"").is_err());
+}
+
+#[test]
+fn missing_interface() {
+    let l = t!(Core::new());
+    assert!(CanSocket::open("tokio-no-such", &l.handle()).is_err());
+}
+
+#[test]
+fn loopback() {
+    let mut l = t!(Core::new());
+    // Needs a virtual CAN interface, which may not be available.
+    let a = match CanSocket::open("vcan0", &l.handle()) {
+        Ok(socket) => socket,
+        Err(_) => return,
+    };
+    let b = t!(CanSocket::open("vcan0", &l.handle()));
+    t!(b.set_filters(&[CanFilter::new(0x100, 0x700)]));
+
+    let send = futures::future::poll_fn(|| {
+        try_nb!(a.send(&t!(CanFrame::new(0x200, b"ignored"))));
+        try_nb!(a.send(&t!(CanFrame::new(0x123, b"kept"))));
+        Ok::<_, io::Error>(().into())
+    });
+    t!(l.run(send));
+
+    let recv = futures::future::poll_fn(|| {
+        Ok::<_, io::Error>(try_nb!(b.recv()).into())
+    });
+    let frame = t!(l.run(recv));
+    assert_eq!(frame.id(), 0x123);
+    assert_eq!(frame.data(), b"kept");
+}
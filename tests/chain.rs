@@ -3,11 +3,11 @@ extern crate tokio_core;
 
 use std::net::TcpStream;
 use std::thread;
-use std::io::{Write, Read};
+use std::io::{Cursor, Write, Read};
 
 use futures::Future;
 use futures::stream::Stream;
-use tokio_core::io::read_to_end;
+use tokio_core::io::{chain, read_exact, read_to_end, take};
 use tokio_core::net::TcpListener;
 use tokio_core::reactor::Core;
 
@@ -48,3 +48,54 @@ fn chain_clients() {
 
     assert_eq!(data, b"foo bar baz");
 }
+
+#[test]
+fn chain_prefix() {
+    let mut l = t!(Core::new());
+    let srv = t!(TcpListener::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let addr = t!(srv.local_addr());
+
+    let t = thread::spawn(move || {
+        let mut s = TcpStream::connect(&addr).unwrap();
+        s.write_all(b"foo bar baz").unwrap();
+    });
+
+    let copied = srv.incoming().into_future().map_err(|e| e.0).and_then(|(client, _)| {
+        let (client, _) = client.unwrap();
+        read_exact(client, [0; 4])
+    }).and_then(|(client, peeked)| {
+        assert_eq!(&peeked, b"foo ");
+        read_to_end(chain(Cursor::new(peeked), client), Vec::new())
+    });
+
+    let (_, data) = t!(l.run(copied));
+    t.join().unwrap();
+
+    assert_eq!(data, b"foo bar baz");
+}
+
+#[test]
+fn take_body() {
+    let mut l = t!(Core::new());
+    let srv = t!(TcpListener::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let addr = t!(srv.local_addr());
+
+    let t = thread::spawn(move || {
+        let mut s = TcpStream::connect(&addr).unwrap();
+        s.write_all(b"foo bar baz").unwrap();
+    });
+
+    let copied = srv.incoming().into_future().map_err(|e| e.0).and_then(|(client, _)| {
+        let (client, _) = client.unwrap();
+        read_to_end(take(client, 7), Vec::new())
+    }).and_then(|(body, data)| {
+        assert_eq!(body.limit(), 0);
+        assert_eq!(data, b"foo bar");
+        read_to_end(body.into_inner(), Vec::new())
+    });
+
+    let (_, data) = t!(l.run(copied));
+    t.join().unwrap();
+
+    assert_eq!(data, b" baz");
+}
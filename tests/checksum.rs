@@ -0,0 +1,49 @@
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::{Cursor, IoSlice, Write};
+
+use futures::Future;
+use tokio_core::io::{read_to_end, write_all};
+use tokio_core::io::{Checksum, ChecksumReader, ChecksumWriter, Crc32};
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn crc32() {
+    let mut crc = Crc32::new();
+    assert_eq!(crc.value(), 0);
+    crc.update(b"1234");
+    crc.update(b"56789");
+    assert_eq!(crc.value(), 0xcbf43926);
+    crc.reset();
+    assert_eq!(crc, Crc32::new());
+}
+
+#[test]
+fn checksum_writes() {
+    let writer = ChecksumWriter::new(Vec::new(), Crc32::new());
+    let (mut writer, _) = t!(write_all(writer, b"123456").wait());
+    let n = t!(writer.write_vectored(&[IoSlice::new(b"78"), IoSlice::new(b"9")]));
+    assert_eq!(n, 3);
+    assert_eq!(writer.checksum().value(), 0xcbf43926);
+
+    let (data, crc) = writer.into_inner();
+    assert_eq!(data, b"123456789");
+    assert_eq!(crc.value(), 0xcbf43926);
+}
+
+#[test]
+fn checksum_reads() {
+    let reader = ChecksumReader::new(Cursor::new(b"123456789".to_vec()), Crc32::new());
+    let (mut reader, data) = t!(read_to_end(reader, Vec::new()).wait());
+    assert_eq!(data, b"123456789");
+    assert_eq!(reader.checksum().value(), 0xcbf43926);
+    reader.checksum_mut().reset();
+    assert_eq!(reader.checksum().value(), 0);
+}
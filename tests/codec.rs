@@ -0,0 +1,637 @@
+extern crate tokio_core;
+
+use std::cmp;
+use std::io;
+
+use tokio_core::io::{Codec, EasyBuf};
+use tokio_core::io::codec::{Base64, Chunk, Chunked, Cobs, Compressed, Compression};
+use tokio_core::io::codec::{Delimited, Hex};
+#[cfg(feature = "flate2")]
+use tokio_core::io::codec::{Deflate, Gzip};
+use tokio_core::io::codec::{LengthDelimited, Lines, Multipart, Part, Slip, Tagged};
+use tokio_core::io::codec::VarintDelimited;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+/// Feeds `data` to `codec` one byte at a time, as if every read returned a
+/// single byte, collecting the frames decoded.
+fn decode_bytewise<C: Codec>(codec: &mut C, data: &[u8]) -> Vec<C::In> {
+    let mut buf = EasyBuf::new();
+    let mut frames = Vec::new();
+    for b in data {
+        buf.get_mut().push(*b);
+        while let Some(frame) = t!(codec.decode(&mut buf)) {
+            frames.push(frame);
+        }
+    }
+    assert_eq!(buf.len(), 0);
+    frames
+}
+
+fn encode<C: Codec>(codec: &mut C, frames: Vec<C::Out>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for frame in frames {
+        t!(codec.encode(frame, &mut buf));
+    }
+    buf
+}
+
+#[test]
+fn length_delimited_default() {
+    let mut codec = LengthDelimited::new();
+    let data = encode(&mut codec, vec![b"hello".to_vec(), Vec::new(), b"world".to_vec()]);
+    assert_eq!(&data[..9], b"\x00\x00\x00\x05hello");
+
+    let frames = decode_bytewise(&mut codec, &data);
+    let frames = frames.iter().map(|f| f.as_slice()).collect::<Vec<_>>();
+    assert_eq!(frames, [&b"hello"[..], b"", b"world"]);
+}
+
+#[test]
+fn length_delimited_little_endian() {
+    let mut codec = LengthDelimited::new();
+    codec.set_length_field_length(2);
+    codec.set_little_endian(true);
+    let data = encode(&mut codec, vec![vec![7; 258]]);
+    assert_eq!(&data[..2], b"\x02\x01");
+
+    let frames = decode_bytewise(&mut codec, &data);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].as_slice(), &[7; 258][..]);
+}
+
+#[test]
+fn length_delimited_header() {
+    // A type byte, then a length counting the whole frame, only the length
+    // field being skipped.
+    let mut codec = LengthDelimited::new();
+    codec.set_length_field_offset(1);
+    codec.set_length_field_length(2);
+    codec.set_length_adjustment(-3);
+    codec.set_num_skip(0);
+
+    let frames = decode_bytewise(&mut codec, b"\x01\x00\x05hi\x02\x00\x03");
+    let frames = frames.iter().map(|f| f.as_slice()).collect::<Vec<_>>();
+    assert_eq!(frames, [&b"\x01\x00\x05hi"[..], b"\x02\x00\x03"]);
+
+    // Adjustments are reversed when encoding.
+    let data = encode(&mut codec, vec![b"hi".to_vec()]);
+    assert_eq!(data, b"\x00\x05hi");
+}
+
+#[test]
+fn length_delimited_max_frame_length() {
+    let mut codec = LengthDelimited::with_max_frame_length(4);
+
+    let mut buf = EasyBuf::from(b"\xff\xff\xff\xff".to_vec());
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    let err = codec.encode(b"hello".to_vec(), &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(encode(&mut codec, vec![b"four".to_vec()]), b"\x00\x00\x00\x04four");
+}
+
+#[test]
+fn length_delimited_field_overflow() {
+    let mut codec = LengthDelimited::new();
+    codec.set_length_field_length(1);
+    let err = codec.encode(vec![0; 256], &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(encode(&mut codec, vec![vec![0; 255]]).len(), 256);
+}
+
+#[test]
+fn lines() {
+    let mut codec = Lines::new();
+    let frames = decode_bytewise(&mut codec, b"hello\r\n\nworld\n");
+    assert_eq!(frames, ["hello", "", "world"]);
+
+    let mut buf = EasyBuf::from(b"one\ntwo".to_vec());
+    assert_eq!(t!(codec.decode(&mut buf)), Some("one".to_string()));
+    assert_eq!(t!(codec.decode(&mut buf)), None);
+    assert_eq!(t!(codec.decode_eof(&mut buf)), "two");
+
+    let data = encode(&mut codec, vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(data, b"a\nb\n");
+    codec.set_crlf(true);
+    let data = encode(&mut codec, vec!["a".to_string()]);
+    assert_eq!(data, b"a\r\n");
+}
+
+#[test]
+fn lines_utf8() {
+    let mut codec = Lines::new();
+    let mut buf = EasyBuf::from(b"caf\xc3\xa9\nbad\xff\n".to_vec());
+    assert_eq!(t!(codec.decode(&mut buf)), Some("caf\u{e9}".to_string()));
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    codec.set_lossy(true);
+    let mut buf = EasyBuf::from(b"bad\xff\n".to_vec());
+    assert_eq!(t!(codec.decode(&mut buf)), Some("bad\u{fffd}".to_string()));
+}
+
+#[test]
+fn lines_max_length() {
+    let mut codec = Lines::with_max_length(3);
+    let frames = decode_bytewise(&mut codec, b"abc\r\n");
+    assert_eq!(frames, ["abc"]);
+
+    // A fourth byte may still be the carriage return of a valid line.
+    let mut buf = EasyBuf::from(b"abcd".to_vec());
+    assert_eq!(t!(codec.decode(&mut buf)), None);
+    buf.get_mut().push(b'e');
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    let mut buf = EasyBuf::from(b"abcd\n".to_vec());
+    assert!(codec.decode(&mut buf).is_err());
+}
+
+#[test]
+fn varint_delimited() {
+    let mut codec = VarintDelimited::new();
+    let frames = vec![b"hi".to_vec(), Vec::new(), vec![3; 300]];
+    let data = encode(&mut codec, frames.clone());
+    assert_eq!(&data[..3], b"\x02hi");
+    assert_eq!(&data[4..6], b"\xac\x02");
+
+    let decoded = decode_bytewise(&mut codec, &data);
+    let decoded = decoded.iter().map(|f| f.as_slice().to_vec()).collect::<Vec<_>>();
+    assert_eq!(decoded, frames);
+}
+
+#[test]
+fn varint_delimited_limits() {
+    let mut codec = VarintDelimited::with_max_frame_length(200);
+    let mut buf = EasyBuf::from(b"\xc9\x01".to_vec());
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(codec.encode(vec![0; 201], &mut Vec::new()).is_err());
+
+    let mut codec = VarintDelimited::new();
+    let mut buf = EasyBuf::from(vec![0xff; 10]);
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+/// Run-length encoding, as pairs of a count and a byte.
+struct RunLength;
+
+impl Compression for RunLength {
+    fn compress(&mut self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut out = Vec::new();
+        for b in data {
+            match out.len() {
+                n if n > 0 && out[n - 1] == *b && out[n - 2] < 255 => out[n - 2] += 1,
+                _ => out.extend_from_slice(&[1, *b]),
+            }
+        }
+        Ok(out)
+    }
+
+    fn decompress(&mut self, data: &[u8], max: usize) -> io::Result<Vec<u8>> {
+        if data.len() % 2 != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "odd length"))
+        }
+        let mut out = Vec::new();
+        for pair in data.chunks(2) {
+            if out.len() + pair[0] as usize > max {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "too long"))
+            }
+            for _ in 0..pair[0] {
+                out.push(pair[1]);
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[test]
+fn compressed() {
+    let mut codec = Compressed::new(LengthDelimited::new(), RunLength);
+    let frames = vec![vec![7; 300], b"hello".to_vec(), Vec::new()];
+    let data = encode(&mut codec, frames.clone());
+    assert_eq!(&data[..8], b"\0\0\0\x04\xff\x07\x2d\x07");
+    assert_eq!(data.len(), 8 + 4 + 8 + 4);
+
+    let decoded = decode_bytewise(&mut codec, &data);
+    let decoded = decoded.iter().map(|f| f.as_slice().to_vec()).collect::<Vec<_>>();
+    assert_eq!(decoded, frames);
+
+    let mut buf = EasyBuf::from(b"\0\0\0\x01a".to_vec());
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // Frames decompressing to more than the maximum frame length are refused.
+    codec.set_max_frame_length(299);
+    let mut buf = EasyBuf::from(data);
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[cfg(feature = "flate2")]
+fn sample_frames() -> Vec<Vec<u8>> {
+    let mut seed = 1u32;
+    let noise = (0..100_000).map(|_| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 16) as u8
+    }).collect();
+    let text = (0..200).map(|i| format!("line {}: hello, world\n", i % 7))
+                       .collect::<String>();
+    vec![Vec::new(), b"a".to_vec(), vec![0; 100_000], noise, text.into_bytes()]
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn deflate() {
+    let mut codec = Compressed::new(LengthDelimited::new(), Deflate::new());
+    let frames = sample_frames();
+    let data = encode(&mut codec, frames.clone());
+    assert!(data.len() < 101_000 + 100_000 / 100);
+
+    let mut buf = EasyBuf::from(data);
+    for frame in frames {
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap().as_slice(), &frame[..]);
+    }
+    assert!(codec.decode(&mut buf).unwrap().is_none());
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn deflate_max_length() {
+    let mut deflate = Deflate::new();
+    let data = deflate.compress(&vec![0; 1_000_000]).unwrap();
+    assert!(data.len() < 10_000);
+    let err = deflate.decompress(&data, 1000).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(deflate.decompress(&data, 1_000_000).unwrap().len(), 1_000_000);
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn gzip() {
+    let mut codec = Compressed::new(LengthDelimited::new(), Gzip::new());
+    let frames = sample_frames();
+    let data = encode(&mut codec, frames.clone());
+
+    let mut buf = EasyBuf::from(data);
+    for frame in frames {
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap().as_slice(), &frame[..]);
+    }
+
+    let mut gzip = Gzip::new();
+    let mut data = gzip.compress(b"hello, world").unwrap();
+    assert_eq!(&data[..3], b"\x1f\x8b\x08");
+    let n = data.len();
+    data[n - 8] ^= 1;
+    let err = gzip.decompress(&data, 100).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[cfg(feature = "flate2")]
+#[test]
+fn gzip_interop() {
+    // Written by another gzip implementation, with fixed and dynamic Huffman
+    // codes.
+    let fixed = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\xcb\xc9\xcc\x4b\x55\x30\xb0\x52\x28\xc9\x48\x55\x28\x2c\xcd\x4c\xce\x56\x48\x2a\xca\x2f\xcf\x53\x48\xcb\xaf\x50\xc8\x2a\xcd\x2d\x28\x56\xc8\x2f\x4b\x2d\x02\x4b\xe7\x24\x56\x55\x2a\xa4\xe4\xa7\x73\xe5\x80\xf4\x18\x92\xa1\xc7\x88\x0c\x3d\xc6\x64\xe8\x31\x21\x43\x8f\x29\x19\x7a\xcc\xc8\xd0\x63\x4e\x9a\x1e\x00\x56\x6d\xe0\x61\xa0\x01\x00\x00";
+    let text = (0..8).map(|i| format!("line {}: the quick brown fox jumps over the lazy dog\n", i))
+                     .collect::<String>();
+    assert_eq!(Gzip::new().decompress(fixed, 416).unwrap(), text.as_bytes());
+
+    let dynamic = b"\x1f\x8b\x08\x00\x00\x00\x00\x00\x02\x03\x6d\x92\x5d\x0e\x84\x20\x0c\x84\xaf\xc2\xd5\x30\x6b\xd6\x4d\x70\xd7\x64\x7d\xea\xe9\x8d\x4c\xb5\x5f\x89\x0f\x94\xd2\x9f\x99\xa1\x60\xf3\x5e\xcb\xbb\xae\x6b\x2d\xa7\x57\xdb\xb6\xd4\x32\x9d\x6e\x37\x16\xc1\xd7\xdc\x72\xc1\xb5\xfa\x41\xd9\x3b\x8e\x32\xf6\x3d\xa3\x39\xfd\xf6\xff\xb4\xdf\xb7\x84\x20\xa1\x79\x18\x21\xf5\xda\xcd\x3d\x8d\xb0\xfb\x72\xe9\xe8\x45\x3a\xca\x1a\x31\x55\x2d\x64\x5e\xc0\xd3\xe8\x70\x44\x4f\x04\x6b\x88\xb5\x70\x83\x1e\x63\xb0\x6c\xa8\x09\x70\x89\x19\xf7\xca\x09\x8e\x0a\x2f\x04\xbd\x98\x96\xa2\x1c\x4f\x1e\xa9\xc7\x7c\x81\x79\xbc\x4c\x6e\x7b\xd4\x11\xaf\x03\x01\x72\x49\xc6\xc7\x17\xfe\xf0\x05\x7c\xc7\xf7\x38\x00\x49\x49\x5d\x60\xa8\x02\x00\x00";
+    let out = Gzip::new().decompress(dynamic, 680).unwrap();
+    assert_eq!(out.len(), 680);
+    assert!(out.starts_with(b"zeta gamma eta alpha beta"));
+    assert!(out.ends_with(b"epsilon epsilon alpha gamma"));
+}
+
+#[test]
+fn chunked() {
+    let data = b"4\r\nWiki\r\n5;ext=1\r\npedia\r\n0\r\nExpires: never\r\n\r\n";
+    let mut codec = Chunked::new();
+    let mut buf = EasyBuf::from(data.to_vec());
+    let mut frames = Vec::new();
+    while let Some(frame) = t!(codec.decode(&mut buf)) {
+        frames.push(frame);
+    }
+    let end = Chunk::End(vec![("Expires".to_string(), "never".to_string())]);
+    assert_eq!(frames, vec![Chunk::Data(b"Wiki".to_vec().into()),
+                            Chunk::Data(b"pedia".to_vec().into()),
+                            end.clone()]);
+
+    // Partial reads split the data of the chunks.
+    let frames = decode_bytewise(&mut codec, data);
+    let mut body = Vec::new();
+    for frame in &frames[..frames.len() - 1] {
+        match *frame {
+            Chunk::Data(ref data) => body.extend_from_slice(data.as_slice()),
+            Chunk::End(..) => panic!("body ended early"),
+        }
+    }
+    assert_eq!(body, b"Wikipedia");
+    assert_eq!(frames[frames.len() - 1], end);
+
+    let data = encode(&mut codec, vec![Chunk::Data(b"Wiki".to_vec().into()),
+                                       Chunk::Data(EasyBuf::new()),
+                                       Chunk::Data(vec![0; 20].into()),
+                                       end]);
+    assert_eq!(&data[..9], b"4\r\nWiki\r\n");
+    assert_eq!(&data[9..13], b"14\r\n");
+    assert_eq!(&data[35..], b"0\r\nExpires: never\r\n\r\n");
+}
+
+#[test]
+fn chunked_errors() {
+    for data in &[&b"x\r\n"[..], b"1\r\nabc", b"1\n", b"0\r\nnocolon\r\n"] {
+        let mut codec = Chunked::new();
+        let mut buf = EasyBuf::from(data.to_vec());
+        let err = loop {
+            match codec.decode(&mut buf) {
+                Ok(Some(_)) => {}
+                Ok(None) => panic!("no error decoding {:?}", data),
+                Err(e) => break e,
+            }
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    let mut codec = Chunked::new();
+    let end = Chunk::End(vec![("a".to_string(), "b\r\nc".to_string())]);
+    assert!(codec.encode(end, &mut Vec::new()).is_err());
+}
+
+#[test]
+fn slip() {
+    let mut codec = Slip::new();
+    let frames = vec![b"hi".to_vec(), vec![0xc0, 1, 0xdb]];
+    let data = encode(&mut codec, frames.clone());
+    assert_eq!(data, b"hi\xc0\xdb\xdc\x01\xdb\xdd\xc0");
+
+    // Empty frames are skipped.
+    let mut data = data;
+    data.insert(0, 0xc0);
+    let decoded = decode_bytewise(&mut codec, &data);
+    let decoded = decoded.iter().map(|f| f.as_slice().to_vec()).collect::<Vec<_>>();
+    assert_eq!(decoded, frames);
+
+    let mut buf = EasyBuf::from(b"a\xdbb\xc0".to_vec());
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    let mut codec = Slip::with_max_frame_length(2);
+    let mut buf = EasyBuf::from(b"abcde".to_vec());
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(codec.encode(b"abc".to_vec(), &mut Vec::new()).is_err());
+}
+
+#[test]
+fn cobs() {
+    let mut codec = Cobs::new();
+    assert_eq!(encode(&mut codec, vec![Vec::new()]), b"\x01\x00");
+    assert_eq!(encode(&mut codec, vec![vec![0]]), b"\x01\x01\x00");
+    assert_eq!(encode(&mut codec, vec![vec![0x11, 0x22, 0, 0x33]]),
+               b"\x03\x11\x22\x02\x33\x00");
+
+    let long = (1..256).map(|b| b as u8).collect::<Vec<_>>();
+    let data = encode(&mut codec, vec![long.clone()]);
+    assert_eq!(data.len(), 255 + 2 + 1);
+    assert_eq!(data[0], 0xff);
+    assert_eq!(&data[255..], b"\x02\xff\x00");
+
+    let frames = vec![b"hi".to_vec(), Vec::new(), vec![0; 3], long, vec![7; 254]];
+    let mut data = encode(&mut codec, frames.clone());
+    data.insert(0, 0);
+    let decoded = decode_bytewise(&mut codec, &data);
+    let decoded = decoded.iter().map(|f| f.as_slice().to_vec()).collect::<Vec<_>>();
+    assert_eq!(decoded, frames);
+
+    let mut buf = EasyBuf::from(b"\x05ab\x00".to_vec());
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn delimited() {
+    let mut codec = Delimited::new(&b"\r\n"[..]);
+    let frames = decode_bytewise(&mut codec, b"EHLO a\rb\r\n\r\nQUIT\r\n");
+    let frames = frames.iter().map(|f| f.as_slice().to_vec()).collect::<Vec<_>>();
+    assert_eq!(frames, vec![b"EHLO a\rb".to_vec(), Vec::new(), b"QUIT".to_vec()]);
+
+    assert_eq!(encode(&mut codec, vec![b"PING".to_vec()]), b"PING\r\n");
+    assert!(codec.encode(b"a\r\nb".to_vec(), &mut Vec::new()).is_err());
+}
+
+#[test]
+fn delimited_escape() {
+    let mut codec = Delimited::new("||");
+    codec.set_escape(Some(b'\\'));
+    let frames = vec![b"a||b".to_vec(), b"c\\".to_vec(), b"|".to_vec()];
+    let data = encode(&mut codec, frames.clone());
+    assert_eq!(data, b"a\\|\\|b||c\\\\||\\|||".to_vec());
+
+    let decoded = decode_bytewise(&mut codec, &data);
+    let decoded = decoded.iter().map(|f| f.as_slice().to_vec()).collect::<Vec<_>>();
+    assert_eq!(decoded, frames);
+}
+
+#[test]
+fn delimited_max_frame_length() {
+    let mut codec = Delimited::new("\n");
+    codec.set_max_frame_length(3);
+    let mut buf = EasyBuf::from(b"abc\n".to_vec());
+    assert_eq!(t!(codec.decode(&mut buf)).unwrap().as_slice(), b"abc");
+
+    let mut buf = EasyBuf::from(b"abcd".to_vec());
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    let mut out = Vec::new();
+    assert!(codec.encode(b"abcd".to_vec(), &mut out).is_err());
+    assert!(out.is_empty());
+}
+
+#[test]
+fn tagged() {
+    let mut codec = Tagged::new();
+    let data = encode(&mut codec, vec![(1, b"hello".to_vec()), (2, Vec::new())]);
+    assert_eq!(data, b"\x01\x00\x00\x00\x05hello\x02\x00\x00\x00\x00");
+
+    let frames = decode_bytewise(&mut codec, &data);
+    let frames = frames.iter().map(|f| (f.0, f.1.as_slice())).collect::<Vec<_>>();
+    assert_eq!(frames, [(1, &b"hello"[..]), (2, b"")]);
+
+    codec.set_length_field_length(2);
+    codec.set_little_endian(true);
+    let data = encode(&mut codec, vec![(7, vec![0; 258])]);
+    assert_eq!(&data[..3], b"\x07\x02\x01");
+    let frames = decode_bytewise(&mut codec, &data);
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].0, 7);
+    assert_eq!(frames[0].1.len(), 258);
+}
+
+#[test]
+fn tagged_max_frame_length() {
+    let mut codec = Tagged::with_max_frame_length(4);
+    codec.set_tag_max_frame_length(9, 100);
+    codec.set_tag_max_frame_length(0, 0);
+    assert_eq!(codec.tag_max_frame_length(1), 4);
+    assert_eq!(codec.tag_max_frame_length(9), 100);
+
+    let mut buf = EasyBuf::from(b"\x01\x00\x00\x00\x05".to_vec());
+    let err = codec.decode(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let mut buf = EasyBuf::from(b"\x00\x00\x00\x00\x01".to_vec());
+    assert!(codec.decode(&mut buf).is_err());
+
+    let frames = decode_bytewise(&mut codec, b"\x09\x00\x00\x00\x05hello");
+    assert_eq!(frames[0].1.as_slice(), b"hello");
+
+    let err = codec.encode((1, b"hello".to_vec()), &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(encode(&mut codec, vec![(9, b"hello".to_vec())]).len(), 10);
+}
+
+#[test]
+fn easy_buf_drain_with() {
+    let mut buf = EasyBuf::from(b"a,bc,,def".to_vec());
+    let pieces = buf.drain_with(|b| b.iter().position(|&b| b == b',').map(|i| i + 1))
+                    .map(|piece| piece.into_vec())
+                    .collect::<Vec<_>>();
+    assert_eq!(pieces, [b"a,".to_vec(), b"bc,".to_vec(), b",".to_vec()]);
+    assert_eq!(buf.as_slice(), b"def");
+
+    // Dropping the iterator early leaves the rest of the pieces in place.
+    let mut buf = EasyBuf::from(b"aabbcc".to_vec());
+    assert_eq!(buf.drain_with(|b| Some(cmp::min(2, b.len()))).next().unwrap().as_slice(),
+               b"aa");
+    assert_eq!(buf.as_slice(), b"bbcc");
+}
+
+#[test]
+#[should_panic]
+fn easy_buf_drain_with_empty_piece() {
+    let mut buf = EasyBuf::from(b"abc".to_vec());
+    buf.drain_with(|_| Some(0)).next();
+}
+
+/// Concatenates the chunks of data decoded by a streaming codec.
+fn concat(chunks: Vec<EasyBuf>) -> Vec<u8> {
+    chunks.iter().flat_map(|c| c.as_slice().to_vec()).collect()
+}
+
+#[test]
+fn hex() {
+    let mut codec = Hex::new();
+    assert_eq!(encode(&mut codec, vec![b"\x00\xab".to_vec(), b"\xff".to_vec()]),
+               b"00abff");
+    codec.set_uppercase(true);
+    assert_eq!(encode(&mut codec, vec![b"\xab".to_vec()]), b"AB");
+
+    assert_eq!(concat(decode_bytewise(&mut codec, b"00aB ff\r\n")), b"\x00\xab\xff");
+
+    // A lone digit waits for the next one.
+    let mut buf = EasyBuf::from(b"616".to_vec());
+    assert_eq!(t!(codec.decode(&mut buf)).unwrap().as_slice(), b"a");
+    assert_eq!(buf.as_slice(), b"6");
+    assert!(codec.decode_eof(&mut buf).is_err());
+
+    let mut buf = EasyBuf::from(b"6g".to_vec());
+    assert_eq!(codec.decode(&mut buf).unwrap_err().kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn base64() {
+    let mut codec = Base64::new();
+    // The bytes which don't make a full group are held until the end.
+    assert_eq!(encode(&mut codec, vec![b"fo".to_vec(), b"ob".to_vec(), b"a".to_vec()]),
+               b"Zm9v");
+    assert_eq!(encode(&mut codec, vec![b"r".to_vec(), Vec::new()]), b"YmFy");
+    assert_eq!(encode(&mut codec, vec![b"f".to_vec(), Vec::new()]), b"Zg==");
+    assert_eq!(encode(&mut codec, vec![b"fo".to_vec(), Vec::new()]), b"Zm8=");
+    assert_eq!(encode(&mut codec, vec![Vec::new()]), b"");
+
+    let decoded = decode_bytewise(&mut codec, b"Zm9v\r\nYmFy Zg==Zm8=");
+    assert_eq!(concat(decoded), b"foobarffo");
+
+    // The characters of an incomplete group wait for the rest.
+    let mut buf = EasyBuf::from(b"Zm9vYm".to_vec());
+    assert_eq!(t!(codec.decode(&mut buf)).unwrap().as_slice(), b"foo");
+    assert_eq!(buf.as_slice(), b"Ym");
+    assert!(codec.decode_eof(&mut buf).is_err());
+
+    for bad in [&b"Zm9-"[..], b"Z===", b"Zg=g"].iter() {
+        let mut buf = EasyBuf::from(bad.to_vec());
+        assert_eq!(codec.decode(&mut buf).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    codec.set_url_safe(true);
+    assert_eq!(encode(&mut codec, vec![b"\xfb\xff".to_vec(), Vec::new()]), b"-_8=");
+    assert_eq!(concat(decode_bytewise(&mut codec, b"-_8=")), b"\xfb\xff");
+}
+
+fn body(part: &[u8]) -> Part {
+    Part::Body(EasyBuf::from(part.to_vec()))
+}
+
+#[test]
+fn multipart() {
+    let data = b"preamble\r\n--xyz\r\n\
+                 A: 1\r\n\r\nfirst\r\n--x\r\n\
+                 --xyz \t\r\n\
+                 \r\nsecond\r\n\
+                 --xyz--\r\nepilogue\r\n--xyz\r\n";
+    let mut codec = Multipart::new("xyz");
+    assert_eq!(decode_bytewise(&mut codec, data),
+               [body(b"A: 1\r\n\r\nfirst\r\n--x"), body(b"\r\nsecond"), Part::End]);
+
+    // The first boundary may start the stream.
+    let mut codec = Multipart::new("xyz");
+    assert_eq!(decode_bytewise(&mut codec, b"--xyz\r\na\r\n--xyz--"),
+               [body(b"a"), Part::End]);
+
+    let encoded = encode(&mut codec, vec![body(b"a"), body(b""), Part::End]);
+    assert_eq!(encoded, b"--xyz\r\na\r\n--xyz\r\n\r\n--xyz--\r\n");
+    let mut codec = Multipart::new("xyz");
+    assert_eq!(decode_bytewise(&mut codec, &encoded), [body(b"a"), body(b""), Part::End]);
+}
+
+#[test]
+fn multipart_errors() {
+    let mut codec = Multipart::new("xyz");
+    let mut buf = EasyBuf::from(b"--xyzabc\r\n".to_vec());
+    assert_eq!(codec.decode(&mut buf).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+    let mut codec = Multipart::new("xyz");
+    codec.set_max_frame_length(3);
+    let mut buf = EasyBuf::from(b"--xyz\r\nabcd".to_vec());
+    assert_eq!(codec.decode(&mut buf).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    assert!(codec.encode(body(b"abcd"), &mut Vec::new()).is_err());
+
+    let mut codec = Multipart::new("xyz");
+    assert!(codec.encode(body(b"a\r\n--xyz"), &mut Vec::new()).is_err());
+}
+
+#[test]
+fn reset() {
+    let mut codec = Base64::new();
+    assert_eq!(encode(&mut codec, vec![b"a".to_vec()]), b"");
+    codec.reset();
+    assert_eq!(encode(&mut codec, vec![Vec::new()]), b"");
+
+    let mut codec = Tagged::new();
+    let mut buf = EasyBuf::from(b"\x01\0\0\0\x05ab".to_vec());
+    assert!(t!(codec.decode(&mut buf)).is_none());
+    codec.reset();
+    let mut buf = EasyBuf::from(b"\x02\0\0\0\x01c".to_vec());
+    let (tag, data) = t!(codec.decode(&mut buf)).unwrap();
+    assert_eq!((tag, data.as_slice()), (2, &b"c"[..]));
+}
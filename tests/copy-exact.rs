@@ -0,0 +1,38 @@
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::{self, Cursor};
+
+use futures::Future;
+use tokio_core::io::{copy_exact, read_to_end};
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn copy_body() {
+    let data = (0..10_000).map(|i| i as u8).collect::<Vec<_>>();
+    let copied = copy_exact(Cursor::new(data.clone()), Vec::new(), 5000);
+    let (reader, body) = t!(copied.wait());
+    assert_eq!(body, &data[..5000]);
+
+    let (_, rest) = t!(read_to_end(reader, Vec::new()).wait());
+    assert_eq!(rest, &data[5000..]);
+}
+
+#[test]
+fn copy_nothing() {
+    let (reader, body) = t!(copy_exact(Cursor::new(b"foo"), Vec::new(), 0).wait());
+    assert!(body.is_empty());
+    assert_eq!(reader.position(), 0);
+}
+
+#[test]
+fn early_eof() {
+    let err = copy_exact(Cursor::new(b"foo"), Vec::new(), 4).wait().err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
@@ -0,0 +1,41 @@
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::Cursor;
+
+use futures::Future;
+use tokio_core::io::{read_to_end, write_all, Counted, Counters};
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn count_both_directions() {
+    let io = Counted::new(Cursor::new(b"hello world".to_vec()));
+    let counters = io.counters().clone();
+
+    let (io, data) = t!(read_to_end(io, Vec::new()).wait());
+    assert_eq!(data, b"hello world");
+    assert_eq!(counters.read(), 11);
+    assert_eq!(counters.written(), 0);
+
+    let (io, _) = t!(write_all(io, b"!!").wait());
+    assert_eq!(counters.read(), 11);
+    assert_eq!(counters.written(), 2);
+    assert_eq!(io.into_inner().into_inner(), b"hello world!!");
+}
+
+#[test]
+fn shared_counters() {
+    let counters = Counters::new();
+    let a = Counted::with_counters(Vec::new(), &counters);
+    let b = Counted::with_counters(Vec::new(), &counters);
+    t!(write_all(a, b"foo").wait());
+    t!(write_all(b, b"barbaz").wait());
+    assert_eq!(counters.written(), 9);
+    assert_eq!(counters.read(), 0);
+}
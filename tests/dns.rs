@@ -0,0 +1,56 @@
+extern crate futures;
+extern crate tokio_core;
+
+use std::net::{self, SocketAddr};
+use std::thread;
+
+use tokio_core::net::{lookup_host, TcpStream};
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn lookup_localhost() {
+    let mut l = t!(Core::new());
+    let addrs = t!(l.run(lookup_host("localhost", &l.handle())));
+    assert!(addrs.iter().any(|a| a.ip().is_loopback()));
+    assert!(addrs.iter().all(|a| a.port() == 0));
+}
+
+#[test]
+fn lookup_literal() {
+    let mut l = t!(Core::new());
+    let addrs = t!(l.run(lookup_host("127.0.0.1", &l.handle())));
+    assert_eq!(addrs, ["127.0.0.1:0".parse::<SocketAddr>().unwrap()]);
+}
+
+#[test]
+fn lookup_invalid() {
+    let mut l = t!(Core::new());
+    assert!(l.run(lookup_host("invalid host name", &l.handle())).is_err());
+}
+
+#[test]
+fn connect_host() {
+    let mut l = t!(Core::new());
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let port = t!(srv.local_addr()).port();
+    let t = thread::spawn(move || {
+        t!(srv.accept()).1
+    });
+
+    let host = format!("127.0.0.1:{}", port);
+    let stream = t!(l.run(TcpStream::connect_host(&host, &l.handle())));
+    assert_eq!(t!(stream.local_addr()), t.join().unwrap());
+}
+
+#[test]
+fn connect_host_without_port() {
+    let mut l = t!(Core::new());
+    assert!(l.run(TcpStream::connect_host("localhost", &l.handle())).is_err());
+}
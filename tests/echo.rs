@@ -1,15 +1,19 @@
 extern crate env_logger;
+#[macro_use]
 extern crate futures;
 extern crate tokio_core;
 
-use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::cmp;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, TcpStream};
 use std::thread;
 
 use futures::Future;
 use futures::stream::Stream;
-use tokio_core::io::{copy, Io};
-use tokio_core::net::TcpListener;
+use futures::sync::oneshot;
+use tokio_core::io::{copy, copy_bidirectional, copy_with_buffer, read_exact, read_to_end};
+use tokio_core::io::{write_all, AsyncRead, AsyncWrite, Io};
+use tokio_core::net::{TcpListener, TcpStream as AsyncTcpStream};
 use tokio_core::reactor::Core;
 
 macro_rules! t {
@@ -49,3 +53,157 @@ fn echo_server() {
 
     assert_eq!(amt, msg.len() as u64 * 1024);
 }
+
+#[test]
+fn echo_server_with_buffer() {
+    drop(env_logger::init());
+
+    let mut l = t!(Core::new());
+    let srv = t!(TcpListener::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let addr = t!(srv.local_addr());
+
+    let msg = (0..16 * 1024).map(|i| i as u8).collect::<Vec<_>>();
+    let expected = msg.clone();
+    let t = thread::spawn(move || {
+        let mut s = TcpStream::connect(&addr).unwrap();
+        t!(s.write_all(&msg));
+        t!(s.shutdown(Shutdown::Write));
+        let mut echoed = Vec::new();
+        t!(s.read_to_end(&mut echoed));
+        echoed
+    });
+
+    let clients = srv.incoming();
+    let client = clients.into_future().map(|e| e.0.unwrap()).map_err(|e| e.0);
+    let halves = client.map(|s| s.0.split());
+    let copied = halves.and_then(|(a, b)| copy_with_buffer(a, b, vec![0; 7]));
+
+    let (amt, buf) = t!(l.run(copied));
+    assert_eq!(amt, expected.len() as u64);
+    assert_eq!(buf.len(), 7);
+    drop(l);
+    assert_eq!(t.join().unwrap(), expected);
+}
+
+#[test]
+fn proxy_half_close() {
+    drop(env_logger::init());
+
+    let mut l = t!(Core::new());
+    let srv = t!(TcpListener::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let addr = t!(srv.local_addr());
+
+    // The backend only answers once it has read everything, so the client's
+    // shutdown has to make it through the proxy.
+    let backend = t!(std::net::TcpListener::bind("127.0.0.1:0"));
+    let backend_addr = t!(backend.local_addr());
+    let t1 = thread::spawn(move || {
+        let (mut s, _) = t!(backend.accept());
+        let mut data = Vec::new();
+        t!(s.read_to_end(&mut data));
+        t!(s.write_all(format!("got {} bytes", data.len()).as_bytes()));
+    });
+
+    let t2 = thread::spawn(move || {
+        let mut s = t!(TcpStream::connect(&addr));
+        t!(s.write_all(b"hello"));
+        t!(s.shutdown(Shutdown::Write));
+        let mut data = Vec::new();
+        t!(s.read_to_end(&mut data));
+        data
+    });
+
+    let handle = l.handle();
+    let client = srv.incoming().into_future().map(|e| e.0.unwrap()).map_err(|e| e.0);
+    let proxied = client.and_then(|(client, _)| {
+        AsyncTcpStream::connect(&backend_addr, &handle).and_then(|backend| {
+            copy_bidirectional(client, backend)
+        })
+    });
+
+    let (up, down) = t!(l.run(proxied));
+    assert_eq!(up, 5);
+    assert_eq!(down, 11);
+    t1.join().unwrap();
+    assert_eq!(t2.join().unwrap(), b"got 5 bytes");
+}
+
+#[test]
+fn split_on_separate_tasks() {
+    drop(env_logger::init());
+
+    let mut l = t!(Core::new());
+    let srv = t!(TcpListener::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let addr = t!(srv.local_addr());
+
+    let t = thread::spawn(move || {
+        let mut s = t!(TcpStream::connect(&addr));
+        t!(s.write_all(b"ping"));
+        t!(s.shutdown(Shutdown::Write));
+        let mut data = Vec::new();
+        t!(s.read_to_end(&mut data));
+        data
+    });
+
+    let handle = l.handle();
+    let client = srv.incoming().into_future().map(|e| e.0.unwrap()).map_err(|e| e.0);
+    let done = client.and_then(|(client, _)| {
+        let (reader, writer) = client.split_shared();
+        let (tx, rx) = oneshot::channel();
+        handle.spawn(read_to_end(reader, Vec::new()).then(|res| {
+            drop(tx.send(res));
+            Ok(())
+        }));
+        write_all(writer, b"pong").and_then(|(writer, _)| {
+            let mut writer = Some(writer);
+            futures::future::poll_fn(move || {
+                try_ready!(AsyncWrite::shutdown(writer.as_mut().unwrap()));
+                Ok(futures::Async::Ready(writer.take().unwrap()))
+            })
+        }).join(rx.then(|res| res.unwrap()))
+    });
+
+    let (writer, (reader, data)) = t!(l.run(done));
+    assert_eq!(data, b"ping");
+    let stream = reader.unsplit(writer);
+    assert_eq!(t!(stream.local_addr()), addr);
+    assert_eq!(t.join().unwrap(), b"pong");
+}
+
+/// An in-memory object which, unlike `TcpStream`, can only be read and written
+/// through `&mut self`.
+struct Pipe(Vec<u8>);
+
+impl Read for Pipe {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = cmp::min(buf.len(), self.0.len());
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0.drain(..n);
+        Ok(n)
+    }
+}
+
+impl Write for Pipe {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsyncRead for Pipe {}
+
+impl AsyncWrite for Pipe {}
+
+#[test]
+fn split_locked() {
+    let (reader, writer) = Pipe(b"abc".to_vec()).split();
+    let (writer, _) = t!(write_all(writer, b"def").wait());
+    let (reader, data) = t!(read_exact(reader, [0; 5]).wait());
+    assert_eq!(&data, b"abcde");
+    let pipe = reader.unsplit(writer);
+    assert_eq!(pipe.0, b"f");
+}
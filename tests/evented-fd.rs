@@ -0,0 +1,323 @@
+#![cfg(unix)]
+
+extern crate futures;
+extern crate libc;
+extern crate mio;
+#[macro_use]
+extern crate tokio_core;
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::prelude::*;
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
+
+use futures::{Async, Future, Stream};
+use futures::future::{poll_fn, Either};
+use tokio_core::io::{read_exact, read_to_end, write_all};
+use tokio_core::reactor::{Core, Detached, EventedFd, PollEvented, Timeout};
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+fn pipe() -> (File, File) {
+    let mut fds = [0; 2];
+    unsafe {
+        assert_eq!(libc::pipe(fds.as_mut_ptr()), 0);
+        for fd in fds.iter() {
+            let flags = libc::fcntl(*fd, libc::F_GETFL);
+            assert_eq!(libc::fcntl(*fd, libc::F_SETFL, flags | libc::O_NONBLOCK), 0);
+        }
+        (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1]))
+    }
+}
+
+#[test]
+fn owned_pipe() {
+    let mut l = t!(Core::new());
+    let (read, mut write) = pipe();
+    let read = t!(PollEvented::new(EventedFd::new(read), &l.handle()));
+
+    t!(write.write_all(b"hello"));
+    drop(write);
+    let (_, buf) = t!(l.run(read_to_end(read, Vec::new())));
+    assert_eq!(buf, b"hello");
+}
+
+#[test]
+fn from_fd() {
+    let mut l = t!(Core::new());
+    let (read, mut write) = pipe();
+    let read = t!(PollEvented::from_fd(read, &l.handle()));
+
+    t!(write.write_all(b"hello"));
+    drop(write);
+    let (read, buf) = t!(l.run(read_to_end(read, Vec::new())));
+    assert_eq!(buf, b"hello");
+    assert!(read.get_ref().get_ref().as_raw_fd() >= 0);
+}
+
+#[test]
+fn borrowed_raw_fd() {
+    let mut l = t!(Core::new());
+    let (mut read, mut write) = pipe();
+    let io = t!(PollEvented::new(EventedFd::new(read.as_raw_fd()), &l.handle()));
+
+    let readable = poll_fn(|| {
+        Ok::<_, ()>(match io.poll_read() {
+            Async::Ready(()) => Async::Ready(()),
+            Async::NotReady => {
+                t!(write.write_all(b"x"));
+                io.need_read();
+                Async::NotReady
+            }
+        })
+    });
+    t!(l.run(readable.map(|()| ())));
+
+    let mut buf = [0; 4];
+    assert_eq!(t!(read.read(&mut buf)), 1);
+    assert_eq!(*io.get_ref().get_ref(), read.as_raw_fd());
+}
+
+#[test]
+fn move_between_cores() {
+    let mut l1 = t!(Core::new());
+    let mut l2 = t!(Core::new());
+    let (read, mut write) = pipe();
+    let read = t!(PollEvented::new(EventedFd::new(read), &l1.handle()));
+    assert!(read.deregister(&l2.handle()).is_err());
+
+    t!(write.write_all(b"hello"));
+    let (read, buf) = t!(l1.run(read_exact(read, [0; 5])));
+    assert_eq!(&buf, b"hello");
+
+    // Once moved to the second event loop the pipe is read from there.
+    let read = t!(read.into_inner(&l1.handle()));
+    let read = t!(PollEvented::new(read, &l2.handle()));
+    t!(write.write_all(b"world"));
+    drop(write);
+    let (_, buf) = t!(l2.run(read_to_end(read, Vec::new())));
+    assert_eq!(buf, b"world");
+}
+
+#[test]
+fn change_interest() {
+    let mut l = t!(Core::new());
+    let handle = l.handle();
+    let (read, mut write) = pipe();
+    let read = t!(PollEvented::with_interest(EventedFd::new(read), &handle,
+                                             mio::Ready::empty()));
+    assert_eq!(read.interest(), mio::Ready::empty());
+    t!(write.write_all(b"hello"));
+
+    // The pipe isn't reported readable while it's not registered for it.
+    {
+        let readable = poll_fn(|| Ok::<_, io::Error>(read.poll_read()));
+        let timeout = t!(Timeout::new(Duration::from_millis(50), &handle));
+        match l.run(readable.select2(timeout)) {
+            Ok(Either::B(..)) => {}
+            _ => panic!("pipe reported readable"),
+        }
+    }
+
+    t!(read.set_interest(&handle, mio::Ready::readable()));
+    assert_eq!(read.interest(), mio::Ready::readable());
+    let (_, buf) = t!(l.run(read_exact(read, [0; 5])));
+    assert_eq!(&buf, b"hello");
+}
+
+#[test]
+fn hup() {
+    let mut l = t!(Core::new());
+    let (read, write) = pipe();
+    let read = t!(PollEvented::new(EventedFd::new(read), &l.handle()));
+    let hup = mio::Ready::from(mio::unix::UnixReady::hup());
+
+    drop(write);
+    let ready = t!(l.run(poll_fn(|| Ok::<_, io::Error>(read.poll_ready(hup)))));
+    assert_eq!(ready, hup);
+    // The reader is woken up as well, to see the EOF.
+    assert_eq!(read.poll_read(), Async::Ready(()));
+
+    // Clearing the hang up leaves the read readiness reported with it.
+    t!(l.run(poll_fn(|| {
+        read.clear_read_ready(hup);
+        assert_eq!(read.poll_ready(hup), Async::NotReady);
+        assert_eq!(read.poll_read(), Async::Ready(()));
+        Ok::<_, io::Error>(Async::Ready(()))
+    })));
+}
+
+#[test]
+fn level_triggered() {
+    let mut l = t!(Core::new());
+    let (read, mut write) = pipe();
+    let read = t!(PollEvented::with_options(EventedFd::new(read), &l.handle(),
+                                            mio::Ready::readable(),
+                                            mio::PollOpt::level()));
+    assert_eq!(read.opts(), mio::PollOpt::level());
+    t!(write.write_all(b"ab"));
+
+    // Reading a byte at a time leaves data in the pipe after `need_read`,
+    // which a level-triggered source is reported for again.
+    let mut got = Vec::new();
+    t!(l.run(poll_fn(|| {
+        while got.len() < 2 {
+            if let Async::NotReady = read.poll_read() {
+                return Ok(Async::NotReady)
+            }
+            let mut buf = [0; 1];
+            assert_eq!(t!(read.get_ref().read(&mut buf)), 1);
+            got.push(buf[0]);
+            read.need_read();
+        }
+        Ok::<_, io::Error>(Async::Ready(()))
+    })));
+    assert_eq!(got, b"ab");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn priority() {
+    use std::net::{TcpListener, TcpStream};
+
+    let mut l = t!(Core::new());
+    let listener = t!(TcpListener::bind("127.0.0.1:0"));
+    let client = t!(TcpStream::connect(t!(listener.local_addr())));
+    let (server, _) = t!(listener.accept());
+    t!(server.set_nonblocking(true));
+    let priority = mio::Ready::from(mio::unix::UnixReady::priority());
+    let server = t!(PollEvented::with_interest(EventedFd::new(server), &l.handle(),
+                                               mio::Ready::readable() | priority));
+
+    // Out-of-band data makes the socket ready with priority.
+    let n = unsafe {
+        libc::send(client.as_raw_fd(), b"!".as_ptr() as *const _, 1, libc::MSG_OOB)
+    };
+    assert_eq!(n, 1);
+    let ready = t!(l.run(poll_fn(|| Ok::<_, io::Error>(server.poll_ready(priority)))));
+    assert_eq!(ready, priority);
+}
+
+#[test]
+fn readiness_stream() {
+    let mut l = t!(Core::new());
+    let (read, mut write) = pipe();
+    let read = t!(PollEvented::new(EventedFd::new(read), &l.handle()));
+    let hup = mio::Ready::from(mio::unix::UnixReady::hup());
+    let mut stream = read.readiness_stream(mio::Ready::readable() | hup);
+
+    t!(write.write_all(b"hello"));
+    let ready = t!(l.run(poll_fn(|| stream.poll())));
+    assert_eq!(ready, Some(mio::Ready::readable()));
+    let mut buf = [0; 16];
+    assert_eq!(t!(read.get_ref().read(&mut buf)), 5);
+    assert!(read.get_ref().read(&mut buf).is_err());
+
+    // The readiness handled is cleared, so the next item is the hang up.
+    drop(write);
+    let ready = t!(l.run(poll_fn(|| stream.poll())));
+    assert_eq!(ready, Some(mio::Ready::readable() | hup));
+}
+
+#[test]
+fn oneshot() {
+    let mut l = t!(Core::new());
+    let handle = l.handle();
+    let (read, mut write) = pipe();
+    let read = t!(PollEvented::with_options(EventedFd::new(read), &handle,
+                                            mio::Ready::readable(),
+                                            mio::PollOpt::edge() | mio::PollOpt::oneshot()));
+    let drain = || {
+        poll_fn(|| {
+            let mut buf = [0; 16];
+            let n = try_nb!((&read).read(&mut buf));
+            // Read until the pipe would block for the source to be waited
+            // for again.
+            assert!((&read).read(&mut [0; 16]).is_err());
+            Ok::<_, io::Error>(Async::Ready(buf[..n].to_vec()))
+        })
+    };
+
+    t!(write.write_all(b"a"));
+    assert_eq!(t!(l.run(drain())), b"a");
+
+    // The source is disabled after its notification, until it's rearmed.
+    t!(write.write_all(b"b"));
+    {
+        let timeout = t!(Timeout::new(Duration::from_millis(50), &handle));
+        match l.run(drain().select2(timeout)) {
+            Ok(Either::B(..)) => {}
+            _ => panic!("disabled pipe reported readable"),
+        }
+    }
+    t!(read.rearm(&handle));
+    assert_eq!(t!(l.run(drain())), b"b");
+}
+
+#[test]
+fn token() {
+    let l = t!(Core::new());
+    let (read, write) = pipe();
+    let read = t!(PollEvented::new(EventedFd::new(read), &l.handle()));
+    let write = t!(PollEvented::new(EventedFd::new(write), &l.handle()));
+    assert!(read.token() != write.token());
+}
+
+#[test]
+fn move_between_threads() {
+    let l = t!(Core::new());
+    let (read, mut write) = pipe();
+    let read = t!(PollEvented::with_interest(EventedFd::new(read), &l.handle(),
+                                             mio::Ready::readable()));
+
+    // Data written before the move is still reported afterwards.
+    t!(write.write_all(b"hello"));
+    let detached = t!(read.detach(&l.handle()));
+    assert_eq!(detached.interest(), mio::Ready::readable());
+
+    let (tx, rx) = channel::<Detached<_>>();
+    let t = thread::spawn(move || {
+        let mut l = t!(Core::new());
+        let read = t!(rx.recv().unwrap().attach(&l.handle()));
+        assert_eq!(read.interest(), mio::Ready::readable());
+        let (_, buf) = t!(l.run(read_exact(read, [0; 5])));
+        buf
+    });
+    tx.send(detached).unwrap();
+    assert_eq!(&t.join().unwrap(), b"hello");
+}
+
+#[test]
+fn write_only() {
+    let mut l = t!(Core::new());
+    let (mut read, write) = pipe();
+    let write = t!(PollEvented::new_write_only(EventedFd::new(write), &l.handle()));
+    assert_eq!(write.interest(), mio::Ready::writable());
+
+    // More than the pipe holds, so that the writes have to wait for it to be
+    // read from.
+    let data = vec![1; 256 * 1024];
+    let t = thread::spawn(move || {
+        let mut buf = Vec::new();
+        while buf.len() < 256 * 1024 {
+            match read.read_to_end(&mut buf) {
+                Ok(_) => break,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(1));
+                }
+                Err(e) => panic!("read failed: {}", e),
+            }
+        }
+        buf
+    });
+    let (write, _) = t!(l.run(write_all(write, data)));
+    drop(write);
+    assert_eq!(t.join().unwrap(), vec![1; 256 * 1024]);
+}
@@ -0,0 +1,45 @@
+#![cfg(target_os = "linux")]
+
+extern crate futures;
+extern crate tokio_core;
+
+use std::thread;
+
+use futures::Future;
+use futures::stream::Stream;
+use tokio_core::reactor::{Core, EventFd};
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn initial_value() {
+    let mut l = t!(Core::new());
+    let efd = t!(EventFd::new(3, &l.handle()));
+    let (n, _efd) = t!(l.run(efd.into_future().map_err(|e| e.0)));
+    assert_eq!(n, Some(3));
+}
+
+#[test]
+fn notify_from_thread() {
+    let mut l = t!(Core::new());
+    let efd = t!(EventFd::new(0, &l.handle()));
+    let notifier = t!(efd.notifier());
+    let t = thread::spawn(move || {
+        t!(notifier.notify(2));
+        t!(notifier.notify(5));
+    });
+    t.join().unwrap();
+
+    let (n, efd) = t!(l.run(efd.into_future().map_err(|e| e.0)));
+    assert_eq!(n, Some(7));
+
+    let write = futures::lazy(|| efd.write(4).map(|()| efd));
+    let efd = t!(l.run(write));
+    let (n, _efd) = t!(l.run(efd.into_future().map_err(|e| e.0)));
+    assert_eq!(n, Some(4));
+}
@@ -0,0 +1,690 @@
+extern crate env_logger;
+extern crate futures;
+extern crate tokio_core;
+
+use std::cell::Cell;
+use std::io::{self, Read, Write};
+use std::rc::Rc;
+use std::net;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use futures::{Async, Future, Sink, Stream};
+use tokio_core::io::{AsyncRead, AsyncWrite, BufferPool, Codec, EasyBuf, Framed, FramedRead, FramedWrite, Io, Resync};
+use tokio_core::io::codec::{Hooked, LengthDelimited, Tagged};
+use tokio_core::net::TcpStream;
+#[cfg(unix)]
+use tokio_core::process::CommandExt;
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+/// Frames are the bytes up to each newline, and whatever is left at the end
+/// of the stream.
+struct Newlines;
+
+impl Codec for Newlines {
+    type In = EasyBuf;
+    type Out = Vec<u8>;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<EasyBuf>> {
+        match buf.as_slice().iter().position(|b| *b == b'\n') {
+            Some(i) => {
+                let line = buf.drain_to(i);
+                buf.drain_to(1);
+                Ok(Some(line))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn decode_eof(&mut self, buf: &mut EasyBuf) -> io::Result<EasyBuf> {
+        let len = buf.len();
+        Ok(buf.drain_to(len))
+    }
+
+    fn encode(&mut self, msg: Vec<u8>, buf: &mut Vec<u8>) -> io::Result<()> {
+        buf.extend_from_slice(&msg);
+        buf.push(b'\n');
+        Ok(())
+    }
+}
+
+#[test]
+fn easy_buf() {
+    let mut buf = EasyBuf::from(b"hello world".to_vec());
+    let hello = buf.drain_to(5);
+    assert_eq!(hello.as_slice(), b"hello");
+    assert_eq!(buf.as_slice(), b" world");
+
+    let world = buf.split_off(1);
+    assert_eq!(buf.as_slice(), b" ");
+    assert_eq!(world.as_slice(), b"world");
+
+    // Mutating a shared buffer leaves the other handles untouched.
+    buf.get_mut().extend_from_slice(b"there");
+    assert_eq!(buf.as_slice(), b" there");
+    assert_eq!(hello.as_slice(), b"hello");
+    assert_eq!(world.as_slice(), b"world");
+    assert_eq!(buf.len(), 6);
+}
+
+#[test]
+fn easy_buf_extend() {
+    let mut buf = EasyBuf::with_capacity(16);
+    buf.extend_from_slice(b"hello world");
+    assert_eq!(buf.capacity(), 16);
+
+    // Draining a frame off of a unique buffer doesn't move the rest of it.
+    drop(buf.drain_to(6));
+    assert_eq!(buf.capacity(), 10);
+    buf.extend_from_slice(b"!");
+    assert_eq!(buf.as_slice(), b"world!");
+
+    buf.reserve(100);
+    assert!(buf.capacity() - buf.len() >= 100);
+
+    // A shared buffer is copied once, leaving the other handle untouched.
+    let other = buf.clone();
+    buf.as_mut_slice()[0] = b'W';
+    assert_eq!(buf.as_slice(), b"World!");
+    assert_eq!(other.as_slice(), b"world!");
+
+    let mut other = other;
+    let tail = other.split_off(5);
+    other.extend_from_slice(b"?");
+    assert_eq!(other.as_slice(), b"world?");
+    assert_eq!(tail.as_slice(), b"!");
+}
+
+#[test]
+fn easy_buf_vec() {
+    let v = b"hello world".to_vec();
+    let ptr = v.as_ptr();
+    let buf = EasyBuf::from(v);
+
+    let world = buf.slice(6, 11);
+    let hello = buf.slice(0, 5);
+    drop(buf);
+    assert_eq!(world.as_slice(), b"world");
+    assert_eq!(world.as_slice().as_ptr(), unsafe { ptr.offset(6) });
+
+    // A shared buffer is copied, and the last handle gets the allocation.
+    assert_eq!(world.into_vec(), b"world");
+    let v: Vec<u8> = hello.into();
+    assert_eq!(v, b"hello");
+    assert_eq!(v.as_ptr(), ptr);
+}
+
+#[test]
+fn decode_frames() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let (mut s, _) = t!(srv.accept());
+        // Split a frame across writes to exercise partial reads.
+        t!(s.write_all(b"hello\nwo"));
+        t!(s.flush());
+        thread::sleep(Duration::from_millis(50));
+        t!(s.write_all(b"rld\n\nlast"));
+    });
+
+    let stream = TcpStream::connect(&addr, &l.handle());
+    let frames = t!(l.run(stream.and_then(|s| s.framed(Newlines).collect())));
+    t.join().unwrap();
+
+    let frames = frames.iter().map(|f| f.as_slice()).collect::<Vec<_>>();
+    assert_eq!(frames, [&b"hello"[..], b"world", b"", b"last"]);
+}
+
+#[test]
+fn encode_frames() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let (mut s, _) = t!(srv.accept());
+        let mut data = Vec::new();
+        t!(s.read_to_end(&mut data));
+        data
+    });
+
+    let stream = TcpStream::connect(&addr, &l.handle());
+    let frames = vec![b"one".to_vec(), b"two".to_vec(), vec![b'x'; 64 * 1024]];
+    let framed = t!(l.run(stream.and_then(|s| {
+        s.framed(Newlines).send_all(futures::stream::iter_ok::<_, io::Error>(frames))
+    })));
+    drop(framed);
+
+    let data = t.join().unwrap();
+    let mut expected = b"one\ntwo\n".to_vec();
+    expected.extend_from_slice(&[b'x'; 64 * 1024]);
+    expected.push(b'\n');
+    assert_eq!(data, expected);
+}
+
+#[test]
+fn max_read_buffer() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let (mut s, _) = t!(srv.accept());
+        t!(s.write_all(&[b'x'; 100]));
+        // Keep the connection open until the client gives up.
+        let mut buf = Vec::new();
+        drop(s.read_to_end(&mut buf));
+    });
+
+    let stream = TcpStream::connect(&addr, &l.handle());
+    let err = l.run(stream.and_then(|s| {
+        let mut framed = s.framed(Newlines);
+        framed.set_max_read_buffer(64);
+        framed.into_future().map_err(|(e, _)| e)
+    })).err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    t.join().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn read_and_write_halves() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let mut child = t!(Command::new("cat")
+                               .stdin(Stdio::piped())
+                               .stdout(Stdio::piped())
+                               .spawn_async(&l.handle()));
+    let stdin = FramedWrite::new(child.stdin().take().unwrap(), Newlines);
+    let stdout = FramedRead::new(child.stdout().take().unwrap(), Newlines);
+
+    let frames = vec![b"one".to_vec(), b"two".to_vec()];
+    let write = stdin.send_all(futures::stream::iter_ok::<_, io::Error>(frames))
+                     .map(drop);
+    let read = stdout.collect();
+    let (((), frames), status) = t!(l.run(write.join(read).join(child)));
+    assert!(status.success());
+
+    let frames = frames.iter().map(|f| f.as_slice()).collect::<Vec<_>>();
+    assert_eq!(frames, [&b"one"[..], b"two"]);
+}
+
+#[test]
+fn switch_codecs() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let (mut s, _) = t!(srv.accept());
+        // The data following the line is likely to be read along with it.
+        t!(s.write_all(b"upgrade\n\x00\x00\x00\x05hello"));
+        let mut data = Vec::new();
+        t!(s.read_to_end(&mut data));
+        data
+    });
+
+    let stream = TcpStream::connect(&addr, &l.handle());
+    let frames = t!(l.run(stream.and_then(|s| {
+        s.framed(Newlines).into_future().map_err(|(e, _)| e)
+    }).and_then(|(line, mut framed)| {
+        assert_eq!(line.unwrap().as_slice(), b"upgrade");
+        t!(framed.start_send(b"ok".to_vec()));
+        let parts = framed.into_parts();
+        assert_eq!(parts.write_buf, b"ok\n");
+        let framed = Framed::from_parts(parts, LengthDelimited::new());
+        framed.flush().and_then(|framed| framed.into_future().map_err(|(e, _)| e))
+    })));
+
+    let (frame, framed) = frames;
+    assert_eq!(frame.unwrap().as_slice(), b"hello");
+    drop(framed);
+    assert_eq!(t.join().unwrap(), b"ok\n");
+}
+
+/// Frames of four bytes starting with `0xaa`.
+struct Magic;
+
+impl Codec for Magic {
+    type In = EasyBuf;
+    type Out = Vec<u8>;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<EasyBuf>> {
+        if buf.is_empty() {
+            return Ok(None)
+        }
+        if buf.as_slice()[0] != 0xaa {
+            let skip = buf.as_slice().iter().position(|b| *b == 0xaa).unwrap_or(buf.len());
+            return Err(Resync::new(skip, "missing magic byte").into())
+        }
+        if buf.len() < 4 {
+            return Ok(None)
+        }
+        Ok(Some(buf.drain_to(4)))
+    }
+
+    fn encode(&mut self, msg: Vec<u8>, buf: &mut Vec<u8>) -> io::Result<()> {
+        buf.extend_from_slice(&msg);
+        Ok(())
+    }
+}
+
+#[test]
+fn resync() {
+    let data = b"\xaa123junk\xaa456junk".to_vec();
+    let frames = FramedRead::new(io::Cursor::new(data), Magic).then(|res| {
+        Ok::<_, ()>(res.map(|f| f.as_slice().to_vec()).map_err(|e| e.kind()))
+    });
+    let frames = frames.collect().wait().unwrap();
+    assert_eq!(frames, vec![Ok(b"\xaa123".to_vec()),
+                            Err(io::ErrorKind::InvalidData),
+                            Ok(b"\xaa456".to_vec()),
+                            Err(io::ErrorKind::InvalidData)]);
+}
+
+#[test]
+fn resync_past_buffer() {
+    // The skipped bytes may not have been read yet.
+    struct Skip;
+
+    impl Codec for Skip {
+        type In = u8;
+        type Out = u8;
+
+        fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<u8>> {
+            let b = match buf.as_slice().first() {
+                Some(b) => *b,
+                None => return Ok(None),
+            };
+            buf.drain_to(1);
+            if b == 0 {
+                return Err(Resync::new(10_000, "skip").into())
+            }
+            Ok(Some(b))
+        }
+
+        fn encode(&mut self, msg: u8, buf: &mut Vec<u8>) -> io::Result<()> {
+            buf.push(msg);
+            Ok(())
+        }
+    }
+
+    let mut data = vec![1, 0];
+    data.extend(vec![3; 10_000]);
+    data.push(2);
+    let frames = FramedRead::new(io::Cursor::new(data), Skip).then(|res| {
+        Ok::<_, ()>(res.map_err(|e| e.kind()))
+    });
+    let frames = frames.collect().wait().unwrap();
+    assert_eq!(frames, vec![Ok(1), Err(io::ErrorKind::InvalidData), Ok(2)]);
+}
+
+#[test]
+fn peek_read_buffer() {
+    let data = b"\xaa123\xaa456junk".to_vec();
+    let mut frames = FramedRead::new(io::Cursor::new(data), Magic);
+    assert_eq!(frames.read_buffer(), b"");
+    assert!(t!(frames.peek_frame()).is_none());
+
+    let frame = t!(frames.poll()).map(|f| f.unwrap().into_vec());
+    assert_eq!(frame, Async::Ready(b"\xaa123".to_vec()));
+    assert_eq!(frames.read_buffer(), b"\xaa456junk");
+    let peeked = t!(frames.peek_frame()).unwrap();
+    assert_eq!(peeked.as_slice(), b"\xaa456");
+    assert_eq!(frames.read_buffer(), b"\xaa456junk");
+
+    let frame = t!(frames.poll()).map(|f| f.unwrap().into_vec());
+    assert_eq!(frame, Async::Ready(b"\xaa456".to_vec()));
+    assert!(frames.peek_frame().is_err());
+    assert!(frames.poll().is_err());
+    assert_eq!(frames.read_buffer(), b"junk");
+    assert!(t!(frames.peek_frame()).is_none());
+}
+
+#[test]
+fn hooks() {
+    let decoded = Rc::new(Cell::new(0));
+    let count = decoded.clone();
+    let mut codec = Hooked::new(Newlines);
+    codec.set_decode_hook(move |frame| {
+        count.set(count.get() + 1);
+        let upper = frame.as_slice().to_ascii_uppercase();
+        *frame = upper.into();
+        Ok(())
+    });
+    let frames = FramedRead::new(io::Cursor::new(b"one\ntwo".to_vec()), codec);
+    let frames = t!(frames.collect().wait());
+    let frames = frames.iter().map(|f| f.as_slice()).collect::<Vec<_>>();
+    assert_eq!(frames, [&b"ONE"[..], b"TWO"]);
+    assert_eq!(decoded.get(), 2);
+
+    let mut framed = FramedWrite::new(Vec::new(), Hooked::new(Newlines));
+    framed.codec_mut().set_encode_hook(|frame| {
+        if frame.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "empty frame"))
+        }
+        frame.insert(0, b'>');
+        Ok(())
+    });
+    let framed = t!(framed.send(b"hi".to_vec()).wait());
+    assert_eq!(framed.get_ref(), b">hi\n");
+    let err = framed.send(Vec::new()).wait().err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+/// Writes each frame followed by a newline, failing partway through frames
+/// starting with `!`.
+struct FailingNewlines;
+
+impl Codec for FailingNewlines {
+    type In = EasyBuf;
+    type Out = Vec<u8>;
+
+    fn decode(&mut self, buf: &mut EasyBuf) -> io::Result<Option<EasyBuf>> {
+        Newlines.decode(buf)
+    }
+
+    fn encode(&mut self, msg: Vec<u8>, buf: &mut Vec<u8>) -> io::Result<()> {
+        buf.extend_from_slice(&msg);
+        if msg.starts_with(b"!") {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "bad frame"))
+        }
+        buf.push(b'\n');
+        Ok(())
+    }
+}
+
+#[test]
+fn encode_error() {
+    let mut sink = FramedWrite::new(Vec::new(), FailingNewlines);
+    assert!(t!(sink.start_send(b"one".to_vec())).is_ready());
+    let err = sink.start_send(b"!two".to_vec()).err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert!(t!(sink.start_send(b"three".to_vec())).is_ready());
+    assert!(t!(sink.poll_complete()).is_ready());
+
+    // Nothing the codec wrote for the failed frame is sent.
+    assert_eq!(sink.get_ref(), b"one\nthree\n");
+}
+
+/// A writer counting the writes made to it.
+struct Writes {
+    data: Vec<u8>,
+    writes: usize,
+}
+
+impl Write for Writes {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes += 1;
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A writer accepting a limited number of bytes before blocking, counting
+/// the vectored writes made to it.
+struct Limited {
+    data: Vec<u8>,
+    budget: usize,
+    vectored: usize,
+}
+
+impl Write for Limited {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.budget == 0 {
+            return Err(io::Error::new(io::ErrorKind::WouldBlock, "no budget"))
+        }
+        let n = std::cmp::min(buf.len(), self.budget);
+        self.data.extend_from_slice(&buf[..n]);
+        self.budget -= n;
+        Ok(n)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice]) -> io::Result<usize> {
+        self.vectored += 1;
+        let mut n = 0;
+        for buf in bufs {
+            n += match self.write(buf) {
+                Ok(n) => n,
+                Err(e) => if n == 0 { return Err(e) } else { break },
+            };
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn vectored_flush() {
+    let writer = Limited { data: Vec::new(), budget: 3, vectored: 0 };
+    let mut sink = FramedWrite::new(writer, Newlines);
+    assert!(t!(sink.start_send(b"hello".to_vec())).is_ready());
+    assert!(t!(sink.poll_complete()).is_not_ready());
+    assert_eq!(sink.get_ref().data, b"hel");
+
+    // The frames sent now are written along with the rest of the first one.
+    assert!(t!(sink.start_send(b"world".to_vec())).is_ready());
+    assert!(t!(sink.start_send(b"!".to_vec())).is_ready());
+    sink.get_mut().budget = 100;
+    assert!(t!(sink.poll_complete()).is_ready());
+    assert_eq!(sink.get_ref().data, b"hello\nworld\n!\n");
+    assert_eq!(sink.get_ref().vectored, 1);
+
+    // Writing everything empties the buffers.
+    assert!(t!(sink.start_send(b"again".to_vec())).is_ready());
+    assert!(t!(sink.poll_complete()).is_ready());
+    assert_eq!(sink.get_ref().data, b"hello\nworld\n!\nagain\n");
+    assert_eq!(sink.get_ref().vectored, 1);
+}
+
+impl Read for Limited {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::WouldBlock, "nothing to read"))
+    }
+}
+
+impl AsyncRead for Limited {}
+
+impl AsyncWrite for Limited {}
+
+#[test]
+fn into_parts_queued() {
+    let writer = Limited { data: Vec::new(), budget: 2, vectored: 0 };
+    let mut framed = writer.framed(Newlines);
+    assert!(t!(framed.start_send(b"one".to_vec())).is_ready());
+    assert!(t!(framed.poll_complete()).is_not_ready());
+    assert!(t!(framed.start_send(vec![b'x'; 1000])).is_ready());
+    assert!(t!(framed.start_send(b"two".to_vec())).is_ready());
+
+    // The unwritten parts of all the frames are handed back in order.
+    let parts = framed.into_parts();
+    let mut expected = b"e\n".to_vec();
+    expected.extend(vec![b'x'; 1000]);
+    expected.extend_from_slice(b"\ntwo\n");
+    assert_eq!(parts.inner.data, b"on");
+    assert_eq!(parts.write_buf, expected);
+}
+
+/// A reader returning the chunks pushed to it, and blocking once they've all
+/// been read.
+struct Chunks(Vec<Vec<u8>>);
+
+impl Read for Chunks {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::WouldBlock, "no chunk"))
+        }
+        let chunk = self.0.remove(0);
+        buf[..chunk.len()].copy_from_slice(&chunk);
+        Ok(chunk.len())
+    }
+}
+
+#[test]
+fn reset() {
+    let mut frames = FramedRead::new(Chunks(vec![b"\x01\0\0\0\x05ab".to_vec()]),
+                                     Tagged::new());
+    assert!(t!(frames.poll()).is_not_ready());
+    assert_eq!(frames.read_buffer(), b"ab");
+
+    // The codec no longer waits for the rest of the first frame.
+    frames.reset();
+    assert_eq!(frames.read_buffer(), b"");
+    frames.get_mut().0.push(b"\x02\0\0\0\x02hi".to_vec());
+    let frame = t!(frames.poll()).map(|f| f.map(|(tag, data)| (tag, data.into_vec())));
+    assert_eq!(frame, Async::Ready(Some((2, b"hi".to_vec()))));
+
+    let writer = Limited { data: Vec::new(), budget: 0, vectored: 0 };
+    let mut sink = FramedWrite::new(writer, Newlines);
+    assert!(t!(sink.start_send(b"dropped".to_vec())).is_ready());
+    assert!(t!(sink.poll_complete()).is_not_ready());
+    sink.reset();
+    sink.get_mut().budget = 100;
+    assert!(t!(sink.start_send(b"sent".to_vec())).is_ready());
+    assert!(t!(sink.poll_complete()).is_ready());
+    assert_eq!(sink.get_ref().data, b"sent\n");
+
+    // The rest of a partly written frame is still written.
+    let writer = Limited { data: Vec::new(), budget: 3, vectored: 0 };
+    let mut sink = FramedWrite::new(writer, Newlines);
+    assert!(t!(sink.start_send(b"hello".to_vec())).is_ready());
+    assert!(t!(sink.poll_complete()).is_not_ready());
+    assert!(t!(sink.start_send(b"dropped".to_vec())).is_ready());
+    sink.reset();
+    sink.get_mut().budget = 100;
+    assert!(t!(sink.start_send(b"sent".to_vec())).is_ready());
+    assert!(t!(sink.poll_complete()).is_ready());
+    assert_eq!(sink.get_ref().data, b"hello\nsent\n");
+}
+
+#[test]
+fn reset_after_eof() {
+    let mut frames = FramedRead::new(io::Cursor::new(b"one\ntwo".to_vec()), Newlines);
+    let frames_read = t!(frames.by_ref().collect().wait());
+    assert_eq!(frames_read.len(), 2);
+
+    frames.reset();
+    frames.get_mut().get_mut().extend_from_slice(b"\nthree\n");
+    let frame = t!(frames.poll()).map(|f| f.map(|f| f.as_slice().to_vec()));
+    assert_eq!(frame, Async::Ready(Some(b"".to_vec())));
+    let frame = t!(frames.poll()).map(|f| f.map(|f| f.as_slice().to_vec()));
+    assert_eq!(frame, Async::Ready(Some(b"three".to_vec())));
+    assert_eq!(t!(frames.poll()).map(|f| f.is_none()), Async::Ready(true));
+
+    // Nor is a failure of `decode_eof` final once the framing is reset.
+    let mut frames = FramedRead::new(io::Cursor::new(b"\x01\0".to_vec()), Tagged::new());
+    assert!(frames.poll().is_err());
+    frames.reset();
+    assert_eq!(t!(frames.poll()).map(|f| f.is_none()), Async::Ready(true));
+}
+
+#[test]
+fn corked() {
+    let writer = Writes { data: Vec::new(), writes: 0 };
+    let mut sink = FramedWrite::new(writer, Newlines);
+    sink.set_corked(true);
+    sink.set_write_high_watermark(20);
+    for _ in 0..4 {
+        sink = t!(sink.send(b"abc".to_vec()).wait());
+    }
+    assert_eq!(sink.get_ref().writes, 0);
+    t!(sink.poll_flush());
+    assert_eq!(sink.get_ref().writes, 1);
+    assert_eq!(sink.get_ref().data, b"abc\nabc\nabc\nabc\n");
+
+    // Reaching the high watermark writes the frames out.
+    for _ in 0..5 {
+        sink = t!(sink.send(b"abc".to_vec()).wait());
+    }
+    assert_eq!(sink.get_ref().writes, 2);
+    assert_eq!(sink.get_ref().data.len(), 36);
+
+    sink.set_corked(false);
+    sink = t!(sink.send(b"abc".to_vec()).wait());
+    assert_eq!(sink.get_ref().writes, 3);
+}
+
+#[test]
+fn corked_close() {
+    let mut l = t!(Core::new());
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let stream = t!(l.run(TcpStream::connect(&addr, &l.handle())));
+    let (mut peer, _) = t!(srv.accept());
+
+    // Closing the sink writes out the frames held back by the cork, and
+    // shuts the stream down.
+    let mut framed = stream.framed(Newlines);
+    framed.set_corked(true);
+    let frames = vec![b"abc".to_vec(), b"def".to_vec()];
+    let frames = futures::stream::iter_ok::<_, io::Error>(frames);
+    drop(t!(l.run(framed.send_all(frames))));
+    let mut data = Vec::new();
+    t!(peer.read_to_end(&mut data));
+    assert_eq!(data, b"abc\ndef\n");
+}
+
+#[test]
+fn buffer_pool() {
+    let pool = BufferPool::with_classes(&[16, 8 * 1024], 2);
+    let buf = pool.get(10);
+    assert_eq!(buf.len(), 16);
+    pool.put(buf);
+    pool.put(vec![0; 8]);
+    assert_eq!(pool.idle(), 1);
+    assert_eq!(pool.get(10).len(), 16);
+    assert_eq!(pool.get(20).len(), 8 * 1024);
+    assert_eq!(pool.get(100_000).len(), 100_000);
+    assert_eq!(pool.idle(), 0);
+
+    // The buffers of a framed adapter go back to the pool once it's dropped,
+    // unless frames still point at them, in which case reading more copies
+    // the buffer into one which does go back.
+    let data = io::Cursor::new(b"foo\nbar\n".to_vec());
+    let mut frames = FramedRead::new(data, Newlines);
+    frames.set_buffer_pool(&pool);
+    let frames = frames.collect().wait().unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(pool.idle(), 1);
+
+    let data = io::Cursor::new(b"foo\nbar\n".to_vec());
+    let mut frames = FramedRead::new(data, Newlines);
+    frames.set_buffer_pool(&pool);
+    let n = frames.fold(0, |n, _| Ok::<_, io::Error>(n + 1)).wait().unwrap();
+    assert_eq!(n, 2);
+    assert_eq!(pool.idle(), 1);
+
+    let writer = Writes { data: Vec::new(), writes: 0 };
+    let mut sink = FramedWrite::new(writer, Newlines);
+    sink.set_buffer_pool(&pool);
+    let sink = t!(sink.send(b"abc".to_vec()).wait());
+    assert_eq!(pool.idle(), 0);
+    drop(sink);
+    assert_eq!(pool.idle(), 1);
+
+    // Each event loop has a pool.
+    let l = t!(Core::new());
+    l.handle().buffer_pool().put(vec![0; 8 * 1024]);
+    assert_eq!(l.handle().buffer_pool().idle(), 1);
+}
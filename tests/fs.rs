@@ -0,0 +1,226 @@
+extern crate futures;
+extern crate tokio_core;
+
+use std::env;
+use std::fs;
+use std::io::{Seek, SeekFrom};
+
+use futures::Future;
+use tokio_core::fs::File;
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn write_seek_read() {
+    let path = env::temp_dir().join(format!("tokio-core-fs-{}", std::process::id()));
+    let mut l = t!(Core::new());
+
+    let write = File::create(&path).and_then(|f| {
+        f.write_all(b"hello world")
+    }).and_then(|(f, _)| f.sync_all());
+    let f = t!(l.run(write));
+    drop(f);
+
+    let read = File::open(&path).and_then(|f| {
+        f.seek(SeekFrom::Start(6))
+    }).and_then(|(f, pos)| {
+        assert_eq!(pos, 6);
+        f.read(vec![0; 32])
+    }).and_then(|(f, buf, n)| {
+        assert_eq!(&buf[..n], b"world");
+        f.metadata()
+    });
+    let (_, metadata) = t!(l.run(read));
+    assert_eq!(metadata.len(), 11);
+
+    t!(fs::remove_file(&path));
+}
+
+#[test]
+fn async_seek() {
+    use tokio_core::io::seek;
+
+    let path = env::temp_dir().join(format!("tokio-core-fs-seek-{}", std::process::id()));
+    t!(fs::write(&path, b"hello world"));
+    let mut l = t!(Core::new());
+
+    let read = File::open(&path).and_then(|f| {
+        seek(f, SeekFrom::End(-5))
+    }).and_then(|(f, pos)| {
+        assert_eq!(pos, 6);
+        f.read(vec![0; 32])
+    }).and_then(|(f, buf, n)| {
+        assert_eq!(&buf[..n], b"world");
+        seek(f, SeekFrom::Current(-3))
+    }).and_then(|(f, pos)| {
+        assert_eq!(pos, 8);
+        f.read(vec![0; 32])
+    });
+    let (mut f, buf, n) = t!(l.run(read));
+    assert_eq!(&buf[..n], b"rld");
+
+    // Seeking synchronously doesn't need a task.
+    assert_eq!(t!(Seek::seek(&mut f, SeekFrom::Start(1))), 1);
+    let (_, buf, n) = t!(l.run(f.read(vec![0; 4])));
+    assert_eq!(&buf[..n], b"ello");
+
+    t!(fs::remove_file(&path));
+}
+
+#[test]
+fn open_missing() {
+    let mut l = t!(Core::new());
+    let path = env::temp_dir().join("tokio-core-fs-does-not-exist");
+    assert!(l.run(File::open(path)).is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn watch_directory() {
+    use futures::stream::Stream;
+    use tokio_core::fs::{Watcher, EventKind};
+
+    let dir = env::temp_dir().join(format!("tokio-core-watch-{}", std::process::id()));
+    t!(fs::create_dir_all(&dir));
+    let mut l = t!(Core::new());
+    let mut watcher = t!(Watcher::new(&l.handle()));
+    let watch = t!(watcher.add(&dir));
+
+    let file = dir.join("a");
+    t!(fs::write(&file, b"hello"));
+    t!(fs::rename(&file, dir.join("b")));
+
+    let events = watcher.filter(|e| e.kind() != EventKind::Modify).take(3);
+    let events = t!(l.run(events.collect()));
+    let kinds = events.iter().map(|e| e.kind()).collect::<Vec<_>>();
+    assert_eq!(kinds, [EventKind::Create, EventKind::RenameFrom,
+                       EventKind::RenameTo]);
+    assert_eq!(events[0].path(), &*file);
+    assert_eq!(events[0].watch(), Some(watch));
+    assert_eq!(events[2].path(), &*dir.join("b"));
+    assert!(events[1].cookie() != 0);
+    assert_eq!(events[1].cookie(), events[2].cookie());
+
+    t!(fs::remove_dir_all(&dir));
+}
+
+#[test]
+fn directory_operations() {
+    use futures::stream::Stream;
+    use tokio_core::fs;
+
+    let root = env::temp_dir().join(format!("tokio-core-dir-{}", std::process::id()));
+    let dir = root.join("a").join("b");
+    let mut l = t!(Core::new());
+
+    t!(l.run(fs::create_dir_all(&dir)));
+    assert!(t!(l.run(fs::metadata(&dir))).is_dir());
+
+    // More entries than are read in a single batch.
+    for i in 0..50 {
+        t!(std::fs::write(dir.join(format!("{}", i)), b"x"));
+    }
+    t!(l.run(fs::rename(dir.join("0"), dir.join("renamed"))));
+    t!(l.run(fs::remove_file(dir.join("1"))));
+
+    let entries = t!(l.run(fs::read_dir(&dir).collect()));
+    let mut names = entries.iter().map(|e| {
+        e.file_name().into_string().unwrap()
+    }).collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names.len(), 49);
+    assert!(names.contains(&"renamed".to_string()));
+    assert!(!names.contains(&"0".to_string()));
+    assert!(!names.contains(&"1".to_string()));
+    assert_eq!(t!(l.run(fs::metadata(dir.join("renamed")))).len(), 1);
+
+    assert!(l.run(fs::read_dir(root.join("missing")).collect()).is_err());
+    assert!(l.run(fs::remove_file(dir.join("1"))).is_err());
+
+    t!(std::fs::remove_dir_all(&root));
+}
+
+#[cfg(unix)]
+#[test]
+fn mmap() {
+    use std::io::Read;
+    use tokio_core::fs::{Advice, Mmap};
+    use tokio_core::io::{copy, read_to_end, AsyncRead};
+    use tokio_core::net::UnixStream;
+
+    let path = env::temp_dir().join(format!("tokio-core-mmap-{}", std::process::id()));
+    let contents = (0..100_000).map(|i| i as u8).collect::<Vec<_>>();
+    t!(fs::write(&path, &contents));
+    let mut l = t!(Core::new());
+
+    let map = t!(l.run(Mmap::open(&path)));
+    assert_eq!(&map[..], &contents[..]);
+    t!(map.advise(Advice::Sequential));
+    t!(map.advise_range(Advice::WillNeed, 5000, 10_000));
+    assert!(map.advise_range(Advice::WillNeed, 99_000, 10_000).is_err());
+
+    let mut reader = map.reader();
+    t!(reader.seek(SeekFrom::End(-10)));
+    let mut tail = Vec::new();
+    t!(reader.read_to_end(&mut tail));
+    assert_eq!(tail, &contents[contents.len() - 10..]);
+    assert!(reader.poll_read().is_ready());
+    assert!(reader.seek(SeekFrom::End(-100_001)).is_err());
+    assert_eq!(reader.position(), contents.len());
+    if let Ok(n) = reader.seek(SeekFrom::Start(u64::MAX)) {
+        assert_eq!(n, u64::MAX);
+        assert!(reader.seek(SeekFrom::Current(1)).is_err());
+    }
+
+    let (a, b) = t!(UnixStream::pair(&l.handle()));
+    let send = copy(map.reader(), a).map(drop);
+    let recv = read_to_end(b, Vec::new()).map(|p| p.1);
+    let (_, received) = t!(l.run(send.join(recv)));
+    assert_eq!(received, contents);
+
+    let empty = env::temp_dir().join(format!("tokio-core-mmap-empty-{}", std::process::id()));
+    t!(fs::write(&empty, b""));
+    let map = t!(l.run(Mmap::open(&empty)));
+    assert!(map.is_empty());
+    t!(map.advise(Advice::WillNeed));
+
+    t!(fs::remove_file(&path));
+    t!(fs::remove_file(&empty));
+}
+
+#[cfg(unix)]
+#[test]
+fn mmap_range() {
+    use tokio_core::fs::Mmap;
+    use tokio_core::io::{copy_exact, read_exact, seek, BufReader};
+
+    let path = env::temp_dir().join(format!("tokio-core-mmap-range-{}", std::process::id()));
+    let contents = (0..10_000).map(|i| i as u8).collect::<Vec<_>>();
+    t!(fs::write(&path, &contents));
+    let mut l = t!(Core::new());
+    let map = t!(l.run(Mmap::open(&path)));
+
+    let (reader, pos) = t!(l.run(seek(map.reader(), SeekFrom::Start(5000))));
+    assert_eq!(pos, 5000);
+    let (_, range) = t!(l.run(copy_exact(reader, Vec::new(), 100)));
+    assert_eq!(range, &contents[5000..5100]);
+
+    // Relative seeks of a `BufReader` don't count the data it has buffered.
+    let reader = BufReader::with_capacity(64, map.reader());
+    let (reader, head) = t!(l.run(read_exact(reader, [0; 10])));
+    assert_eq!(head, &contents[..10]);
+    assert_eq!(reader.buffer().len(), 54);
+    let (reader, pos) = t!(l.run(seek(reader, SeekFrom::Current(-5))));
+    assert_eq!(pos, 5);
+    assert!(reader.buffer().is_empty());
+    let (_, data) = t!(l.run(read_exact(reader, [0; 5])));
+    assert_eq!(data, &contents[5..10]);
+
+    t!(fs::remove_file(&path));
+}
@@ -0,0 +1,81 @@
+#![cfg(target_os = "linux")]
+
+extern crate futures;
+extern crate libc;
+#[macro_use]
+extern crate tokio_core;
+
+use std::io;
+
+use futures::future::poll_fn;
+use tokio_core::net::NetlinkSocket;
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+const NLMSG_DONE: u16 = 3;
+const RTM_NEWLINK: u16 = 16;
+const RTM_GETLINK: u16 = 18;
+
+fn u16_at(buf: &[u8], pos: usize) -> u16 {
+    u16::from_ne_bytes([buf[pos], buf[pos + 1]])
+}
+
+fn u32_at(buf: &[u8], pos: usize) -> u32 {
+    u32::from_ne_bytes([buf[pos], buf[pos + 1], buf[pos + 2], buf[pos + 3]])
+}
+
+#[test]
+fn dump_links() {
+    let mut l = t!(Core::new());
+    let socket = t!(NetlinkSocket::bind(libc::NETLINK_ROUTE, 0, &l.handle()));
+    assert!(t!(socket.port_id()) != 0);
+
+    // An `nlmsghdr` followed by an empty `ifinfomsg`.
+    let mut req = Vec::new();
+    req.extend_from_slice(&32u32.to_ne_bytes());
+    req.extend_from_slice(&RTM_GETLINK.to_ne_bytes());
+    let flags = (libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16;
+    req.extend_from_slice(&flags.to_ne_bytes());
+    req.extend_from_slice(&1u32.to_ne_bytes());
+    req.extend_from_slice(&0u32.to_ne_bytes());
+    req.extend_from_slice(&[0; 16]);
+
+    let send = poll_fn(|| Ok::<_, io::Error>(try_nb!(socket.send(&req)).into()));
+    assert_eq!(t!(l.run(send)), req.len());
+
+    let mut links = 0;
+    let recv = poll_fn(|| {
+        let mut buf = vec![0; 32 * 1024];
+        loop {
+            let (n, port) = try_nb!(socket.recv_from(&mut buf));
+            assert_eq!(port, 0);
+            let mut pos = 0;
+            while pos + 16 <= n {
+                let len = u32_at(&buf, pos) as usize;
+                match u16_at(&buf, pos + 4) {
+                    RTM_NEWLINK => links += 1,
+                    NLMSG_DONE => return Ok::<_, io::Error>(().into()),
+                    ty => panic!("unexpected message type {}", ty),
+                }
+                pos += (len + 3) & !3;
+            }
+        }
+    });
+    t!(l.run(recv));
+    // There's always at least the loopback interface.
+    assert!(links >= 1);
+}
+
+#[test]
+fn membership() {
+    let l = t!(Core::new());
+    let socket = t!(NetlinkSocket::bind(libc::NETLINK_ROUTE, 0, &l.handle()));
+    t!(socket.add_membership(libc::RTNLGRP_LINK));
+    t!(socket.drop_membership(libc::RTNLGRP_LINK));
+}
@@ -0,0 +1,179 @@
+#![cfg(unix)]
+
+extern crate futures;
+extern crate libc;
+extern crate tokio_core;
+
+use std::os::unix::process::{CommandExt as StdCommandExt, ExitStatusExt};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use futures::Future;
+use tokio_core::io::{read_to_end, write_all, AsyncRead, AsyncWrite};
+use tokio_core::process::CommandExt;
+use tokio_core::signal;
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn exit_status() {
+    let mut l = t!(Core::new());
+    let child = t!(Command::new("sh").arg("-c").arg("exit 3")
+                                     .spawn_async(&l.handle()));
+    let status = t!(l.run(child));
+    assert_eq!(status.code(), Some(3));
+}
+
+#[test]
+fn piped_io() {
+    let mut l = t!(Core::new());
+    let mut child = t!(Command::new("cat")
+                               .stdin(Stdio::piped())
+                               .stdout(Stdio::piped())
+                               .spawn_async(&l.handle()));
+    let stdin = child.stdin().take().unwrap();
+    let stdout = child.stdout().take().unwrap();
+
+    let write = write_all(stdin, b"hello, world".to_vec()).map(drop);
+    let read = read_to_end(stdout, Vec::new()).map(|p| p.1);
+    let (((), data), status) = t!(l.run(write.join(read).join(child)));
+    assert_eq!(data, b"hello, world");
+    assert!(status.success());
+}
+
+#[test]
+fn async_pipes() {
+    fn reader<R: AsyncRead>(r: R) -> R { r }
+    fn writer<W: AsyncWrite>(w: W) -> W { w }
+
+    let mut l = t!(Core::new());
+    let mut child = t!(Command::new("cat")
+                               .stdin(Stdio::piped())
+                               .stdout(Stdio::piped())
+                               .spawn_async(&l.handle()));
+    let stdin = writer(child.stdin().take().unwrap());
+    let stdout = reader(child.stdout().take().unwrap());
+
+    let write = write_all(stdin, b"hello").and_then(|(mut stdin, _)| {
+        futures::future::poll_fn(move || stdin.shutdown())
+    });
+    let read = read_to_end(stdout, Vec::new()).map(|p| p.1);
+    let (((), data), status) = t!(l.run(write.join(read).join(child)));
+    assert_eq!(data, b"hello");
+    assert!(status.success());
+}
+
+#[test]
+fn wait_with_output() {
+    let mut l = t!(Core::new());
+    let child = t!(Command::new("sh").arg("-c").arg("echo out; echo err >&2")
+                                     .stdout(Stdio::piped())
+                                     .stderr(Stdio::piped())
+                                     .spawn_async(&l.handle()));
+    let output = t!(l.run(child.wait_with_output()));
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"out\n");
+    assert_eq!(output.stderr, b"err\n");
+}
+
+#[test]
+fn kill_and_wait() {
+    let mut l = t!(Core::new());
+    let child = t!(Command::new("sleep").arg("30").spawn_async(&l.handle()));
+    let status = t!(l.run(child.kill_and_wait()));
+    assert_eq!(status.signal(), Some(libc::SIGKILL));
+}
+
+#[test]
+fn kill_and_wait_exited() {
+    let mut l = t!(Core::new());
+    let child = t!(Command::new("true").spawn_async(&l.handle()));
+    // Give the child time to exit before it's killed.
+    thread::sleep(Duration::from_millis(100));
+    let status = t!(l.run(child.kill_and_wait()));
+    assert!(status.success());
+}
+
+#[test]
+fn send_signal() {
+    let mut l = t!(Core::new());
+    let mut child = t!(Command::new("sleep").arg("30").spawn_async(&l.handle()));
+    t!(child.signal(signal::SIGTERM));
+    let status = t!(l.run(child));
+    assert_eq!(status.signal(), Some(libc::SIGTERM));
+}
+
+#[test]
+fn kill_on_drop() {
+    let l = t!(Core::new());
+    let mut child = t!(Command::new("sleep").arg("30").spawn_async(&l.handle()));
+    let pid = child.id() as libc::pid_t;
+    child.set_kill_on_drop(true);
+    drop(child);
+
+    // The child may or may not have been reaped already when it was dropped.
+    let mut status = 0;
+    let rc = unsafe { libc::waitpid(pid, &mut status, 0) };
+    if rc == pid {
+        assert!(libc::WIFSIGNALED(status));
+        assert_eq!(libc::WTERMSIG(status), libc::SIGKILL);
+    } else {
+        assert_eq!(rc, -1);
+    }
+}
+
+#[test]
+fn process_group() {
+    let mut l = t!(Core::new());
+    let mut child = t!(Command::new("sh")
+                               .arg("-c")
+                               .arg("sleep 30 & wait")
+                               .process_group(0)
+                               .spawn_async(&l.handle()));
+    let pgid = unsafe { libc::getpgid(child.id() as libc::pid_t) };
+    assert_eq!(pgid, child.id() as libc::pid_t);
+    t!(child.signal_group(signal::SIGTERM));
+    let status = t!(l.run(child));
+    assert_eq!(status.signal(), Some(libc::SIGTERM));
+}
+
+#[test]
+fn pre_exec() {
+    let mut l = t!(Core::new());
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg("umask").stdout(Stdio::piped());
+    unsafe {
+        cmd.pre_exec(|| {
+            libc::umask(0o027);
+            Ok(())
+        });
+    }
+    let child = t!(cmd.spawn_async(&l.handle()));
+    let output = t!(l.run(child.wait_with_output()));
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"0027\n");
+}
+
+#[test]
+fn drop_privileges() {
+    if unsafe { libc::geteuid() } != 0 {
+        return
+    }
+    let mut l = t!(Core::new());
+    let child = t!(Command::new("id")
+                           .arg("-u")
+                           .uid(65534)
+                           .gid(65534)
+                           .stdout(Stdio::piped())
+                           .spawn_async(&l.handle()));
+    let output = t!(l.run(child.wait_with_output()));
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"65534\n");
+}
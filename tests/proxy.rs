@@ -0,0 +1,168 @@
+extern crate env_logger;
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::{self, Read, Write};
+use std::net::{self, SocketAddr};
+use std::thread;
+
+use tokio_core::io::read_to_end;
+use tokio_core::net::{http_connect, socks5_connect};
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+/// Runs a fake proxy on a helper thread, serving a single client with `f`.
+fn proxy<F>(f: F) -> (SocketAddr, thread::JoinHandle<()>)
+    where F: FnOnce(net::TcpStream) + Send + 'static,
+{
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        f(t!(srv.accept()).0);
+    });
+    (addr, t)
+}
+
+fn socks5_handshake(s: &mut net::TcpStream) -> Vec<u8> {
+    let mut greeting = [0; 3];
+    t!(s.read_exact(&mut greeting));
+    assert_eq!(greeting, [5, 1, 0]);
+    t!(s.write_all(&[5, 0]));
+
+    let mut request = [0; 4];
+    t!(s.read_exact(&mut request));
+    assert_eq!(&request[..3], &[5, 1, 0]);
+    let mut addr = match request[3] {
+        1 => vec![0; 4],
+        3 => {
+            let mut len = [0; 1];
+            t!(s.read_exact(&mut len));
+            vec![0; len[0] as usize]
+        }
+        4 => vec![0; 16],
+        n => panic!("unexpected address type {}", n),
+    };
+    t!(s.read_exact(&mut addr));
+    let mut port = [0; 2];
+    t!(s.read_exact(&mut port));
+    assert_eq!(port, [0x1f, 0x90]);
+    addr
+}
+
+#[test]
+fn socks5_domain() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (addr, t) = proxy(|mut s| {
+        assert_eq!(socks5_handshake(&mut s), b"example.com");
+        t!(s.write_all(&[5, 0, 0, 3, 4, b'h', b'o', b's', b't', 0, 80]));
+        t!(s.write_all(b"tunneled"));
+    });
+
+    let stream = t!(l.run(socks5_connect(&addr, "example.com:8080", &l.handle())));
+    let (_, data) = t!(l.run(read_to_end(stream, Vec::new())));
+    assert_eq!(data, b"tunneled");
+    t.join().unwrap();
+}
+
+#[test]
+fn socks5_ip() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (addr, t) = proxy(|mut s| {
+        assert_eq!(socks5_handshake(&mut s), [10, 0, 0, 1]);
+        t!(s.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 80]));
+        t!(s.write_all(b"tunneled"));
+    });
+
+    let stream = t!(l.run(socks5_connect(&addr, "10.0.0.1:8080", &l.handle())));
+    let (_, data) = t!(l.run(read_to_end(stream, Vec::new())));
+    assert_eq!(data, b"tunneled");
+    t.join().unwrap();
+}
+
+#[test]
+fn socks5_refused() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (addr, t) = proxy(|mut s| {
+        socks5_handshake(&mut s);
+        t!(s.write_all(&[5, 5, 0, 1, 0, 0, 0, 0, 0, 0]));
+    });
+
+    let err = l.run(socks5_connect(&addr, "[::1]:8080", &l.handle()))
+               .err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+    t.join().unwrap();
+}
+
+#[test]
+fn socks5_invalid_target() {
+    let mut l = t!(Core::new());
+    let addr = "127.0.0.1:1".parse().unwrap();
+    let err = l.run(socks5_connect(&addr, "example.com", &l.handle()))
+               .err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+fn http_request(s: &mut net::TcpStream) -> String {
+    let mut request = Vec::new();
+    while !request.ends_with(b"\r\n\r\n") {
+        let mut byte = [0; 1];
+        t!(s.read_exact(&mut byte));
+        request.push(byte[0]);
+    }
+    String::from_utf8(request).unwrap()
+}
+
+#[test]
+fn http() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (addr, t) = proxy(|mut s| {
+        assert_eq!(http_request(&mut s),
+                   "CONNECT example.com:8080 HTTP/1.1\r\n\
+                    Host: example.com:8080\r\n\r\n");
+        t!(s.write_all(b"HTTP/1.1 200 Connection established\r\n\
+                         Proxy-Agent: test\r\n\r\ntunneled"));
+    });
+
+    let stream = t!(l.run(http_connect(&addr, "example.com:8080", &l.handle())));
+    let (_, data) = t!(l.run(read_to_end(stream, Vec::new())));
+    assert_eq!(data, b"tunneled");
+    t.join().unwrap();
+}
+
+#[test]
+fn http_invalid_target() {
+    let mut l = t!(Core::new());
+    let addr = "127.0.0.1:1".parse().unwrap();
+    let targets = ["example.com\r\nX-Injected: 1\r\n\r\n:80",
+                   "example.com :80",
+                   "example.com\0:80"];
+    for target in targets.iter() {
+        let err = l.run(http_connect(&addr, target, &l.handle())).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
+
+#[test]
+fn http_refused() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (addr, t) = proxy(|mut s| {
+        http_request(&mut s);
+        t!(s.write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n"));
+    });
+
+    let err = l.run(http_connect(&addr, "example.com:8080", &l.handle()))
+               .err().unwrap();
+    assert!(err.to_string().contains("407"));
+    t.join().unwrap();
+}
@@ -0,0 +1,83 @@
+#![cfg(unix)]
+
+extern crate env_logger;
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+
+use tokio_core::io::{read_to_end, write_all};
+use tokio_core::pty::{PtyMaster, WindowSize};
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn read_slave_output() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (master, mut slave) = t!(PtyMaster::open(&l.handle()));
+    assert!(master.slave_path().exists());
+
+    t!(slave.write_all(b"hello\n"));
+    drop(slave);
+    let (_, data) = t!(l.run(read_to_end(master, Vec::new())));
+    // The terminal's default output processing turns newlines into CRLF.
+    assert_eq!(data, b"hello\r\n");
+}
+
+#[test]
+fn write_slave_input() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (master, slave) = t!(PtyMaster::open(&l.handle()));
+
+    let t = thread::spawn(move || {
+        let mut slave = slave;
+        let mut buf = [0; 64];
+        let n = t!(slave.read(&mut buf));
+        buf[..n].to_vec()
+    });
+    // The slave sees a hangup once the master is closed, so the master is
+    // kept open until the input has been read.
+    let (_master, _) = t!(l.run(write_all(master, b"input\n")));
+    assert_eq!(t.join().unwrap(), b"input\n");
+}
+
+#[test]
+fn window_size() {
+    let l = t!(Core::new());
+    let (master, slave) = t!(PtyMaster::open(&l.handle()));
+
+    t!(master.set_window_size(WindowSize::new(24, 80)));
+    assert_eq!(t!(slave.window_size()), WindowSize::new(24, 80));
+    t!(slave.set_window_size(WindowSize::new(50, 132)));
+    let size = t!(master.window_size());
+    assert_eq!((size.rows(), size.cols()), (50, 132));
+}
+
+#[test]
+fn child_process() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (master, slave) = t!(PtyMaster::open(&l.handle()));
+
+    let mut child = t!(Command::new("sh")
+                           .arg("-c")
+                           .arg("test -t 1 && echo tty")
+                           .stdin(Stdio::null())
+                           .stdout(Stdio::from(t!(slave.try_clone()).into_std()))
+                           .stderr(Stdio::null())
+                           .spawn());
+    drop(slave);
+    let (_, data) = t!(l.run(read_to_end(master, Vec::new())));
+    assert!(t!(child.wait()).success());
+    assert_eq!(data, b"tty\r\n");
+}
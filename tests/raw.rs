@@ -0,0 +1,76 @@
+#![cfg(unix)]
+
+extern crate futures;
+#[macro_use]
+extern crate tokio_core;
+
+use std::io;
+use std::net::SocketAddr;
+
+use futures::future::poll_fn;
+use tokio_core::net::RawSocket;
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+fn checksum(data: &[u8]) -> u16 {
+    let mut sum = 0u32;
+    for chunk in data.chunks(2) {
+        let word = if chunk.len() == 2 {
+            (chunk[0] as u32) << 8 | chunk[1] as u32
+        } else {
+            (chunk[0] as u32) << 8
+        };
+        sum += word;
+    }
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+#[test]
+fn ping_localhost() {
+    let mut l = t!(Core::new());
+    let socket = match RawSocket::icmpv4(&l.handle()) {
+        Ok(s) => s,
+        // Raw sockets need privileges the test may not be running with.
+        Err(ref e) if e.kind() == io::ErrorKind::PermissionDenied => return,
+        Err(e) => panic!("failed to create raw socket: {}", e),
+    };
+    assert!(!t!(socket.header_included()));
+    t!(socket.set_ttl(32));
+    assert_eq!(t!(socket.ttl()), 32);
+
+    let id = std::process::id() as u16;
+    let mut packet = vec![8, 0, 0, 0, (id >> 8) as u8, id as u8, 0, 1];
+    packet.extend_from_slice(b"tokio-core");
+    let sum = checksum(&packet);
+    packet[2] = (sum >> 8) as u8;
+    packet[3] = sum as u8;
+
+    let target: SocketAddr = "127.0.0.1:0".parse().unwrap();
+    let send = poll_fn(|| {
+        Ok::<_, io::Error>(try_nb!(socket.send_to(&packet, &target)).into())
+    });
+    assert_eq!(t!(l.run(send)), packet.len());
+
+    let recv = poll_fn(|| {
+        let mut buf = [0; 1024];
+        loop {
+            let (n, addr) = try_nb!(socket.recv_from(&mut buf));
+            assert_eq!(addr, target);
+            // Skip the IPv4 header, and our own echo request.
+            let icmp = &buf[((buf[0] & 0xf) as usize * 4)..n];
+            if icmp[0] == 0 && icmp[4..6] == packet[4..6] {
+                return Ok::<_, io::Error>(icmp[8..].to_vec().into())
+            }
+        }
+    });
+    assert_eq!(t!(l.run(recv)), b"tokio-core");
+}
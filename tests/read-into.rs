@@ -0,0 +1,47 @@
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::Cursor;
+
+use futures::Future;
+use tokio_core::io::read_into;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn up_to_capacity() {
+    let data = (0..100).collect::<Vec<u8>>();
+    let mut buf = Vec::with_capacity(32);
+    buf.push(b'x');
+    let cap = buf.capacity();
+
+    let (reader, buf, n) = t!(read_into(Cursor::new(data.clone()), buf).wait());
+    assert_eq!(n, cap - 1);
+    assert_eq!(buf.capacity(), cap);
+    assert_eq!(buf[0], b'x');
+    assert_eq!(&buf[1..], &data[..n]);
+
+    let (_, rest, m) = t!(read_into(reader, Vec::with_capacity(200)).wait());
+    assert_eq!(m, 100 - n);
+    assert_eq!(rest, &data[n..]);
+}
+
+#[test]
+fn eof() {
+    let mut buf = Vec::with_capacity(4);
+    buf.push(1);
+    let (_, buf, n) = t!(read_into(Cursor::new(Vec::new()), buf).wait());
+    assert_eq!(n, 0);
+    assert_eq!(buf, [1]);
+}
+
+#[test]
+#[should_panic]
+fn no_capacity() {
+    read_into(Cursor::new(Vec::new()), Vec::new());
+}
@@ -0,0 +1,43 @@
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::{self, Cursor};
+
+use futures::Future;
+use tokio_core::io::{read_to_end_limited, Growth};
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn within_limit() {
+    let data = (0..100).collect::<Vec<u8>>();
+    let reader = Cursor::new(data.clone());
+    let (_, buf) = t!(read_to_end_limited(reader, b"x".to_vec(), 100, Growth::Double).wait());
+    assert_eq!(buf[0], b'x');
+    assert_eq!(&buf[1..], &data[..]);
+}
+
+#[test]
+fn over_limit() {
+    let reader = Cursor::new(vec![0; 101]);
+    let err = read_to_end_limited(reader, Vec::new(), 100, Growth::Double).wait().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn fixed_growth() {
+    let reader = Cursor::new(vec![1; 25]);
+    let (_, buf) = t!(read_to_end_limited(reader, Vec::new(), 1024, Growth::Fixed(10)).wait());
+    assert_eq!(buf, vec![1; 25]);
+    assert_eq!(buf.capacity(), 30);
+
+    // The buffer doesn't grow past the limit.
+    let reader = Cursor::new(vec![1; 25]);
+    let (_, buf) = t!(read_to_end_limited(reader, Vec::new(), 25, Growth::Fixed(1000)).wait());
+    assert_eq!(buf.capacity(), 25);
+}
@@ -0,0 +1,65 @@
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::{Cursor, Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::Duration;
+
+use futures::Future;
+use futures::stream::Stream;
+use tokio_core::io::read_until;
+use tokio_core::net::TcpListener;
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn delimiter() {
+    let reader = Cursor::new(b"GET / HTTP/1.1\r\nHost: a\r\n\r\nbody".to_vec());
+    let (mut reader, head) = t!(read_until(reader, b"\r\n\r\n", Vec::new()).wait());
+    assert_eq!(head, b"GET / HTTP/1.1\r\nHost: a\r\n\r\n");
+
+    // Nothing past the delimiter was consumed.
+    let mut rest = Vec::new();
+    t!(reader.read_to_end(&mut rest));
+    assert_eq!(rest, b"body");
+}
+
+#[test]
+fn eof_before_delimiter() {
+    let reader = Cursor::new(b"no newline".to_vec());
+    let (_, buf) = t!(read_until(reader, [b'\n'], b"> ".to_vec()).wait());
+    assert_eq!(buf, b"> no newline");
+}
+
+#[test]
+fn socket() {
+    let mut l = t!(Core::new());
+    let srv = t!(TcpListener::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let addr = t!(srv.local_addr());
+
+    let t = thread::spawn(move || {
+        let mut s = t!(TcpStream::connect(&addr));
+        t!(s.write_all(b"first"));
+        t!(s.flush());
+        thread::sleep(Duration::from_millis(50));
+        t!(s.write_all(b" line\nsecond line\n"));
+    });
+
+    let client = srv.incoming().into_future().map(|e| e.0.unwrap()).map_err(|e| e.0);
+    let lines = client.and_then(|(s, _)| {
+        read_until(s, [b'\n'], Vec::new())
+    }).and_then(|(s, first)| {
+        read_until(s, [b'\n'], Vec::new()).map(|(_, second)| (first, second))
+    });
+    let (first, second) = t!(l.run(lines));
+    assert_eq!(first, b"first line\n");
+    assert_eq!(second, b"second line\n");
+    t.join().unwrap();
+}
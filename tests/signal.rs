@@ -0,0 +1,111 @@
+#![cfg(unix)]
+
+extern crate futures;
+extern crate libc;
+extern crate tokio_core;
+
+use futures::stream::Stream;
+use futures::Future;
+use tokio_core::reactor::Core;
+use tokio_core::signal::{self, Signal};
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+fn raise(signum: libc::c_int) {
+    assert_eq!(unsafe { libc::kill(libc::getpid(), signum) }, 0);
+}
+
+#[test]
+fn notify_both() {
+    let mut l = t!(Core::new());
+    let a = t!(Signal::new(signal::SIGUSR1, &l.handle()));
+    let b = t!(Signal::new(signal::SIGUSR1, &l.handle()));
+    raise(signal::SIGUSR1);
+
+    let (signum, _a) = t!(l.run(a.into_future().map_err(|e| e.0)));
+    assert_eq!(signum, Some(signal::SIGUSR1));
+    let (signum, _b) = t!(l.run(b.into_future().map_err(|e| e.0)));
+    assert_eq!(signum, Some(signal::SIGUSR1));
+}
+
+#[test]
+fn multiple_cores() {
+    let mut l1 = t!(Core::new());
+    let mut l2 = t!(Core::new());
+    let a = t!(Signal::new(signal::SIGUSR2, &l1.handle()));
+    let b = t!(Signal::new(signal::SIGUSR2, &l2.handle()));
+    raise(signal::SIGUSR2);
+
+    let (signum, _b) = t!(l2.run(b.into_future().map_err(|e| e.0)));
+    assert_eq!(signum, Some(signal::SIGUSR2));
+    let (signum, _a) = t!(l1.run(a.into_future().map_err(|e| e.0)));
+    assert_eq!(signum, Some(signal::SIGUSR2));
+}
+
+#[test]
+fn forbidden() {
+    let l = t!(Core::new());
+    assert!(Signal::new(libc::SIGKILL, &l.handle()).is_err());
+    assert!(Signal::new(0, &l.handle()).is_err());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn signalfd() {
+    use tokio_core::signal::SignalFd;
+
+    let mut l = t!(Core::new());
+    let signals = t!(SignalFd::new(&[libc::SIGURG], &l.handle()));
+    // `SignalFd` only blocks the signal in this thread, so direct the signal
+    // at it rather than at the whole process.
+    assert_eq!(unsafe { libc::raise(libc::SIGURG) }, 0);
+
+    let (info, _signals) = t!(l.run(signals.into_future().map_err(|e| e.0)));
+    let info = info.unwrap();
+    assert_eq!(info.signo(), libc::SIGURG);
+    assert_eq!(info.pid(), unsafe { libc::getpid() } as u32);
+    assert_eq!(info.uid(), unsafe { libc::getuid() });
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn signalfd_forbidden() {
+    use tokio_core::signal::SignalFd;
+
+    let l = t!(Core::new());
+    assert!(SignalFd::new(&[libc::SIGKILL], &l.handle()).is_err());
+    assert!(SignalFd::new(&[0], &l.handle()).is_err());
+}
+
+#[cfg(target_os = "linux")]
+fn blocked(signum: libc::c_int) -> bool {
+    unsafe {
+        let mut mask: libc::sigset_t = std::mem::zeroed();
+        assert_eq!(libc::pthread_sigmask(libc::SIG_BLOCK, std::ptr::null(), &mut mask), 0);
+        libc::sigismember(&mask, signum) == 1
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn signalfd_unblocks_on_drop() {
+    use tokio_core::signal::SignalFd;
+
+    let l = t!(Core::new());
+    let a = t!(SignalFd::new(&[libc::SIGURG], &l.handle()));
+    let b = t!(SignalFd::new(&[libc::SIGURG, libc::SIGWINCH], &l.handle()));
+    assert!(blocked(libc::SIGURG) && blocked(libc::SIGWINCH));
+    drop(a);
+    assert!(blocked(libc::SIGURG));
+
+    // A signal left pending is discarded rather than delivered once it's
+    // unblocked.
+    assert_eq!(unsafe { libc::raise(libc::SIGWINCH) }, 0);
+    drop(b);
+    assert!(!blocked(libc::SIGURG) && !blocked(libc::SIGWINCH));
+}
@@ -0,0 +1,131 @@
+#![cfg(unix)]
+
+extern crate env_logger;
+extern crate futures;
+extern crate tokio_core;
+
+use std::cell::RefCell;
+use std::io::{self, Read, Write};
+use std::net::{self, Shutdown};
+use std::os::unix::io::RawFd;
+use std::rc::Rc;
+use std::thread;
+
+use futures::Future;
+use tokio_core::io::{splice_bidirectional, splice_copy, Splice};
+use tokio_core::net::TcpStream;
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+fn data(len: usize, seed: u8) -> Vec<u8> {
+    (0..len).map(|i| (i as u8).wrapping_mul(seed)).collect()
+}
+
+/// Accepts a connection on `srv` in a thread which writes `out` to it, shuts
+/// down its writing half and reads it to the end.
+fn peer(srv: net::TcpListener, out: Vec<u8>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut s = t!(srv.accept()).0;
+        let mut w = t!(s.try_clone());
+        let writer = thread::spawn(move || {
+            t!(w.write_all(&out));
+            t!(w.shutdown(Shutdown::Write));
+        });
+        let mut data = Vec::new();
+        t!(s.read_to_end(&mut data));
+        writer.join().unwrap();
+        data
+    })
+}
+
+#[test]
+fn copy() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let srv1 = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let srv2 = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let a = TcpStream::connect(&t!(srv1.local_addr()), &l.handle());
+    let b = TcpStream::connect(&t!(srv2.local_addr()), &l.handle());
+    let sent = data(1024 * 1024, 7);
+    let t1 = peer(srv1, sent.clone());
+    let t2 = peer(srv2, Vec::new());
+
+    let copy = a.join(b).and_then(|(a, b)| splice_copy(a, b));
+    assert_eq!(t!(l.run(copy)), sent.len() as u64);
+    assert!(t1.join().unwrap().is_empty());
+    assert!(t2.join().unwrap() == sent);
+}
+
+#[test]
+fn bidirectional() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let srv1 = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let srv2 = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let a = TcpStream::connect(&t!(srv1.local_addr()), &l.handle());
+    let b = TcpStream::connect(&t!(srv2.local_addr()), &l.handle());
+    let a_data = data(512 * 1024, 3);
+    let b_data = data(300 * 1024, 5);
+    let t1 = peer(srv1, a_data.clone());
+    let t2 = peer(srv2, b_data.clone());
+
+    let copy = a.join(b).and_then(|(a, b)| splice_bidirectional(a, b));
+    let (a_to_b, b_to_a) = t!(l.run(copy));
+    assert_eq!(a_to_b, a_data.len() as u64);
+    assert_eq!(b_to_a, b_data.len() as u64);
+    assert!(t1.join().unwrap() == b_data);
+    assert!(t2.join().unwrap() == a_data);
+}
+
+/// A writer into memory which the kernel refuses to splice to.
+struct Unspliceable(Rc<RefCell<Vec<u8>>>);
+
+impl Read for Unspliceable {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Ok(0)
+    }
+}
+
+impl Write for Unspliceable {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Splice for Unspliceable {
+    fn splice_to(&mut self, _pipe: RawFd, _len: usize) -> io::Result<usize> {
+        Err(io::Error::from_raw_os_error(22))
+    }
+
+    fn splice_from(&mut self, _pipe: RawFd, _len: usize) -> io::Result<usize> {
+        Err(io::Error::from_raw_os_error(22))
+    }
+}
+
+#[test]
+fn copy_to_unspliceable() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let a = TcpStream::connect(&t!(srv.local_addr()), &l.handle());
+    let sent = data(300 * 1024, 11);
+    let t = peer(srv, sent.clone());
+
+    let received = Rc::new(RefCell::new(Vec::new()));
+    let writer = Unspliceable(received.clone());
+    let copy = a.and_then(|a| splice_copy(a, writer));
+    assert_eq!(t!(l.run(copy)), sent.len() as u64);
+    assert!(t.join().unwrap().is_empty());
+    assert!(*received.borrow() == sent);
+}
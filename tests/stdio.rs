@@ -0,0 +1,53 @@
+extern crate futures;
+extern crate tokio_core;
+
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use tokio_core::io::{copy, stdin, stdout};
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+// When run as a child of `copy_stdin_to_stdout` this copies all of stdin to
+// stdout through the event loop; otherwise it does nothing.
+#[test]
+fn child_echo() {
+    if env::var_os("TOKIO_CORE_STDIO_CHILD").is_none() {
+        return
+    }
+    let mut l = t!(Core::new());
+    let input = t!(stdin(&l.handle()));
+    let output = t!(stdout(&l.handle()));
+    t!(l.run(copy(input, output)));
+}
+
+#[test]
+fn copy_stdin_to_stdout() {
+    let mut child = t!(Command::new(t!(env::current_exe()))
+                               .arg("child_echo")
+                               .arg("--exact")
+                               .arg("--nocapture")
+                               .env("TOKIO_CORE_STDIO_CHILD", "1")
+                               .stdin(Stdio::piped())
+                               .stdout(Stdio::piped())
+                               .spawn());
+    let payload = (0..16 * 1024).map(|i| (b'a' + (i % 26) as u8) as char)
+                                .collect::<String>();
+    {
+        let mut stdin = child.stdin.take().unwrap();
+        t!(stdin.write_all(b"<<"));
+        t!(stdin.write_all(payload.as_bytes()));
+        t!(stdin.write_all(b">>"));
+    }
+    let output = t!(child.wait_with_output());
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("<<{}>>", payload)));
+}
@@ -2,12 +2,14 @@ extern crate env_logger;
 extern crate futures;
 extern crate tokio_core;
 
+use std::io::Read;
 use std::net;
 use std::sync::mpsc::channel;
 use std::thread;
 
 use futures::Future;
 use futures::stream::Stream;
+use tokio_core::io::{write_all, AsyncWrite};
 use tokio_core::reactor::Core;
 use tokio_core::net::{TcpListener, TcpStream};
 
@@ -83,3 +85,59 @@ fn accept2() {
     mine.unwrap();
     t.join().unwrap();
 }
+
+#[test]
+fn accept_with_options() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let mut srv = t!(TcpListener::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    srv.set_accept_nodelay(true);
+    let addr = t!(srv.local_addr());
+
+    let t = thread::spawn(move || {
+        net::TcpStream::connect(&addr).unwrap()
+    });
+
+    let client = srv.incoming().into_future().map_err(|e| e.0);
+    let (mine, _remaining) = t!(l.run(client));
+    let (mine, _addr) = mine.unwrap();
+    assert!(t!(mine.nodelay()));
+    t.join().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn tos() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        t!(srv.accept()).0
+    });
+
+    let stream = t!(l.run(TcpStream::connect(&addr, &l.handle())));
+    t!(stream.set_tos(0x28));
+    assert_eq!(t!(stream.tos()), 0x28);
+    t.join().unwrap();
+}
+
+#[test]
+fn shutdown() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let mut data = Vec::new();
+        t!(t!(srv.accept()).0.read_to_end(&mut data));
+        data
+    });
+
+    let stream = TcpStream::connect(&addr, &l.handle());
+    let done = stream.and_then(|s| write_all(s, b"hello")).and_then(|(mut s, _)| {
+        futures::future::poll_fn(move || AsyncWrite::shutdown(&mut s))
+    });
+    t!(l.run(done));
+    assert_eq!(t.join().unwrap(), b"hello");
+}
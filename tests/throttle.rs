@@ -0,0 +1,72 @@
+extern crate env_logger;
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::{Read, Write};
+use std::net;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::Future;
+use tokio_core::io::{read_to_end, write_all, Throttle};
+use tokio_core::net::TcpStream;
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn read_rate() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let (mut s, _) = t!(srv.accept());
+        t!(s.write_all(&[0; 3000]));
+    });
+
+    // A burst of 1000 bytes and then 2000 bytes at 10000 bytes per second.
+    let handle = l.handle();
+    let start = Instant::now();
+    let stream = TcpStream::connect(&addr, &handle).and_then(|s| {
+        let mut s = Throttle::new(s, &handle);
+        s.set_read_rate(Some(10_000));
+        read_to_end(s, Vec::new())
+    });
+    let (_, data) = t!(l.run(stream));
+    assert_eq!(data.len(), 3000);
+    assert!(start.elapsed() >= Duration::from_millis(150));
+    t.join().unwrap();
+}
+
+#[test]
+fn write_rate() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let (mut s, _) = t!(srv.accept());
+        let mut data = Vec::new();
+        t!(s.read_to_end(&mut data));
+        data
+    });
+
+    let handle = l.handle();
+    let start = Instant::now();
+    let stream = TcpStream::connect(&addr, &handle).and_then(|s| {
+        let mut s = Throttle::new(s, &handle);
+        s.set_write_rate(Some(10_000));
+        write_all(s, vec![1; 3000])
+    });
+    let (s, _) = t!(l.run(stream));
+    assert!(start.elapsed() >= Duration::from_millis(150));
+    assert_eq!(s.write_rate(), Some(10_000));
+    drop(s);
+    assert_eq!(t.join().unwrap(), vec![1; 3000]);
+}
@@ -0,0 +1,73 @@
+extern crate env_logger;
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::{self, Write};
+use std::net;
+use std::thread;
+use std::time::Duration;
+
+use futures::Future;
+use tokio_core::io::{read_exact, read_to_end, TimeoutIo};
+use tokio_core::net::TcpStream;
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn read_timeout() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let (mut s, _) = t!(srv.accept());
+        t!(s.write_all(b"foo"));
+        thread::sleep(Duration::from_millis(500));
+    });
+
+    let handle = l.handle();
+    let stream = TcpStream::connect(&addr, &handle).and_then(|s| {
+        let mut s = TimeoutIo::new(s, &handle);
+        s.set_read_timeout(Some(Duration::from_millis(50)));
+        read_to_end(s, Vec::new())
+    });
+    match l.run(stream) {
+        Ok(..) => panic!("the read should have timed out"),
+        Err(e) => assert_eq!(e.kind(), io::ErrorKind::TimedOut),
+    }
+    t.join().unwrap();
+}
+
+#[test]
+fn read_timeout_reset() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let (mut s, _) = t!(srv.accept());
+        for _ in 0..5 {
+            thread::sleep(Duration::from_millis(50));
+            t!(s.write_all(b"a"));
+        }
+    });
+
+    // The whole read takes longer than the timeout, but there's never more
+    // than the timeout between bytes.
+    let handle = l.handle();
+    let stream = TcpStream::connect(&addr, &handle).and_then(|s| {
+        let mut s = TimeoutIo::new(s, &handle);
+        s.set_read_timeout(Some(Duration::from_millis(200)));
+        read_exact(s, [0; 5])
+    });
+    let (s, buf) = t!(l.run(stream));
+    assert_eq!(&buf, b"aaaaa");
+    assert_eq!(s.read_timeout(), Some(Duration::from_millis(200)));
+    t.join().unwrap();
+}
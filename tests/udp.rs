@@ -5,8 +5,8 @@ extern crate tokio_core;
 use std::io;
 use std::net::SocketAddr;
 
-use futures::{Future, Poll};
-use tokio_core::net::UdpSocket;
+use futures::{Future, Poll, Stream, Sink};
+use tokio_core::net::{UdpSocket, UdpCodec, Malformed, UdpPair};
 use tokio_core::reactor::Core;
 
 macro_rules! t {
@@ -63,3 +63,478 @@ impl Future for RecvMessage {
         Ok(().into())
     }
 }
+
+struct ByteCodec;
+
+impl UdpCodec for ByteCodec {
+    type In = (SocketAddr, Vec<u8>);
+    type Out = (SocketAddr, Vec<u8>);
+
+    fn decode(&mut self, addr: &SocketAddr, buf: &[u8])
+              -> io::Result<(SocketAddr, Vec<u8>)> {
+        Ok((*addr, buf.to_vec()))
+    }
+
+    fn encode(&mut self, (addr, buf): (SocketAddr, Vec<u8>), into: &mut Vec<u8>)
+              -> SocketAddr {
+        into.extend(buf);
+        addr
+    }
+}
+
+#[test]
+fn send_framed() {
+    let mut l = t!(Core::new());
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let b = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let a_addr = t!(a.local_addr());
+    let b_addr = t!(b.local_addr());
+
+    let a = a.framed(ByteCodec);
+    let b = b.framed(ByteCodec);
+
+    let send = a.send((b_addr, b"4567".to_vec()));
+    let recv = b.into_future().map_err(|e| e.0);
+    let (_a, (msg, _b)) = t!(l.run(send.join(recv)));
+    assert_eq!(msg, Some((a_addr, b"4567".to_vec())));
+}
+
+#[test]
+fn send_dgrams() {
+    let mut l = t!(Core::new());
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let b = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let a_addr = t!(a.local_addr());
+    let b_addr = t!(b.local_addr());
+
+    let send = a.send_dgram(b"4321", b_addr);
+    let recv = b.recv_dgram(vec![0; 32]);
+    let ((_a, buf), (_b, rbuf, n, addr)) = t!(l.run(send.join(recv)));
+    assert_eq!(buf, b"4321");
+    assert_eq!(n, 4);
+    assert_eq!(&rbuf[..n], b"4321");
+    assert_eq!(addr, a_addr);
+}
+
+#[cfg(unix)]
+#[test]
+fn multicast_if() {
+    let l = t!(Core::new());
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let lo = "127.0.0.1".parse().unwrap();
+    t!(a.set_multicast_if_v4(&lo));
+    assert_eq!(t!(a.multicast_if_v4()), lo);
+}
+
+#[test]
+fn broadcast() {
+    let l = t!(Core::new());
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    assert!(!t!(a.broadcast()));
+    t!(a.set_broadcast(true));
+    assert!(t!(a.broadcast()));
+    t!(a.set_broadcast(false));
+    assert!(!t!(a.broadcast()));
+}
+
+struct RecvMany {
+    socket: UdpSocket,
+    expected_addr: SocketAddr,
+    received: Vec<Vec<u8>>,
+}
+
+impl Future for RecvMany {
+    type Item = Vec<Vec<u8>>;
+    type Error = io::Error;
+
+    fn poll(&mut self) -> Poll<Vec<Vec<u8>>, io::Error> {
+        while self.received.len() < 3 {
+            let mut a = [0; 32];
+            let mut b = [0; 32];
+            let mut c = [0; 32];
+            let mut results = Vec::new();
+            {
+                let mut bufs = [&mut a[..], &mut b[..], &mut c[..]];
+                let n = try_nb!(self.socket.recv_many(&mut bufs, &mut results));
+                assert_eq!(n, results.len());
+            }
+            for (i, &(n, addr)) in results.iter().enumerate() {
+                assert_eq!(addr, self.expected_addr);
+                let buf = [&a, &b, &c][i];
+                self.received.push(buf[..n].to_vec());
+            }
+        }
+        Ok(::std::mem::take(&mut self.received).into())
+    }
+}
+
+#[test]
+fn recv_many() {
+    let mut l = t!(Core::new());
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let b = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let a_addr = t!(a.local_addr());
+    let b_addr = t!(b.local_addr());
+
+    let send = a.send_dgram(b"1", b_addr)
+                .and_then(move |(a, _)| a.send_dgram(b"22", b_addr))
+                .and_then(move |(a, _)| a.send_dgram(b"333", b_addr));
+    let (_a, _) = t!(l.run(send));
+
+    let err = b.recv_many(&mut [], &mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    let recv = RecvMany {
+        socket: b,
+        expected_addr: a_addr,
+        received: Vec::new(),
+    };
+    let received = t!(l.run(recv));
+    assert_eq!(received, vec![b"1".to_vec(), b"22".to_vec(), b"333".to_vec()]);
+}
+
+#[test]
+fn send_many() {
+    let mut l = t!(Core::new());
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let b = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let a_addr = t!(a.local_addr());
+    let b_addr = t!(b.local_addr());
+
+    let mut sent = 0;
+    let send = futures::future::poll_fn(|| {
+        let msgs = [(&b"1"[..], b_addr), (&b"22"[..], b_addr), (&b"333"[..], b_addr)];
+        while sent < msgs.len() {
+            sent += try_nb!(a.send_many(&msgs[sent..]));
+        }
+        Ok::<_, io::Error>(().into())
+    });
+    t!(l.run(send));
+
+    let recv = RecvMany {
+        socket: b,
+        expected_addr: a_addr,
+        received: Vec::new(),
+    };
+    let received = t!(l.run(recv));
+    assert_eq!(received, vec![b"1".to_vec(), b"22".to_vec(), b"333".to_vec()]);
+
+    let err = a.send_many(&[]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn connected() {
+    let mut l = t!(Core::new());
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let b = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let a_addr = t!(a.local_addr());
+    let b_addr = t!(b.local_addr());
+    t!(a.connect(&b_addr));
+    t!(b.connect(&a_addr));
+
+    let send = futures::future::poll_fn(|| {
+        let n = try_nb!(a.send(b"hello"));
+        assert_eq!(n, 5);
+        Ok::<_, io::Error>(().into())
+    });
+    let recv = futures::future::poll_fn(|| {
+        let mut buf = [0; 32];
+        let n = try_nb!(b.recv(&mut buf));
+        Ok::<_, io::Error>(buf[..n].to_vec().into())
+    });
+    let (_, msg) = t!(l.run(send.join(recv)));
+    assert_eq!(msg, b"hello");
+}
+
+#[cfg(unix)]
+#[test]
+fn peek_from() {
+    let mut l = t!(Core::new());
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let b = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let a_addr = t!(a.local_addr());
+    let b_addr = t!(b.local_addr());
+
+    let (_a, _) = t!(l.run(a.send_dgram(b"peeked", b_addr)));
+    let peek = futures::future::poll_fn(|| {
+        let mut buf = [0; 4];
+        let (n, addr) = try_nb!(b.peek_from(&mut buf));
+        assert_eq!(addr, a_addr);
+        Ok::<_, io::Error>(buf[..n].to_vec().into())
+    });
+    assert_eq!(t!(l.run(peek)), b"peek");
+    let (_b, buf, n, addr) = t!(l.run(b.recv_dgram(vec![0; 32])));
+    assert_eq!(&buf[..n], b"peeked");
+    assert_eq!(addr, a_addr);
+}
+
+#[cfg(unix)]
+#[test]
+fn raw_fd_round_trip() {
+    use std::os::unix::prelude::*;
+
+    let mut l = t!(Core::new());
+    let std = t!(std::net::UdpSocket::bind("127.0.0.1:0"));
+    t!(std.set_ttl(42));
+    let addr = t!(std.local_addr());
+
+    let a = t!(unsafe { UdpSocket::from_raw_fd(std.into_raw_fd(), &l.handle()) });
+    assert_eq!(t!(a.local_addr()), addr);
+    assert_eq!(t!(a.ttl()), 42);
+
+    let b = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let (b, _) = t!(l.run(b.send_dgram(b"hello", addr)));
+    let (a, buf, n, _) = t!(l.run(a.recv_dgram(vec![0; 32])));
+    assert_eq!(&buf[..n], b"hello");
+
+    let std = t!(a.into_std(&l.handle()));
+    assert_eq!(t!(std.local_addr()), addr);
+    assert_eq!(t!(std.ttl()), 42);
+    t!(std.set_nonblocking(false));
+    let b_addr = t!(b.local_addr());
+    t!(std.send_to(b"back", &b_addr));
+    let (_b, buf, n, from) = t!(l.run(b.recv_dgram(vec![0; 32])));
+    assert_eq!(&buf[..n], b"back");
+    assert_eq!(from, addr);
+}
+
+#[test]
+fn ttl() {
+    let l = t!(Core::new());
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    t!(a.set_ttl(42));
+    assert_eq!(t!(a.ttl()), 42);
+}
+
+#[cfg(unix)]
+#[test]
+fn tos() {
+    let l = t!(Core::new());
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    t!(a.set_tos(0xb8));
+    assert_eq!(t!(a.tos()), 0xb8);
+
+    let b = match UdpSocket::bind(&t!("[::1]:0".parse()), &l.handle()) {
+        Ok(b) => b,
+        Err(_) => return,
+    };
+    t!(b.set_traffic_class(0xb8));
+    assert_eq!(t!(b.traffic_class()), 0xb8);
+}
+
+#[cfg(unix)]
+#[test]
+fn hop_limits_v6() {
+    let l = t!(Core::new());
+    let a = match UdpSocket::bind(&t!("[::1]:0".parse()), &l.handle()) {
+        Ok(a) => a,
+        // IPv6 may not be available on the host running the tests
+        Err(_) => return,
+    };
+    t!(a.set_unicast_hops_v6(17));
+    assert_eq!(t!(a.unicast_hops_v6()), 17);
+    t!(a.set_multicast_hops_v6(3));
+    assert_eq!(t!(a.multicast_hops_v6()), 3);
+}
+
+#[derive(Debug)]
+enum Digit {
+    Value(u8),
+    Bad(SocketAddr, Vec<u8>),
+}
+
+impl From<Malformed> for Digit {
+    fn from(m: Malformed) -> Digit {
+        Digit::Bad(*m.addr(), m.data().to_vec())
+    }
+}
+
+struct DigitCodec;
+
+impl UdpCodec for DigitCodec {
+    type In = Digit;
+    type Out = (SocketAddr, Vec<u8>);
+
+    fn decode(&mut self, _addr: &SocketAddr, buf: &[u8]) -> io::Result<Digit> {
+        match buf {
+            &[b] if b.is_ascii_digit() => Ok(Digit::Value(b - b'0')),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "not a digit")),
+        }
+    }
+
+    fn encode(&mut self, (addr, buf): (SocketAddr, Vec<u8>), into: &mut Vec<u8>)
+              -> SocketAddr {
+        into.extend(buf);
+        addr
+    }
+}
+
+fn send_digits(l: &mut Core, to: SocketAddr) -> SocketAddr {
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let a_addr = t!(a.local_addr());
+    let send = a.send_dgram(b"1", to)
+                .and_then(move |(a, _)| a.send_dgram(b"xx", to))
+                .and_then(move |(a, _)| a.send_dgram(b"2", to));
+    t!(l.run(send));
+    a_addr
+}
+
+#[test]
+fn framed_propagate_malformed() {
+    let mut l = t!(Core::new());
+    let b = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let b_addr = t!(b.local_addr());
+    send_digits(&mut l, b_addr);
+
+    let b = b.framed(DigitCodec);
+    let (first, b) = t!(l.run(b.into_future().map_err(|e| e.0)));
+    match first {
+        Some(Digit::Value(1)) => {}
+        other => panic!("unexpected frame: {:?}", other),
+    }
+    let err = l.run(b.into_future()).err().unwrap().0;
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn framed_skip_malformed() {
+    let mut l = t!(Core::new());
+    let b = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let b_addr = t!(b.local_addr());
+    send_digits(&mut l, b_addr);
+
+    let mut b = b.framed(DigitCodec);
+    b.skip_malformed();
+    let frames = t!(l.run(b.take(2).collect()));
+    match &frames[..] {
+        &[Digit::Value(1), Digit::Value(2)] => {}
+        other => panic!("unexpected frames: {:?}", other),
+    }
+}
+
+#[test]
+fn framed_yield_malformed() {
+    let mut l = t!(Core::new());
+    let b = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let b_addr = t!(b.local_addr());
+    let a_addr = send_digits(&mut l, b_addr);
+
+    let mut b = b.framed(DigitCodec);
+    b.yield_malformed();
+    let frames = t!(l.run(b.take(3).collect()));
+    match &frames[..] {
+        &[Digit::Value(1), Digit::Bad(addr, ref data), Digit::Value(2)] => {
+            assert_eq!(addr, a_addr);
+            assert_eq!(data, b"xx");
+        }
+        other => panic!("unexpected frames: {:?}", other),
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn recv_from_pktinfo() {
+    let mut l = t!(Core::new());
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let b = t!(UdpSocket::bind(&t!("0.0.0.0:0".parse()), &l.handle()));
+    let a_addr = t!(a.local_addr());
+    let port = t!(b.local_addr()).port();
+    let b_addr = t!(format!("127.0.0.1:{}", port).parse());
+    t!(b.set_recv_pktinfo(true));
+
+    let (_a, _) = t!(l.run(a.send_dgram(b"where", b_addr)));
+    let recv = futures::future::poll_fn(|| {
+        let mut buf = [0; 32];
+        let (n, addr, info) = try_nb!(b.recv_from_pktinfo(&mut buf));
+        assert_eq!(&buf[..n], b"where");
+        assert_eq!(addr, a_addr);
+        Ok::<_, io::Error>(info.into())
+    });
+    let info = t!(l.run(recv)).expect("missing packet info");
+    assert_eq!(info.dst_addr(), b_addr.ip());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn gso_segments() {
+    let mut l = t!(Core::new());
+    let a = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let b = t!(UdpSocket::bind(&t!("127.0.0.1:0".parse()), &l.handle()));
+    let b_addr = t!(b.local_addr());
+    if a.set_gso_segment_size(4).is_err() {
+        // UDP_SEGMENT requires Linux 4.18
+        return
+    }
+    assert_eq!(t!(a.gso_segment_size()), 4);
+
+    let (_a, _) = t!(l.run(a.send_dgram(b"aaaabbbbcc", b_addr)));
+    let mut segments = Vec::new();
+    let recv = futures::future::poll_fn(|| {
+        while segments.len() < 3 {
+            let mut buf = [0; 32];
+            let (n, _addr, segment) = try_nb!(b.recv_from_gro(&mut buf));
+            assert_eq!(segment, None);
+            segments.push(buf[..n].to_vec());
+        }
+        Ok::<_, io::Error>(().into())
+    });
+    t!(l.run(recv));
+    assert_eq!(segments, vec![b"aaaa".to_vec(), b"bbbb".to_vec(), b"cc".to_vec()]);
+}
+
+#[test]
+fn pair() {
+    let mut l = t!(Core::new());
+    let (a, b) = t!(UdpSocket::pair(&l.handle()));
+    let b_addr = t!(b.local_addr());
+
+    let (_a, _) = t!(l.run(a.send_dgram(b"hello", b_addr)));
+    let (_b, buf, n, _) = t!(l.run(b.recv_dgram(vec![0; 32])));
+    assert_eq!(&buf[..n], b"hello");
+}
+
+fn impaired(pair: &UdpPair, sent: u8, expected: usize) -> Vec<u8> {
+    let mut l = t!(Core::new());
+    let (a, b) = t!(pair.build(&l.handle()));
+
+    let mut next = 1;
+    let send = futures::future::poll_fn(|| {
+        while next <= sent {
+            try_nb!(a.send(&[next]));
+            next += 1;
+        }
+        Ok::<_, io::Error>(().into())
+    });
+    t!(l.run(send));
+
+    let mut received = Vec::new();
+    let recv = futures::future::poll_fn(|| {
+        while received.len() < expected {
+            let mut buf = [0; 1];
+            try_nb!(b.recv(&mut buf));
+            received.push(buf[0]);
+        }
+        Ok::<_, io::Error>(().into())
+    });
+    t!(l.run(recv));
+    received
+}
+
+#[test]
+fn pair_drop() {
+    let received = impaired(UdpPair::new().drop_every(3), 7, 5);
+    assert_eq!(received, vec![1, 2, 4, 5, 7]);
+}
+
+#[test]
+fn pair_duplicate() {
+    let received = impaired(UdpPair::new().duplicate_every(2), 4, 6);
+    assert_eq!(received, vec![1, 2, 2, 3, 4, 4]);
+}
+
+#[test]
+fn pair_reorder() {
+    let received = impaired(UdpPair::new().reorder_every(2), 5, 5);
+    assert_eq!(received, vec![1, 3, 2, 5, 4]);
+}
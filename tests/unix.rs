@@ -0,0 +1,368 @@
+#![cfg(unix)]
+
+extern crate env_logger;
+extern crate futures;
+extern crate libc;
+#[macro_use]
+extern crate tokio_core;
+
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::os::unix::net;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+use std::thread;
+
+use futures::Future;
+use futures::stream::Stream;
+use tokio_core::io::{read_to_end, write_all};
+use tokio_core::net::{UnixListener, UnixStream, UnixDatagram};
+use tokio_core::net::{UnixSeqpacket, UnixSeqpacketListener};
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+fn socket_path() -> PathBuf {
+    static NEXT: AtomicUsize = ATOMIC_USIZE_INIT;
+    let n = NEXT.fetch_add(1, Ordering::SeqCst);
+    let path = env::temp_dir().join(format!("tokio-core-unix-{}-{}",
+                                            std::process::id(), n));
+    drop(fs::remove_file(&path));
+    path
+}
+
+#[test]
+fn connect() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let path = socket_path();
+    let srv = t!(net::UnixListener::bind(&path));
+    let t = thread::spawn(move || {
+        let mut s = t!(srv.accept()).0;
+        t!(s.write_all(b"hello"));
+    });
+
+    let stream = t!(UnixStream::connect(&path, &l.handle()));
+    let (_stream, data) = t!(l.run(read_to_end(stream, Vec::new())));
+    assert_eq!(data, b"hello");
+    t.join().unwrap();
+    t!(fs::remove_file(&path));
+}
+
+#[test]
+fn accept() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let path = socket_path();
+    let srv = t!(UnixListener::bind(&path, &l.handle()));
+    assert_eq!(t!(srv.local_addr()).as_pathname(), Some(&*path));
+
+    let t = {
+        let path = path.clone();
+        thread::spawn(move || {
+            let mut s = t!(net::UnixStream::connect(&path));
+            let mut data = Vec::new();
+            t!(s.read_to_end(&mut data));
+            data
+        })
+    };
+
+    let client = srv.incoming().into_future().map_err(|e| e.0);
+    let (mine, _remaining) = t!(l.run(client));
+    let (mine, _addr) = mine.unwrap();
+    t!(l.run(write_all(mine, b"world")));
+    assert_eq!(t.join().unwrap(), b"world");
+    t!(fs::remove_file(&path));
+}
+
+#[test]
+fn connect_missing() {
+    let l = t!(Core::new());
+    let path = socket_path();
+    assert!(UnixStream::connect(&path, &l.handle()).is_err());
+}
+
+#[test]
+fn datagram_send_to_recv_from() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let a_path = socket_path();
+    let b_path = socket_path();
+    let a = t!(UnixDatagram::bind(&a_path, &l.handle()));
+    let b = t!(UnixDatagram::bind(&b_path, &l.handle()));
+
+    let recv = futures::future::poll_fn(|| {
+        let mut buf = [0; 32];
+        let (n, addr) = try_nb!(b.recv_from(&mut buf));
+        Ok::<_, io::Error>((buf[..n].to_vec(), addr).into())
+    });
+    let send = futures::future::poll_fn(|| {
+        Ok::<_, io::Error>(try_nb!(a.send_to(b"ping", &b_path)).into())
+    });
+    assert_eq!(t!(l.run(send)), 4);
+    let (data, addr) = t!(l.run(recv));
+    assert_eq!(data, b"ping");
+    assert_eq!(addr.as_pathname(), Some(&*a_path));
+
+    t!(fs::remove_file(&a_path));
+    t!(fs::remove_file(&b_path));
+}
+
+#[test]
+fn datagram_connected() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let path = socket_path();
+    let server = t!(UnixDatagram::bind(&path, &l.handle()));
+    let client = t!(UnixDatagram::unbound(&l.handle()));
+    t!(client.connect(&path));
+    assert_eq!(t!(client.peer_addr()).as_pathname(), Some(&*path));
+
+    let recv = futures::future::poll_fn(|| {
+        let mut buf = [0; 32];
+        let n = try_nb!(server.recv(&mut buf));
+        Ok::<_, io::Error>(buf[..n].to_vec().into())
+    });
+    let send = futures::future::poll_fn(|| {
+        Ok::<_, io::Error>(try_nb!(client.send(b"connected")).into())
+    });
+    assert_eq!(t!(l.run(send)), 9);
+    assert_eq!(t!(l.run(recv)), b"connected");
+
+    let recv = futures::future::lazy(|| {
+        let mut buf = [0; 32];
+        Ok::<_, ()>(client.recv(&mut buf).unwrap_err().kind())
+    });
+    assert_eq!(t!(l.run(recv)), io::ErrorKind::WouldBlock);
+
+    t!(fs::remove_file(&path));
+}
+
+#[test]
+fn seqpacket_pair_preserves_boundaries() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (a, b) = t!(UnixSeqpacket::pair(&l.handle()));
+
+    let send = futures::future::poll_fn(|| {
+        try_nb!(a.send(b"first"));
+        try_nb!(a.send(b"second"));
+        Ok::<_, io::Error>(().into())
+    });
+    t!(l.run(send));
+
+    let mut msgs = Vec::new();
+    let recv = futures::future::poll_fn(|| {
+        while msgs.len() < 2 {
+            let mut buf = [0; 32];
+            let n = try_nb!(b.recv(&mut buf));
+            msgs.push(buf[..n].to_vec());
+        }
+        Ok::<_, io::Error>(().into())
+    });
+    t!(l.run(recv));
+    assert_eq!(msgs, [&b"first"[..], &b"second"[..]]);
+}
+
+#[test]
+fn seqpacket_listener() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let path = socket_path();
+    let srv = t!(UnixSeqpacketListener::bind(&path, &l.handle()));
+    assert_eq!(t!(srv.local_addr()).as_pathname(), Some(&*path));
+
+    let client = t!(UnixSeqpacket::connect(&path, &l.handle()));
+    let accept = srv.incoming().into_future().map_err(|e| e.0);
+    let (accepted, _srv) = t!(l.run(accept));
+    let (server, _addr) = accepted.unwrap();
+
+    let send = futures::future::poll_fn(|| {
+        Ok::<_, io::Error>(try_nb!(client.send(b"hello")).into())
+    });
+    assert_eq!(t!(l.run(send)), 5);
+    let recv = futures::future::poll_fn(|| {
+        let mut buf = [0; 3];
+        let n = try_nb!(server.recv(&mut buf));
+        Ok::<_, io::Error>(buf[..n].to_vec().into())
+    });
+    assert_eq!(t!(l.run(recv)), b"hel");
+
+    drop(client);
+    let eof = futures::future::poll_fn(|| {
+        let mut buf = [0; 32];
+        Ok::<_, io::Error>(try_nb!(server.recv(&mut buf)).into())
+    });
+    assert_eq!(t!(l.run(eof)), 0);
+    t!(fs::remove_file(&path));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn abstract_namespace() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let name = format!("\0tokio-core-abstract-{}", std::process::id());
+    let name = Path::new(OsStr::from_bytes(name.as_bytes()));
+
+    let srv = t!(UnixListener::bind(name, &l.handle()));
+    assert_eq!(t!(srv.local_addr()).as_pathname(), None);
+    let client = t!(UnixStream::connect(name, &l.handle()));
+    let accept = srv.incoming().into_future().map_err(|e| e.0);
+    let (accepted, _srv) = t!(l.run(accept));
+    let (server, _addr) = accepted.unwrap();
+    t!(l.run(write_all(client, b"abstract")));
+    let (_server, data) = t!(l.run(read_to_end(server, Vec::new())));
+    assert_eq!(data, b"abstract");
+
+    let dgram_name = format!("\0tokio-core-abstract-dgram-{}", std::process::id());
+    let dgram_name = Path::new(OsStr::from_bytes(dgram_name.as_bytes()));
+    let server = t!(UnixDatagram::bind(dgram_name, &l.handle()));
+    let client = t!(UnixDatagram::unbound(&l.handle()));
+    let send = futures::future::poll_fn(|| {
+        Ok::<_, io::Error>(try_nb!(client.send_to(b"ping", dgram_name)).into())
+    });
+    assert_eq!(t!(l.run(send)), 4);
+    let recv = futures::future::poll_fn(|| {
+        let mut buf = [0; 32];
+        let n = try_nb!(server.recv(&mut buf));
+        Ok::<_, io::Error>(buf[..n].to_vec().into())
+    });
+    assert_eq!(t!(l.run(recv)), b"ping");
+}
+
+#[test]
+fn peer_cred() {
+    let mut l = t!(Core::new());
+    let path = socket_path();
+    let srv = t!(UnixListener::bind(&path, &l.handle()));
+    let client = t!(UnixStream::connect(&path, &l.handle()));
+    let accept = srv.incoming().into_future().map_err(|e| e.0);
+    let (accepted, _srv) = t!(l.run(accept));
+    let (server, _addr) = accepted.unwrap();
+
+    for cred in [t!(client.peer_cred()), t!(server.peer_cred())].iter() {
+        assert_eq!(cred.uid(), unsafe { libc::geteuid() } as u32);
+        assert_eq!(cred.gid(), unsafe { libc::getegid() } as u32);
+        if cfg!(any(target_os = "linux", target_os = "android")) {
+            assert_eq!(cred.pid(), Some(std::process::id() as i32));
+        }
+    }
+    t!(fs::remove_file(&path));
+}
+
+#[test]
+fn pass_fds_over_stream() {
+    use std::fs::File;
+    use std::os::unix::prelude::*;
+
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (a, b) = t!(net::UnixStream::pair());
+    let a = t!(UnixStream::from_stream(a, &l.handle()));
+    let b = t!(UnixStream::from_stream(b, &l.handle()));
+
+    let path = socket_path();
+    t!(fs::write(&path, b"passed"));
+    let file = t!(File::open(&path));
+
+    let send = futures::future::poll_fn(|| {
+        let fds = [file.as_raw_fd()];
+        Ok::<_, io::Error>(try_nb!(a.send_with_fds(b"fd", &fds)).into())
+    });
+    assert_eq!(t!(l.run(send)), 2);
+    drop(file);
+
+    let recv = futures::future::poll_fn(|| {
+        let mut buf = [0; 16];
+        let mut fds = [-1; 4];
+        let (n, nfds) = try_nb!(b.recv_with_fds(&mut buf, &mut fds));
+        Ok::<_, io::Error>((buf[..n].to_vec(), fds[..nfds].to_vec()).into())
+    });
+    let (data, fds) = t!(l.run(recv));
+    assert_eq!(data, b"fd");
+    assert_eq!(fds.len(), 1);
+
+    let flags = unsafe { libc::fcntl(fds[0], libc::F_GETFD) };
+    assert!(flags & libc::FD_CLOEXEC != 0);
+    let mut received = unsafe { File::from_raw_fd(fds[0]) };
+    let mut contents = Vec::new();
+    t!(received.read_to_end(&mut contents));
+    assert_eq!(contents, b"passed");
+    t!(fs::remove_file(&path));
+}
+
+#[test]
+fn pass_fds_over_datagram() {
+    use std::os::unix::prelude::*;
+
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (a, b) = t!(net::UnixDatagram::pair());
+    let a = t!(UnixDatagram::from_datagram(a, &l.handle()));
+    let b = t!(UnixDatagram::from_datagram(b, &l.handle()));
+    let (c, d) = t!(net::UnixStream::pair());
+
+    let send = futures::future::poll_fn(|| {
+        let fds = [c.as_raw_fd(), d.as_raw_fd()];
+        Ok::<_, io::Error>(try_nb!(a.send_with_fds(b"two", &fds)).into())
+    });
+    assert_eq!(t!(l.run(send)), 3);
+
+    // Only room for one descriptor, so the second is closed.
+    let recv = futures::future::poll_fn(|| {
+        let mut buf = [0; 16];
+        let mut fds = [-1; 1];
+        let (n, nfds) = try_nb!(b.recv_with_fds(&mut buf, &mut fds));
+        Ok::<_, io::Error>((n, fds[..nfds].to_vec()).into())
+    });
+    let (n, fds) = t!(l.run(recv));
+    assert_eq!(n, 3);
+    assert_eq!(fds.len(), 1);
+    let mut received = unsafe { net::UnixStream::from_raw_fd(fds[0]) };
+    drop(c);
+    t!((&d).write_all(b"x"));
+    let mut buf = [0; 1];
+    t!(received.read_exact(&mut buf));
+    assert_eq!(&buf, b"x");
+}
+
+#[test]
+fn stream_pair() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (a, b) = t!(UnixStream::pair(&l.handle()));
+
+    let (a, _) = t!(l.run(write_all(a, b"ping")));
+    drop(a);
+    let (_, data) = t!(l.run(read_to_end(b, Vec::new())));
+    assert_eq!(data, b"ping");
+}
+
+#[test]
+fn datagram_pair() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (a, b) = t!(UnixDatagram::pair(&l.handle()));
+
+    let send = futures::future::poll_fn(|| {
+        Ok::<_, io::Error>(try_nb!(a.send(b"hello")).into())
+    });
+    assert_eq!(t!(l.run(send)), 5);
+    let recv = futures::future::poll_fn(|| {
+        let mut buf = [0; 16];
+        let n = try_nb!(b.recv(&mut buf));
+        Ok::<_, io::Error>(buf[..n].to_vec().into())
+    });
+    assert_eq!(t!(l.run(recv)), b"hello");
+}
@@ -0,0 +1,136 @@
+extern crate env_logger;
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::{self, Read, Write};
+use std::net;
+use std::thread;
+
+use futures::Future;
+use tokio_core::io::{read_exact, read_to_end, upgrade, write_all};
+use tokio_core::io::{AsyncRead, AsyncWrite, Io, IoFuture, Upgrade};
+use tokio_core::net::TcpStream;
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+/// A toy transport which exchanges a greeting and then flips every bit of the
+/// data passing through it.
+struct Inverted<T> {
+    io: T,
+}
+
+impl<T: Read> Read for Inverted<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = try!(self.io.read(buf));
+        for b in buf[..n].iter_mut() {
+            *b = !*b;
+        }
+        Ok(n)
+    }
+}
+
+impl<T: Write> Write for Inverted<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let inverted = buf.iter().map(|b| !*b).collect::<Vec<_>>();
+        self.io.write(&inverted)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.io.flush()
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for Inverted<T> {
+    fn poll_read(&mut self) -> futures::Async<()> {
+        self.io.poll_read()
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for Inverted<T> {
+    fn poll_write(&mut self) -> futures::Async<()> {
+        self.io.poll_write()
+    }
+}
+
+struct Invert;
+
+impl<T: Io + Send + 'static> Upgrade<T> for Invert {
+    type Output = Inverted<T>;
+    type Future = IoFuture<Inverted<T>>;
+
+    fn upgrade(&self, io: T) -> IoFuture<Inverted<T>> {
+        write_all(io, b"HELLO").and_then(|(io, _)| {
+            read_exact(io, [0; 2])
+        }).and_then(|(io, reply)| {
+            if &reply == b"OK" {
+                Ok(Inverted { io: io })
+            } else {
+                Err(io::Error::new(io::ErrorKind::Other, "bad handshake"))
+            }
+        }).boxed()
+    }
+}
+
+fn server(reply: &'static [u8]) -> (net::SocketAddr, thread::JoinHandle<Vec<u8>>) {
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let mut s = t!(srv.accept()).0;
+        let mut hello = [0; 5];
+        t!(s.read_exact(&mut hello));
+        assert_eq!(&hello, b"HELLO");
+        t!(s.write_all(reply));
+        let mut rest = Vec::new();
+        drop(s.read_to_end(&mut rest));
+        rest
+    });
+    (addr, t)
+}
+
+#[test]
+fn upgrade_after_connect() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (addr, t) = server(b"OK");
+
+    let stream = upgrade(TcpStream::connect(&addr, &l.handle()), Invert);
+    let stream = t!(l.run(stream));
+    t!(l.run(write_all(stream, b"secret")));
+    let inverted = "secret".bytes().map(|b| !b).collect::<Vec<_>>();
+    assert_eq!(t.join().unwrap(), inverted);
+}
+
+#[test]
+fn failed_handshake() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let (addr, t) = server(b"NO");
+
+    let stream = upgrade(TcpStream::connect(&addr, &l.handle()), Invert);
+    assert!(l.run(stream).is_err());
+    t.join().unwrap();
+}
+
+#[test]
+fn closure_upgrade() {
+    drop(env_logger::init());
+    let mut l = t!(Core::new());
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let mut s = t!(srv.accept()).0;
+        t!(s.write_all(b"data"));
+    });
+
+    let identity = |io: TcpStream| Ok::<_, io::Error>(io);
+    let stream = t!(l.run(upgrade(TcpStream::connect(&addr, &l.handle()), identity)));
+    let (_, data) = t!(l.run(read_to_end(stream, Vec::new())));
+    assert_eq!(data, b"data");
+    t.join().unwrap();
+}
@@ -0,0 +1,50 @@
+#![cfg(target_os = "linux")]
+
+extern crate futures;
+extern crate libc;
+extern crate tokio_core;
+
+use std::io;
+
+use futures::Future;
+use futures::stream::Stream;
+use tokio_core::io::{read_to_end, write_all};
+use tokio_core::net::{VsockAddr, VsockListener, VsockStream};
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+fn unsupported(e: &io::Error) -> bool {
+    let codes = [libc::EAFNOSUPPORT, libc::EADDRNOTAVAIL, libc::ENODEV];
+    codes.iter().any(|&code| e.raw_os_error() == Some(code))
+}
+
+#[test]
+fn loopback() {
+    let mut l = t!(Core::new());
+    let port = 0x7000 + std::process::id() % 0x1000;
+    let addr = VsockAddr::new(libc::VMADDR_CID_LOCAL, port);
+    let srv = match VsockListener::bind(&addr, &l.handle()) {
+        Ok(srv) => srv,
+        // Local vsock connections need kernel support which may be missing.
+        Err(ref e) if unsupported(e) => return,
+        Err(e) => panic!("failed to bind: {}", e),
+    };
+    assert_eq!(t!(srv.local_addr()), addr);
+
+    let client = VsockStream::connect(&addr, &l.handle());
+    let server = srv.incoming().into_future().map_err(|e| e.0);
+    let (client, (server, _)) = t!(l.run(client.join(server)));
+    let (server, peer) = server.unwrap();
+    assert_eq!(t!(client.peer_addr()), addr);
+    assert_eq!(t!(client.local_addr()), peer);
+
+    t!(l.run(write_all(client, b"vsock")));
+    let (_server, data) = t!(l.run(read_to_end(server, Vec::new())));
+    assert_eq!(data, b"vsock");
+}
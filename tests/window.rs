@@ -0,0 +1,59 @@
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::Cursor;
+
+use futures::Future;
+use tokio_core::io::{read_exact, write_all, Window};
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+#[test]
+fn set_range() {
+    let mut window = Window::new(b"hello world".to_vec());
+    assert_eq!(window.set_range(6..).as_ref(), b"world");
+    assert_eq!(window.set_range(..=4).as_ref(), b"hello");
+    assert_eq!(window.set_range(2..2).as_ref(), b"");
+    assert_eq!(window.set_range(..).as_ref(), b"hello world");
+    assert_eq!(window.set_start(11).as_ref(), b"");
+    assert_eq!(window.set_start(0).set_end(5).as_ref(), b"hello");
+}
+
+#[test]
+#[should_panic]
+fn set_range_out_of_bounds() {
+    Window::new(vec![0; 4]).set_range(2..5);
+}
+
+#[test]
+fn grow_to_end() {
+    let mut window = Window::new(b"hello".to_vec());
+    window.get_mut().extend_from_slice(b" world");
+    assert_eq!(window.as_ref(), b"hello");
+    window.grow_to_end().set_start(6);
+    assert_eq!(window.as_ref(), b"world");
+
+    let (_, window) = t!(write_all(Vec::new(), window).wait());
+    assert_eq!(window.into_inner(), b"hello world");
+}
+
+#[test]
+fn borrowed_pieces() {
+    let mut buf = [0; 11];
+    {
+        let mut reader = Cursor::new(&b"hello world"[..]);
+        let mut window = Window::new(&mut buf[..]);
+        for &(start, end) in [(0, 5), (5, 6), (6, 11)].iter() {
+            window.set_range(start..end);
+            let (r, w) = t!(read_exact(reader, window).wait());
+            reader = r;
+            window = w;
+        }
+    }
+    assert_eq!(&buf, b"hello world");
+}
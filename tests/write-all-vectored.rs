@@ -0,0 +1,78 @@
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::{self, IoSlice, Read, Write};
+use std::net;
+use std::thread;
+
+use futures::Future;
+use tokio_core::io::write_all_vectored;
+use tokio_core::net::TcpStream;
+use tokio_core::reactor::Core;
+
+macro_rules! t {
+    ($e:expr) => (match $e {
+        Ok(e) => e,
+        Err(e) => panic!("{} failed with {:?}", stringify!($e), e),
+    })
+}
+
+/// A writer accepting at most three bytes per call, across buffers.
+struct Trickle {
+    data: Vec<u8>,
+    calls: usize,
+}
+
+impl Write for Trickle {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_vectored(&[IoSlice::new(buf)])
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice]) -> io::Result<usize> {
+        self.calls += 1;
+        let mut n = 0;
+        for buf in bufs {
+            let take = (3 - n).min(buf.len());
+            self.data.extend_from_slice(&buf[..take]);
+            n += take;
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn partial_writes() {
+    let writer = Trickle { data: Vec::new(), calls: 0 };
+    let bufs = vec![&b"head"[..], b"", b"e", b"rbody"];
+    let (writer, bufs) = t!(write_all_vectored(writer, bufs).wait());
+    assert_eq!(writer.data, b"headerbody");
+    assert_eq!(writer.calls, 4);
+    assert_eq!(bufs.len(), 4);
+}
+
+#[test]
+fn socket() {
+    let mut l = t!(Core::new());
+    let srv = t!(net::TcpListener::bind("127.0.0.1:0"));
+    let addr = t!(srv.local_addr());
+    let t = thread::spawn(move || {
+        let (mut s, _) = t!(srv.accept());
+        let mut data = Vec::new();
+        t!(s.read_to_end(&mut data));
+        data
+    });
+
+    let body = vec![7; 256 * 1024];
+    let bufs = vec![b"HEADER".to_vec(), body.clone()];
+    let stream = TcpStream::connect(&addr, &l.handle());
+    let (stream, _) = t!(l.run(stream.and_then(|s| write_all_vectored(s, bufs))));
+    drop(stream);
+
+    let data = t.join().unwrap();
+    assert_eq!(&data[..6], b"HEADER");
+    assert_eq!(&data[6..], &body[..]);
+}